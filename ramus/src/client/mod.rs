@@ -0,0 +1,605 @@
+//! A blocking HTTP/1.1 client that reuses connections to each origin server.
+//!
+//! The responses are parsed with the same types that the [`server`](crate::server) uses for
+//! requests, and the whole body is read before [`Client::send`] returns.
+//!
+//! ```no_run
+//! use ramus::{client::Client, http::Uri};
+//!
+//! let client = Client::new();
+//! let uri = Uri::from_bytes(b"http://example.com/").expect("valid uri");
+//! let response = client.get(uri).expect("request failed");
+//! println!("{}", String::from_utf8_lossy(response.body()));
+//! ```
+//!
+//! Only the "http" scheme is supported.
+mod request;
+mod response;
+
+pub use request::*;
+pub use response::*;
+
+use std::{
+    collections::HashMap,
+    error, fmt,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::http::{Authority, Host, Limits, StatusCode, Uri};
+
+/// An error from sending a request with a [`Client`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Connecting, writing the request or reading the response failed, which includes timeouts
+    /// as an [`io::ErrorKind::TimedOut`].
+    Io(io::Error),
+    /// The URI does not have the "http" scheme or an authority that can be connected to.
+    InvalidUri,
+    /// The response is not valid, where the [`StatusCode`] is the error from parsing it, such
+    /// as a [`StatusCode::PAYLOAD_TOO_LARGE`] when the body is larger than [`Limits::body`].
+    InvalidResponse(StatusCode),
+    /// More redirects were sent than [`Client::max_redirects`] allows.
+    TooManyRedirects,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{error}"),
+            Error::InvalidUri => f.write_str("the uri cannot be requested"),
+            Error::InvalidResponse(status) => write!(f, "invalid response: {}", status.reason()),
+            Error::TooManyRedirects => f.write_str("too many redirects"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<StatusCode> for Error {
+    /// The status codes from the parsers, where a [`StatusCode::REQUEST_TIMEOUT`] is a timeout
+    /// while reading the response.
+    fn from(status: StatusCode) -> Self {
+        if status == StatusCode::REQUEST_TIMEOUT {
+            Self::Io(io::ErrorKind::TimedOut.into())
+        } else {
+            Self::InvalidResponse(status)
+        }
+    }
+}
+
+/// A connection that is waiting in the pool for the next request.
+#[derive(Debug)]
+struct Idle {
+    reader: BufReader<TcpStream>,
+    since: Instant,
+}
+
+/// A blocking HTTP/1.1 client.
+///
+/// Persistent connections are kept in a pool for each origin server [`Authority`] and are
+/// reused by later requests, so a [`Client`] should be shared, such as in an
+/// [`Arc`](std::sync::Arc), instead of created for each request.
+#[derive(Debug)]
+pub struct Client {
+    pool: Mutex<HashMap<Authority, Vec<Idle>>>,
+    limits: Limits,
+    connect_timeout: Duration,
+    timeout: Option<Duration>,
+    max_redirects: usize,
+    max_idle: usize,
+    idle_timeout: Duration,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Client {
+    /// The default time allowed to connect to a server.
+    pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// The default time allowed to send a request and read its whole response.
+    pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// The default number of redirects that are followed.
+    pub const MAX_REDIRECTS: usize = 10;
+
+    /// The default number of idle connections kept for each origin server.
+    pub const MAX_IDLE: usize = 8;
+
+    /// The default time that an idle connection is kept before it is closed.
+    pub const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+    /// Create a [`Client`] with the default settings.
+    pub fn new() -> Self {
+        Self {
+            pool: Mutex::new(HashMap::new()),
+            limits: Limits::default(),
+            connect_timeout: Self::CONNECT_TIMEOUT,
+            timeout: Some(Self::TIMEOUT),
+            max_redirects: Self::MAX_REDIRECTS,
+            max_idle: Self::MAX_IDLE,
+            idle_timeout: Self::IDLE_TIMEOUT,
+        }
+    }
+
+    /// Set the [`Limits`] used when parsing responses, where the [`Limits::request_line`] is
+    /// the limit of the `status-line`.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the time allowed to connect to a server.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set the time allowed to send a request and read its whole response, including any
+    /// redirects and retries, or `None` to wait forever.
+    ///
+    /// Connecting to a server is limited by the [`Client::connect_timeout`] instead.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the number of redirects that are followed before an [`Error::TooManyRedirects`],
+    /// where 0 returns the redirect response instead of following it.
+    pub fn max_redirects(mut self, max: usize) -> Self {
+        self.max_redirects = max;
+        self
+    }
+
+    /// Set the number of idle connections kept for each origin server, where 0 closes each
+    /// connection after its response.
+    pub fn max_idle(mut self, max: usize) -> Self {
+        self.max_idle = max;
+        self
+    }
+
+    /// Set the time that an idle connection is kept before it is closed.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Send a [`Method::Get`](crate::http::Method::Get) request for the URI.
+    ///
+    /// See [`Client::send`] for the errors returned.
+    pub fn get(&self, uri: Uri) -> Result<ClientResponse, Error> {
+        self.send(ClientRequest::get(uri))
+    }
+
+    /// Send the request and read the whole response, following any redirects.
+    ///
+    /// The `301`, `302`, `303`, `307` and `308` responses with a `Location` header field are
+    /// followed, up to [`Client::max_redirects`] times, see [`ClientRequest`] for how the
+    /// request changes.
+    ///
+    /// An idempotent request that fails on a pooled connection before any of the response is
+    /// read is sent again on a new connection, as the server may have closed the idle
+    /// connection, see [RFC7230 Section
+    /// 6.3.1](https://datatracker.ietf.org/doc/html/rfc7230#section-6.3.1).
+    ///
+    /// Returns an [`Error::InvalidUri`] if the URI, or the `Location` of a redirect, is not an
+    /// "http" URI with an authority.
+    /// Returns an [`Error::Io`] if connecting, writing or reading fails or times out.
+    /// Returns an [`Error::InvalidResponse`] if the response cannot be parsed.
+    /// Returns an [`Error::TooManyRedirects`] if there are more than [`Client::max_redirects`].
+    pub fn send(&self, mut request: ClientRequest) -> Result<ClientResponse, Error> {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let mut redirects = 0;
+        loop {
+            let response = self.send_once(&request, deadline)?;
+            let status = response.status();
+            let location = match status {
+                StatusCode::MOVED_PERMANENTLY
+                | StatusCode::FOUND
+                | StatusCode::SEE_OTHER
+                | StatusCode::TEMPORARY_REDIRECT
+                | StatusCode::PERMANENT_REDIRECT
+                    if self.max_redirects > 0 =>
+                {
+                    response.headers().get("location")
+                }
+                _ => None,
+            };
+            let Some(location) = location else {
+                return Ok(response);
+            };
+
+            if redirects == self.max_redirects {
+                return Err(Error::TooManyRedirects);
+            }
+            redirects += 1;
+            let reference = Uri::from_bytes(location.as_bytes()).map_err(|_| Error::InvalidUri)?;
+            let uri = request.uri().resolve(&reference);
+            request = request.redirect(status, uri);
+        }
+    }
+
+    fn send_once(
+        &self,
+        request: &ClientRequest,
+        deadline: Option<Instant>,
+    ) -> Result<ClientResponse, Error> {
+        let uri = request.uri();
+        if uri.scheme().as_str() != "http" {
+            return Err(Error::InvalidUri);
+        }
+        let authority = uri.authority().ok_or(Error::InvalidUri)?;
+        let origin = authority.origin(80);
+
+        loop {
+            let (mut reader, pooled) = match self.checkout(&origin) {
+                Some(reader) => (reader, true),
+                None => (self.connect(&origin)?, false),
+            };
+
+            let mut connection = Deadline {
+                reader: &mut reader,
+                deadline,
+            };
+            match self.exchange(&mut connection, request, authority) {
+                Ok((response, reusable)) => {
+                    if reusable {
+                        self.checkin(origin, reader);
+                    }
+                    return Ok(response);
+                }
+                Err(Error::Io(error))
+                    if pooled && request.method().is_idempotent() && is_closed(&error) => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Write the request and read the response on the connection.
+    fn exchange(
+        &self,
+        connection: &mut Deadline<'_>,
+        request: &ClientRequest,
+        authority: &Authority,
+    ) -> Result<(ClientResponse, bool), Error> {
+        request.write_to(&mut BufWriter::new(&mut *connection), authority)?;
+        // the connection closing before the response starts is an io error so that a request on
+        // a stale pooled connection can be retried
+        if connection.fill_buf()?.is_empty() {
+            return Err(Error::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+        let (response, reusable) = ClientResponse::read_from(
+            connection,
+            request.method(),
+            request.uri().clone(),
+            &self.limits,
+        )?;
+        Ok((response, reusable))
+    }
+
+    fn connect(&self, origin: &Authority) -> Result<BufReader<TcpStream>, Error> {
+        let port = origin.port().unwrap_or(80);
+        let addrs: Vec<SocketAddr> = match origin.host() {
+            Host::IpvN(addr) => vec![SocketAddr::new(*addr, port)],
+            Host::Domain(name) => (name.as_str(), port).to_socket_addrs()?.collect(),
+            Host::IpvFuture(_) => return Err(Error::InvalidUri),
+        };
+
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no addresses for host");
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, self.connect_timeout) {
+                Ok(stream) => {
+                    stream.set_nodelay(true)?;
+                    return Ok(BufReader::new(stream));
+                }
+                Err(error) => last_error = error,
+            }
+        }
+        Err(Error::Io(last_error))
+    }
+
+    /// Take the most recently used connection to the origin server from the pool, closing any
+    /// that have been idle for longer than the [`Client::idle_timeout`].
+    fn checkout(&self, origin: &Authority) -> Option<BufReader<TcpStream>> {
+        let mut pool = self.pool.lock().unwrap_or_else(|e| e.into_inner());
+        let idle = pool.get_mut(origin)?;
+        idle.retain(|idle| idle.since.elapsed() < self.idle_timeout);
+        idle.pop().map(|idle| idle.reader)
+    }
+
+    fn checkin(&self, origin: Authority, reader: BufReader<TcpStream>) {
+        // any bytes after the response mean the connection is out of sync with the server
+        if !reader.buffer().is_empty() {
+            return;
+        }
+        let mut pool = self.pool.lock().unwrap_or_else(|e| e.into_inner());
+        let idle = pool.entry(origin).or_default();
+        if idle.len() < self.max_idle {
+            idle.push(Idle {
+                reader,
+                since: Instant::now(),
+            });
+        }
+    }
+}
+
+/// A connection whose reads and writes time out at the deadline, so that the
+/// [`Client::timeout`] limits a whole exchange instead of each read of a slow server.
+struct Deadline<'c> {
+    reader: &'c mut BufReader<TcpStream>,
+    deadline: Option<Instant>,
+}
+
+impl Deadline<'_> {
+    /// Returns the time left until the deadline, or a timeout error once it has passed.
+    fn remaining(&self) -> io::Result<Option<Duration>> {
+        let Some(deadline) = self.deadline else {
+            return Ok(None);
+        };
+        match deadline.checked_duration_since(Instant::now()) {
+            Some(remaining) if !remaining.is_zero() => Ok(Some(remaining)),
+            _ => Err(io::ErrorKind::TimedOut.into()),
+        }
+    }
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.reader.buffer().is_empty() {
+            self.reader.get_ref().set_read_timeout(self.remaining()?)?;
+        }
+        self.reader.read(buf)
+    }
+}
+
+impl BufRead for Deadline<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.reader.buffer().is_empty() {
+            self.reader.get_ref().set_read_timeout(self.remaining()?)?;
+        }
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.reader.consume(amount);
+    }
+}
+
+impl Write for Deadline<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut stream = self.reader.get_ref();
+        stream.set_write_timeout(self.remaining()?)?;
+        stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.reader.get_ref().flush()
+    }
+}
+
+/// Returns true if the error is from the server closing the connection.
+fn is_closed(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        http::{Method, Request, Response, StatusCode, Uri},
+        server::Server,
+    };
+
+    use super::{Client, ClientRequest, Error};
+
+    fn uri(uri: &str) -> Uri {
+        Uri::from_bytes(uri.as_bytes()).unwrap()
+    }
+
+    /// Serve each connection with the handler on a raw socket, and count the connections.
+    fn raw_server<F>(handler: F) -> (String, Arc<AtomicUsize>)
+    where
+        F: Fn(&mut BufReader<TcpStream>, &mut TcpStream) + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let count = Arc::clone(&connections);
+        let handler = Arc::new(handler);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                count.fetch_add(1, Ordering::SeqCst);
+                let handler = Arc::clone(&handler);
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    handler(&mut reader, &mut stream);
+                });
+            }
+        });
+        (addr, connections)
+    }
+
+    /// Read a request head and return the request-line.
+    fn read_head(reader: &mut BufReader<TcpStream>) -> Option<String> {
+        let mut head = String::new();
+        while !head.ends_with("\r\n\r\n") {
+            if reader.read_line(&mut head).ok()? == 0 {
+                return None;
+            }
+        }
+        head.lines().next().map(str::to_string)
+    }
+
+    #[test]
+    fn connections_are_reused() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || {
+            server.serve(|request: &Request| {
+                Response::new(StatusCode::OK).with_body(request.uri().path().as_str())
+            })
+        });
+        let (proxy, connections) = raw_server(move |reader, stream| {
+            // forward the bytes to the server, counting the connections from the client
+            let mut upstream = TcpStream::connect(addr).unwrap();
+            let mut downstream = upstream.try_clone().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            thread::spawn(move || io::copy(&mut downstream, &mut writer));
+            let _ = io::copy(reader, &mut upstream);
+        });
+
+        let client = Client::new();
+        for path in ["/one", "/two"] {
+            let response = client.get(uri(&format!("{proxy}{path}"))).unwrap();
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(path.as_bytes(), response.body());
+        }
+        assert_eq!(1, connections.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn stale_pooled_connection_is_retried() {
+        let (addr, connections) = raw_server(|reader, stream| {
+            // closes the connection after one response without the close option
+            read_head(reader).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .unwrap();
+        });
+
+        let client = Client::new();
+        assert_eq!(b"ok", client.get(uri(&format!("{addr}/"))).unwrap().body());
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(b"ok", client.get(uri(&format!("{addr}/"))).unwrap().body());
+        assert_eq!(2, connections.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn redirects_are_followed() {
+        let (addr, _) = raw_server(|reader, stream| {
+            while let Some(line) = read_head(reader) {
+                let response: &[u8] = match line.as_str() {
+                    "POST /form HTTP/1.1" => {
+                        let mut body = [0; 4];
+                        reader.read_exact(&mut body).unwrap();
+                        b"HTTP/1.1 303 See Other\r\nLocation: done?id=1\r\nContent-Length: 0\r\n\r\n"
+                    }
+                    "GET /done?id=1 HTTP/1.1" => {
+                        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\ndone\r\n0\r\n\r\n"
+                    }
+                    _ => b"HTTP/1.1 302 Found\r\nLocation: /loop\r\nContent-Length: 0\r\n\r\n",
+                };
+                stream.write_all(response).unwrap();
+            }
+        });
+
+        let client = Client::new();
+        let request =
+            ClientRequest::new(Method::Post, uri(&format!("{addr}/form"))).with_body("data");
+        let response = client.send(request).unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(b"done", response.body());
+        assert_eq!("/done", response.uri().path().as_str());
+
+        let error = client.get(uri(&format!("{addr}/loop"))).unwrap_err();
+        assert!(matches!(error, Error::TooManyRedirects));
+
+        let response = Client::new()
+            .max_redirects(0)
+            .get(uri(&format!("{addr}/loop")))
+            .unwrap();
+        assert_eq!(StatusCode::FOUND, response.status());
+    }
+
+    #[test]
+    fn slow_response_times_out() {
+        let (addr, _) = raw_server(|reader, _| {
+            read_head(reader);
+            thread::sleep(Duration::from_millis(500));
+        });
+
+        let client = Client::new().timeout(Some(Duration::from_millis(50)));
+        match client.get(uri(&format!("{addr}/"))) {
+            Err(Error::Io(error)) => assert!(matches!(
+                error.kind(),
+                io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+            )),
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn trickling_response_times_out() {
+        let (addr, _) = raw_server(|reader, stream| {
+            // each byte arrives well within the timeout, but the whole response does not
+            read_head(reader);
+            for byte in b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n".repeat(10) {
+                if stream.write_all(&[byte]).is_err() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        });
+
+        let client = Client::new().timeout(Some(Duration::from_millis(200)));
+        let start = Instant::now();
+        match client.get(uri(&format!("{addr}/"))) {
+            Err(Error::Io(error)) => assert!(matches!(
+                error.kind(),
+                io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+            )),
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn only_http_uris_can_be_requested() {
+        let client = Client::new();
+        assert!(matches!(
+            client.get(uri("https://example.com/")),
+            Err(Error::InvalidUri)
+        ));
+        assert!(matches!(
+            client.get(uri("/relative")),
+            Err(Error::InvalidUri)
+        ));
+    }
+}
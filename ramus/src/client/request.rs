@@ -0,0 +1,188 @@
+use std::io::{self, Write};
+
+use crate::http::{Authority, HeaderField, Headers, Method, StatusCode, Uri};
+
+/// A request to send with a [`Client`](super::Client).
+///
+/// The `Host`, `Content-Length` and `Transfer-Encoding` header fields are always set by the
+/// client from the [`Uri`] and the body, replacing any set on the request.
+///
+/// ```
+/// use ramus::{
+///     client::ClientRequest,
+///     http::{Method, Uri},
+/// };
+///
+/// let uri = Uri::from_bytes(b"http://example.com/items").expect("valid uri");
+/// let request = ClientRequest::new(Method::Post, uri)
+///     .with_header("Content-Type", "text/plain")
+///     .with_body("hello");
+/// assert_eq!(b"hello", request.body());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClientRequest {
+    method: Method,
+    uri: Uri,
+    headers: Headers,
+    body: Vec<u8>,
+}
+
+impl ClientRequest {
+    /// Create a [`ClientRequest`] with the method and target URI, and no header fields or body.
+    pub fn new(method: Method, uri: Uri) -> Self {
+        Self {
+            method,
+            uri,
+            headers: Headers::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Create a [`Method::Get`] request for the URI.
+    pub fn get(uri: Uri) -> Self {
+        Self::new(Method::Get, uri)
+    }
+
+    /// Add a header field to the request.
+    pub fn with_header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.append(HeaderField::new(name, value));
+        self
+    }
+
+    /// Set the body of the request, which is sent with a `Content-Length`.
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Returns the [`Method`] of the request.
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Returns the target [`Uri`] of the request.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Returns the header fields of the request.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the body of the request.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns the request to send when following a redirect to the URI, as defined in
+    /// [RFC7231 Section 6.4](https://datatracker.ietf.org/doc/html/rfc7231#section-6.4).
+    ///
+    /// A `303 See Other`, or a `301`/`302` for a `POST`, is followed with a `GET` and without
+    /// the body, whereas a `307` or `308` repeats the same request. The credentials are not
+    /// sent to a different authority.
+    pub(crate) fn redirect(mut self, status: StatusCode, uri: Uri) -> Self {
+        let to_get = status == StatusCode::SEE_OTHER && self.method != Method::Head
+            || matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND)
+                && self.method == Method::Post;
+        if to_get {
+            self.method = Method::Get;
+            self.body.clear();
+            self.headers.remove("content-type");
+        }
+        if uri.authority() != self.uri.authority() {
+            self.headers.remove("authorization");
+            self.headers.remove("cookie");
+        }
+        self.uri = uri;
+        self
+    }
+
+    /// Write the request as a HTTP/1.1 message in origin-form to the server at the authority.
+    pub(crate) fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        authority: &Authority,
+    ) -> io::Result<()> {
+        let path = match self.uri.path().as_str() {
+            "" => "/",
+            path => path,
+        };
+        write!(writer, "{} {}", self.method.as_str(), path)?;
+        if !self.uri.query().as_str().is_empty() {
+            write!(writer, "?{}", self.uri.query().as_str())?;
+        }
+        writer.write_all(b" HTTP/1.1\r\n")?;
+
+        match authority.port() {
+            Some(port) if port != 0 => write!(writer, "Host: {}:{}\r\n", authority.host(), port)?,
+            _ => write!(writer, "Host: {}\r\n", authority.host())?,
+        }
+        for field in self.headers.iter() {
+            if !matches!(
+                field.name(),
+                "host" | "content-length" | "transfer-encoding"
+            ) {
+                write!(writer, "{}: {}\r\n", field.name(), field.value())?;
+            }
+        }
+        // RFC7230 Section 3.3.2: a body is expected for a POST or PUT so the length is always sent
+        if !self.body.is_empty() || matches!(self.method, Method::Post | Method::Put) {
+            write!(writer, "Content-Length: {}\r\n", self.body.len())?;
+        }
+        writer.write_all(b"\r\n")?;
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::{Method, StatusCode, Uri};
+
+    use super::ClientRequest;
+
+    fn uri(bytes: &str) -> Uri {
+        Uri::from_bytes(bytes.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn request_is_written_in_origin_form() {
+        let request = ClientRequest::new(Method::Post, uri("http://a:8080/b?c=d"))
+            .with_header("Host", "ignored")
+            .with_header("Accept", "*/*")
+            .with_body("hi");
+        let mut dst = Vec::new();
+        request
+            .write_to(&mut dst, request.uri().authority().unwrap())
+            .unwrap();
+        assert_eq!(
+            "POST /b?c=d HTTP/1.1\r\nHost: a:8080\r\naccept: */*\r\nContent-Length: 2\r\n\r\nhi",
+            String::from_utf8(dst).unwrap()
+        );
+    }
+
+    #[test]
+    fn see_other_is_followed_with_get() {
+        let request = ClientRequest::new(Method::Post, uri("http://a/form"))
+            .with_header("Content-Type", "text/plain")
+            .with_header("Authorization", "Basic YTpi")
+            .with_body("data")
+            .redirect(StatusCode::SEE_OTHER, uri("http://b/done"));
+        assert_eq!(Method::Get, request.method());
+        assert!(request.body().is_empty());
+        assert!(!request.headers().contains("content-type"));
+        assert!(!request.headers().contains("authorization"));
+    }
+
+    #[test]
+    fn temporary_redirect_repeats_the_request() {
+        let request = ClientRequest::new(Method::Put, uri("http://a/old"))
+            .with_header("Authorization", "Basic YTpi")
+            .with_body("data")
+            .redirect(StatusCode::TEMPORARY_REDIRECT, uri("http://a/new"));
+        assert_eq!(Method::Put, request.method());
+        assert_eq!(b"data", request.body());
+        assert!(request.headers().contains("authorization"));
+    }
+}
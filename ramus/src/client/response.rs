@@ -0,0 +1,276 @@
+use std::io::{BufRead, Read};
+
+use crate::http::{
+    chunked, content_length, read_headers, utils, Headers, Limits, Method, StatusCode, StatusLine,
+    Uri, Version,
+};
+
+/// The number of interim 1xx responses that are skipped before a final response, so that a
+/// server cannot keep the client reading interim responses forever.
+const MAX_INTERIM: usize = 16;
+
+/// A response received by a [`Client`](super::Client), with the whole body read.
+#[derive(Clone, Debug)]
+pub struct ClientResponse {
+    line: StatusLine,
+    headers: Headers,
+    body: Vec<u8>,
+    uri: Uri,
+}
+
+impl ClientResponse {
+    /// Returns the [`StatusLine`] of the response.
+    pub fn status_line(&self) -> &StatusLine {
+        &self.line
+    }
+
+    /// Returns the [`Version`] of the response.
+    pub fn version(&self) -> Version {
+        self.line.version()
+    }
+
+    /// Returns the [`StatusCode`] of the response, see [`StatusLine::status`].
+    pub fn status(&self) -> StatusCode {
+        self.line.status()
+    }
+
+    /// Returns the header fields of the response.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the body of the response.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns the body of the response, consuming the response.
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+
+    /// Returns the URI of the request that this is the response to, which is the target of the
+    /// last redirect when any were followed.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Read a response to a request with the [`Method`] from the reader.
+    ///
+    /// Up to [`MAX_INTERIM`] interim 1xx responses are skipped, as defined in [RFC7231 Section
+    /// 6.2](https://datatracker.ietf.org/doc/html/rfc7231#section-6.2), except for a
+    /// `101 Switching Protocols` which is returned without a body, as the connection no longer
+    /// speaks HTTP/1.1 after it.
+    ///
+    /// Returns the response and whether the connection can be used for another request, which
+    /// is not the case when the server closes the connection, switches protocols or the body is
+    /// close-delimited.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if there are more than [`MAX_INTERIM`] interim
+    /// responses.
+    pub(crate) fn read_from<R: BufRead>(
+        reader: &mut R,
+        method: Method,
+        uri: Uri,
+        limits: &Limits,
+    ) -> Result<(Self, bool), StatusCode> {
+        let mut interim = 0;
+        let (line, headers) = loop {
+            let line = utils::read_line(reader, limits.request_line, StatusCode::BAD_REQUEST)?
+                .ok_or(StatusCode::BAD_REQUEST)?;
+            let line = StatusLine::from_bytes(&line)?;
+            if !line.version().is_supported() {
+                return Err(StatusCode::HTTP_VERSION_NOT_SUPPORTED);
+            }
+            let headers = read_headers(reader, limits)?;
+            if line.code() == 101 {
+                let response = Self {
+                    line,
+                    headers,
+                    body: Vec::new(),
+                    uri,
+                };
+                return Ok((response, false));
+            }
+            if !matches!(line.code(), 100..=199) {
+                break (line, headers);
+            }
+            interim += 1;
+            if interim > MAX_INTERIM {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        };
+        let version = line.version();
+
+        let has_option = |option: &str| {
+            headers
+                .get_all("connection")
+                .flat_map(|value| value.split(','))
+                .any(|value| value.trim().eq_ignore_ascii_case(option))
+        };
+        let mut reusable = if version.is_persistent_by_default() {
+            !has_option("close")
+        } else {
+            has_option("keep-alive")
+        };
+
+        // RFC7230 Section 3.3.3: the length of the body of a response
+        let body = if method == Method::Head || matches!(line.code(), 204 | 304) {
+            Vec::new()
+        } else if let Some(coding) = headers.get_all("transfer-encoding").last() {
+            // the chunked coding must be the last applied, otherwise the body is close-delimited
+            let is_chunked = coding
+                .rsplit(',')
+                .next()
+                .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"));
+            if is_chunked {
+                chunked::read_chunked(reader, limits)?
+            } else {
+                reusable = false;
+                read_to_end(reader, limits)?
+            }
+        } else if let Some(length) = content_length(&headers)? {
+            if length > limits.body as u64 {
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+            let mut body = vec![0; length as usize];
+            reader
+                .read_exact(&mut body)
+                .map_err(utils::io_error_status)?;
+            body
+        } else {
+            reusable = false;
+            read_to_end(reader, limits)?
+        };
+
+        let response = Self {
+            line,
+            headers,
+            body,
+            uri,
+        };
+        Ok((response, reusable))
+    }
+}
+
+/// Read a close-delimited body, until the server closes the connection.
+fn read_to_end<R: BufRead>(reader: &mut R, limits: &Limits) -> Result<Vec<u8>, StatusCode> {
+    let mut body = Vec::new();
+    reader
+        .take(limits.body as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(utils::io_error_status)?;
+    if body.len() > limits.body {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::{Limits, Method, StatusCode, Uri};
+
+    use super::{ClientResponse, MAX_INTERIM};
+
+    fn read(mut bytes: &[u8], method: Method) -> Result<(ClientResponse, bool), StatusCode> {
+        let uri = Uri::from_bytes(b"http://a/").unwrap();
+        ClientResponse::read_from(&mut bytes, method, uri, &Limits::default())
+    }
+
+    #[test]
+    fn unsupported_version_is_rejected() {
+        assert_eq!(
+            StatusCode::HTTP_VERSION_NOT_SUPPORTED,
+            read(b"HTTP/2.0 200 OK\r\n\r\n", Method::Get).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn unregistered_code_is_understood_by_class() {
+        let (response, _) = read(
+            b"HTTP/1.1 299 Custom\r\nContent-Length: 0\r\n\r\n",
+            Method::Get,
+        )
+        .unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(299, response.status_line().code());
+        assert_eq!("Custom", response.status_line().reason());
+    }
+
+    #[test]
+    fn interim_responses_are_skipped() {
+        let (response, reusable) = read(
+            b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+            Method::Post,
+        )
+        .unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(b"ok", response.body());
+        assert!(reusable);
+    }
+
+    #[test]
+    fn endless_interim_responses_are_rejected() {
+        let bytes = b"HTTP/1.1 100 Continue\r\n\r\n".repeat(MAX_INTERIM + 1);
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            read(&bytes, Method::Get).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn switching_protocols_is_returned() {
+        let (response, reusable) = read(
+            b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n\x81\x00",
+            Method::Get,
+        )
+        .unwrap();
+        assert_eq!(StatusCode::SWITCHING_PROTOCOLS, response.status());
+        assert!(response.body().is_empty());
+        assert!(!reusable);
+    }
+
+    #[test]
+    fn chunked_body_is_decoded() {
+        let (response, reusable) = read(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n",
+            Method::Get,
+        )
+        .unwrap();
+        assert_eq!(b"hello", response.body());
+        assert!(reusable);
+    }
+
+    #[test]
+    fn body_without_length_is_close_delimited() {
+        let (response, reusable) =
+            read(b"HTTP/1.0 200 OK\r\n\r\nuntil the end", Method::Get).unwrap();
+        assert_eq!(b"until the end", response.body());
+        assert!(!reusable);
+    }
+
+    #[test]
+    fn head_response_has_no_body() {
+        let (response, reusable) = read(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\nConnection: close\r\n\r\n",
+            Method::Head,
+        )
+        .unwrap();
+        assert!(response.body().is_empty());
+        assert!(!reusable);
+    }
+
+    #[test]
+    fn body_larger_than_limit_is_rejected() {
+        let mut bytes = &b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n"[..];
+        let limits = Limits {
+            body: 10,
+            ..Limits::default()
+        };
+        let uri = Uri::from_bytes(b"http://a/").unwrap();
+        assert_eq!(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            ClientResponse::read_from(&mut bytes, Method::Get, uri, &limits).unwrap_err()
+        );
+    }
+}
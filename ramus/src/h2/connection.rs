@@ -0,0 +1,1309 @@
+//! Crate Module:
+//! A HTTP/2 connection, with streams as defined in [RFC9113 Section
+//! 5](https://datatracker.ietf.org/doc/html/rfc9113#section-5).
+//!
+//! The connection thread reads every frame and a thread is spawned for each request once the
+//! whole request has been received. Frames are written by whichever thread needs to send them
+//! while holding the lock on the [`SendState`], and the threads sending DATA frames wait on a
+//! [`Condvar`] for WINDOW_UPDATE frames when the flow-control windows are exhausted.
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufWriter, Read, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Condvar, Mutex, MutexGuard,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    hpack::{Decoder, Encoder, Field},
+    http::{
+        content_length, validate_host, Body, HeaderField, Headers, Limits, Method, Request,
+        RequestLine, Response, Scheme, StatusCode, Uri, Version,
+    },
+    server::{Handler, Stream},
+};
+
+use super::{
+    frame::{setting, Error, Frame},
+    ErrorCode, PREFACE,
+};
+
+/// The default initial window size and the window size of a new connection.
+const DEFAULT_WINDOW_SIZE: i64 = 65_535;
+/// The largest flow-control window allowed.
+const MAX_WINDOW_SIZE: i64 = (1 << 31) - 1;
+/// The default SETTINGS_MAX_FRAME_SIZE, which is never changed for the frames received.
+const DEFAULT_MAX_FRAME_SIZE: u32 = 16_384;
+/// The largest SETTINGS_MAX_FRAME_SIZE allowed.
+const MAX_FRAME_SIZE: u32 = (1 << 24) - 1;
+/// The SETTINGS_MAX_CONCURRENT_STREAMS sent to the client.
+const MAX_CONCURRENT_STREAMS: u32 = 100;
+/// The default SETTINGS_HEADER_TABLE_SIZE, which is never changed for the header blocks
+/// received and is the largest dynamic table used for the header blocks sent.
+const HEADER_TABLE_SIZE: usize = 4096;
+
+/// A HTTP/1.1 request that was upgraded to HTTP/2, which is answered on stream 1.
+#[derive(Debug)]
+pub(crate) struct Upgrade {
+    pub(crate) request: Request,
+    /// The settings from the `HTTP2-Settings` header field.
+    pub(crate) settings: Vec<(u16, u32)>,
+}
+
+/// Serve a HTTP/2 connection until the client closes it, or it is closed because of a
+/// connection error or being idle for longer than the timeout.
+///
+/// The reader must be positioned at the start of the client connection preface.
+pub(crate) fn serve<R: BufRead, H: Handler>(
+    reader: &mut R,
+    stream: &Stream,
+    handler: &H,
+    limits: &Limits,
+    timeout: Duration,
+    upgrade: Option<Upgrade>,
+) -> io::Result<()> {
+    stream.tcp().set_read_timeout(Some(timeout))?;
+    stream.tcp().set_write_timeout(Some(timeout))?;
+
+    let sender = Sender::new(stream);
+    sender.write(&Frame::Settings {
+        ack: false,
+        params: vec![
+            (setting::HEADER_TABLE_SIZE, HEADER_TABLE_SIZE as u32),
+            (setting::ENABLE_PUSH, 0),
+            (setting::MAX_CONCURRENT_STREAMS, MAX_CONCURRENT_STREAMS),
+            (setting::MAX_HEADER_LIST_SIZE, limits.header_block as u32),
+        ],
+    })?;
+
+    thread::scope(|scope| {
+        let mut connection = Connection {
+            sender: &sender,
+            limits,
+            decoder: Decoder::new(HEADER_TABLE_SIZE)
+                .max_list_size(limits.header_block.saturating_mul(2)),
+            last_stream_id: 0,
+            receiving: HashMap::new(),
+        };
+        let mut dispatch = |stream_id, request: Request| {
+            let sender = &sender;
+            let running = Running::start(&sender.handlers);
+            scope.spawn(move || {
+                let _running = running;
+                let response = handler
+                    .handle(&request)
+                    .with_compression(&request)
+                    .with_conditions(&request)
+                    .with_range(&request);
+                sender.send_response(stream_id, request.method(), response);
+            });
+        };
+
+        if let Some(upgrade) = upgrade {
+            // RFC7540 Section 3.2: the upgraded request is sent on stream 1, which is
+            // half-closed (remote) as the whole request has already been received
+            if let Err(error) = sender.apply_settings(&upgrade.settings) {
+                return connection.close(error);
+            }
+            connection.last_stream_id = 1;
+            sender.open(1);
+            dispatch(1, upgrade.request);
+        }
+
+        let result = connection.run(reader, &mut dispatch);
+        sender.close();
+        result
+    })
+}
+
+/// The state needed to write frames to the connection.
+struct SendState<'a> {
+    writer: BufWriter<&'a Stream>,
+    encoder: Encoder,
+    /// The connection flow-control window for sending DATA frames.
+    window: i64,
+    /// The flow-control window of each stream that can still send frames.
+    streams: HashMap<u32, i64>,
+    initial_window_size: i64,
+    max_frame_size: usize,
+    /// Set once the connection is closing and so no more frames can be sent.
+    closed: bool,
+}
+
+impl SendState<'_> {
+    fn write(&mut self, frame: &Frame) -> io::Result<()> {
+        if self.closed {
+            return Err(io::ErrorKind::NotConnected.into());
+        }
+        let result = frame
+            .write_to(&mut self.writer)
+            .and_then(|_| self.writer.flush());
+        if result.is_err() {
+            self.closed = true;
+        }
+        result
+    }
+}
+
+/// The [`SendState`] shared between the connection thread and the stream threads.
+struct Sender<'a> {
+    state: Mutex<SendState<'a>>,
+    window_update: Condvar,
+    /// The number of handler threads that are still running, including those of streams that
+    /// have been reset.
+    handlers: AtomicUsize,
+}
+
+/// Counts a running handler thread for as long as it is alive, even if the handler panics.
+struct Running<'s>(&'s AtomicUsize);
+
+impl<'s> Running<'s> {
+    fn start(handlers: &'s AtomicUsize) -> Self {
+        handlers.fetch_add(1, Ordering::SeqCst);
+        Self(handlers)
+    }
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<'a> Sender<'a> {
+    fn new(stream: &'a Stream) -> Self {
+        Self {
+            state: Mutex::new(SendState {
+                writer: BufWriter::new(stream),
+                encoder: Encoder::new(HEADER_TABLE_SIZE),
+                window: DEFAULT_WINDOW_SIZE,
+                streams: HashMap::new(),
+                initial_window_size: DEFAULT_WINDOW_SIZE,
+                max_frame_size: DEFAULT_MAX_FRAME_SIZE as usize,
+                closed: false,
+            }),
+            window_update: Condvar::new(),
+            handlers: AtomicUsize::new(0),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SendState<'a>> {
+        // a stream thread that panics while holding the lock cannot leave the state invalid
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self, frame: &Frame) -> io::Result<()> {
+        self.lock().write(frame)
+    }
+
+    /// Returns the number of streams that are open or half-closed.
+    fn active_streams(&self) -> usize {
+        self.lock().streams.len()
+    }
+
+    fn open(&self, stream_id: u32) {
+        let mut state = self.lock();
+        let window = state.initial_window_size;
+        state.streams.insert(stream_id, window);
+    }
+
+    fn is_open(&self, stream_id: u32) -> bool {
+        self.lock().streams.contains_key(&stream_id)
+    }
+
+    /// Close the stream so that no more frames are sent for it.
+    fn close_stream(&self, stream_id: u32) {
+        self.lock().streams.remove(&stream_id);
+        self.window_update.notify_all();
+    }
+
+    /// Close the connection so that no more frames are sent.
+    fn close(&self) {
+        self.lock().closed = true;
+        self.window_update.notify_all();
+    }
+
+    /// Apply the settings sent by the client as defined in [RFC9113 Section
+    /// 6.5.2](https://datatracker.ietf.org/doc/html/rfc9113#section-6.5.2).
+    ///
+    /// SETTINGS_MAX_CONCURRENT_STREAMS has no effect as the server never opens a stream.
+    fn apply_settings(&self, params: &[(u16, u32)]) -> Result<(), Error> {
+        let mut state = self.lock();
+        for (id, value) in params {
+            match *id {
+                setting::HEADER_TABLE_SIZE => {
+                    let size = (*value as usize).min(HEADER_TABLE_SIZE);
+                    state.encoder.set_max_table_size(size);
+                }
+                setting::ENABLE_PUSH if *value > 1 => {
+                    return Err(Error::Connection(ErrorCode::PROTOCOL_ERROR));
+                }
+                setting::INITIAL_WINDOW_SIZE => {
+                    let value = *value as i64;
+                    if value > MAX_WINDOW_SIZE {
+                        return Err(Error::Connection(ErrorCode::FLOW_CONTROL_ERROR));
+                    }
+                    let delta = value - state.initial_window_size;
+                    for window in state.streams.values_mut() {
+                        *window += delta;
+                        if *window > MAX_WINDOW_SIZE {
+                            return Err(Error::Connection(ErrorCode::FLOW_CONTROL_ERROR));
+                        }
+                    }
+                    state.initial_window_size = value;
+                }
+                setting::MAX_FRAME_SIZE => {
+                    if !(DEFAULT_MAX_FRAME_SIZE..=MAX_FRAME_SIZE).contains(value) {
+                        return Err(Error::Connection(ErrorCode::PROTOCOL_ERROR));
+                    }
+                    state.max_frame_size = *value as usize;
+                }
+                _ => {}
+            }
+        }
+        self.window_update.notify_all();
+        Ok(())
+    }
+
+    /// Increase a flow-control window, where a stream identifier of 0 is the connection.
+    ///
+    /// A WINDOW_UPDATE for a stream that is closed is ignored.
+    fn window_update(&self, stream_id: u32, increment: u32) -> Result<(), Error> {
+        let mut state = self.lock();
+        let window = match stream_id {
+            0 => &mut state.window,
+            _ => match state.streams.get_mut(&stream_id) {
+                Some(window) => window,
+                None => return Ok(()),
+            },
+        };
+        *window += increment as i64;
+        if *window > MAX_WINDOW_SIZE {
+            return Err(match stream_id {
+                0 => Error::Connection(ErrorCode::FLOW_CONTROL_ERROR),
+                _ => Error::Stream(stream_id, ErrorCode::FLOW_CONTROL_ERROR),
+            });
+        }
+        self.window_update.notify_all();
+        Ok(())
+    }
+
+    /// Send the response on the stream, returns once the whole response has been sent or the
+    /// stream or connection has been closed.
+    fn send_response(&self, stream_id: u32, method: Method, response: Response) {
+        let response = response.with_date();
+        let code = response.status().as_u16();
+        let has_body = !matches!(code, 100..=199 | 204 | 304);
+
+        let mut headers = response.headers().clone();
+        // RFC9113 Section 8.2.2: connection-specific header fields must not be sent
+        for name in [
+            "connection",
+            "keep-alive",
+            "proxy-connection",
+            "transfer-encoding",
+            "upgrade",
+            "content-length",
+        ] {
+            headers.remove(name);
+        }
+        match response.body() {
+            Body::Bytes(bytes) if has_body => {
+                headers.insert("content-length", bytes.len().to_string());
+            }
+            Body::Sized(_, length) | Body::Seekable(_, length) if has_body => {
+                headers.insert("content-length", length.to_string());
+            }
+            Body::Empty if has_body => headers.insert("content-length", "0"),
+            _ => {}
+        }
+
+        let send_body =
+            has_body && method != Method::Head && !matches!(response.body(), Body::Empty);
+        let fields = std::iter::once(Field::new(":status", code.to_string()))
+            .chain(headers.iter().map(Field::from));
+        if !self.send_headers(stream_id, fields, !send_body) || !send_body {
+            return;
+        }
+
+        match response.into_body() {
+            Body::Bytes(bytes) => {
+                self.send_data(stream_id, &bytes, true);
+            }
+            Body::Stream(mut stream) => self.send_stream(stream_id, &mut stream, None),
+            Body::Sized(stream, length) => {
+                self.send_stream(stream_id, &mut stream.take(length), Some(length))
+            }
+            Body::Seekable(stream, length) => {
+                self.send_stream(stream_id, &mut stream.take(length), Some(length))
+            }
+            Body::Empty => {}
+        }
+    }
+
+    /// Send the body from the reader as DATA frames, resetting the stream if reading fails or
+    /// the reader ends before the expected length.
+    fn send_stream(&self, stream_id: u32, stream: &mut dyn Read, length: Option<u64>) {
+        let mut buf = vec![0; DEFAULT_MAX_FRAME_SIZE as usize];
+        let mut sent = 0;
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) if length.is_some_and(|length| sent != length) => {
+                    self.reset(stream_id, ErrorCode::INTERNAL_ERROR);
+                    break;
+                }
+                Ok(0) => {
+                    self.send_data(stream_id, &[], true);
+                    break;
+                }
+                Ok(n) => {
+                    sent += n as u64;
+                    if !self.send_data(stream_id, &buf[..n], false) {
+                        break;
+                    }
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => {
+                    self.reset(stream_id, ErrorCode::INTERNAL_ERROR);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Send a header block as a HEADERS frame followed by any CONTINUATION frames needed.
+    ///
+    /// Returns false if the stream or connection has been closed.
+    fn send_headers<I>(&self, stream_id: u32, fields: I, end_stream: bool) -> bool
+    where
+        I: IntoIterator<Item = Field>,
+    {
+        let mut state = self.lock();
+        if state.closed || !state.streams.contains_key(&stream_id) {
+            return false;
+        }
+
+        let mut block = Vec::new();
+        state.encoder.encode(fields, &mut block);
+        let max_frame_size = state.max_frame_size;
+        let mut fragments = block.chunks(max_frame_size).peekable();
+        let mut frame = Frame::Headers {
+            stream_id,
+            end_stream,
+            end_headers: block.len() <= max_frame_size,
+            block: fragments.next().unwrap_or_default().to_vec(),
+        };
+        loop {
+            if state.write(&frame).is_err() {
+                return false;
+            }
+            match fragments.next() {
+                Some(fragment) => {
+                    frame = Frame::Continuation {
+                        stream_id,
+                        end_headers: fragments.peek().is_none(),
+                        block: fragment.to_vec(),
+                    }
+                }
+                None => break,
+            }
+        }
+
+        if end_stream {
+            state.streams.remove(&stream_id);
+        }
+        true
+    }
+
+    /// Send the data as DATA frames, waiting for the flow-control windows when needed.
+    ///
+    /// Returns false if the stream or connection has been closed.
+    fn send_data(&self, stream_id: u32, mut data: &[u8], end_stream: bool) -> bool {
+        loop {
+            let mut state = self.lock();
+            let size = loop {
+                if state.closed {
+                    return false;
+                }
+                let stream_window = match state.streams.get(&stream_id) {
+                    Some(window) => *window,
+                    None => return false,
+                };
+                let available = state
+                    .window
+                    .min(stream_window)
+                    .min(state.max_frame_size as i64)
+                    .max(0) as usize;
+                if available > 0 || data.is_empty() {
+                    break data.len().min(available);
+                }
+                state = self
+                    .window_update
+                    .wait(state)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            };
+
+            let (fragment, rest) = data.split_at(size);
+            let end_stream = end_stream && rest.is_empty();
+            let frame = Frame::Data {
+                stream_id,
+                end_stream,
+                data: fragment.to_vec(),
+                size: size as u32,
+            };
+            if state.write(&frame).is_err() {
+                return false;
+            }
+            state.window -= size as i64;
+            if let Some(window) = state.streams.get_mut(&stream_id) {
+                *window -= size as i64;
+            }
+            if end_stream {
+                state.streams.remove(&stream_id);
+            }
+            if rest.is_empty() {
+                return true;
+            }
+            data = rest;
+        }
+    }
+
+    /// Close the stream and send a RST_STREAM frame with the error code.
+    fn reset(&self, stream_id: u32, error: ErrorCode) {
+        let mut state = self.lock();
+        state.streams.remove(&stream_id);
+        // the connection thread will find out about a failed write on its next read
+        let _ = state.write(&Frame::RstStream { stream_id, error });
+        self.window_update.notify_all();
+    }
+}
+
+/// A request that is still being received.
+struct Receiving {
+    line: RequestLine,
+    headers: Headers,
+    scheme: Scheme,
+    body: Vec<u8>,
+    content_length: Option<u64>,
+}
+
+/// The state of the connection that is only used by the connection thread.
+struct Connection<'s, 'a> {
+    sender: &'s Sender<'a>,
+    limits: &'s Limits,
+    decoder: Decoder,
+    /// The highest stream identifier that the client has opened.
+    last_stream_id: u32,
+    receiving: HashMap<u32, Receiving>,
+}
+
+impl Connection<'_, '_> {
+    /// Read and handle frames until the connection is closed, passing each whole request to
+    /// the `dispatch` function.
+    fn run<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        dispatch: &mut impl FnMut(u32, Request),
+    ) -> io::Result<()> {
+        let mut preface = [0; PREFACE.len()];
+        reader.read_exact(&mut preface)?;
+        if &preface != PREFACE {
+            return self.close(Error::Connection(ErrorCode::PROTOCOL_ERROR));
+        }
+
+        // RFC9113 Section 3.4: the client connection preface ends with a SETTINGS frame
+        match Frame::read_from(reader, DEFAULT_MAX_FRAME_SIZE) {
+            Ok(frame @ Frame::Settings { ack: false, .. }) => {
+                if let Err(error) = self.handle(reader, frame, dispatch) {
+                    return self.close(error);
+                }
+            }
+            Ok(_) => return self.close(Error::Connection(ErrorCode::PROTOCOL_ERROR)),
+            Err(error) => return self.close(error),
+        }
+
+        loop {
+            // wait for the start of the next frame, the connection is only closed when idle
+            // if there are no streams still in progress
+            match reader.fill_buf() {
+                Ok([]) => return Ok(()),
+                Ok(_) => {}
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                    ) =>
+                {
+                    if self.sender.active_streams() == 0 {
+                        return self.close(Error::Connection(ErrorCode::NO_ERROR));
+                    }
+                    continue;
+                }
+                Err(error) => return Err(error),
+            }
+
+            let result = Frame::read_from(reader, DEFAULT_MAX_FRAME_SIZE)
+                .and_then(|frame| self.handle(reader, frame, dispatch));
+            match result {
+                Ok(()) => {}
+                Err(Error::Stream(stream_id, error)) => {
+                    self.receiving.remove(&stream_id);
+                    self.sender.reset(stream_id, error);
+                }
+                Err(error) => return self.close(error),
+            }
+        }
+    }
+
+    /// Close the connection, sending a GOAWAY frame for a connection error.
+    fn close(&self, error: Error) -> io::Result<()> {
+        let error = match error {
+            Error::Io(error) => return Err(error),
+            Error::Connection(error) => error,
+            Error::Stream(..) => ErrorCode::PROTOCOL_ERROR,
+        };
+        self.sender.write(&Frame::GoAway {
+            last_stream_id: self.last_stream_id,
+            error,
+        })
+    }
+
+    fn handle<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        frame: Frame,
+        dispatch: &mut impl FnMut(u32, Request),
+    ) -> Result<(), Error> {
+        match frame {
+            Frame::Data {
+                stream_id,
+                end_stream,
+                data,
+                size,
+            } => {
+                // RFC9113 Section 6.9: the received data is consumed straight away, so the
+                // flow-control windows are restored as soon as it arrives
+                if size > 0 {
+                    self.sender.write(&Frame::WindowUpdate {
+                        stream_id: 0,
+                        increment: size,
+                    })?;
+                }
+                self.receive_data(stream_id, end_stream, data, size, dispatch)
+            }
+            Frame::Headers {
+                stream_id,
+                end_stream,
+                end_headers,
+                mut block,
+            } => {
+                if !end_headers {
+                    self.read_continuation(reader, stream_id, &mut block)?;
+                }
+                self.receive_headers(stream_id, end_stream, &block, dispatch)
+            }
+            Frame::Priority { .. } | Frame::Unknown { .. } => Ok(()),
+            Frame::RstStream { stream_id, .. } => {
+                if stream_id > self.last_stream_id {
+                    return Err(Error::Connection(ErrorCode::PROTOCOL_ERROR));
+                }
+                self.receiving.remove(&stream_id);
+                self.sender.close_stream(stream_id);
+                Ok(())
+            }
+            Frame::Settings { ack: true, .. } => Ok(()),
+            Frame::Settings { ack: false, params } => {
+                self.sender.apply_settings(&params)?;
+                Ok(self.sender.write(&Frame::Settings {
+                    ack: true,
+                    params: Vec::new(),
+                })?)
+            }
+            // RFC9113 Section 8.4: a client cannot push a response
+            Frame::PushPromise { .. } => Err(Error::Connection(ErrorCode::PROTOCOL_ERROR)),
+            Frame::Ping { ack: true, .. } => Ok(()),
+            Frame::Ping { ack: false, data } => {
+                Ok(self.sender.write(&Frame::Ping { ack: true, data })?)
+            }
+            // the streams that are in progress are still completed
+            Frame::GoAway { .. } => Ok(()),
+            Frame::WindowUpdate {
+                stream_id,
+                increment,
+            } => {
+                if stream_id > self.last_stream_id {
+                    return Err(Error::Connection(ErrorCode::PROTOCOL_ERROR));
+                }
+                self.sender.window_update(stream_id, increment)
+            }
+            // RFC9113 Section 6.10: CONTINUATION must follow a HEADERS frame
+            Frame::Continuation { .. } => Err(Error::Connection(ErrorCode::PROTOCOL_ERROR)),
+        }
+    }
+
+    /// Read the CONTINUATION frames that complete a header block, no other frames are allowed
+    /// until the END_HEADERS flag is set.
+    fn read_continuation<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        stream_id: u32,
+        block: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        loop {
+            match Frame::read_from(reader, DEFAULT_MAX_FRAME_SIZE)? {
+                Frame::Continuation {
+                    stream_id: id,
+                    end_headers,
+                    block: fragment,
+                } if id == stream_id => {
+                    block.extend_from_slice(&fragment);
+                    // the compressed block cannot be much larger than the field section
+                    if block.len() > self.limits.header_block.saturating_mul(2) {
+                        return Err(Error::Connection(ErrorCode::ENHANCE_YOUR_CALM));
+                    }
+                    if end_headers {
+                        return Ok(());
+                    }
+                }
+                _ => return Err(Error::Connection(ErrorCode::PROTOCOL_ERROR)),
+            }
+        }
+    }
+
+    fn receive_headers(
+        &mut self,
+        stream_id: u32,
+        end_stream: bool,
+        block: &[u8],
+        dispatch: &mut impl FnMut(u32, Request),
+    ) -> Result<(), Error> {
+        // the header block must always be decoded to keep the dynamic table in sync
+        let fields = self
+            .decoder
+            .decode(block)
+            .map_err(|_| Error::Connection(ErrorCode::COMPRESSION_ERROR))?;
+
+        if self.receiving.contains_key(&stream_id) {
+            // RFC9113 Section 8.1: trailers must end the stream and have no pseudo-headers
+            if !end_stream || fields.iter().any(|field| field.name().starts_with(b":")) {
+                return Err(Error::Stream(stream_id, ErrorCode::PROTOCOL_ERROR));
+            }
+            return self.end_stream(stream_id, dispatch);
+        }
+
+        // RFC9113 Section 5.1.1: streams opened by the client use odd identifiers that
+        // increase
+        if stream_id.is_multiple_of(2) || stream_id <= self.last_stream_id {
+            return Err(Error::Connection(if stream_id.is_multiple_of(2) {
+                ErrorCode::PROTOCOL_ERROR
+            } else {
+                ErrorCode::STREAM_CLOSED
+            }));
+        }
+        self.last_stream_id = stream_id;
+        // a stream that has been reset is closed while its handler may still be running, so
+        // both are limited or resetting streams could start any number of handlers
+        if self.sender.active_streams() >= MAX_CONCURRENT_STREAMS as usize
+            || self.sender.handlers.load(Ordering::SeqCst) >= MAX_CONCURRENT_STREAMS as usize
+        {
+            return Err(Error::Stream(stream_id, ErrorCode::REFUSED_STREAM));
+        }
+
+        match request_head(fields, self.limits) {
+            Ok((line, headers, scheme)) => {
+                let content_length = content_length(&headers)
+                    .map_err(|_| Error::Stream(stream_id, ErrorCode::PROTOCOL_ERROR))?;
+                self.sender.open(stream_id);
+                self.receiving.insert(
+                    stream_id,
+                    Receiving {
+                        line,
+                        headers,
+                        scheme,
+                        body: Vec::new(),
+                        content_length,
+                    },
+                );
+                if end_stream {
+                    self.end_stream(stream_id, dispatch)?;
+                }
+                Ok(())
+            }
+            // RFC9113 Section 8.1.1: a malformed request is a stream error
+            Err(StatusCode::BAD_REQUEST) => {
+                Err(Error::Stream(stream_id, ErrorCode::PROTOCOL_ERROR))
+            }
+            Err(status) => {
+                self.sender.open(stream_id);
+                self.respond_early(stream_id, status, end_stream);
+                Ok(())
+            }
+        }
+    }
+
+    fn receive_data(
+        &mut self,
+        stream_id: u32,
+        end_stream: bool,
+        data: Vec<u8>,
+        size: u32,
+        dispatch: &mut impl FnMut(u32, Request),
+    ) -> Result<(), Error> {
+        let receiving = match self.receiving.get_mut(&stream_id) {
+            Some(receiving) => receiving,
+            None if stream_id > self.last_stream_id => {
+                return Err(Error::Connection(ErrorCode::PROTOCOL_ERROR));
+            }
+            None if self.sender.is_open(stream_id) => {
+                return Err(Error::Stream(stream_id, ErrorCode::STREAM_CLOSED));
+            }
+            // frames can still arrive shortly after the stream was reset
+            None => return Ok(()),
+        };
+
+        receiving.body.extend_from_slice(&data);
+        if matches!(receiving.content_length, Some(length) if receiving.body.len() as u64 > length)
+        {
+            return Err(Error::Stream(stream_id, ErrorCode::PROTOCOL_ERROR));
+        }
+        if receiving.body.len() > self.limits.body {
+            self.receiving.remove(&stream_id);
+            self.respond_early(stream_id, StatusCode::PAYLOAD_TOO_LARGE, end_stream);
+            return Ok(());
+        }
+
+        if end_stream {
+            self.end_stream(stream_id, dispatch)
+        } else {
+            if size > 0 {
+                self.sender.write(&Frame::WindowUpdate {
+                    stream_id,
+                    increment: size,
+                })?;
+            }
+            Ok(())
+        }
+    }
+
+    /// The whole request has been received, so it is passed on to be handled.
+    fn end_stream(
+        &mut self,
+        stream_id: u32,
+        dispatch: &mut impl FnMut(u32, Request),
+    ) -> Result<(), Error> {
+        if let Some(receiving) = self.receiving.remove(&stream_id) {
+            // RFC9113 Section 8.1.1: the content-length must match the length of the body
+            if matches!(receiving.content_length, Some(length) if length != receiving.body.len() as u64)
+            {
+                return Err(Error::Stream(stream_id, ErrorCode::PROTOCOL_ERROR));
+            }
+            let mut request =
+                Request::from_parts(receiving.line, receiving.headers, receiving.body);
+            request.set_scheme(receiving.scheme);
+            match request.decode_content(self.limits) {
+                Ok(()) => dispatch(stream_id, request),
+                Err(status) => self.respond_early(stream_id, status, true),
+            }
+        }
+        Ok(())
+    }
+
+    /// Respond with the status code before the whole request has been received, resetting the
+    /// stream if the client has not finished sending the request.
+    fn respond_early(&self, stream_id: u32, status: StatusCode, end_stream: bool) {
+        self.sender
+            .send_response(stream_id, Method::Get, Response::new(status));
+        if !end_stream {
+            self.sender.reset(stream_id, ErrorCode::NO_ERROR);
+        }
+    }
+}
+
+/// Create the [`RequestLine`], [`Headers`] and [`Scheme`] of a request from the fields of a
+/// header block, as defined in [RFC9113 Section
+/// 8.3](https://datatracker.ietf.org/doc/html/rfc9113#section-8.3).
+///
+/// Returns a [`StatusCode::BAD_REQUEST`] if the request is malformed, and the same status codes
+/// as a HTTP/1.1 request for exceeding the [`Limits`].
+fn request_head(
+    fields: Vec<Field>,
+    limits: &Limits,
+) -> Result<(RequestLine, Headers, Scheme), StatusCode> {
+    let (mut method, mut scheme, mut path, mut authority) = (None, None, None, None);
+    let mut headers = Headers::new();
+    let mut cookies = Vec::new();
+    let mut list_size = 0;
+
+    for field in fields {
+        // RFC9113 Section 6.5.2: the size of a field is its name and value plus 32 octets
+        list_size += field.size();
+        if list_size > limits.header_block {
+            return Err(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+        }
+
+        if let Some(pseudo) = field.name().strip_prefix(b":") {
+            // pseudo-header fields must come before any other field
+            if !headers.is_empty() || !cookies.is_empty() {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            let slot = match pseudo {
+                b"method" => &mut method,
+                b"scheme" => &mut scheme,
+                b"path" => &mut path,
+                b"authority" => &mut authority,
+                _ => return Err(StatusCode::BAD_REQUEST),
+            };
+            if slot.replace(field.value().to_vec()).is_some() {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            continue;
+        }
+
+        // field names must be lowercase
+        if field.name().iter().any(u8::is_ascii_uppercase) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        if field.name().len() + field.value().len() + 2 > limits.header_size {
+            return Err(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+        }
+        let field = HeaderField::try_from(field)?;
+        match field.name() {
+            "connection" | "keep-alive" | "proxy-connection" | "transfer-encoding" | "upgrade" => {
+                return Err(StatusCode::BAD_REQUEST)
+            }
+            "te" if field.value() != "trailers" => return Err(StatusCode::BAD_REQUEST),
+            // RFC9113 Section 8.2.3: the cookie may be split into many fields
+            "cookie" => {
+                cookies.push(field.value().to_string());
+                continue;
+            }
+            _ => {}
+        }
+        if headers.len() >= limits.header_count {
+            return Err(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+        }
+        headers.append(field);
+    }
+    if !cookies.is_empty() {
+        headers.append(HeaderField::new("cookie", cookies.join("; ")));
+    }
+
+    let method = Method::from_bytes(&method.ok_or(StatusCode::BAD_REQUEST)?)?;
+    let (scheme, path) = match (scheme, path) {
+        (Some(scheme), Some(path)) if !scheme.is_empty() && !path.is_empty() => {
+            (Scheme::from_bytes(&scheme)?, path)
+        }
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    if path.len() > limits.uri {
+        return Err(StatusCode::URI_TOO_LONG);
+    }
+    let uri = Uri::from_bytes(&path)?;
+
+    // RFC9113 Section 8.3.1: the :authority is used as the Host when it is missing
+    if let Some(authority) = authority {
+        if !headers.contains("host") {
+            let authority = String::from_utf8(authority).map_err(|_| StatusCode::BAD_REQUEST)?;
+            headers.insert("host", authority);
+        }
+    }
+    validate_host(&headers, Version::HTTP_2_0)?;
+
+    Ok((
+        RequestLine::new(method, uri, Version::HTTP_2_0),
+        headers,
+        scheme,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{SocketAddr, TcpStream},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        h2::{
+            frame::{Error, Frame},
+            ErrorCode, PREFACE,
+        },
+        hpack::{Decoder, Encoder, Field},
+        http::{Limits, Request, Response, StatusCode},
+        server::Server,
+    };
+
+    use super::request_head;
+
+    fn spawn() -> SocketAddr {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || {
+            server.serve(|request: &Request| {
+                Response::new(StatusCode::OK)
+                    .with_header("Connection", "close")
+                    .with_body(request.body().to_vec())
+            })
+        });
+        addr
+    }
+
+    fn connect(addr: SocketAddr) -> TcpStream {
+        let stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream
+    }
+
+    fn headers(stream_id: u32, end_stream: bool, fields: &[(&str, &str)]) -> Frame {
+        let mut block = Vec::new();
+        Encoder::new(4096).encode(
+            fields.iter().map(|(name, value)| Field::new(*name, *value)),
+            &mut block,
+        );
+        Frame::Headers {
+            stream_id,
+            end_stream,
+            end_headers: true,
+            block,
+        }
+    }
+
+    fn write(stream: &mut TcpStream, frames: &[Frame]) {
+        let mut bytes = Vec::new();
+        for frame in frames {
+            frame.write_to(&mut bytes).unwrap();
+        }
+        stream.write_all(&bytes).unwrap();
+    }
+
+    /// Read frames until the stream has ended, returning the status, body and other frames.
+    fn read_response<R: Read>(
+        reader: &mut R,
+        decoder: &mut Decoder,
+        stream_id: u32,
+    ) -> (String, Vec<u8>, Vec<Frame>) {
+        let mut status = String::new();
+        let mut body = Vec::new();
+        let mut others = Vec::new();
+        loop {
+            match Frame::read_from(reader, 16_384) {
+                Ok(Frame::Headers {
+                    stream_id: id,
+                    end_stream,
+                    block,
+                    ..
+                }) if id == stream_id => {
+                    let fields = decoder.decode(&block).unwrap();
+                    status = String::from_utf8(fields[0].value().to_vec()).unwrap();
+                    if end_stream {
+                        break;
+                    }
+                }
+                Ok(Frame::Data {
+                    stream_id: id,
+                    end_stream,
+                    data,
+                    ..
+                }) if id == stream_id => {
+                    body.extend_from_slice(&data);
+                    if end_stream {
+                        break;
+                    }
+                }
+                Ok(frame @ Frame::RstStream { .. }) | Ok(frame @ Frame::GoAway { .. }) => {
+                    others.push(frame);
+                    break;
+                }
+                Ok(frame) => others.push(frame),
+                Err(Error::Io(error)) => panic!("failed to read frame: {}", error),
+                Err(error) => panic!("invalid frame: {:?}", error),
+            }
+        }
+        (status, body, others)
+    }
+
+    fn start(stream: &mut TcpStream) {
+        stream.write_all(PREFACE).unwrap();
+        write(
+            stream,
+            &[Frame::Settings {
+                ack: false,
+                params: Vec::new(),
+            }],
+        );
+    }
+
+    #[test]
+    fn request_with_prior_knowledge_is_handled() {
+        let mut stream = connect(spawn());
+        start(&mut stream);
+        write(
+            &mut stream,
+            &[headers(
+                1,
+                true,
+                &[
+                    (":method", "GET"),
+                    (":scheme", "http"),
+                    (":path", "/"),
+                    (":authority", "example.com"),
+                ],
+            )],
+        );
+        let (status, _, others) = read_response(&mut stream, &mut Decoder::new(4096), 1);
+        assert_eq!("200", status);
+        assert!(others
+            .iter()
+            .any(|frame| matches!(frame, Frame::Settings { ack: false, .. })));
+        assert!(others
+            .iter()
+            .any(|frame| matches!(frame, Frame::Settings { ack: true, .. })));
+    }
+
+    #[test]
+    fn request_body_is_received_from_data_frames() {
+        let mut stream = connect(spawn());
+        start(&mut stream);
+        write(
+            &mut stream,
+            &[
+                headers(
+                    1,
+                    false,
+                    &[
+                        (":method", "POST"),
+                        (":scheme", "http"),
+                        (":path", "/"),
+                        (":authority", "a"),
+                    ],
+                ),
+                Frame::Data {
+                    stream_id: 1,
+                    end_stream: false,
+                    data: b"ping ".to_vec(),
+                    size: 5,
+                },
+                Frame::Data {
+                    stream_id: 1,
+                    end_stream: true,
+                    data: b"pong".to_vec(),
+                    size: 4,
+                },
+            ],
+        );
+        let (status, body, _) = read_response(&mut stream, &mut Decoder::new(4096), 1);
+        assert_eq!("200", status);
+        assert_eq!(b"ping pong".to_vec(), body);
+    }
+
+    #[test]
+    fn streams_are_multiplexed() {
+        let mut stream = connect(spawn());
+        start(&mut stream);
+        let fields = [
+            (":method", "GET"),
+            (":scheme", "http"),
+            (":path", "/"),
+            (":authority", "a"),
+        ];
+        write(
+            &mut stream,
+            &[headers(1, true, &fields), headers(3, true, &fields)],
+        );
+        let mut decoder = Decoder::new(4096);
+        let (first, _, _) = read_response(&mut stream, &mut decoder, 1);
+        let (second, _, _) = read_response(&mut stream, &mut decoder, 3);
+        assert_eq!("200", first);
+        assert_eq!("200", second);
+    }
+
+    #[test]
+    fn reset_streams_count_until_their_handler_returns() {
+        let (running, most) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handler = {
+            let (running, most) = (running.clone(), most.clone());
+            move |_: &Request| {
+                most.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(200));
+                running.fetch_sub(1, Ordering::SeqCst);
+                Response::new(StatusCode::OK)
+            }
+        };
+        thread::spawn(move || server.serve(handler));
+
+        let mut stream = connect(addr);
+        start(&mut stream);
+        let fields = [
+            (":method", "GET"),
+            (":scheme", "http"),
+            (":path", "/"),
+            (":authority", "a"),
+        ];
+        for stream_id in (1..600).step_by(2) {
+            write(
+                &mut stream,
+                &[
+                    headers(stream_id, true, &fields),
+                    Frame::RstStream {
+                        stream_id,
+                        error: ErrorCode::CANCEL,
+                    },
+                ],
+            );
+        }
+        let mut refused = false;
+        while !refused {
+            refused = matches!(
+                Frame::read_from(&mut stream, 16_384).unwrap(),
+                Frame::RstStream {
+                    error: ErrorCode::REFUSED_STREAM,
+                    ..
+                }
+            );
+        }
+        thread::sleep(Duration::from_millis(300));
+        assert!((1..=100).contains(&most.load(Ordering::SeqCst)));
+    }
+
+    #[test]
+    fn ping_is_acknowledged() {
+        let mut stream = connect(spawn());
+        start(&mut stream);
+        write(
+            &mut stream,
+            &[Frame::Ping {
+                ack: false,
+                data: *b"12345678",
+            }],
+        );
+        loop {
+            match Frame::read_from(&mut stream, 16_384).unwrap() {
+                Frame::Ping { ack, data } => {
+                    assert!(ack);
+                    assert_eq!(*b"12345678", data);
+                    break;
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    #[test]
+    fn missing_pseudo_header_resets_stream() {
+        let mut stream = connect(spawn());
+        start(&mut stream);
+        write(
+            &mut stream,
+            &[headers(1, true, &[(":method", "GET"), (":scheme", "http")])],
+        );
+        let (_, _, others) = read_response(&mut stream, &mut Decoder::new(4096), 1);
+        assert_eq!(
+            Some(&Frame::RstStream {
+                stream_id: 1,
+                error: ErrorCode::PROTOCOL_ERROR
+            }),
+            others.last()
+        );
+    }
+
+    #[test]
+    fn even_stream_identifier_is_a_connection_error() {
+        let mut stream = connect(spawn());
+        start(&mut stream);
+        write(
+            &mut stream,
+            &[headers(
+                2,
+                true,
+                &[(":method", "GET"), (":scheme", "http"), (":path", "/")],
+            )],
+        );
+        let (_, _, others) = read_response(&mut stream, &mut Decoder::new(4096), 2);
+        assert_eq!(
+            Some(&Frame::GoAway {
+                last_stream_id: 0,
+                error: ErrorCode::PROTOCOL_ERROR
+            }),
+            others.last()
+        );
+    }
+
+    #[test]
+    fn http_1_1_request_is_upgraded_to_h2c() {
+        let mut stream = connect(spawn());
+        stream
+            .write_all(
+                b"GET / HTTP/1.1\r\nHost: upgraded\r\nConnection: Upgrade, HTTP2-Settings\r\n\
+                  Upgrade: h2c\r\nHTTP2-Settings: \r\n\r\n",
+            )
+            .unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut head = String::new();
+        while !head.ends_with("\r\n\r\n") {
+            reader.read_line(&mut head).unwrap();
+        }
+        assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(head.contains("upgrade: h2c\r\n"));
+
+        start(&mut stream);
+        let (status, _, _) = read_response(&mut reader, &mut Decoder::new(4096), 1);
+        assert_eq!("200", status);
+    }
+
+    #[test]
+    fn connection_specific_fields_are_malformed() {
+        let fields = |extra: (&str, &str)| {
+            vec![
+                Field::new(":method", "GET"),
+                Field::new(":scheme", "http"),
+                Field::new(":path", "/"),
+                Field::new(":authority", "a"),
+                Field::new(extra.0, extra.1),
+            ]
+        };
+        let limits = Limits::default();
+        assert!(request_head(fields(("te", "trailers")), &limits).is_ok());
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            request_head(fields(("te", "gzip")), &limits).map(|_| ())
+        );
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            request_head(fields(("connection", "close")), &limits).map(|_| ())
+        );
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            request_head(fields(("X-Upper", "a")), &limits).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn cookie_fields_are_joined() {
+        let fields = vec![
+            Field::new(":method", "GET"),
+            Field::new(":scheme", "http"),
+            Field::new(":path", "/"),
+            Field::new(":authority", "a"),
+            Field::new("cookie", "a=1"),
+            Field::new("cookie", "b=2"),
+        ];
+        let (_, headers, _) = request_head(fields, &Limits::default()).unwrap();
+        assert_eq!(Some("a=1; b=2"), headers.get("cookie"));
+        assert_eq!(Some("a"), headers.get("host"));
+    }
+}
@@ -0,0 +1,95 @@
+/// A HTTP/2 error code as listed in [RFC9113 Section
+/// 7](https://datatracker.ietf.org/doc/html/rfc9113#section-7).
+///
+/// Error codes are sent in RST_STREAM and GOAWAY frames to give the reason for a stream or
+/// connection error.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ErrorCode(pub(crate) u32);
+
+impl ErrorCode {
+    /// Returns the error code as a number.
+    ///
+    /// ```
+    /// use ramus::h2::ErrorCode;
+    ///
+    /// assert_eq!(0x7, ErrorCode::REFUSED_STREAM.as_u32());
+    /// ```
+    pub const fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+macro_rules! const_error_codes {
+    (
+        $(
+            $(#[$comment:meta])+
+            $name:ident => $code:literal,
+        )*
+    ) => {
+        impl ErrorCode {
+            $(
+                $(#[$comment])+
+                pub const $name: ErrorCode = ErrorCode($code);
+            )*
+
+            /// Returns the name of the error code, unknown error codes are named
+            /// "UNKNOWN_ERROR".
+            pub const fn name(&self) -> &'static str {
+                match self.0 {
+                    $(
+                        $code => stringify!($name),
+                    )*
+                    // RFC9113 Section 7: unknown error codes must not trigger any special
+                    // behavior and may be treated as INTERNAL_ERROR
+                    _ => "UNKNOWN_ERROR"
+                }
+            }
+        }
+    }
+}
+
+const_error_codes! {
+    /// The associated condition is not a result of an error.
+    NO_ERROR => 0x0,
+    /// The endpoint detected an unspecific protocol error.
+    PROTOCOL_ERROR => 0x1,
+    /// The endpoint encountered an unexpected internal error.
+    INTERNAL_ERROR => 0x2,
+    /// The endpoint detected that its peer violated the flow-control protocol.
+    FLOW_CONTROL_ERROR => 0x3,
+    /// The endpoint sent a SETTINGS frame but did not receive a response in a timely manner.
+    SETTINGS_TIMEOUT => 0x4,
+    /// The endpoint received a frame after a stream was half-closed.
+    STREAM_CLOSED => 0x5,
+    /// The endpoint received a frame with an invalid size.
+    FRAME_SIZE_ERROR => 0x6,
+    /// The endpoint refused the stream prior to performing any application processing.
+    REFUSED_STREAM => 0x7,
+    /// The endpoint uses this error code to indicate that the stream is no longer needed.
+    CANCEL => 0x8,
+    /// The endpoint is unable to maintain the field section compression context for the
+    /// connection.
+    COMPRESSION_ERROR => 0x9,
+    /// The connection established in response to a CONNECT request was reset or abnormally
+    /// closed.
+    CONNECT_ERROR => 0xa,
+    /// The endpoint detected that its peer is exhibiting a behavior that might be generating
+    /// excessive load.
+    ENHANCE_YOUR_CALM => 0xb,
+    /// The underlying transport has properties that do not meet minimum security requirements.
+    INADEQUATE_SECURITY => 0xc,
+    /// The endpoint requires that HTTP/1.1 be used instead of HTTP/2.
+    HTTP_1_1_REQUIRED => 0xd,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorCode;
+
+    #[test]
+    fn error_codes_are_named() {
+        assert_eq!("NO_ERROR", ErrorCode::NO_ERROR.name());
+        assert_eq!("HTTP_1_1_REQUIRED", ErrorCode::HTTP_1_1_REQUIRED.name());
+        assert_eq!("UNKNOWN_ERROR", ErrorCode(0xff).name());
+    }
+}
@@ -0,0 +1,566 @@
+//! Crate Module:
+//! HTTP/2 frames as defined in [RFC9113 Section 4](https://datatracker.ietf.org/doc/html/rfc9113#section-4)
+//! and [RFC9113 Section 6](https://datatracker.ietf.org/doc/html/rfc9113#section-6).
+//!
+//! ```text
+//! HTTP Frame {
+//!   Length (24),
+//!   Type (8),
+//!   Flags (8),
+//!   Reserved (1),
+//!   Stream Identifier (31),
+//!   Frame Payload (..),
+//! }
+//! ```
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+use super::ErrorCode;
+
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const PRIORITY: u8 = 0x2;
+const RST_STREAM: u8 = 0x3;
+const SETTINGS: u8 = 0x4;
+const PUSH_PROMISE: u8 = 0x5;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+const WINDOW_UPDATE: u8 = 0x8;
+const CONTINUATION: u8 = 0x9;
+
+const END_STREAM: u8 = 0x1;
+const ACK: u8 = 0x1;
+const END_HEADERS: u8 = 0x4;
+const PADDED: u8 = 0x8;
+const PRIORITY_FLAG: u8 = 0x20;
+
+/// The number of octets in the frame header.
+const HEADER_LEN: usize = 9;
+
+/// The identifiers of the settings parameters as defined in [RFC9113 Section
+/// 6.5.2](https://datatracker.ietf.org/doc/html/rfc9113#section-6.5.2).
+pub(crate) mod setting {
+    pub(crate) const HEADER_TABLE_SIZE: u16 = 0x1;
+    pub(crate) const ENABLE_PUSH: u16 = 0x2;
+    pub(crate) const MAX_CONCURRENT_STREAMS: u16 = 0x3;
+    pub(crate) const INITIAL_WINDOW_SIZE: u16 = 0x4;
+    pub(crate) const MAX_FRAME_SIZE: u16 = 0x5;
+    pub(crate) const MAX_HEADER_LIST_SIZE: u16 = 0x6;
+}
+
+/// An error while reading or handling frames.
+#[derive(Debug)]
+pub(crate) enum Error {
+    /// The underlying connection failed.
+    Io(io::Error),
+    /// A connection error, which is sent in a GOAWAY frame before closing the connection.
+    Connection(ErrorCode),
+    /// A stream error, which is sent in a RST_STREAM frame for the stream.
+    Stream(u32, ErrorCode),
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// A HTTP/2 frame.
+///
+/// The padding and priority fields are removed when a frame is read and are never written.
+#[derive(PartialEq)]
+pub(crate) enum Frame {
+    Data {
+        stream_id: u32,
+        end_stream: bool,
+        data: Vec<u8>,
+        /// The size of the whole payload including any padding, which is counted for flow
+        /// control.
+        size: u32,
+    },
+    Headers {
+        stream_id: u32,
+        end_stream: bool,
+        end_headers: bool,
+        block: Vec<u8>,
+    },
+    Priority {
+        stream_id: u32,
+    },
+    RstStream {
+        stream_id: u32,
+        error: ErrorCode,
+    },
+    Settings {
+        ack: bool,
+        params: Vec<(u16, u32)>,
+    },
+    PushPromise {
+        stream_id: u32,
+    },
+    Ping {
+        ack: bool,
+        data: [u8; 8],
+    },
+    GoAway {
+        last_stream_id: u32,
+        error: ErrorCode,
+    },
+    WindowUpdate {
+        stream_id: u32,
+        increment: u32,
+    },
+    Continuation {
+        stream_id: u32,
+        end_headers: bool,
+        block: Vec<u8>,
+    },
+    /// A frame of an unknown type, which must be ignored.
+    Unknown {
+        stream_id: u32,
+    },
+}
+
+impl fmt::Debug for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Data {
+                stream_id,
+                end_stream,
+                data,
+                ..
+            } => f
+                .debug_struct("Data")
+                .field("stream_id", stream_id)
+                .field("end_stream", end_stream)
+                .field("len", &data.len())
+                .finish(),
+            Self::Headers {
+                stream_id,
+                end_stream,
+                end_headers,
+                ..
+            } => f
+                .debug_struct("Headers")
+                .field("stream_id", stream_id)
+                .field("end_stream", end_stream)
+                .field("end_headers", end_headers)
+                .finish_non_exhaustive(),
+            Self::Priority { stream_id } => f
+                .debug_struct("Priority")
+                .field("stream_id", stream_id)
+                .finish(),
+            Self::RstStream { stream_id, error } => f
+                .debug_struct("RstStream")
+                .field("stream_id", stream_id)
+                .field("error", error)
+                .finish(),
+            Self::Settings { ack, params } => f
+                .debug_struct("Settings")
+                .field("ack", ack)
+                .field("params", params)
+                .finish(),
+            Self::PushPromise { stream_id } => f
+                .debug_struct("PushPromise")
+                .field("stream_id", stream_id)
+                .finish(),
+            Self::Ping { ack, data } => f
+                .debug_struct("Ping")
+                .field("ack", ack)
+                .field("data", data)
+                .finish(),
+            Self::GoAway {
+                last_stream_id,
+                error,
+            } => f
+                .debug_struct("GoAway")
+                .field("last_stream_id", last_stream_id)
+                .field("error", error)
+                .finish(),
+            Self::WindowUpdate {
+                stream_id,
+                increment,
+            } => f
+                .debug_struct("WindowUpdate")
+                .field("stream_id", stream_id)
+                .field("increment", increment)
+                .finish(),
+            Self::Continuation {
+                stream_id,
+                end_headers,
+                ..
+            } => f
+                .debug_struct("Continuation")
+                .field("stream_id", stream_id)
+                .field("end_headers", end_headers)
+                .finish_non_exhaustive(),
+            Self::Unknown { stream_id } => f
+                .debug_struct("Unknown")
+                .field("stream_id", stream_id)
+                .finish(),
+        }
+    }
+}
+
+impl Frame {
+    /// Read a single frame from the reader.
+    ///
+    /// Returns a [`Error::Connection`] with a [`ErrorCode::FRAME_SIZE_ERROR`] when the frame is
+    /// larger than the `max_frame_size` or the size is not valid for the frame type, and a
+    /// [`ErrorCode::PROTOCOL_ERROR`] when the stream identifier or padding is not valid for the
+    /// frame type.
+    pub(crate) fn read_from<R: Read>(reader: &mut R, max_frame_size: u32) -> Result<Self, Error> {
+        let mut header = [0; HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([0, header[0], header[1], header[2]]);
+        let kind = header[3];
+        let flags = header[4];
+        let stream_id =
+            u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & !(1 << 31);
+
+        if length > max_frame_size {
+            return Err(Error::Connection(ErrorCode::FRAME_SIZE_ERROR));
+        }
+        let mut payload = vec![0; length as usize];
+        reader.read_exact(&mut payload)?;
+
+        let protocol_error = Error::Connection(ErrorCode::PROTOCOL_ERROR);
+        let frame_size_error = Error::Connection(ErrorCode::FRAME_SIZE_ERROR);
+        let frame = match kind {
+            DATA if stream_id == 0 => return Err(protocol_error),
+            DATA => Self::Data {
+                stream_id,
+                end_stream: flags & END_STREAM != 0,
+                data: strip_padding(payload, flags)?,
+                size: length,
+            },
+            HEADERS if stream_id == 0 => return Err(protocol_error),
+            HEADERS => {
+                let mut block = strip_padding(payload, flags)?;
+                if flags & PRIORITY_FLAG != 0 {
+                    if block.len() < 5 {
+                        return Err(frame_size_error);
+                    }
+                    if dependency(&block) == stream_id {
+                        return Err(Error::Stream(stream_id, ErrorCode::PROTOCOL_ERROR));
+                    }
+                    block.drain(..5);
+                }
+                Self::Headers {
+                    stream_id,
+                    end_stream: flags & END_STREAM != 0,
+                    end_headers: flags & END_HEADERS != 0,
+                    block,
+                }
+            }
+            PRIORITY if stream_id == 0 => return Err(protocol_error),
+            PRIORITY if length != 5 => {
+                return Err(Error::Stream(stream_id, ErrorCode::FRAME_SIZE_ERROR))
+            }
+            PRIORITY if dependency(&payload) == stream_id => {
+                return Err(Error::Stream(stream_id, ErrorCode::PROTOCOL_ERROR))
+            }
+            PRIORITY => Self::Priority { stream_id },
+            RST_STREAM if stream_id == 0 => return Err(protocol_error),
+            RST_STREAM if length != 4 => return Err(frame_size_error),
+            RST_STREAM => Self::RstStream {
+                stream_id,
+                error: ErrorCode(read_u32(&payload)),
+            },
+            SETTINGS if stream_id != 0 => return Err(protocol_error),
+            SETTINGS if flags & ACK != 0 && length != 0 => return Err(frame_size_error),
+            SETTINGS if !length.is_multiple_of(6) => return Err(frame_size_error),
+            SETTINGS => Self::Settings {
+                ack: flags & ACK != 0,
+                params: parse_settings(&payload).ok_or(frame_size_error)?,
+            },
+            PUSH_PROMISE => Self::PushPromise { stream_id },
+            PING if stream_id != 0 => return Err(protocol_error),
+            PING if length != 8 => return Err(frame_size_error),
+            PING => {
+                let mut data = [0; 8];
+                data.copy_from_slice(&payload);
+                Self::Ping {
+                    ack: flags & ACK != 0,
+                    data,
+                }
+            }
+            GOAWAY if stream_id != 0 => return Err(protocol_error),
+            GOAWAY if length < 8 => return Err(frame_size_error),
+            GOAWAY => Self::GoAway {
+                last_stream_id: read_u32(&payload) & !(1 << 31),
+                error: ErrorCode(read_u32(&payload[4..])),
+            },
+            WINDOW_UPDATE if length != 4 => return Err(frame_size_error),
+            WINDOW_UPDATE => match read_u32(&payload) & !(1 << 31) {
+                0 if stream_id == 0 => return Err(protocol_error),
+                0 => return Err(Error::Stream(stream_id, ErrorCode::PROTOCOL_ERROR)),
+                increment => Self::WindowUpdate {
+                    stream_id,
+                    increment,
+                },
+            },
+            CONTINUATION if stream_id == 0 => return Err(protocol_error),
+            CONTINUATION => Self::Continuation {
+                stream_id,
+                end_headers: flags & END_HEADERS != 0,
+                block: payload,
+            },
+            _ => Self::Unknown { stream_id },
+        };
+        Ok(frame)
+    }
+
+    /// Write the frame to the writer.
+    ///
+    /// The caller must make sure that the payload is not larger than the maximum frame size of
+    /// the peer.
+    pub(crate) fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let flag = |set: bool, flag: u8| if set { flag } else { 0 };
+        let (kind, flags, stream_id, payload) = match self {
+            Self::Data {
+                stream_id,
+                end_stream,
+                data,
+                ..
+            } => (
+                DATA,
+                flag(*end_stream, END_STREAM),
+                *stream_id,
+                data.clone(),
+            ),
+            Self::Headers {
+                stream_id,
+                end_stream,
+                end_headers,
+                block,
+            } => (
+                HEADERS,
+                flag(*end_stream, END_STREAM) | flag(*end_headers, END_HEADERS),
+                *stream_id,
+                block.clone(),
+            ),
+            Self::Priority { stream_id } => (PRIORITY, 0, *stream_id, vec![0; 5]),
+            Self::RstStream { stream_id, error } => {
+                (RST_STREAM, 0, *stream_id, error.0.to_be_bytes().to_vec())
+            }
+            Self::Settings { ack, params } => {
+                let payload = params
+                    .iter()
+                    .flat_map(|(id, value)| id.to_be_bytes().into_iter().chain(value.to_be_bytes()))
+                    .collect();
+                (SETTINGS, flag(*ack, ACK), 0, payload)
+            }
+            Self::PushPromise { stream_id } => (PUSH_PROMISE, 0, *stream_id, Vec::new()),
+            Self::Ping { ack, data } => (PING, flag(*ack, ACK), 0, data.to_vec()),
+            Self::GoAway {
+                last_stream_id,
+                error,
+            } => {
+                let mut payload = last_stream_id.to_be_bytes().to_vec();
+                payload.extend_from_slice(&error.0.to_be_bytes());
+                (GOAWAY, 0, 0, payload)
+            }
+            Self::WindowUpdate {
+                stream_id,
+                increment,
+            } => (
+                WINDOW_UPDATE,
+                0,
+                *stream_id,
+                increment.to_be_bytes().to_vec(),
+            ),
+            Self::Continuation {
+                stream_id,
+                end_headers,
+                block,
+            } => (
+                CONTINUATION,
+                flag(*end_headers, END_HEADERS),
+                *stream_id,
+                block.clone(),
+            ),
+            Self::Unknown { stream_id } => (0xff, 0, *stream_id, Vec::new()),
+        };
+
+        let length = (payload.len() as u32).to_be_bytes();
+        writer.write_all(&length[1..])?;
+        writer.write_all(&[kind, flags])?;
+        writer.write_all(&stream_id.to_be_bytes())?;
+        writer.write_all(&payload)
+    }
+}
+
+/// Parse the payload of a SETTINGS frame into a list of identifier and value pairs.
+///
+/// ```text
+/// Setting {
+///   Identifier (16),
+///   Value (32),
+/// }
+/// ```
+pub(crate) fn parse_settings(payload: &[u8]) -> Option<Vec<(u16, u32)>> {
+    if !payload.len().is_multiple_of(6) {
+        return None;
+    }
+    Some(
+        payload
+            .chunks(6)
+            .map(|param| {
+                (
+                    u16::from_be_bytes([param[0], param[1]]),
+                    read_u32(&param[2..]),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Remove the padding from the payload when the PADDED flag is set.
+///
+/// The length of the padding must be less than the length of the payload, otherwise it is a
+/// connection error of type PROTOCOL_ERROR.
+fn strip_padding(mut payload: Vec<u8>, flags: u8) -> Result<Vec<u8>, Error> {
+    if flags & PADDED == 0 {
+        return Ok(payload);
+    }
+    let pad_length = *payload
+        .first()
+        .ok_or(Error::Connection(ErrorCode::PROTOCOL_ERROR))? as usize;
+    if pad_length >= payload.len() {
+        return Err(Error::Connection(ErrorCode::PROTOCOL_ERROR));
+    }
+    payload.truncate(payload.len() - pad_length);
+    payload.remove(0);
+    Ok(payload)
+}
+
+/// Returns the stream dependency from the start of priority fields.
+fn dependency(payload: &[u8]) -> u32 {
+    read_u32(payload) & !(1 << 31)
+}
+
+fn read_u32(src: &[u8]) -> u32 {
+    u32::from_be_bytes([src[0], src[1], src[2], src[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, ErrorCode, Frame};
+
+    fn round_trip(frame: Frame) -> Frame {
+        let mut bytes = Vec::new();
+        frame.write_to(&mut bytes).unwrap();
+        Frame::read_from(&mut bytes.as_slice(), 16_384).unwrap()
+    }
+
+    fn read(bytes: &[u8]) -> Result<Frame, Error> {
+        Frame::read_from(&mut &bytes[..], 16_384)
+    }
+
+    fn assert_connection_error(code: ErrorCode, result: Result<Frame, Error>) {
+        match result {
+            Err(Error::Connection(error)) => assert_eq!(code, error),
+            other => panic!("expected connection error {:?} but got {:?}", code, other),
+        }
+    }
+
+    #[test]
+    fn frames_can_be_written_and_read() {
+        let frames = [
+            Frame::Data {
+                stream_id: 1,
+                end_stream: true,
+                data: b"hello".to_vec(),
+                size: 5,
+            },
+            Frame::Headers {
+                stream_id: 3,
+                end_stream: false,
+                end_headers: true,
+                block: vec![0x82],
+            },
+            Frame::RstStream {
+                stream_id: 5,
+                error: ErrorCode::CANCEL,
+            },
+            Frame::Settings {
+                ack: false,
+                params: vec![(0x3, 100), (0x4, 1)],
+            },
+            Frame::Ping {
+                ack: true,
+                data: [1, 2, 3, 4, 5, 6, 7, 8],
+            },
+            Frame::GoAway {
+                last_stream_id: 7,
+                error: ErrorCode::NO_ERROR,
+            },
+            Frame::WindowUpdate {
+                stream_id: 0,
+                increment: 1024,
+            },
+            Frame::Continuation {
+                stream_id: 3,
+                end_headers: true,
+                block: vec![0x84],
+            },
+        ];
+        for frame in frames {
+            let debug = format!("{:?}", frame);
+            assert_eq!(debug, format!("{:?}", round_trip(frame)));
+        }
+    }
+
+    #[test]
+    fn padding_and_priority_are_removed() {
+        // HEADERS with PADDED and PRIORITY flags, 2 octets of padding
+        let bytes = [
+            0, 0, 9, 0x1, 0x2c, 0, 0, 0, 1, 2, 0, 0, 0, 0, 16, 0x82, 0, 0,
+        ];
+        assert_eq!(
+            Frame::Headers {
+                stream_id: 1,
+                end_stream: false,
+                end_headers: true,
+                block: vec![0x82],
+            },
+            read(&bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn padding_longer_than_payload_is_a_protocol_error() {
+        let bytes = [0, 0, 2, 0x0, 0x8, 0, 0, 0, 1, 2, 0];
+        assert_connection_error(ErrorCode::PROTOCOL_ERROR, read(&bytes));
+    }
+
+    #[test]
+    fn frame_larger_than_max_frame_size_is_a_frame_size_error() {
+        let bytes = [0, 0x40, 1, 0x0, 0, 0, 0, 0, 1];
+        assert_connection_error(ErrorCode::FRAME_SIZE_ERROR, read(&bytes));
+    }
+
+    #[test]
+    fn settings_on_a_stream_is_a_protocol_error() {
+        let bytes = [0, 0, 0, 0x4, 0, 0, 0, 0, 1];
+        assert_connection_error(ErrorCode::PROTOCOL_ERROR, read(&bytes));
+    }
+
+    #[test]
+    fn ping_with_wrong_length_is_a_frame_size_error() {
+        let bytes = [0, 0, 1, 0x6, 0, 0, 0, 0, 0, 0];
+        assert_connection_error(ErrorCode::FRAME_SIZE_ERROR, read(&bytes));
+    }
+
+    #[test]
+    fn zero_window_update_on_stream_is_a_stream_error() {
+        let bytes = [0, 0, 4, 0x8, 0, 0, 0, 0, 1, 0, 0, 0, 0];
+        assert!(matches!(
+            read(&bytes),
+            Err(Error::Stream(1, ErrorCode::PROTOCOL_ERROR))
+        ));
+    }
+}
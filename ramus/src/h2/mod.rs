@@ -0,0 +1,134 @@
+//! HTTP/2 as defined in [RFC9113](https://datatracker.ietf.org/doc/html/rfc9113).
+//!
+//! The [`Server`](crate::server::Server) starts a HTTP/2 connection over cleartext TCP ("h2c")
+//! in two ways:
+//! - With prior knowledge, when the client starts the connection with the connection preface,
+//!   as defined in [RFC9113 Section
+//!   3.3](https://datatracker.ietf.org/doc/html/rfc9113#section-3.3).
+//! - With an upgrade from a HTTP/1.1 request that has the `Upgrade: h2c` and `HTTP2-Settings`
+//!   header fields, as defined in [RFC7540 Section
+//!   3.2](https://datatracker.ietf.org/doc/html/rfc7540#section-3.2). The upgraded request is
+//!   answered on stream 1.
+//!
+//! With the `tls` feature, a TLS connection uses HTTP/2 when the client selects "h2" with ALPN,
+//! as defined in [RFC9113 Section 3.2](https://datatracker.ietf.org/doc/html/rfc9113#section-3.2).
+//!
+//! Each stream is handled on its own thread using the same [`Handler`](crate::server::Handler)
+//! as HTTP/1.x requests, so a handler does not need to know which version was used.
+mod connection;
+mod error_code;
+mod frame;
+
+pub use error_code::*;
+
+pub(crate) use connection::{serve, Upgrade};
+
+use crate::http::{
+    utils::base64::{self, Alphabet},
+    Request, Version,
+};
+
+/// The client connection preface as defined in [RFC9113 Section
+/// 3.4](https://datatracker.ietf.org/doc/html/rfc9113#section-3.4).
+///
+/// ```text
+/// PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n
+/// ```
+pub const PREFACE: &[u8; 24] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Returns true if the bytes could be the start of the client connection preface.
+///
+/// At least the method "PRI" must have been received, which is not a method that any HTTP/1.x
+/// request will use.
+pub(crate) fn is_preface(src: &[u8]) -> bool {
+    let len = src.len().min(PREFACE.len());
+    len >= 3 && src[..len] == PREFACE[..len]
+}
+
+/// Returns the settings from the `HTTP2-Settings` header field when the request asks to be
+/// upgraded to HTTP/2, as defined in [RFC7540 Section
+/// 3.2](https://datatracker.ietf.org/doc/html/rfc7540#section-3.2).
+///
+/// ```text
+/// HTTP2-Settings    = token68
+/// ```
+///
+/// Returns `None` if the request is not a HTTP/1.1 request with the "h2c" upgrade protocol, or
+/// if it does not have exactly one valid `HTTP2-Settings` header field that is also a
+/// connection option, in which case the request is handled as HTTP/1.1.
+pub(crate) fn upgrade_settings(request: &Request) -> Option<Vec<(u16, u32)>> {
+    let has_option = |name, option: &str| {
+        request
+            .headers()
+            .get_all(name)
+            .flat_map(|value| value.split(','))
+            .any(|value| value.trim().eq_ignore_ascii_case(option))
+    };
+    if request.version() != Version::HTTP_1_1
+        || !has_option("upgrade", "h2c")
+        || !has_option("connection", "upgrade")
+        || !has_option("connection", "http2-settings")
+    {
+        return None;
+    }
+
+    let mut values = request.headers().get_all("http2-settings");
+    match (values.next(), values.next()) {
+        (Some(value), None) => {
+            let payload = base64::decode(value.trim().as_bytes(), Alphabet::UrlSafe)?;
+            frame::parse_settings(&payload)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::{test_request, Method};
+
+    use super::{is_preface, upgrade_settings};
+
+    #[test]
+    fn preface_is_detected_from_the_method() {
+        assert!(is_preface(b"PRI"));
+        assert!(is_preface(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\0\0\0\x04"));
+        assert!(!is_preface(b"PR"));
+        assert!(!is_preface(b"POST / HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn upgrade_settings_are_decoded() {
+        let fields = [
+            ("Connection", "Upgrade, HTTP2-Settings"),
+            ("Upgrade", "h2c"),
+            ("HTTP2-Settings", "AAMAAABkAAQAAP__"),
+        ];
+        let request = test_request(Method::Get, "/", &fields, "");
+        assert_eq!(
+            Some(vec![(0x3, 100), (0x4, 0xffff)]),
+            upgrade_settings(&request)
+        );
+    }
+
+    #[test]
+    fn upgrade_requires_settings_connection_option() {
+        let fields = [
+            ("Connection", "Upgrade"),
+            ("Upgrade", "h2c"),
+            ("HTTP2-Settings", "AAMAAABkAAQAAP__"),
+        ];
+        let request = test_request(Method::Get, "/", &fields, "");
+        assert_eq!(None, upgrade_settings(&request));
+    }
+
+    #[test]
+    fn upgrade_is_ignored_for_other_protocols() {
+        let fields = [
+            ("Connection", "Upgrade, HTTP2-Settings"),
+            ("Upgrade", "websocket"),
+            ("HTTP2-Settings", ""),
+        ];
+        let request = test_request(Method::Get, "/", &fields, "");
+        assert_eq!(None, upgrade_settings(&request));
+    }
+}
@@ -0,0 +1,805 @@
+//! HPACK header compression as defined in [RFC7541](https://datatracker.ietf.org/doc/html/rfc7541).
+//!
+//! HPACK is used by HTTP/2 to compress the header fields of each message, but the [`Encoder`]
+//! and [`Decoder`] do not depend on HTTP/2 and can be used on their own. Each side keeps a
+//! dynamic table of the fields seen so far, so the header blocks of a connection must be
+//! decoded in the same order that they were encoded.
+//!
+//! ```
+//! use ramus::hpack::{Decoder, Encoder, Field};
+//!
+//! let fields = vec![Field::new(":method", "GET"), Field::new("accept", "text/html")];
+//!
+//! let mut encoder = Encoder::new(4096);
+//! let mut block = Vec::new();
+//! encoder.encode(&fields, &mut block);
+//!
+//! let mut decoder = Decoder::new(4096);
+//! assert_eq!(Ok(fields), decoder.decode(&block));
+//! ```
+mod huffman;
+mod table;
+
+use std::borrow::Borrow;
+
+use crate::http::{HeaderField, Headers, StatusCode};
+
+use table::{DynamicTable, STATIC_TABLE};
+
+/// A header field as a name and value of octets, as defined in [RFC7541 Section
+/// 1.3](https://datatracker.ietf.org/doc/html/rfc7541#section-1.3).
+///
+/// A sensitive field is never added to the dynamic table by any encoder, as defined in [RFC7541
+/// Section 7.1.3](https://datatracker.ietf.org/doc/html/rfc7541#section-7.1.3).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    name: Vec<u8>,
+    value: Vec<u8>,
+    sensitive: bool,
+}
+
+impl Field {
+    /// Create a [`Field`] with the given name and value.
+    pub fn new(name: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            sensitive: false,
+        }
+    }
+
+    /// Create a sensitive [`Field`], such as a field with a password or a cookie, that is
+    /// always encoded as a literal that must never be indexed.
+    pub fn sensitive(name: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> Self {
+        Self {
+            sensitive: true,
+            ..Self::new(name, value)
+        }
+    }
+
+    /// Returns the name of the field.
+    pub fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    /// Returns the value of the field.
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    /// Returns true if the field must never be indexed.
+    pub fn is_sensitive(&self) -> bool {
+        self.sensitive
+    }
+
+    /// Returns the size of the field as defined in [RFC7541 Section
+    /// 4.1](https://datatracker.ietf.org/doc/html/rfc7541#section-4.1), which is the length of
+    /// the name and value plus 32 octets.
+    ///
+    /// ```
+    /// use ramus::hpack::Field;
+    ///
+    /// assert_eq!(57, Field::new(":authority", "www.example.com").size());
+    /// ```
+    pub fn size(&self) -> usize {
+        DynamicTable::entry_size(&self.name, &self.value)
+    }
+}
+
+impl From<&HeaderField> for Field {
+    fn from(field: &HeaderField) -> Self {
+        Self::new(field.name(), field.value())
+    }
+}
+
+impl From<HeaderField> for Field {
+    fn from(field: HeaderField) -> Self {
+        Self::from(&field)
+    }
+}
+
+impl TryFrom<Field> for HeaderField {
+    type Error = StatusCode;
+
+    /// Convert a [`Field`] into a [`HeaderField`].
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] when the name is not a `token`, such as a HTTP/2
+    /// pseudo-header field, or the value is not valid, see [`HeaderField::from_bytes`].
+    fn try_from(field: Field) -> Result<Self, Self::Error> {
+        let mut line = field.name;
+        line.extend_from_slice(b": ");
+        line.extend_from_slice(&field.value);
+        HeaderField::from_bytes(&line)
+    }
+}
+
+impl From<&Headers> for Vec<Field> {
+    fn from(headers: &Headers) -> Self {
+        headers.iter().map(Field::from).collect()
+    }
+}
+
+impl TryFrom<Vec<Field>> for Headers {
+    type Error = StatusCode;
+
+    /// Convert a list of [`Field`]s into [`Headers`], see [`HeaderField::try_from`] for the
+    /// errors returned.
+    fn try_from(fields: Vec<Field>) -> Result<Self, Self::Error> {
+        let mut headers = Headers::new();
+        for field in fields {
+            headers.append(HeaderField::try_from(field)?);
+        }
+        Ok(headers)
+    }
+}
+
+/// An error while decoding a header block.
+///
+/// The dynamic table is no longer in sync with the encoder after an error, so the
+/// [`Decoder`] must not be used again. In HTTP/2 this is a connection error of type
+/// `COMPRESSION_ERROR`, as defined in [RFC9113 Section
+/// 4.3](https://datatracker.ietf.org/doc/html/rfc9113#section-4.3).
+#[derive(Debug, PartialEq)]
+pub struct DecoderError;
+
+/// Decodes header blocks, keeping the dynamic table between each block.
+#[derive(Debug)]
+pub struct Decoder {
+    table: DynamicTable,
+    /// The maximum size that the encoder is allowed to use for the dynamic table, which in
+    /// HTTP/2 is the value of `SETTINGS_HEADER_TABLE_SIZE` that was sent.
+    max_size_limit: usize,
+    /// The maximum size of the list of fields decoded from a single header block.
+    max_list_size: usize,
+}
+
+impl Decoder {
+    /// Create a [`Decoder`] that allows the encoder to use a dynamic table up to the maximum
+    /// size.
+    pub fn new(max_table_size: usize) -> Self {
+        Self {
+            table: DynamicTable::new(max_table_size),
+            max_size_limit: max_table_size,
+            max_list_size: usize::MAX,
+        }
+    }
+
+    /// Set the maximum size of the list of fields decoded from a single header block, where
+    /// the size of each field is [`Field::size`].
+    ///
+    /// A small header block can refer to the same large entry in the dynamic table many
+    /// times, so this limits the memory used to decode it.
+    pub fn max_list_size(mut self, size: usize) -> Self {
+        self.max_list_size = size;
+        self
+    }
+
+    /// Change the maximum size that the encoder is allowed to use for the dynamic table.
+    ///
+    /// The encoder must be told of the new limit, such as with a SETTINGS frame in HTTP/2, and
+    /// will send a dynamic table size update in the next header block.
+    pub fn set_max_table_size(&mut self, size: usize) {
+        self.max_size_limit = size;
+    }
+
+    /// Returns the size of the dynamic table, which is the sum of the [`Field::size`] of each
+    /// entry.
+    pub fn table_size(&self) -> usize {
+        self.table.size()
+    }
+
+    /// Decode a whole header block into a list of fields, as defined in [RFC7541 Section
+    /// 6](https://datatracker.ietf.org/doc/html/rfc7541#section-6).
+    ///
+    /// ```text
+    /// Indexed Header Field         1xxxxxxx
+    /// Literal Incremental Indexing 01xxxxxx
+    /// Dynamic Table Size Update    001xxxxx
+    /// Literal Never Indexed        0001xxxx
+    /// Literal Without Indexing     0000xxxx
+    /// ```
+    ///
+    /// Returns a [`DecoderError`] if the header block is not valid, or the list of fields is
+    /// larger than the [`Decoder::max_list_size`].
+    pub fn decode(&mut self, mut src: &[u8]) -> Result<Vec<Field>, DecoderError> {
+        let mut fields = Vec::new();
+        let mut list_size = 0usize;
+        // a dynamic table size update is only valid at the start of a header block
+        let mut size_update_allowed = true;
+
+        while let [first, ..] = src {
+            let field = match first {
+                0x80.. => {
+                    let (index, rest) = decode_integer(src, 7)?;
+                    let (name, value) = self.get(index)?;
+                    src = rest;
+                    Field::new(name, value)
+                }
+                0x40..=0x7f => {
+                    let (field, rest) = self.decode_literal(src, 6)?;
+                    self.table.insert(field.name.clone(), field.value.clone());
+                    src = rest;
+                    field
+                }
+                0x20..=0x3f => {
+                    if !size_update_allowed {
+                        return Err(DecoderError);
+                    }
+                    let (max_size, rest) = decode_integer(src, 5)?;
+                    if max_size > self.max_size_limit {
+                        return Err(DecoderError);
+                    }
+                    self.table.set_max_size(max_size);
+                    src = rest;
+                    continue;
+                }
+                _ => {
+                    let (mut field, rest) = self.decode_literal(src, 4)?;
+                    field.sensitive = first & 0x10 != 0;
+                    src = rest;
+                    field
+                }
+            };
+            size_update_allowed = false;
+
+            list_size = list_size.saturating_add(field.size());
+            if list_size > self.max_list_size {
+                return Err(DecoderError);
+            }
+            fields.push(field);
+        }
+
+        Ok(fields)
+    }
+
+    /// Returns the field at the index, where the static table is first and the dynamic table
+    /// follows it, as defined in [RFC7541 Section
+    /// 2.3.3](https://datatracker.ietf.org/doc/html/rfc7541#section-2.3.3).
+    fn get(&self, index: usize) -> Result<(&[u8], &[u8]), DecoderError> {
+        match index {
+            0 => Err(DecoderError),
+            1..=61 => {
+                let (name, value) = STATIC_TABLE[index - 1];
+                Ok((name.as_bytes(), value.as_bytes()))
+            }
+            _ => self.table.get(index - 62).ok_or(DecoderError),
+        }
+    }
+
+    /// Decode a literal header field where the name is either an index or a string literal.
+    fn decode_literal<'a>(
+        &self,
+        src: &'a [u8],
+        prefix: u8,
+    ) -> Result<(Field, &'a [u8]), DecoderError> {
+        let (index, rest) = decode_integer(src, prefix)?;
+        let (name, rest) = if index == 0 {
+            decode_string(rest)?
+        } else {
+            (self.get(index)?.0.to_vec(), rest)
+        };
+        let (value, rest) = decode_string(rest)?;
+        Ok((Field::new(name, value), rest))
+    }
+}
+
+/// Encodes header blocks, keeping the dynamic table between each block.
+///
+/// A field is sent as an index when it is already in either table, otherwise it is sent as a
+/// literal and added to the dynamic table, unless it is sensitive or larger than the dynamic
+/// table. String literals are Huffman encoded unless that would make them longer.
+#[derive(Debug)]
+pub struct Encoder {
+    table: DynamicTable,
+    huffman: bool,
+    /// The smallest and the last maximum size set since the last header block, which must be
+    /// sent as dynamic table size updates at the start of the next header block.
+    size_update: Option<(usize, usize)>,
+}
+
+impl Encoder {
+    /// Create an [`Encoder`] with a dynamic table of the maximum size, which must not be
+    /// larger than the maximum size allowed by the decoder.
+    pub fn new(max_table_size: usize) -> Self {
+        Self {
+            table: DynamicTable::new(max_table_size),
+            huffman: true,
+            size_update: None,
+        }
+    }
+
+    /// Set whether string literals are Huffman encoded unless that would make them longer,
+    /// which is enabled by default.
+    pub fn huffman(mut self, enabled: bool) -> Self {
+        self.huffman = enabled;
+        self
+    }
+
+    /// Change the maximum size of the dynamic table, which must not be larger than the maximum
+    /// size allowed by the decoder.
+    ///
+    /// The change is sent as a dynamic table size update at the start of the next header
+    /// block, as defined in [RFC7541 Section
+    /// 4.2](https://datatracker.ietf.org/doc/html/rfc7541#section-4.2).
+    pub fn set_max_table_size(&mut self, size: usize) {
+        if size == self.table.max_size() && self.size_update.is_none() {
+            return;
+        }
+        self.table.set_max_size(size);
+        self.size_update = match self.size_update {
+            Some((smallest, _)) => Some((smallest.min(size), size)),
+            None => Some((size, size)),
+        };
+    }
+
+    /// Returns the size of the dynamic table, which is the sum of the [`Field::size`] of each
+    /// entry.
+    pub fn table_size(&self) -> usize {
+        self.table.size()
+    }
+
+    /// Encode the fields into a header block, appending it to `dst`.
+    pub fn encode<I>(&mut self, fields: I, dst: &mut Vec<u8>)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Field>,
+    {
+        if let Some((smallest, last)) = self.size_update.take() {
+            if smallest < last {
+                encode_integer(smallest, 5, 0x20, dst);
+            }
+            encode_integer(last, 5, 0x20, dst);
+        }
+
+        for field in fields {
+            self.encode_field(field.borrow(), dst);
+        }
+    }
+
+    fn encode_field(&mut self, field: &Field, dst: &mut Vec<u8>) {
+        let (exact_index, name_index) = self.find(field);
+        match exact_index {
+            Some(index) if !field.sensitive => {
+                encode_integer(index, 7, 0x80, dst);
+                return;
+            }
+            _ => {}
+        }
+
+        let indexed = !field.sensitive && field.size() <= self.table.max_size();
+        let (prefix, flags) = match (field.sensitive, indexed) {
+            (true, _) => (4, 0x10),
+            (false, true) => (6, 0x40),
+            (false, false) => (4, 0x00),
+        };
+        match name_index {
+            Some(index) => encode_integer(index, prefix, flags, dst),
+            None => {
+                dst.push(flags);
+                self.encode_string(&field.name, dst);
+            }
+        }
+        self.encode_string(&field.value, dst);
+
+        if indexed {
+            self.table.insert(field.name.clone(), field.value.clone());
+        }
+    }
+
+    /// Returns the index of an entry that matches the whole field and the index of an entry
+    /// that only matches the name, preferring the static table.
+    fn find(&self, field: &Field) -> (Option<usize>, Option<usize>) {
+        let dynamic = (0..self.table.len()).filter_map(|i| {
+            self.table
+                .get(i)
+                .map(|(name, value)| (i + STATIC_TABLE.len() + 1, name, value))
+        });
+        let entries = STATIC_TABLE
+            .iter()
+            .enumerate()
+            .map(|(i, (name, value))| (i + 1, name.as_bytes(), value.as_bytes()))
+            .chain(dynamic);
+
+        let mut name_index = None;
+        for (index, name, value) in entries {
+            if name == field.name.as_slice() {
+                if value == field.value.as_slice() {
+                    return (Some(index), name_index.or(Some(index)));
+                }
+                name_index = name_index.or(Some(index));
+            }
+        }
+        (None, name_index)
+    }
+
+    /// Encode a string literal, as defined in [RFC7541 Section
+    /// 5.2](https://datatracker.ietf.org/doc/html/rfc7541#section-5.2).
+    fn encode_string(&self, src: &[u8], dst: &mut Vec<u8>) {
+        let huffman_len = huffman::encoded_len(src);
+        if self.huffman && huffman_len <= src.len() {
+            encode_integer(huffman_len, 7, 0x80, dst);
+            huffman::encode(src, dst);
+        } else {
+            encode_integer(src.len(), 7, 0x00, dst);
+            dst.extend_from_slice(src);
+        }
+    }
+}
+
+/// Decode an integer with a prefix of the given number of bits, as defined in [RFC7541 Section
+/// 5.1](https://datatracker.ietf.org/doc/html/rfc7541#section-5.1).
+fn decode_integer(src: &[u8], prefix: u8) -> Result<(usize, &[u8]), DecoderError> {
+    let mask = (1usize << prefix) - 1;
+    let (first, mut rest) = src.split_first().ok_or(DecoderError)?;
+    let mut value = *first as usize & mask;
+    if value < mask {
+        return Ok((value, rest));
+    }
+
+    let mut shift = 0;
+    loop {
+        let (byte, next) = rest.split_first().ok_or(DecoderError)?;
+        rest = next;
+        // limit the number of octets so that the value cannot overflow
+        if shift > 28 {
+            return Err(DecoderError);
+        }
+        value += ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok((value, rest));
+        }
+    }
+}
+
+/// Encode an integer with a prefix of the given number of bits, the bits of `flags` above the
+/// prefix are kept in the first octet.
+fn encode_integer(value: usize, prefix: u8, flags: u8, dst: &mut Vec<u8>) {
+    let mask = (1usize << prefix) - 1;
+    if value < mask {
+        dst.push(flags | value as u8);
+        return;
+    }
+
+    dst.push(flags | mask as u8);
+    let mut value = value - mask;
+    while value >= 0x80 {
+        dst.push(0x80 | (value & 0x7f) as u8);
+        value >>= 7;
+    }
+    dst.push(value as u8);
+}
+
+/// Decode a string literal, as defined in [RFC7541 Section
+/// 5.2](https://datatracker.ietf.org/doc/html/rfc7541#section-5.2).
+///
+/// ```text
+/// | H |    String Length (7+)     |
+/// |  String Data (Length octets)  |
+/// ```
+fn decode_string(src: &[u8]) -> Result<(Vec<u8>, &[u8]), DecoderError> {
+    let huffman = src.first().ok_or(DecoderError)? & 0x80 == 0x80;
+    let (length, rest) = decode_integer(src, 7)?;
+    if length > rest.len() {
+        return Err(DecoderError);
+    }
+    let (string, rest) = rest.split_at(length);
+    if huffman {
+        Ok((huffman::decode(string).ok_or(DecoderError)?, rest))
+    } else {
+        Ok((string.to_vec(), rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::{HeaderField, Headers, StatusCode};
+
+    use super::{decode_integer, encode_integer, Decoder, DecoderError, Encoder, Field};
+
+    fn hex(src: &str) -> Vec<u8> {
+        let digits: Vec<u8> = src.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        digits
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+            .collect()
+    }
+
+    fn fields(fields: &[(&str, &str)]) -> Vec<Field> {
+        fields
+            .iter()
+            .map(|(name, value)| Field::new(*name, *value))
+            .collect()
+    }
+
+    /// A list of fields, the encoded header block as hex and the size of the dynamic table
+    /// after the header block.
+    type Block<'a> = (&'a [(&'a str, &'a str)], &'a str, usize);
+
+    /// Encode and decode each header block in order, checking the encoded bytes and the size
+    /// of the dynamic table after each one.
+    fn assert_blocks(mut encoder: Encoder, mut decoder: Decoder, blocks: &[Block<'_>]) {
+        for (expected, encoded, table_size) in blocks {
+            let expected = fields(expected);
+            let mut block = Vec::new();
+            encoder.encode(&expected, &mut block);
+            assert_eq!(hex(encoded), block);
+            assert_eq!(*table_size, encoder.table_size());
+
+            assert_eq!(Ok(expected), decoder.decode(&hex(encoded)));
+            assert_eq!(*table_size, decoder.table_size());
+        }
+    }
+
+    const REQUESTS: [&[(&str, &str)]; 3] = [
+        &[
+            (":method", "GET"),
+            (":scheme", "http"),
+            (":path", "/"),
+            (":authority", "www.example.com"),
+        ],
+        &[
+            (":method", "GET"),
+            (":scheme", "http"),
+            (":path", "/"),
+            (":authority", "www.example.com"),
+            ("cache-control", "no-cache"),
+        ],
+        &[
+            (":method", "GET"),
+            (":scheme", "https"),
+            (":path", "/index.html"),
+            (":authority", "www.example.com"),
+            ("custom-key", "custom-value"),
+        ],
+    ];
+
+    const RESPONSES: [&[(&str, &str)]; 3] = [
+        &[
+            (":status", "302"),
+            ("cache-control", "private"),
+            ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+            ("location", "https://www.example.com"),
+        ],
+        &[
+            (":status", "307"),
+            ("cache-control", "private"),
+            ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+            ("location", "https://www.example.com"),
+        ],
+        &[
+            (":status", "200"),
+            ("cache-control", "private"),
+            ("date", "Mon, 21 Oct 2013 20:13:22 GMT"),
+            ("location", "https://www.example.com"),
+            ("content-encoding", "gzip"),
+            (
+                "set-cookie",
+                "foo=ASDJKHQKBZXOQWEOPIUAXQWEOIU; max-age=3600; version=1",
+            ),
+        ],
+    ];
+
+    #[test]
+    fn integer_examples_from_rfc() {
+        // RFC7541 Appendix C.1
+        for (value, prefix, encoded) in [
+            (10, 5, vec![0x0a]),
+            (1337, 5, vec![0x1f, 0x9a, 0x0a]),
+            (42, 8, vec![0x2a]),
+        ] {
+            let mut dst = Vec::new();
+            encode_integer(value, prefix, 0, &mut dst);
+            assert_eq!(encoded, dst);
+            assert_eq!(Ok((value, [].as_ref())), decode_integer(&dst, prefix));
+        }
+    }
+
+    #[test]
+    fn truncated_or_overflowing_integer_is_an_error() {
+        assert_eq!(Err(DecoderError), decode_integer(&[0x1f, 0x9a], 5));
+        assert_eq!(
+            Err(DecoderError),
+            decode_integer(&[0x1f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01], 5)
+        );
+    }
+
+    #[test]
+    fn field_representation_examples_from_rfc() {
+        // RFC7541 Appendix C.2.1
+        let mut decoder = Decoder::new(4096);
+        assert_eq!(
+            Ok(fields(&[("custom-key", "custom-header")])),
+            decoder.decode(&hex(
+                "400a 6375 7374 6f6d 2d6b 6579 0d63 7573 746f 6d2d 6865 6164 6572"
+            ))
+        );
+        assert_eq!(55, decoder.table_size());
+
+        // RFC7541 Appendix C.2.2
+        let mut decoder = Decoder::new(4096);
+        assert_eq!(
+            Ok(fields(&[(":path", "/sample/path")])),
+            decoder.decode(&hex("040c 2f73 616d 706c 652f 7061 7468"))
+        );
+        assert_eq!(0, decoder.table_size());
+
+        // RFC7541 Appendix C.2.3
+        let mut decoder = Decoder::new(4096);
+        assert_eq!(
+            Ok(vec![Field::sensitive("password", "secret")]),
+            decoder.decode(&hex("1008 7061 7373 776f 7264 0673 6563 7265 74"))
+        );
+        assert_eq!(0, decoder.table_size());
+
+        let mut block = Vec::new();
+        Encoder::new(4096)
+            .huffman(false)
+            .encode([Field::sensitive("password", "secret")], &mut block);
+        assert_eq!(hex("1008 7061 7373 776f 7264 0673 6563 7265 74"), block);
+
+        // RFC7541 Appendix C.2.4
+        let mut decoder = Decoder::new(4096);
+        assert_eq!(Ok(fields(&[(":method", "GET")])), decoder.decode(&[0x82]));
+        assert_eq!(0, decoder.table_size());
+    }
+
+    #[test]
+    fn requests_without_huffman_from_rfc() {
+        // RFC7541 Appendix C.3
+        assert_blocks(
+            Encoder::new(4096).huffman(false),
+            Decoder::new(4096),
+            &[
+                (
+                    REQUESTS[0],
+                    "8286 8441 0f77 7777 2e65 7861 6d70 6c65 2e63 6f6d",
+                    57,
+                ),
+                (REQUESTS[1], "8286 84be 5808 6e6f 2d63 6163 6865", 110),
+                (
+                    REQUESTS[2],
+                    "8287 85bf 400a 6375 7374 6f6d 2d6b 6579 0c63 7573 746f 6d2d 7661 6c75 65",
+                    164,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn requests_with_huffman_from_rfc() {
+        // RFC7541 Appendix C.4
+        assert_blocks(
+            Encoder::new(4096),
+            Decoder::new(4096),
+            &[
+                (
+                    REQUESTS[0],
+                    "8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff",
+                    57,
+                ),
+                (REQUESTS[1], "8286 84be 5886 a8eb 1064 9cbf", 110),
+                (
+                    REQUESTS[2],
+                    "8287 85bf 4088 25a8 49e9 5ba9 7d7f 8925 a849 e95b b8e8 b4bf",
+                    164,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn responses_without_huffman_from_rfc() {
+        // RFC7541 Appendix C.5
+        assert_blocks(
+            Encoder::new(256).huffman(false),
+            Decoder::new(256),
+            &[
+                (
+                    RESPONSES[0],
+                    "4803 3330 3258 0770 7269 7661 7465 611d 4d6f 6e2c 2032 3120 4f63 7420 \
+                     3230 3133 2032 303a 3133 3a32 3120 474d 546e 1768 7474 7073 3a2f 2f77 \
+                     7777 2e65 7861 6d70 6c65 2e63 6f6d",
+                    222,
+                ),
+                (RESPONSES[1], "4803 3330 37c1 c0bf", 222),
+                (
+                    RESPONSES[2],
+                    "88c1 611d 4d6f 6e2c 2032 3120 4f63 7420 3230 3133 2032 303a 3133 3a32 \
+                     3220 474d 54c0 5a04 677a 6970 7738 666f 6f3d 4153 444a 4b48 514b 425a \
+                     584f 5157 454f 5049 5541 5851 5745 4f49 553b 206d 6178 2d61 6765 3d33 \
+                     3630 303b 2076 6572 7369 6f6e 3d31",
+                    215,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn responses_with_huffman_from_rfc() {
+        // RFC7541 Appendix C.6
+        assert_blocks(
+            Encoder::new(256),
+            Decoder::new(256),
+            &[
+                (
+                    RESPONSES[0],
+                    "4882 6402 5885 aec3 771a 4b61 96d0 7abe 9410 54d4 44a8 2005 9504 0b81 \
+                     66e0 82a6 2d1b ff6e 919d 29ad 1718 63c7 8f0b 97c8 e9ae 82ae 43d3",
+                    222,
+                ),
+                (RESPONSES[1], "4883 640e ffc1 c0bf", 222),
+                (
+                    RESPONSES[2],
+                    "88c1 6196 d07a be94 1054 d444 a820 0595 040b 8166 e084 a62d 1bff c05a \
+                     839b d9ab 77ad 94e7 821d d7f2 e6c7 b335 dfdf cd5b 3960 d5af 2708 7f36 \
+                     72c1 ab27 0fb5 291f 9587 3160 65c0 03ed 4ee5 b106 3d50 07",
+                    215,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn index_outside_of_tables_is_an_error() {
+        let mut decoder = Decoder::new(4096);
+        assert_eq!(Err(DecoderError), decoder.decode(&[0x80]));
+        assert_eq!(Err(DecoderError), decoder.decode(&[0xbe]));
+    }
+
+    #[test]
+    fn size_update_above_limit_or_after_field_is_an_error() {
+        let mut decoder = Decoder::new(4096);
+        // 4097 with a 5 bit prefix
+        assert_eq!(Err(DecoderError), decoder.decode(&[0x3f, 0xe2, 0x1f]));
+        assert_eq!(Err(DecoderError), decoder.decode(&[0x82, 0x20]));
+        assert_eq!(Ok(Vec::new()), decoder.decode(&[0x20]));
+    }
+
+    #[test]
+    fn size_updates_are_sent_at_the_start_of_the_next_block() {
+        let mut encoder = Encoder::new(4096);
+        encoder.set_max_table_size(0);
+        encoder.set_max_table_size(1024);
+        let mut block = Vec::new();
+        encoder.encode([Field::new(":method", "GET")], &mut block);
+        // 0, then 1024 with a 5 bit prefix, then the indexed field
+        assert_eq!(vec![0x20, 0x3f, 0xe1, 0x07, 0x82], block);
+
+        let mut decoder = Decoder::new(4096);
+        assert_eq!(Ok(fields(&[(":method", "GET")])), decoder.decode(&block));
+        assert_eq!(1024, decoder.table.max_size());
+    }
+
+    #[test]
+    fn list_larger_than_max_list_size_is_an_error() {
+        let mut decoder = Decoder::new(4096).max_list_size(64);
+        // :method GET and :scheme http are 42 and 43 octets
+        assert_eq!(Err(DecoderError), decoder.decode(&[0x82, 0x86]));
+    }
+
+    #[test]
+    fn header_fields_convert_to_and_from_fields() {
+        let mut headers = Headers::new();
+        headers.insert("Content-Type", "text/plain");
+        headers.insert("Accept", "*/*");
+
+        let fields = Vec::<Field>::from(&headers);
+        assert_eq!(
+            vec![
+                Field::new("content-type", "text/plain"),
+                Field::new("accept", "*/*")
+            ],
+            fields
+        );
+        assert_eq!(Ok(headers), Headers::try_from(fields));
+    }
+
+    #[test]
+    fn pseudo_header_field_is_not_a_header_field() {
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            HeaderField::try_from(Field::new(":status", "200"))
+        );
+    }
+}
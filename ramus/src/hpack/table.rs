@@ -0,0 +1,191 @@
+//! The static and dynamic tables as defined in [RFC7541 Section
+//! 2.3](https://datatracker.ietf.org/doc/html/rfc7541#section-2.3).
+use std::collections::VecDeque;
+
+/// The static table as defined in [RFC7541 Appendix
+/// A](https://datatracker.ietf.org/doc/html/rfc7541#appendix-A), the index of each entry is one
+/// more than the index in this array.
+pub(crate) const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// The dynamic table as defined in [RFC7541 Section
+/// 2.3.2](https://datatracker.ietf.org/doc/html/rfc7541#section-2.3.2).
+///
+/// New entries are added to the front and so have the lowest index, the oldest entries are
+/// evicted from the back when the size of the table is more than the maximum size.
+#[derive(Debug)]
+pub(crate) struct DynamicTable {
+    entries: VecDeque<(Vec<u8>, Vec<u8>)>,
+    size: usize,
+    max_size: usize,
+}
+
+impl DynamicTable {
+    pub(crate) fn new(max_size: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            size: 0,
+            max_size,
+        }
+    }
+
+    /// The size of an entry as defined in [RFC7541 Section
+    /// 4.1](https://datatracker.ietf.org/doc/html/rfc7541#section-4.1).
+    pub(crate) fn entry_size(name: &[u8], value: &[u8]) -> usize {
+        name.len() + value.len() + 32
+    }
+
+    /// Returns the entry at the index, where an index of 0 is the first entry in the dynamic
+    /// table.
+    pub(crate) fn get(&self, index: usize) -> Option<(&[u8], &[u8])> {
+        self.entries
+            .get(index)
+            .map(|(name, value)| (name.as_slice(), value.as_slice()))
+    }
+
+    /// Returns the number of entries in the table.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the size of the table, which is the sum of the size of each entry.
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Add an entry to the table, evicting the oldest entries to make room.
+    ///
+    /// An entry larger than the maximum size empties the table and is not added, as defined in
+    /// [RFC7541 Section 4.4](https://datatracker.ietf.org/doc/html/rfc7541#section-4.4).
+    pub(crate) fn insert(&mut self, name: Vec<u8>, value: Vec<u8>) {
+        let size = Self::entry_size(&name, &value);
+        self.evict(self.max_size.saturating_sub(size));
+        if size <= self.max_size {
+            self.size += size;
+            self.entries.push_front((name, value));
+        }
+    }
+
+    /// Change the maximum size of the table, evicting entries until the size is within it.
+    pub(crate) fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.evict(max_size);
+    }
+
+    pub(crate) fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    fn evict(&mut self, size: usize) {
+        while self.size > size {
+            match self.entries.pop_back() {
+                Some((name, value)) => self.size -= Self::entry_size(&name, &value),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DynamicTable;
+
+    #[test]
+    fn newest_entry_has_the_lowest_index() {
+        let mut table = DynamicTable::new(4096);
+        table.insert(b"a".to_vec(), b"1".to_vec());
+        table.insert(b"b".to_vec(), b"2".to_vec());
+        assert_eq!(Some((b"b".as_ref(), b"2".as_ref())), table.get(0));
+        assert_eq!(Some((b"a".as_ref(), b"1".as_ref())), table.get(1));
+        assert_eq!(None, table.get(2));
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted_when_full() {
+        // each entry is 34 octets
+        let mut table = DynamicTable::new(68);
+        table.insert(b"a".to_vec(), b"1".to_vec());
+        table.insert(b"b".to_vec(), b"2".to_vec());
+        table.insert(b"c".to_vec(), b"3".to_vec());
+        assert_eq!(Some((b"c".as_ref(), b"3".as_ref())), table.get(0));
+        assert_eq!(Some((b"b".as_ref(), b"2".as_ref())), table.get(1));
+        assert_eq!(None, table.get(2));
+    }
+
+    #[test]
+    fn entry_larger_than_max_size_empties_the_table() {
+        let mut table = DynamicTable::new(40);
+        table.insert(b"a".to_vec(), b"1".to_vec());
+        table.insert(b"long-name".to_vec(), b"long-value".to_vec());
+        assert_eq!(None, table.get(0));
+    }
+
+    #[test]
+    fn reducing_max_size_evicts_entries() {
+        let mut table = DynamicTable::new(4096);
+        table.insert(b"a".to_vec(), b"1".to_vec());
+        table.set_max_size(0);
+        assert_eq!(None, table.get(0));
+    }
+}
@@ -0,0 +1,813 @@
+use std::fmt;
+
+use sha2::{Digest as _, Sha256};
+
+use super::{
+    utils::{
+        abnf,
+        base64::{self, Alphabet},
+        md5::md5,
+        percent_decode,
+    },
+    Method, StatusCode,
+};
+
+/// The credentials of a client in an `Authorization` or `Proxy-Authorization` header field, as
+/// defined in [RFC7235 Section 2.1](https://datatracker.ietf.org/doc/html/rfc7235#section-2.1).
+///
+/// ```text
+/// credentials = auth-scheme [ 1*SP ( token68 / #auth-param ) ]
+///
+/// auth-scheme = token
+/// auth-param = token BWS "=" BWS ( token / quoted-string )
+/// token68 = 1*( ALPHA / DIGIT / "-" / "." / "_" / "~" / "+" / "/" ) *"="
+/// ```
+///
+/// The scheme specific credentials are derived from it, see [`BasicCredentials`],
+/// [`BearerToken`] and [`DigestCredentials`].
+///
+/// ```
+/// use ramus::http::Credentials;
+///
+/// let credentials = Credentials::from_bytes(b"Newauth realm=\"apps\", type=1")
+///     .expect("valid credentials");
+/// assert_eq!("Newauth", credentials.scheme());
+/// assert_eq!(Some("apps"), credentials.param("Realm"));
+/// assert_eq!(None, credentials.token68());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Credentials(AuthParts);
+
+impl Credentials {
+    /// Create [`Credentials`] for the scheme with a `token68`, any characters that are not
+    /// valid are removed.
+    pub fn with_token68(scheme: &str, token68: &str) -> Self {
+        Self(AuthParts::new(scheme).token68(token68))
+    }
+
+    /// Create [`Credentials`] for the scheme with `auth-param`s.
+    pub fn with_params<'a, I>(scheme: &str, params: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let parts = params
+            .into_iter()
+            .fold(AuthParts::new(scheme), |parts, (name, value)| {
+                parts.param(name, value)
+            });
+        Self(parts)
+    }
+
+    /// Derive [`Credentials`] from a slice of bytes.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] when the slice of bytes does not match the ABNF
+    /// syntax of `credentials`.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        match AuthParts::list_from_bytes(src)?.as_mut_slice() {
+            [parts] => Ok(Self(std::mem::take(parts))),
+            _ => Err(StatusCode::BAD_REQUEST),
+        }
+    }
+
+    /// Returns the `auth-scheme`, which is case-insensitive.
+    pub fn scheme(&self) -> &str {
+        &self.0.scheme
+    }
+
+    /// Returns true if the `auth-scheme` is the scheme, ignoring case.
+    pub fn is_scheme(&self, scheme: &str) -> bool {
+        self.0.scheme.eq_ignore_ascii_case(scheme)
+    }
+
+    /// Returns the `token68`, if the credentials have one instead of `auth-param`s.
+    pub fn token68(&self) -> Option<&str> {
+        self.0.token68.as_deref()
+    }
+
+    /// Returns the value of the `auth-param` with the name, which is case-insensitive.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.0.get(name)
+    }
+
+    /// Returns all of the `auth-param`s in the order they were sent.
+    pub fn params(&self) -> &[(String, String)] {
+        &self.0.params
+    }
+}
+
+impl fmt::Display for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // RFC7616 Section 3.4: these digest parameters are never quoted in a response
+        self.0.fmt(f, &["algorithm", "nc", "qop", "userhash"])
+    }
+}
+
+/// A challenge of the server in a `WWW-Authenticate` or `Proxy-Authenticate` header field, as
+/// defined in [RFC7235 Section 2.1](https://datatracker.ietf.org/doc/html/rfc7235#section-2.1).
+///
+/// ```text
+/// WWW-Authenticate = 1#challenge
+/// challenge = auth-scheme [ 1*SP ( token68 / #auth-param ) ]
+/// ```
+///
+/// ```
+/// use ramus::http::Challenge;
+///
+/// let challenges = Challenge::list_from_bytes(
+///     b"Newauth realm=\"apps\", type=1, title=\"Login to \\\"apps\\\"\", Basic realm=\"simple\"",
+/// )
+/// .expect("valid challenges");
+/// assert_eq!(2, challenges.len());
+/// assert_eq!(Some("Login to \"apps\""), challenges[0].param("title"));
+/// assert_eq!("Basic realm=\"simple\", charset=\"UTF-8\"", Challenge::basic("simple").to_string());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Challenge(AuthParts);
+
+impl Challenge {
+    /// Create a [`Challenge`] for the scheme without any `auth-param`s, any characters that are
+    /// not valid in a `token` are removed.
+    pub fn new(scheme: &str) -> Self {
+        Self(AuthParts::new(scheme))
+    }
+
+    /// A `Basic` challenge for the realm, which asks for the credentials in UTF-8 as defined in
+    /// [RFC7617 Section 2.1](https://datatracker.ietf.org/doc/html/rfc7617#section-2.1).
+    pub fn basic(realm: &str) -> Self {
+        Self::new("Basic")
+            .with_param("realm", realm)
+            .with_param("charset", "UTF-8")
+    }
+
+    /// A `Bearer` challenge for the realm as defined in [RFC6750 Section
+    /// 3](https://datatracker.ietf.org/doc/html/rfc6750#section-3).
+    pub fn bearer(realm: &str) -> Self {
+        Self::new("Bearer").with_param("realm", realm)
+    }
+
+    /// Add an `auth-param` to the challenge.
+    pub fn with_param(mut self, name: &str, value: &str) -> Self {
+        self.0 = self.0.param(name, value);
+        self
+    }
+
+    /// Set the `token68` of the challenge, which replaces any `auth-param`s.
+    pub fn with_token68(mut self, token68: &str) -> Self {
+        self.0 = self.0.token68(token68);
+        self
+    }
+
+    /// Derive a list of [`Challenge`] from a slice of bytes, as a header field can have more
+    /// than one challenge.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] when the slice of bytes does not match the ABNF
+    /// syntax of `1#challenge`.
+    pub fn list_from_bytes(src: &[u8]) -> Result<Vec<Self>, StatusCode> {
+        let list = AuthParts::list_from_bytes(src)?;
+        if list.is_empty() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Ok(list.into_iter().map(Self).collect())
+    }
+
+    /// Returns the `auth-scheme`, which is case-insensitive.
+    pub fn scheme(&self) -> &str {
+        &self.0.scheme
+    }
+
+    /// Returns the `token68`, if the challenge has one instead of `auth-param`s.
+    pub fn token68(&self) -> Option<&str> {
+        self.0.token68.as_deref()
+    }
+
+    /// Returns the value of the `auth-param` with the name, which is case-insensitive.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.0.get(name)
+    }
+
+    /// Returns all of the `auth-param`s in the order they were sent.
+    pub fn params(&self) -> &[(String, String)] {
+        &self.0.params
+    }
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // RFC7616 Section 3.3: these digest parameters are never quoted in a challenge
+        self.0.fmt(f, &["algorithm", "stale", "userhash"])
+    }
+}
+
+/// The syntax shared by [`Credentials`] and [`Challenge`].
+#[derive(Clone, Debug, Default, PartialEq)]
+struct AuthParts {
+    scheme: String,
+    token68: Option<String>,
+    params: Vec<(String, String)>,
+}
+
+impl AuthParts {
+    fn new(scheme: &str) -> Self {
+        let mut scheme = scheme.to_string();
+        scheme.retain(|c| c.is_ascii() && abnf::is_tchar(c as u8));
+        Self {
+            scheme,
+            ..Self::default()
+        }
+    }
+
+    fn token68(mut self, token68: &str) -> Self {
+        let mut token68 = token68.to_string();
+        token68.retain(|c| c.is_ascii() && (is_token68_char(c as u8) || c == '='));
+        self.token68 = Some(token68);
+        self.params.clear();
+        self
+    }
+
+    fn param(mut self, name: &str, value: &str) -> Self {
+        let mut name = name.to_string();
+        name.retain(|c| c.is_ascii() && abnf::is_tchar(c as u8));
+        let mut value = value.to_string();
+        value.retain(|c| c == '\t' || !c.is_ascii_control());
+        self.params.push((name, value));
+        self.token68 = None;
+        self
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parse a comma separated list, where an element that starts with a `token` and then a
+    /// space or nothing starts another challenge and the other elements are its parameters.
+    fn list_from_bytes(src: &[u8]) -> Result<Vec<Self>, StatusCode> {
+        let mut list: Vec<Self> = Vec::new();
+        // only a scheme that is followed by a parameter can have more parameters
+        let mut has_params = false;
+        for element in abnf::split_unquoted(src, b',') {
+            if element.is_empty() {
+                continue;
+            }
+            let scheme_len = element.iter().take_while(|b| abnf::is_tchar(**b)).count();
+            let rest = &element[scheme_len..];
+            if abnf::trim_ows(rest).starts_with(b"=") {
+                let param = parse_auth_param(element)?;
+                match list.last_mut() {
+                    Some(parts) if has_params => parts.params.push(param),
+                    _ => return Err(StatusCode::BAD_REQUEST),
+                }
+                continue;
+            }
+            if scheme_len == 0 || (!rest.is_empty() && rest[0] != b' ') {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+
+            let mut parts = Self::new(&String::from_utf8_lossy(&element[..scheme_len]));
+            let rest = abnf::trim_ows(rest);
+            has_params = !rest.is_empty() && !is_token68(rest);
+            if has_params {
+                parts.params.push(parse_auth_param(rest)?);
+            } else if !rest.is_empty() {
+                parts.token68 = Some(String::from_utf8_lossy(rest).into_owned());
+            }
+            list.push(parts);
+        }
+        Ok(list)
+    }
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>, unquoted: &[&str]) -> fmt::Result {
+        f.write_str(&self.scheme)?;
+        if let Some(token68) = &self.token68 {
+            return write!(f, " {token68}");
+        }
+        for (i, (name, value)) in self.params.iter().enumerate() {
+            f.write_str(if i == 0 { " " } else { ", " })?;
+            let is_token = !value.is_empty() && value.bytes().all(abnf::is_tchar);
+            if is_token && unquoted.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                write!(f, "{name}={value}")?;
+            } else {
+                write!(
+                    f,
+                    "{name}=\"{}\"",
+                    value.replace('\\', "\\\\").replace('"', "\\\"")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_token68_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b'+' | b'/')
+}
+
+fn is_token68(src: &[u8]) -> bool {
+    let len = src.iter().take_while(|b| is_token68_char(**b)).count();
+    len > 0 && src[len..].iter().all(|b| *b == b'=')
+}
+
+fn parse_auth_param(src: &[u8]) -> Result<(String, String), StatusCode> {
+    let i = src
+        .iter()
+        .position(|b| *b == b'=')
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let (name, value) = (abnf::trim_ows(&src[..i]), abnf::trim_ows(&src[i + 1..]));
+    if name.is_empty() || !name.iter().copied().all(abnf::is_tchar) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let value = match abnf::parse_quoted_string(value) {
+        Some((value, [])) => value,
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+        None if !value.is_empty() && value.iter().copied().all(abnf::is_tchar) => {
+            String::from_utf8_lossy(value).into_owned()
+        }
+        None => return Err(StatusCode::BAD_REQUEST),
+    };
+    Ok((String::from_utf8_lossy(name).into_owned(), value))
+}
+
+/// The user-id and password of the `Basic` authentication scheme, as defined in
+/// [RFC7617](https://datatracker.ietf.org/doc/html/rfc7617).
+///
+/// The user-id and password are sent as base64 in the `token68`, which is not encrypted and so
+/// should only be used over TLS.
+///
+/// ```
+/// use ramus::http::{BasicCredentials, Credentials};
+///
+/// let credentials = Credentials::from_bytes(b"Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==")
+///     .expect("valid credentials");
+/// let basic = BasicCredentials::from_credentials(&credentials).expect("valid basic");
+/// assert_eq!(("Aladdin", "open sesame"), (basic.user_id(), basic.password()));
+/// assert_eq!(credentials, Credentials::from(basic));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct BasicCredentials {
+    user_id: String,
+    password: String,
+}
+
+impl BasicCredentials {
+    /// Create [`BasicCredentials`] from a user-id, which cannot contain a ":", and a password.
+    pub fn new(user_id: impl Into<String>, password: impl Into<String>) -> Self {
+        let mut user_id = user_id.into();
+        user_id.retain(|c| c != ':');
+        Self {
+            user_id,
+            password: password.into(),
+        }
+    }
+
+    /// Derive [`BasicCredentials`] from [`Credentials`] with the `Basic` scheme.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] for another scheme, or when the `token68` is not
+    /// the base64 of a user-id and password in UTF-8 separated by a ":".
+    pub fn from_credentials(credentials: &Credentials) -> Result<Self, StatusCode> {
+        if !credentials.is_scheme("Basic") {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let decoded = credentials
+            .token68()
+            .and_then(|token68| base64::decode(token68.as_bytes(), Alphabet::Standard))
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        let (user_id, password) = decoded.split_once(':').ok_or(StatusCode::BAD_REQUEST)?;
+        Ok(Self::new(user_id, password))
+    }
+
+    /// Returns the user-id.
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Returns the password.
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+}
+
+impl From<BasicCredentials> for Credentials {
+    fn from(basic: BasicCredentials) -> Self {
+        let pair = format!("{}:{}", basic.user_id, basic.password);
+        Self::with_token68(
+            "Basic",
+            &base64::encode(pair.as_bytes(), Alphabet::Standard),
+        )
+    }
+}
+
+/// The access token of the `Bearer` authentication scheme, as defined in [RFC6750 Section
+/// 2.1](https://datatracker.ietf.org/doc/html/rfc6750#section-2.1).
+///
+/// ```text
+/// credentials = "Bearer" 1*SP b64token
+/// b64token = 1*( ALPHA / DIGIT / "-" / "." / "_" / "~" / "+" / "/" ) *"="
+/// ```
+///
+/// ```
+/// use ramus::http::{BearerToken, Credentials};
+///
+/// let credentials =
+///     Credentials::from_bytes(b"Bearer mF_9.B5f-4.1JqM").expect("valid credentials");
+/// let token = BearerToken::from_credentials(&credentials).expect("valid token");
+/// assert_eq!("mF_9.B5f-4.1JqM", token.as_str());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct BearerToken(String);
+
+impl BearerToken {
+    /// Derive a [`BearerToken`] from [`Credentials`] with the `Bearer` scheme.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] for another scheme or when there is no
+    /// `b64token`.
+    pub fn from_credentials(credentials: &Credentials) -> Result<Self, StatusCode> {
+        if !credentials.is_scheme("Bearer") {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        credentials
+            .token68()
+            .map(|token| Self(token.to_string()))
+            .ok_or(StatusCode::BAD_REQUEST)
+    }
+
+    /// Returns the access token.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<BearerToken> for Credentials {
+    fn from(token: BearerToken) -> Self {
+        Self::with_token68("Bearer", &token.0)
+    }
+}
+
+/// The hash algorithm of the `Digest` authentication scheme, as defined in [RFC7616 Section
+/// 3.2](https://datatracker.ietf.org/doc/html/rfc7616#section-3.2).
+///
+/// The "-sess" variants hash the nonces into the user's secret once for a session.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    /// MD5, which is the default when no algorithm is sent and is only kept for backwards
+    /// compatibility.
+    Md5,
+    /// MD5-sess.
+    Md5Sess,
+    /// SHA-256.
+    Sha256,
+    /// SHA-256-sess.
+    Sha256Sess,
+}
+
+impl DigestAlgorithm {
+    /// Derive a [`DigestAlgorithm`] from a slice of bytes, which is case-insensitive.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] for any other algorithm, such as SHA-512-256.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        [Self::Md5, Self::Md5Sess, Self::Sha256, Self::Sha256Sess]
+            .into_iter()
+            .find(|algorithm| src.eq_ignore_ascii_case(algorithm.as_str().as_bytes()))
+            .ok_or(StatusCode::BAD_REQUEST)
+    }
+
+    /// Returns the name of the algorithm.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Sess => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn is_session(self) -> bool {
+        matches!(self, Self::Md5Sess | Self::Sha256Sess)
+    }
+
+    /// The lowercase hex of the hash of the data, which is `H(data)` in RFC7616.
+    fn hash(self, data: &[u8]) -> String {
+        let hex = |digest: &[u8]| digest.iter().map(|b| format!("{b:02x}")).collect();
+        match self {
+            Self::Md5 | Self::Md5Sess => hex(&md5(data)),
+            Self::Sha256 | Self::Sha256Sess => hex(&Sha256::digest(data)),
+        }
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The response of a client to a `Digest` challenge, as defined in [RFC7616 Section
+/// 3.4](https://datatracker.ietf.org/doc/html/rfc7616#section-3.4).
+///
+/// The client proves that it knows the password by sending a hash of it with the nonce of the
+/// challenge, the method and the URI, see [`DigestCredentials::verify`].
+///
+/// ```
+/// use ramus::http::{Credentials, DigestCredentials, Method};
+///
+/// // RFC7616 Section 3.9.1
+/// let credentials = Credentials::from_bytes(
+///     concat!(
+///         r#"Digest username="Mufasa", realm="http-auth@example.org", uri="/dir/index.html", "#,
+///         r#"algorithm=MD5, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", nc=00000001, "#,
+///         r#"cnonce="f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ", qop=auth, "#,
+///         r#"response="8ca523f5e9506fed4657c9700eebdbec", "#,
+///         r#"opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#,
+///     )
+///     .as_bytes(),
+/// )
+/// .expect("valid credentials");
+/// let digest = DigestCredentials::from_credentials(&credentials).expect("valid digest");
+/// assert_eq!("Mufasa", digest.username());
+/// assert!(digest.verify(Method::Get, "Circle of Life", b""));
+/// assert!(!digest.verify(Method::Get, "Circle of Death", b""));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DigestCredentials {
+    username: String,
+    realm: String,
+    uri: String,
+    algorithm: DigestAlgorithm,
+    nonce: String,
+    cnonce: Option<String>,
+    nc: Option<String>,
+    qop: Option<String>,
+    response: String,
+    opaque: Option<String>,
+    userhash: bool,
+}
+
+impl DigestCredentials {
+    /// Derive [`DigestCredentials`] from [`Credentials`] with the `Digest` scheme.
+    ///
+    /// The username can be sent as `username*`, an extended value in UTF-8 as defined in
+    /// [RFC5987](https://datatracker.ietf.org/doc/html/rfc5987).
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] for another scheme, when a required parameter is
+    /// missing, or for an unknown algorithm or quality of protection.
+    pub fn from_credentials(credentials: &Credentials) -> Result<Self, StatusCode> {
+        if !credentials.is_scheme("Digest") {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let required = |name| {
+            credentials
+                .param(name)
+                .map(str::to_string)
+                .ok_or(StatusCode::BAD_REQUEST)
+        };
+        let optional = |name| credentials.param(name).map(str::to_string);
+
+        let username = match (
+            credentials.param("username"),
+            credentials.param("username*"),
+        ) {
+            (Some(username), None) => username.to_string(),
+            (None, Some(ext_value)) => decode_ext_value(ext_value)?,
+            _ => return Err(StatusCode::BAD_REQUEST),
+        };
+        let algorithm = credentials
+            .param("algorithm")
+            .map_or(Ok(DigestAlgorithm::Md5), |algorithm| {
+                DigestAlgorithm::from_bytes(algorithm.as_bytes())
+            })?;
+        let qop = optional("qop");
+        let (cnonce, nc) = (optional("cnonce"), optional("nc"));
+        match qop.as_deref() {
+            Some("auth" | "auth-int") if cnonce.is_some() && nc.is_some() => {}
+            // RFC2069 compatibility, which has no cnonce
+            None if !algorithm.is_session() => {}
+            _ => return Err(StatusCode::BAD_REQUEST),
+        }
+
+        Ok(Self {
+            username,
+            realm: required("realm")?,
+            uri: required("uri")?,
+            algorithm,
+            nonce: required("nonce")?,
+            cnonce,
+            nc,
+            qop,
+            response: required("response")?,
+            opaque: optional("opaque"),
+            userhash: optional("userhash").is_some_and(|userhash| userhash == "true"),
+        })
+    }
+
+    /// Returns the username, which is the hash of the username and realm when
+    /// [`DigestCredentials::userhash`] is true.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Returns the realm of the challenge.
+    pub fn realm(&self) -> &str {
+        &self.realm
+    }
+
+    /// Returns the `request-target` that the client used for the response, which should be the
+    /// same as the request URI.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Returns the algorithm of the response.
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the nonce of the challenge.
+    pub fn nonce(&self) -> &str {
+        &self.nonce
+    }
+
+    /// Returns the `nc` parameter, the hex count of requests the client sent with the nonce.
+    pub fn nonce_count(&self) -> Option<&str> {
+        self.nc.as_deref()
+    }
+
+    /// Returns the quality of protection, "auth" or "auth-int".
+    pub fn qop(&self) -> Option<&str> {
+        self.qop.as_deref()
+    }
+
+    /// Returns the opaque value of the challenge.
+    pub fn opaque(&self) -> Option<&str> {
+        self.opaque.as_deref()
+    }
+
+    /// Returns true if the username is hashed.
+    pub fn userhash(&self) -> bool {
+        self.userhash
+    }
+
+    /// Returns true if the response was computed with the password, for a request with the
+    /// method and body, as defined in [RFC7616 Section
+    /// 3.4.1](https://datatracker.ietf.org/doc/html/rfc7616#section-3.4.1).
+    ///
+    /// The body is only used for the "auth-int" quality of protection.
+    pub fn verify(&self, method: Method, password: &str, body: &[u8]) -> bool {
+        let h = |data: String| self.algorithm.hash(data.as_bytes());
+        let cnonce = self.cnonce.as_deref().unwrap_or_default();
+
+        let mut a1 = h(format!("{}:{}:{password}", self.username, self.realm));
+        if self.algorithm.is_session() {
+            a1 = h(format!("{a1}:{}:{cnonce}", self.nonce));
+        }
+        let a2 = match self.qop.as_deref() {
+            Some("auth-int") => h(format!(
+                "{}:{}:{}",
+                method.as_str(),
+                self.uri,
+                self.algorithm.hash(body)
+            )),
+            _ => h(format!("{}:{}", method.as_str(), self.uri)),
+        };
+        let expected = match &self.qop {
+            Some(qop) => h(format!(
+                "{a1}:{}:{}:{cnonce}:{qop}:{a2}",
+                self.nonce,
+                self.nc.as_deref().unwrap_or_default()
+            )),
+            None => h(format!("{a1}:{}:{a2}", self.nonce)),
+        };
+
+        // compare every byte so the time taken does not depend on where they differ
+        let response = self.response.to_ascii_lowercase();
+        expected.len() == response.len()
+            && expected
+                .bytes()
+                .zip(response.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+/// Decode an `ext-value` in UTF-8, such as `UTF-8''J%C3%A4s%C3%B8n`.
+fn decode_ext_value(src: &str) -> Result<String, StatusCode> {
+    let (charset, rest) = src.split_once('\'').ok_or(StatusCode::BAD_REQUEST)?;
+    let (_language, value) = rest.split_once('\'').ok_or(StatusCode::BAD_REQUEST)?;
+    if !charset.eq_ignore_ascii_case("UTF-8") {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    percent_decode(value.as_bytes())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or(StatusCode::BAD_REQUEST)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::{Method, StatusCode};
+
+    use super::{BasicCredentials, Challenge, Credentials, DigestCredentials};
+
+    #[test]
+    fn invalid_credentials_are_a_bad_request() {
+        for src in [
+            &b""[..],
+            b"Basic, realm=a",
+            b"realm=a",
+            b"Newauth a=\"b\"c",
+            b"Basic abc, Bearer def",
+            b"Basic abc, realm=a",
+        ] {
+            assert_eq!(
+                Err(StatusCode::BAD_REQUEST),
+                Credentials::from_bytes(src),
+                "{}",
+                String::from_utf8_lossy(src)
+            );
+        }
+    }
+
+    #[test]
+    fn challenges_are_split_on_schemes() {
+        let challenges = Challenge::list_from_bytes(
+            b"Bearer, Basic realm=\"a, b\" , charset=UTF-8,,Token abc==",
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["Bearer", "Basic", "Token"],
+            challenges.iter().map(Challenge::scheme).collect::<Vec<_>>()
+        );
+        assert_eq!(Some("a, b"), challenges[1].param("realm"));
+        assert_eq!(Some("UTF-8"), challenges[1].param("charset"));
+        assert_eq!(Some("abc=="), challenges[2].token68());
+    }
+
+    #[test]
+    fn values_are_quoted_unless_never_quoted() {
+        let challenge = Challenge::new("Digest")
+            .with_param("realm", "say \"hi\"")
+            .with_param("algorithm", "SHA-256")
+            .with_param("stale", "true");
+        assert_eq!(
+            r#"Digest realm="say \"hi\"", algorithm=SHA-256, stale=true"#,
+            challenge.to_string()
+        );
+        assert_eq!(
+            Ok(challenge.clone()),
+            Challenge::list_from_bytes(challenge.to_string().as_bytes())
+                .map(|mut list| list.remove(0))
+        );
+    }
+
+    #[test]
+    fn basic_user_id_and_password_are_utf8() {
+        // RFC7617 Section 2.1 example with a charset of UTF-8
+        let credentials = Credentials::from_bytes(b"Basic dGVzdDoxMjPCow==").unwrap();
+        let basic = BasicCredentials::from_credentials(&credentials).unwrap();
+        assert_eq!(("test", "123\u{a3}"), (basic.user_id(), basic.password()));
+
+        let credentials = Credentials::from_bytes(b"Basic bm8tY29sb24=").unwrap();
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            BasicCredentials::from_credentials(&credentials)
+        );
+    }
+
+    #[test]
+    fn digest_sha256_with_username_ext_value() {
+        // RFC7616 Section 3.9.1 with SHA-256
+        let credentials = Credentials::with_params(
+            "Digest",
+            [
+                ("username*", "UTF-8''Mufasa"),
+                ("realm", "http-auth@example.org"),
+                ("uri", "/dir/index.html"),
+                ("algorithm", "SHA-256"),
+                ("nonce", "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v"),
+                ("nc", "00000001"),
+                ("cnonce", "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ"),
+                ("qop", "auth"),
+                (
+                    "response",
+                    "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1",
+                ),
+            ],
+        );
+        let digest = DigestCredentials::from_credentials(&credentials).unwrap();
+        assert_eq!("Mufasa", digest.username());
+        assert!(digest.verify(Method::Get, "Circle of Life", b""));
+        assert!(!digest.verify(Method::Post, "Circle of Life", b""));
+    }
+
+    #[test]
+    fn digest_session_algorithm_requires_qop() {
+        let credentials = Credentials::from_bytes(
+            b"Digest username=a, realm=b, uri=\"/\", nonce=c, response=d, algorithm=MD5-sess",
+        )
+        .unwrap();
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            DigestCredentials::from_credentials(&credentials)
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Crate Module:
+//! The chunked transfer coding as defined in [RFC7230 Section
+//! 4.1](https://datatracker.ietf.org/doc/html/rfc7230#section-4.1).
+//!
+//! ```text
+//! chunked-body = *chunk
+//!                last-chunk
+//!                trailer-part
+//!                CRLF
+//!
+//! chunk = chunk-size [ chunk-ext ] CRLF
+//!         chunk-data CRLF
+//! chunk-size = 1*HEXDIG
+//! last-chunk = 1*("0") [ chunk-ext ] CRLF
+//!
+//! trailer-part = *( header-field CRLF )
+//! ```
+use std::io::{self, BufRead, Write};
+
+use super::{utils, HeaderField, Limits, StatusCode};
+
+/// The maximum number of octets in a `chunk-size` line, which includes any `chunk-ext`.
+const CHUNK_SIZE_LINE_MAX: usize = 1024;
+
+/// A writer that encodes each write as a single chunk.
+///
+/// [`ChunkedWriter::finish`] must be called to write the `last-chunk` and end the body.
+#[derive(Debug)]
+pub(crate) struct ChunkedWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Write the `last-chunk` and an empty `trailer-part` and return the inner writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(b"0\r\n\r\n")?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // an empty chunk would be read as the last-chunk
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        write!(self.inner, "{:X}\r\n", buf.len())?;
+        self.inner.write_all(buf)?;
+        self.inner.write_all(b"\r\n")?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Read a whole chunked body from the reader, any `chunk-ext` and `trailer-part` are discarded.
+///
+/// Returns a [`StatusCode::BAD_REQUEST`] if the body does not match the ABNF syntax.
+/// Returns a [`StatusCode::PAYLOAD_TOO_LARGE`] when the decoded body is longer than
+/// [`Limits::body`], and a [`StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE`] when the trailer
+/// fields exceed the header limits.
+pub(crate) fn read_chunked<R: BufRead>(
+    reader: &mut R,
+    limits: &Limits,
+) -> Result<Vec<u8>, StatusCode> {
+    let mut body = Vec::new();
+    loop {
+        let line = utils::read_line(reader, CHUNK_SIZE_LINE_MAX, StatusCode::BAD_REQUEST)?
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        let size = parse_chunk_size(&line)?;
+        if size == 0 {
+            break;
+        }
+        if size > (limits.body - body.len()) as u64 {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+
+        let start = body.len();
+        body.resize(start + size as usize, 0);
+        reader
+            .read_exact(&mut body[start..])
+            .map_err(utils::io_error_status)?;
+        match utils::read_line(reader, 0, StatusCode::BAD_REQUEST)? {
+            Some(crlf) if crlf.is_empty() => {}
+            _ => return Err(StatusCode::BAD_REQUEST),
+        }
+    }
+
+    let mut trailers = 0;
+    loop {
+        let field = utils::read_line(
+            reader,
+            limits.header_size,
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+        )?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+        if field.is_empty() {
+            return Ok(body);
+        }
+        trailers += 1;
+        if trailers > limits.header_count {
+            return Err(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+        }
+        HeaderField::from_bytes(&field)?;
+    }
+}
+
+/// Parse the `chunk-size` from the start of a line, ignoring any `chunk-ext` after it.
+///
+/// Both uppercase and lowercase hex digits are accepted as senders commonly use lowercase.
+fn parse_chunk_size(line: &[u8]) -> Result<u64, StatusCode> {
+    let digits = line.iter().take_while(|b| b.is_ascii_hexdigit()).count();
+    match &line[digits..] {
+        [] | [b';', ..] | [b' ' | b'\t', ..] if digits > 0 => line[..digits]
+            .iter()
+            .try_fold(0u64, |size, b| {
+                // is_ascii_hexdigit checked above so to_digit cannot fail
+                let digit = (*b as char).to_digit(16).unwrap_or_default() as u64;
+                size.checked_mul(16)?.checked_add(digit)
+            })
+            .ok_or(StatusCode::PAYLOAD_TOO_LARGE),
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{parse_chunk_size, read_chunked, ChunkedWriter, Limits, StatusCode};
+
+    #[test]
+    fn each_write_is_a_chunk() {
+        let mut writer = ChunkedWriter::new(Vec::new());
+        writer.write_all(b"Hello, ").unwrap();
+        writer.write_all(b"").unwrap();
+        writer.write_all(b"World! 0123456789").unwrap();
+        assert_eq!(
+            b"7\r\nHello, \r\n11\r\nWorld! 0123456789\r\n0\r\n\r\n".as_ref(),
+            writer.finish().unwrap()
+        );
+    }
+
+    #[test]
+    fn chunk_size_without_hex_digits_is_a_bad_request() {
+        assert_eq!(Err(StatusCode::BAD_REQUEST), parse_chunk_size(b""));
+        assert_eq!(Err(StatusCode::BAD_REQUEST), parse_chunk_size(b";ext"));
+        assert_eq!(Err(StatusCode::BAD_REQUEST), parse_chunk_size(b"1G"));
+    }
+
+    #[test]
+    fn chunk_size_ignores_chunk_ext() {
+        assert_eq!(Ok(0x1a), parse_chunk_size(b"1a;name=value"));
+        assert_eq!(Ok(0xFF), parse_chunk_size(b"FF"));
+    }
+
+    #[test]
+    fn chunk_size_that_overflows_is_payload_too_large() {
+        assert_eq!(
+            Err(StatusCode::PAYLOAD_TOO_LARGE),
+            parse_chunk_size(b"FFFFFFFFFFFFFFFFF")
+        );
+    }
+
+    #[test]
+    fn chunked_body_is_decoded_and_trailers_discarded() {
+        let mut bytes = &b"7\r\nHello, \r\n6;ext\r\nWorld!\r\n0\r\nExpires: never\r\n\r\nnext"[..];
+        assert_eq!(
+            Ok(b"Hello, World!".to_vec()),
+            read_chunked(&mut bytes, &Limits::default())
+        );
+        assert_eq!(b"next", bytes);
+    }
+
+    #[test]
+    fn chunked_body_above_limit_is_payload_too_large() {
+        let limits = Limits {
+            body: 10,
+            ..Limits::default()
+        };
+        let mut bytes = &b"7\r\nHello, \r\n6\r\nWorld!\r\n0\r\n\r\n"[..];
+        assert_eq!(
+            Err(StatusCode::PAYLOAD_TOO_LARGE),
+            read_chunked(&mut bytes, &limits)
+        );
+    }
+
+    #[test]
+    fn chunk_data_without_crlf_is_a_bad_request() {
+        let mut bytes = &b"5\r\nHello, World\r\n0\r\n\r\n"[..];
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            read_chunked(&mut bytes, &Limits::default())
+        );
+    }
+}
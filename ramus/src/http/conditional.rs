@@ -0,0 +1,457 @@
+use std::{fmt, time::SystemTime};
+
+use super::{utils::abnf, Headers, HttpDate, Method, Request, StatusCode};
+
+/// Entity tag as defined in [RFC7232 Section
+/// 2.3](https://datatracker.ietf.org/doc/html/rfc7232#section-2.3).
+///
+/// ```text
+/// ETag = entity-tag
+///
+/// entity-tag = [ weak ] opaque-tag
+/// weak = %x57.2F ; "W/", case-sensitive
+/// opaque-tag = DQUOTE *etagc DQUOTE
+/// etagc = %x21 / %x23-7E / obs-text
+/// ; VCHAR except double quotes, plus obs-text
+/// ```
+///
+/// ```
+/// use ramus::http::EntityTag;
+///
+/// let etag = EntityTag::from_bytes(b"W/\"xyzzy\"").expect("valid entity-tag");
+/// assert!(etag.is_weak());
+/// assert_eq!("xyzzy", etag.tag());
+/// assert!(etag.weak_eq(&EntityTag::strong("xyzzy")));
+/// assert!(!etag.strong_eq(&EntityTag::strong("xyzzy")));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EntityTag {
+    weak: bool,
+    tag: String,
+}
+
+impl EntityTag {
+    /// Create a strong [`EntityTag`], which changes whenever the octets of the representation
+    /// change.
+    ///
+    /// Any characters that are not valid in an `opaque-tag` are removed.
+    pub fn strong(tag: impl Into<String>) -> Self {
+        let mut tag = tag.into();
+        tag.retain(|c| !c.is_ascii() || is_etagc(c as u8));
+        Self { weak: false, tag }
+    }
+
+    /// Create a weak [`EntityTag`], which only changes when the meaning of the representation
+    /// changes.
+    ///
+    /// Any characters that are not valid in an `opaque-tag` are removed.
+    pub fn weak(tag: impl Into<String>) -> Self {
+        Self {
+            weak: true,
+            ..Self::strong(tag)
+        }
+    }
+
+    /// Derive an [`EntityTag`] from a slice of bytes.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the slice of bytes does not match the ABNF
+    /// syntax.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        match Self::parse_prefix(src) {
+            Some((etag, [])) => Ok(etag),
+            _ => Err(StatusCode::BAD_REQUEST),
+        }
+    }
+
+    /// Parse an `entity-tag` from the start of the slice, returning the rest of the slice.
+    fn parse_prefix(src: &[u8]) -> Option<(Self, &[u8])> {
+        let (weak, src) = match src.strip_prefix(b"W/") {
+            Some(src) => (true, src),
+            None => (false, src),
+        };
+        let src = src.strip_prefix(b"\"")?;
+        let end = src.iter().position(|b| !is_etagc(*b))?;
+        let rest = src[end..].strip_prefix(b"\"")?;
+        let tag = String::from_utf8_lossy(&src[..end]).into_owned();
+        Some((Self { weak, tag }, rest))
+    }
+
+    /// Returns true if this is a weak entity-tag.
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// Returns the `opaque-tag`, without the double quotes.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// The strong comparison as defined in [RFC7232 Section
+    /// 2.3.2](https://datatracker.ietf.org/doc/html/rfc7232#section-2.3.2), where both
+    /// entity-tags must not be weak and their `opaque-tag`s must match.
+    pub fn strong_eq(&self, other: &EntityTag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// The weak comparison as defined in [RFC7232 Section
+    /// 2.3.2](https://datatracker.ietf.org/doc/html/rfc7232#section-2.3.2), where only the
+    /// `opaque-tag`s must match.
+    pub fn weak_eq(&self, other: &EntityTag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+impl fmt::Display for EntityTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+const fn is_etagc(b: u8) -> bool {
+    matches!(b, 0x21 | 0x23..=0x7E | 0x80..=0xFF)
+}
+
+/// Parse a list of entity-tags, as used by `If-Match` and `If-None-Match`.
+///
+/// ```text
+/// 1#entity-tag
+/// ```
+///
+/// The `opaque-tag` may contain a comma and so the list cannot just be split on commas.
+fn parse_list(mut src: &[u8]) -> Option<Vec<EntityTag>> {
+    let mut etags = Vec::new();
+    loop {
+        // RFC7230 Section 7: empty list elements are allowed and ignored
+        while let [b',' | b' ' | b'\t', rest @ ..] = src {
+            src = rest;
+        }
+        if src.is_empty() {
+            return Some(etags);
+        }
+        let (etag, rest) = EntityTag::parse_prefix(src)?;
+        etags.push(etag);
+        src = abnf::trim_ows(rest);
+        if !src.is_empty() {
+            src = src.strip_prefix(b",")?;
+        }
+    }
+}
+
+/// The validators of the selected representation of a resource, which are used to evaluate the
+/// preconditions of a request as defined in [RFC7232 Section
+/// 6](https://datatracker.ietf.org/doc/html/rfc7232#section-6).
+///
+/// The [`Server`](crate::server::Server) evaluates the preconditions of a `GET` or `HEAD`
+/// request automatically, using the `ETag` and `Last-Modified` of a 2xx response, which can be
+/// set with [`Response::with_validators`](super::Response::with_validators).
+///
+/// Any other method changes the state of the resource and so a handler must evaluate the
+/// preconditions before doing so:
+///
+/// ```
+/// use ramus::http::{EntityTag, Request, Response, StatusCode, Validators};
+///
+/// fn put(request: &Request) -> Response {
+///     let validators = Validators::new().with_etag(EntityTag::strong("v1"));
+///     if let Err(status) = validators.evaluate(request) {
+///         return Response::new(status);
+///     }
+///     // replace the resource ...
+///     let validators = Validators::new().with_etag(EntityTag::strong("v2"));
+///     Response::new(StatusCode::NO_CONTENT).with_validators(&validators)
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Validators {
+    exists: bool,
+    etag: Option<EntityTag>,
+    last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    /// Create the [`Validators`] of a representation that exists, without an entity-tag or
+    /// modification date.
+    pub fn new() -> Self {
+        Self {
+            exists: true,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// Create the [`Validators`] of a resource that has no current representation, such as a
+    /// `PUT` to create a new resource, so that "If-None-Match: *" succeeds and "If-Match: *"
+    /// fails.
+    pub fn missing() -> Self {
+        Self {
+            exists: false,
+            ..Self::new()
+        }
+    }
+
+    /// Set the entity-tag of the representation.
+    pub fn with_etag(mut self, etag: EntityTag) -> Self {
+        self.etag = Some(etag);
+        self
+    }
+
+    /// Set the time that the representation was last modified, which is truncated to the
+    /// second as that is the resolution of a `HTTP-date`.
+    pub fn with_last_modified(mut self, time: SystemTime) -> Self {
+        self.last_modified = Some(HttpDate::from(time).into());
+        self
+    }
+
+    /// Returns the entity-tag of the representation.
+    pub fn etag(&self) -> Option<&EntityTag> {
+        self.etag.as_ref()
+    }
+
+    /// Returns the time that the representation was last modified.
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    /// The validators of a representation from the `ETag` and `Last-Modified` of a response.
+    pub(crate) fn from_headers(headers: &Headers) -> Self {
+        Self {
+            exists: true,
+            etag: headers
+                .get("etag")
+                .and_then(|etag| EntityTag::from_bytes(etag.as_bytes()).ok()),
+            last_modified: headers
+                .get("last-modified")
+                .and_then(|date| HttpDate::from_bytes(date.as_bytes()).ok())
+                .map(SystemTime::from),
+        }
+    }
+
+    /// Evaluate the preconditions of the request in the order defined in [RFC7232 Section
+    /// 6](https://datatracker.ietf.org/doc/html/rfc7232#section-6):
+    /// 1. `If-Match`, or `If-Unmodified-Since` when there is no `If-Match`, which fails with a
+    ///    [`StatusCode::PRECONDITION_FAILED`].
+    /// 2. `If-None-Match`, or `If-Modified-Since` for a `GET` or `HEAD` when there is no
+    ///    `If-None-Match`, which fails with a [`StatusCode::NOT_MODIFIED`] for a `GET` or
+    ///    `HEAD`, otherwise a [`StatusCode::PRECONDITION_FAILED`].
+    ///
+    /// `If-Match` uses the strong comparison and `If-None-Match` uses the weak comparison. A
+    /// date precondition is ignored when it is not a valid `HTTP-date` or there is no
+    /// modification date to compare it with.
+    pub fn evaluate(&self, request: &Request) -> Result<(), StatusCode> {
+        let headers = request.headers();
+        let is_safe = matches!(request.method(), Method::Get | Method::Head);
+
+        if let Some(value) = list(headers, "if-match") {
+            if !self.matches(&value, EntityTag::strong_eq) {
+                return Err(StatusCode::PRECONDITION_FAILED);
+            }
+        } else if let Some(since) = date(headers, "if-unmodified-since") {
+            if self.last_modified.is_some_and(|modified| modified > since) {
+                return Err(StatusCode::PRECONDITION_FAILED);
+            }
+        }
+
+        if let Some(value) = list(headers, "if-none-match") {
+            if self.matches(&value, EntityTag::weak_eq) {
+                return Err(if is_safe {
+                    StatusCode::NOT_MODIFIED
+                } else {
+                    StatusCode::PRECONDITION_FAILED
+                });
+            }
+        } else if let Some(since) = date(headers, "if-modified-since").filter(|_| is_safe) {
+            // RFC7232 Section 3.3: a date later than the current time is invalid
+            let is_valid = since <= SystemTime::now();
+            if is_valid && self.last_modified.is_some_and(|modified| modified <= since) {
+                return Err(StatusCode::NOT_MODIFIED);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the value of `If-Match` or `If-None-Match` matches the representation,
+    /// which for "*" is whether the representation exists.
+    fn matches(&self, value: &str, eq: fn(&EntityTag, &EntityTag) -> bool) -> bool {
+        if value.trim() == "*" {
+            return self.exists;
+        }
+        let Some(etag) = &self.etag else {
+            return false;
+        };
+        // an invalid list cannot match any entity-tag
+        parse_list(value.as_bytes()).is_some_and(|etags| etags.iter().any(|other| eq(etag, other)))
+    }
+}
+
+impl Default for Validators {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the combined value of a list header field, as defined in [RFC7230 Section
+/// 3.2.2](https://datatracker.ietf.org/doc/html/rfc7230#section-3.2.2).
+fn list(headers: &Headers, name: &str) -> Option<String> {
+    let values: Vec<_> = headers.get_all(name).collect();
+    (!values.is_empty()).then(|| values.join(","))
+}
+
+fn date(headers: &Headers, name: &str) -> Option<SystemTime> {
+    headers
+        .get(name)
+        .and_then(|value| HttpDate::from_bytes(value.as_bytes()).ok())
+        .map(SystemTime::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::http::{test_request, Method, StatusCode};
+
+    use super::{parse_list, EntityTag, Validators};
+
+    fn validators() -> Validators {
+        Validators::new()
+            .with_etag(EntityTag::strong("v1"))
+            .with_last_modified(UNIX_EPOCH + Duration::from_millis(784_111_777_500))
+    }
+
+    #[test]
+    fn invalid_entity_tag_is_a_bad_request() {
+        for src in [&b"v1"[..], b"\"v1", b"w/\"v1\"", b"\"v\"1\"", b"\"v 1\""] {
+            assert_eq!(Err(StatusCode::BAD_REQUEST), EntityTag::from_bytes(src));
+        }
+        assert_eq!("W/\"v1\"", EntityTag::weak("v\"1").to_string());
+    }
+
+    #[test]
+    fn entity_tag_list_may_contain_commas() {
+        assert_eq!(
+            Some(vec![EntityTag::strong("a,b"), EntityTag::weak("c")]),
+            parse_list(b" ,\"a,b\" ,W/\"c\",")
+        );
+        assert_eq!(None, parse_list(b"\"a\" \"b\""));
+    }
+
+    #[test]
+    fn if_match_uses_strong_comparison() {
+        let validators = validators();
+        for (fields, result) in [
+            (&[("If-Match", "\"v0\", \"v1\"")][..], Ok(())),
+            (&[("If-Match", "*")][..], Ok(())),
+            (
+                &[("If-Match", "W/\"v1\"")][..],
+                Err(StatusCode::PRECONDITION_FAILED),
+            ),
+            (
+                &[("If-Match", "\"v0\""), ("If-Match", "\"v1\"")][..],
+                Ok(()),
+            ),
+        ] {
+            assert_eq!(
+                result,
+                validators.evaluate(&test_request(Method::Put, "/", fields, "")),
+                "{fields:?}"
+            );
+        }
+        assert_eq!(
+            Err(StatusCode::PRECONDITION_FAILED),
+            Validators::missing().evaluate(&test_request(
+                Method::Put,
+                "/",
+                &[("If-Match", "*")],
+                ""
+            ))
+        );
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        let validators = validators();
+        let fields = &[("If-None-Match", "W/\"v1\"")];
+        assert_eq!(
+            Err(StatusCode::NOT_MODIFIED),
+            validators.evaluate(&test_request(Method::Get, "/", fields, ""))
+        );
+        assert_eq!(
+            Err(StatusCode::PRECONDITION_FAILED),
+            validators.evaluate(&test_request(Method::Post, "/", fields, ""))
+        );
+        assert_eq!(
+            Ok(()),
+            Validators::missing().evaluate(&test_request(
+                Method::Put,
+                "/",
+                &[("If-None-Match", "*")],
+                ""
+            ))
+        );
+    }
+
+    #[test]
+    fn dates_are_compared_to_the_second() {
+        let validators = validators();
+        for (fields, result) in [
+            (
+                &[("If-Modified-Since", "Sun, 06 Nov 1994 08:49:37 GMT")],
+                Err(StatusCode::NOT_MODIFIED),
+            ),
+            (
+                &[("If-Modified-Since", "Sun, 06 Nov 1994 08:49:36 GMT")],
+                Ok(()),
+            ),
+            (
+                &[("If-Modified-Since", "Thu, 01 Jan 2199 00:00:00 GMT")],
+                Ok(()),
+            ),
+            (&[("If-Modified-Since", "yesterday")], Ok(())),
+            (
+                &[("If-Unmodified-Since", "Sun, 06 Nov 1994 08:49:37 GMT")],
+                Ok(()),
+            ),
+            (
+                &[("If-Unmodified-Since", "Sun, 06 Nov 1994 08:49:36 GMT")],
+                Err(StatusCode::PRECONDITION_FAILED),
+            ),
+        ] {
+            assert_eq!(
+                result,
+                validators.evaluate(&test_request(Method::Get, "/", fields, "")),
+                "{fields:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn entity_tags_take_precedence_over_dates() {
+        let validators = validators();
+        // If-None-Match does not match and so If-Modified-Since is ignored
+        let fields = &[
+            ("If-None-Match", "\"v0\""),
+            ("If-Modified-Since", "Sun, 06 Nov 1994 08:49:37 GMT"),
+        ];
+        assert_eq!(
+            Ok(()),
+            validators.evaluate(&test_request(Method::Get, "/", fields, ""))
+        );
+        // If-Match matches and so If-Unmodified-Since is ignored
+        let fields = &[
+            ("If-Match", "\"v1\""),
+            ("If-Unmodified-Since", "Sun, 06 Nov 1994 08:49:36 GMT"),
+        ];
+        assert_eq!(
+            Ok(()),
+            validators.evaluate(&test_request(Method::Put, "/", fields, ""))
+        );
+        // If-Modified-Since is only evaluated for GET and HEAD
+        let fields = &[("If-Modified-Since", "Sun, 06 Nov 1994 08:49:37 GMT")];
+        assert_eq!(
+            Ok(()),
+            validators.evaluate(&test_request(Method::Post, "/", fields, ""))
+        );
+    }
+}
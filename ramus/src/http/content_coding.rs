@@ -0,0 +1,252 @@
+use std::{fmt, io::Read};
+
+use super::{MediaType, StatusCode};
+
+/// Content coding as defined in [RFC7231 Section
+/// 3.1.2.1](https://datatracker.ietf.org/doc/html/rfc7231#section-3.1.2.1), which is used by
+/// `Content-Encoding` and `Accept-Encoding`.
+///
+/// ```text
+/// content-coding = token
+/// ```
+///
+/// Each coding is only supported when its cargo feature is enabled, "gzip", "deflate" or
+/// "brotli", see [`ContentCoding::enabled`]. The "deflate" coding is the "zlib" format of
+/// [RFC1950](https://datatracker.ietf.org/doc/html/rfc1950), as defined in [RFC7230 Section
+/// 4.2.2](https://datatracker.ietf.org/doc/html/rfc7230#section-4.2.2).
+///
+/// ```
+/// use ramus::http::ContentCoding;
+///
+/// assert_eq!(Ok(ContentCoding::Gzip), ContentCoding::from_bytes(b"x-gzip"));
+/// assert_eq!("br", ContentCoding::Brotli.as_str());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ContentCoding {
+    /// The "gzip" coding of [RFC1952](https://datatracker.ietf.org/doc/html/rfc1952).
+    Gzip,
+    /// The "deflate" coding, a "zlib" data format of
+    /// [RFC1950](https://datatracker.ietf.org/doc/html/rfc1950).
+    Deflate,
+    /// The "br" coding of [RFC7932](https://datatracker.ietf.org/doc/html/rfc7932).
+    Brotli,
+}
+
+/// The known length of a response body below which it is not worth compressing.
+pub(crate) const MIN_COMPRESS_LENGTH: u64 = 1024;
+
+/// The quality and window size used by the "br" coding, which favour speed as the body is
+/// compressed while it is sent.
+#[cfg(feature = "brotli")]
+const BROTLI_QUALITY: u32 = 5;
+#[cfg(feature = "brotli")]
+const BROTLI_WINDOW: u32 = 22;
+
+#[cfg(feature = "brotli")]
+const BUFFER_SIZE: usize = 8 * 1024;
+
+impl ContentCoding {
+    /// Derive a [`ContentCoding`] from a slice of bytes, which is case-insensitive, "x-gzip" is
+    /// the same as "gzip" as required by [RFC7230 Section
+    /// 4.2.3](https://datatracker.ietf.org/doc/html/rfc7230#section-4.2.3).
+    ///
+    /// Returns a [`StatusCode::UNSUPPORTED_MEDIA_TYPE`] for any other coding, as defined in
+    /// [RFC7231 Section 3.1.2.2](https://datatracker.ietf.org/doc/html/rfc7231#section-3.1.2.2).
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        match src.to_ascii_lowercase().as_slice() {
+            b"gzip" | b"x-gzip" => Ok(Self::Gzip),
+            b"deflate" => Ok(Self::Deflate),
+            b"br" => Ok(Self::Brotli),
+            _ => Err(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+        }
+    }
+
+    /// Returns the codings that have been enabled by cargo features, in the order that the
+    /// server prefers them.
+    pub fn enabled() -> &'static [ContentCoding] {
+        &[
+            #[cfg(feature = "brotli")]
+            Self::Brotli,
+            #[cfg(feature = "gzip")]
+            Self::Gzip,
+            #[cfg(feature = "deflate")]
+            Self::Deflate,
+        ]
+    }
+
+    /// Returns true if the coding has been enabled by its cargo feature.
+    pub fn is_enabled(self) -> bool {
+        Self::enabled().contains(&self)
+    }
+
+    /// Returns the registered name of the coding.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+            Self::Brotli => "br",
+        }
+    }
+
+    /// Wrap the reader so that the coding is applied to what is read from it, the reader must
+    /// be one of the [`ContentCoding::enabled`] codings.
+    pub(crate) fn encoder(self, reader: Box<dyn Read + Send>) -> Box<dyn Read + Send> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Box::new(flate2::read::GzEncoder::new(
+                reader,
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "deflate")]
+            Self::Deflate => Box::new(flate2::read::ZlibEncoder::new(
+                reader,
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "brotli")]
+            Self::Brotli => Box::new(brotli::CompressorReader::new(
+                reader,
+                BUFFER_SIZE,
+                BROTLI_QUALITY,
+                BROTLI_WINDOW,
+            )),
+            #[allow(unreachable_patterns)]
+            _ => {
+                drop(reader);
+                unreachable!("the {self} content-coding is not enabled")
+            }
+        }
+    }
+
+    /// Decode the slice of bytes, stopping as soon as the decoded bytes are longer than the
+    /// limit so that a small body cannot decompress into an unbounded one.
+    ///
+    /// Returns a [`StatusCode::PAYLOAD_TOO_LARGE`] when the limit is exceeded, a
+    /// [`StatusCode::BAD_REQUEST`] when the bytes are not valid for the coding and a
+    /// [`StatusCode::UNSUPPORTED_MEDIA_TYPE`] when the coding is not enabled.
+    pub(crate) fn decode(self, src: &[u8], limit: usize) -> Result<Vec<u8>, StatusCode> {
+        let decoder = self
+            .decoder(src)
+            .ok_or(StatusCode::UNSUPPORTED_MEDIA_TYPE)?;
+        let mut decoded = Vec::new();
+        decoder
+            .take(limit as u64 + 1)
+            .read_to_end(&mut decoded)
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        if decoded.len() > limit {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        Ok(decoded)
+    }
+
+    fn decoder(self, src: &[u8]) -> Option<Box<dyn Read + '_>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Some(Box::new(flate2::read::GzDecoder::new(src))),
+            #[cfg(feature = "deflate")]
+            Self::Deflate => Some(Box::new(flate2::read::ZlibDecoder::new(src))),
+            #[cfg(feature = "brotli")]
+            Self::Brotli => Some(Box::new(brotli::Decompressor::new(src, BUFFER_SIZE))),
+            #[allow(unreachable_patterns)]
+            _ => {
+                let _ = src;
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Display for ContentCoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returns true if a body of the media type is worth compressing, which is not the case for
+/// media types that are already compressed, such as most images, audio and video, or for a
+/// `text/event-stream` as each event must be sent as soon as it is written.
+pub(crate) fn is_compressible(media_type: &MediaType) -> bool {
+    match (media_type.type_(), media_type.subtype()) {
+        ("image", subtype) => subtype == "svg+xml" || subtype == "bmp" || subtype == "x-icon",
+        ("audio" | "video", _) => false,
+        ("font", subtype) => !matches!(subtype, "woff" | "woff2"),
+        ("text", "event-stream") => false,
+        ("application", subtype) => !matches!(
+            subtype,
+            "zip"
+                | "gzip"
+                | "x-gzip"
+                | "x-bzip2"
+                | "x-xz"
+                | "zstd"
+                | "x-7z-compressed"
+                | "x-rar-compressed"
+                | "pdf"
+                | "octet-stream"
+        ),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::{MediaType, StatusCode};
+
+    use super::{is_compressible, ContentCoding};
+
+    #[test]
+    fn unknown_coding_is_unsupported_media_type() {
+        assert_eq!(Ok(ContentCoding::Brotli), ContentCoding::from_bytes(b"BR"));
+        assert_eq!(
+            Err(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            ContentCoding::from_bytes(b"compress")
+        );
+    }
+
+    #[test]
+    fn compressed_media_types_are_skipped() {
+        let compressible =
+            |src: &str| is_compressible(&MediaType::from_bytes(src.as_bytes()).unwrap());
+        assert!(compressible("text/html; charset=utf-8"));
+        assert!(compressible("application/json"));
+        assert!(compressible("image/svg+xml"));
+        assert!(!compressible("image/png"));
+        assert!(!compressible("video/mp4"));
+        assert!(!compressible("application/zip"));
+        assert!(!compressible("text/event-stream"));
+    }
+
+    #[test]
+    fn enabled_codings_round_trip() {
+        let body = b"a body that repeats, a body that repeats, a body that repeats".repeat(64);
+        for coding in ContentCoding::enabled() {
+            let mut encoded = Vec::new();
+            std::io::Read::read_to_end(
+                &mut coding.encoder(Box::new(std::io::Cursor::new(body.clone()))),
+                &mut encoded,
+            )
+            .unwrap();
+            assert!(encoded.len() < body.len());
+            assert_eq!(Ok(body.clone()), coding.decode(&encoded, body.len()));
+            assert_eq!(
+                Err(StatusCode::PAYLOAD_TOO_LARGE),
+                coding.decode(&encoded, body.len() - 1)
+            );
+        }
+    }
+
+    #[test]
+    fn disabled_coding_is_not_decoded() {
+        for coding in [
+            ContentCoding::Gzip,
+            ContentCoding::Deflate,
+            ContentCoding::Brotli,
+        ] {
+            if !coding.is_enabled() {
+                assert_eq!(
+                    Err(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+                    coding.decode(b"", 1)
+                );
+            }
+        }
+    }
+}
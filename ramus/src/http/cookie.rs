@@ -0,0 +1,701 @@
+use std::{fmt, time::Duration};
+
+use super::{
+    utils::{abnf, percent_decode},
+    FromRequest, Headers, Host, HttpDate, Path, Request, StatusCode, Uri,
+};
+
+/// The cookies sent by a client in the `Cookie` header field, as defined in [RFC6265 Section
+/// 4.2](https://datatracker.ietf.org/doc/html/rfc6265#section-4.2).
+///
+/// ```text
+/// cookie-header = "Cookie:" OWS cookie-string OWS
+/// cookie-string = cookie-pair *( ";" SP cookie-pair )
+///
+/// cookie-pair = cookie-name "=" cookie-value
+/// cookie-name = token
+/// cookie-value = *cookie-octet / ( DQUOTE *cookie-octet DQUOTE )
+/// cookie-octet = %x21 / %x23-2B / %x2D-3A / %x3C-5B / %x5D-7E
+/// ```
+///
+/// A pair that does not match the syntax is skipped rather than rejecting the whole request,
+/// as a client sends every cookie that it has stored for the server. The pairs of every
+/// `Cookie` field are in the jar, as a HTTP/2 client may split them across fields. The
+/// `pct-encoded` octets of a name or value are decoded, see [`SetCookie::new`].
+///
+/// ```
+/// use ramus::http::CookieJar;
+///
+/// let jar = CookieJar::from_bytes(b"theme=dark; session=\"abc123\"; =skipped");
+/// assert_eq!(Some("dark"), jar.get("theme"));
+/// assert_eq!(Some("abc123"), jar.get("session"));
+/// assert_eq!(2, jar.len());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CookieJar(Vec<(String, String)>);
+
+impl CookieJar {
+    /// Derive a [`CookieJar`] from the slice of bytes of a `cookie-string`.
+    pub fn from_bytes(src: &[u8]) -> Self {
+        let mut jar = Self::default();
+        jar.extend(src);
+        jar
+    }
+
+    /// Derive a [`CookieJar`] from all of the `Cookie` header fields.
+    pub fn from_headers(headers: &Headers) -> Self {
+        let mut jar = Self::default();
+        for value in headers.get_all("cookie") {
+            jar.extend(value.as_bytes());
+        }
+        jar
+    }
+
+    fn extend(&mut self, src: &[u8]) {
+        for pair in src.split(|b| *b == b';') {
+            let pair = abnf::trim_ows(pair);
+            let Some(i) = pair.iter().position(|b| *b == b'=') else {
+                continue;
+            };
+            let (name, value) = (&pair[..i], &pair[i + 1..]);
+            let value = match value {
+                [b'"', value @ .., b'"'] => value,
+                value => value,
+            };
+            if !name.is_empty()
+                && name.iter().copied().all(abnf::is_tchar)
+                && value.iter().copied().all(is_cookie_octet)
+            {
+                self.0.push((decode(name), decode(value)));
+            }
+        }
+    }
+
+    /// Returns the value of the first cookie with the name, which is case-sensitive.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the value of the first cookie with the name when it has been signed with the
+    /// key, see [`SetCookie::signed`], without the signature.
+    ///
+    /// Returns [`None`] if the cookie is missing or the signature does not match, such as when
+    /// the client has changed the value.
+    #[cfg(feature = "signed-cookies")]
+    pub fn get_signed(&self, name: &str, key: &CookieKey) -> Option<&str> {
+        let (value, signature) = self.get(name)?.rsplit_once('.')?;
+        key.verify(name, value, signature).then_some(value)
+    }
+
+    /// Returns an iterator over the names and values of the cookies, in the order they were
+    /// sent.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Returns the number of cookies in the jar.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if there are no cookies in the jar.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromRequest for CookieJar {
+    type Rejection = StatusCode;
+
+    /// Never fails, a request without a `Cookie` has an empty jar, see [`Request::cookies`].
+    fn from_request(request: &Request) -> Result<Self, StatusCode> {
+        Ok(request.cookies())
+    }
+}
+
+fn is_cookie_octet(b: u8) -> bool {
+    matches!(b, 0x21 | 0x23..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E)
+}
+
+/// Encode every octet that is not allowed, and "%", as `pct-encoded`, so that a name or
+/// value of any characters can be sent.
+fn encode(src: &str, allowed: fn(u8) -> bool) -> String {
+    let mut encoded = String::with_capacity(src.len());
+    for byte in src.bytes() {
+        if allowed(byte) && byte != b'%' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Decode the `pct-encoded` octets of a name or value, which is kept as it is when a "%" is
+/// not followed by two hex digits, as it was not encoded by [`SetCookie`].
+fn decode(src: &[u8]) -> String {
+    match percent_decode(src) {
+        Some(decoded) => String::from_utf8_lossy(&decoded).into_owned(),
+        None => String::from_utf8_lossy(src).into_owned(),
+    }
+}
+
+/// The value of the `SameSite` attribute of a [`SetCookie`], which controls whether the cookie
+/// is sent with a request from another site, as defined in [RFC6265bis Section
+/// 4.1.2.7](https://datatracker.ietf.org/doc/html/draft-ietf-httpbis-rfc6265bis#section-4.1.2.7).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SameSite {
+    /// Only sent with requests from the same site.
+    Strict,
+    /// Also sent when navigating to the site from another site.
+    Lax,
+    /// Sent with every request, which requires the cookie to be [`SetCookie::with_secure`].
+    None,
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Strict => "Strict",
+            Self::Lax => "Lax",
+            Self::None => "None",
+        })
+    }
+}
+
+/// A cookie for the client to store, sent in a `Set-Cookie` header field as defined in
+/// [RFC6265 Section 4.1](https://datatracker.ietf.org/doc/html/rfc6265#section-4.1).
+///
+/// ```text
+/// set-cookie-header = "Set-Cookie:" SP set-cookie-string
+/// set-cookie-string = cookie-pair *( ";" SP cookie-av )
+/// cookie-av = expires-av / max-age-av / domain-av / path-av / secure-av / httponly-av /
+///             extension-av
+/// ```
+///
+/// Characters that are not valid in the name or value are `pct-encoded` when the cookie is
+/// sent, and decoded again by the [`CookieJar`], see [`SetCookie::new`]. Characters that are
+/// not valid in an attribute are removed, so the header field is always valid.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use ramus::http::{Response, SameSite, SetCookie, StatusCode};
+///
+/// let cookie = SetCookie::new("session", "abc123")
+///     .with_path("/")
+///     .with_max_age(Duration::from_secs(3600))
+///     .with_secure(true)
+///     .with_http_only(true)
+///     .with_same_site(SameSite::Lax);
+/// assert_eq!(
+///     "session=abc123; Max-Age=3600; Path=/; Secure; HttpOnly; SameSite=Lax",
+///     cookie.to_string()
+/// );
+///
+/// let response = Response::new(StatusCode::OK).with_cookie(&cookie);
+/// assert_eq!(Some(cookie.to_string().as_str()), response.headers().get("set-cookie"));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetCookie {
+    name: String,
+    value: String,
+    expires: Option<HttpDate>,
+    max_age: Option<u64>,
+    domain: Option<String>,
+    path: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+impl SetCookie {
+    /// Create a [`SetCookie`] with the name and value and no attributes, which the client
+    /// removes at the end of the session.
+    ///
+    /// Any octet of the name that is not a `tchar`, or of the value that is not a
+    /// `cookie-octet`, is sent `pct-encoded` along with "%" itself, so the value of the cookie
+    /// in the [`CookieJar`] is the same as the value here.
+    ///
+    /// ```
+    /// use ramus::http::{CookieJar, SetCookie};
+    ///
+    /// let cookie = SetCookie::new("greeting", "hello, world; 100%");
+    /// assert_eq!("greeting=hello%2C%20world%3B%20100%25", cookie.to_string());
+    ///
+    /// let jar = CookieJar::from_bytes(cookie.to_string().as_bytes());
+    /// assert_eq!(Some("hello, world; 100%"), jar.get("greeting"));
+    /// ```
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            expires: None,
+            max_age: None,
+            domain: None,
+            path: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// Create a [`SetCookie`] that removes the cookie with the name from the client, by
+    /// expiring it in the past.
+    ///
+    /// The `Domain` and `Path` must be the same as those of the cookie that is removed.
+    pub fn removal(name: impl Into<String>) -> Self {
+        Self::new(name, "")
+            .with_max_age(Duration::ZERO)
+            .with_expires(HttpDate::from(std::time::UNIX_EPOCH))
+    }
+
+    /// Derive a [`SetCookie`] from the slice of bytes of a `set-cookie-string`, using the
+    /// parsing algorithm of [RFC6265 Section
+    /// 5.2](https://datatracker.ietf.org/doc/html/rfc6265#section-5.2), which ignores any
+    /// attribute that is not valid.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if there is no "=" in the `cookie-pair` or the
+    /// name is empty.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        let mut attributes = src.split(|b| *b == b';');
+        let pair = attributes.next().unwrap_or_default();
+        let i = pair
+            .iter()
+            .position(|b| *b == b'=')
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        let name = decode(abnf::trim_ows(&pair[..i]));
+        if name.is_empty() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let value = decode(abnf::trim_ows(&pair[i + 1..]));
+        let mut cookie = Self::new(name, value);
+
+        for attribute in attributes {
+            let attribute = String::from_utf8_lossy(abnf::trim_ows(attribute));
+            let (name, value) = attribute
+                .split_once('=')
+                .map(|(name, value)| (name.trim(), value.trim()))
+                .unwrap_or((&attribute, ""));
+            match name.to_ascii_lowercase().as_str() {
+                "expires" => {
+                    if let Ok(date) = HttpDate::from_bytes(value.as_bytes()) {
+                        cookie.expires = Some(date);
+                    }
+                }
+                "max-age" => {
+                    if let Ok(seconds) = value.parse::<i64>() {
+                        cookie.max_age = Some(seconds.max(0) as u64);
+                    }
+                }
+                "domain" if !value.is_empty() => cookie = cookie.with_domain(value),
+                "path" if value.starts_with('/') => cookie = cookie.with_path(value),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => {
+                    cookie.same_site = match value.to_ascii_lowercase().as_str() {
+                        "strict" => Some(SameSite::Strict),
+                        "lax" => Some(SameSite::Lax),
+                        "none" => Some(SameSite::None),
+                        _ => cookie.same_site,
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(cookie)
+    }
+
+    /// Set the `Expires` attribute, the date after which the client removes the cookie.
+    pub fn with_expires(mut self, date: HttpDate) -> Self {
+        self.expires = Some(date);
+        self
+    }
+
+    /// Set the `Max-Age` attribute, how long until the client removes the cookie, which takes
+    /// precedence over `Expires`. It is sent in whole seconds.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age.as_secs());
+        self
+    }
+
+    /// Set the `Domain` attribute, so that the cookie is also sent to the subdomains of the
+    /// domain. A leading "." is ignored.
+    pub fn with_domain(mut self, domain: &str) -> Self {
+        let mut domain = domain.trim_start_matches('.').to_ascii_lowercase();
+        domain.retain(is_av_char);
+        self.domain = Some(domain);
+        self
+    }
+
+    /// Set the `Path` attribute, so that the cookie is only sent with requests for the path or
+    /// a path below it.
+    pub fn with_path(mut self, path: &str) -> Self {
+        let mut path = path.to_string();
+        path.retain(is_av_char);
+        self.path = Some(path);
+        self
+    }
+
+    /// Set the `Secure` attribute, so that the cookie is only sent over a secure connection.
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Set the `HttpOnly` attribute, so that the cookie cannot be read by scripts.
+    pub fn with_http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    /// Set the `SameSite` attribute.
+    pub fn with_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    /// Sign the value with the key, so that a value that has been changed by the client is
+    /// detected by [`CookieJar::get_signed`]. The signature is appended to the value.
+    ///
+    /// The value is still visible to the client, so it must not contain a secret.
+    ///
+    /// ```
+    /// use ramus::http::{CookieJar, CookieKey, SetCookie};
+    ///
+    /// let key = CookieKey::new(b"a secret of at least thirty-two bytes");
+    /// let cookie = SetCookie::new("user", "ferris").signed(&key);
+    ///
+    /// let jar = CookieJar::from_bytes(format!("user={}", cookie.value()).as_bytes());
+    /// assert_eq!(Some("ferris"), jar.get_signed("user", &key));
+    ///
+    /// let tampered = format!("user={}", cookie.value().replace("ferris", "admin"));
+    /// let jar = CookieJar::from_bytes(tampered.as_bytes());
+    /// assert_eq!(Some(None), jar.get("user").map(|_| jar.get_signed("user", &key)));
+    /// ```
+    #[cfg(feature = "signed-cookies")]
+    pub fn signed(mut self, key: &CookieKey) -> Self {
+        let signature = key.sign(&self.name, &self.value);
+        self.value = format!("{}.{signature}", self.value);
+        self
+    }
+
+    /// Returns the name of the cookie, before it is encoded.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the value of the cookie, before it is encoded.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns the `Expires` attribute.
+    pub fn expires(&self) -> Option<HttpDate> {
+        self.expires
+    }
+
+    /// Returns the `Max-Age` attribute.
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age.map(Duration::from_secs)
+    }
+
+    /// Returns the `Domain` attribute.
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+
+    /// Returns the `Path` attribute.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Returns true if the `Secure` attribute is set.
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    /// Returns true if the `HttpOnly` attribute is set.
+    pub fn is_http_only(&self) -> bool {
+        self.http_only
+    }
+
+    /// Returns the `SameSite` attribute.
+    pub fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    /// Returns true if the cookie should be sent with a request for the URI, as defined in
+    /// [RFC6265 Section 5.4](https://datatracker.ietf.org/doc/html/rfc6265#section-5.4).
+    ///
+    /// A `Secure` cookie is only sent to a "https" URI, the host must domain-match the
+    /// `Domain`, see [`domain_matches`], and the path must path-match the `Path`, see
+    /// [`path_matches`]. A cookie without a `Domain` or `Path` is treated as matching any host
+    /// or path, as those defaults depend on the request that set the cookie.
+    ///
+    /// ```
+    /// use ramus::http::{SetCookie, Uri};
+    ///
+    /// let cookie = SetCookie::new("id", "1").with_domain("example.com").with_path("/docs");
+    /// let uri = |src: &str| Uri::from_bytes(src.as_bytes()).expect("valid uri");
+    /// assert!(cookie.matches(&uri("http://www.example.com/docs/web")));
+    /// assert!(!cookie.matches(&uri("http://example.org/docs")));
+    /// assert!(!cookie.matches(&uri("http://example.com/documents")));
+    /// ```
+    pub fn matches(&self, uri: &Uri) -> bool {
+        if self.secure && uri.scheme().as_str() != "https" {
+            return false;
+        }
+        let domain_matches = match (&self.domain, uri.authority()) {
+            (None, _) => true,
+            (Some(domain), Some(authority)) => domain_matches(authority.host(), domain),
+            (Some(_), None) => false,
+        };
+        domain_matches
+            && self
+                .path
+                .as_deref()
+                .is_none_or(|path| path_matches(uri.path(), path))
+    }
+}
+
+impl fmt::Display for SetCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}={}",
+            encode(&self.name, abnf::is_tchar),
+            encode(&self.value, is_cookie_octet)
+        )?;
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={expires}")?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={max_age}")?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={domain}")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={path}")?;
+        }
+        if self.secure {
+            f.write_str("; Secure")?;
+        }
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={same_site}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Any CHAR except CTLs or ";", as allowed in the value of a `Domain` or `Path` attribute.
+fn is_av_char(c: char) -> bool {
+    c.is_ascii() && !c.is_ascii_control() && c != ';'
+}
+
+/// Returns true if the host domain-matches the domain, as defined in [RFC6265 Section
+/// 5.1.3](https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.3).
+///
+/// The host matches when it is the domain, ignoring case, or when it is a domain name that
+/// ends with "." and the domain, so "www.example.com" matches "example.com". An IP address
+/// only matches itself.
+pub fn domain_matches(host: &Host, domain: &str) -> bool {
+    let domain = domain.trim_start_matches('.');
+    match host {
+        Host::Domain(host) => {
+            host.eq_ignore_ascii_case(domain)
+                || host.len() > domain.len()
+                    && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+                    && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+        }
+        host => host.to_string().eq_ignore_ascii_case(domain),
+    }
+}
+
+/// Returns true if the path of a request path-matches the path of a cookie, as defined in
+/// [RFC6265 Section 5.1.4](https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.4).
+///
+/// The paths match when they are the same, or when the cookie path is a prefix of the request
+/// path that ends with "/" or is followed by a "/", so "/docs" matches "/docs/web" but not
+/// "/documents". An empty request path is the same as "/".
+pub fn path_matches(request_path: &Path, cookie_path: &str) -> bool {
+    let request_path = match request_path.as_str() {
+        "" => "/",
+        path => path,
+    };
+    request_path == cookie_path
+        || request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/')
+                || request_path.as_bytes().get(cookie_path.len()) == Some(&b'/'))
+}
+
+/// The secret key used to sign cookies with HMAC-SHA256, see [`SetCookie::signed`].
+///
+/// The secret should be at least 32 random bytes and must be kept the same across restarts
+/// of the server, otherwise the cookies that have already been signed are no longer valid.
+#[cfg(feature = "signed-cookies")]
+#[derive(Clone)]
+pub struct CookieKey(hmac::Hmac<sha2::Sha256>);
+
+#[cfg(feature = "signed-cookies")]
+impl CookieKey {
+    /// Create a [`CookieKey`] from the secret.
+    pub fn new(secret: &[u8]) -> Self {
+        use hmac::KeyInit;
+
+        Self(hmac::Hmac::new_from_slice(secret).expect("HMAC accepts a key of any length"))
+    }
+
+    fn mac(&self, name: &str, value: &str) -> hmac::Hmac<sha2::Sha256> {
+        use hmac::Mac;
+
+        // the name is signed as well so that the value cannot be moved to another cookie
+        let mut mac = self.0.clone();
+        mac.update(name.as_bytes());
+        mac.update(b"=");
+        mac.update(value.as_bytes());
+        mac
+    }
+
+    fn sign(&self, name: &str, value: &str) -> String {
+        use hmac::Mac;
+
+        let signature = self.mac(name, value).finalize().into_bytes();
+        super::utils::base64::encode(&signature, super::utils::base64::Alphabet::UrlSafe)
+    }
+
+    fn verify(&self, name: &str, value: &str, signature: &str) -> bool {
+        use hmac::Mac;
+
+        super::utils::base64::decode(
+            signature.as_bytes(),
+            super::utils::base64::Alphabet::UrlSafe,
+        )
+        .is_some_and(|signature| self.mac(name, value).verify_slice(&signature).is_ok())
+    }
+}
+
+#[cfg(feature = "signed-cookies")]
+impl fmt::Debug for CookieKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CookieKey").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::http::{HeaderField, Headers, Host, HttpDate, Path, SameSite, StatusCode};
+
+    use super::{domain_matches, path_matches, CookieJar, SetCookie};
+
+    #[test]
+    fn cookies_of_every_field_are_in_the_jar() {
+        let mut headers = Headers::new();
+        headers.append(HeaderField::new("Cookie", "a=1; b=2"));
+        headers.append(HeaderField::new(
+            "Cookie",
+            "c=\"3\"; bad name=4; d=semi\\colon",
+        ));
+        let jar = CookieJar::from_headers(&headers);
+        assert_eq!(
+            vec![("a", "1"), ("b", "2"), ("c", "3")],
+            jar.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn removal_expires_in_the_past() {
+        assert_eq!(
+            "id=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0; Path=/",
+            SetCookie::removal("id").with_path("/").to_string()
+        );
+    }
+
+    #[test]
+    fn invalid_characters_are_encoded_in_the_pair_and_removed_from_attributes() {
+        let cookie = SetCookie::new("a b", "x;y z%\u{e9}")
+            .with_path("/a;b")
+            .with_domain(".Example.COM");
+        assert_eq!(
+            "a%20b=x%3By%20z%25%C3%A9; Domain=example.com; Path=/ab",
+            cookie.to_string()
+        );
+        assert_eq!(
+            Ok(&cookie),
+            SetCookie::from_bytes(cookie.to_string().as_bytes()).as_ref()
+        );
+
+        let jar = CookieJar::from_bytes(cookie.to_string().as_bytes());
+        assert_eq!(Some("x;y z%\u{e9}"), jar.get("a b"));
+    }
+
+    #[test]
+    fn value_with_a_percent_that_is_not_encoded_is_kept() {
+        let jar = CookieJar::from_bytes(b"discount=50%; code=a%2");
+        assert_eq!(Some("50%"), jar.get("discount"));
+        assert_eq!(Some("a%2"), jar.get("code"));
+    }
+
+    #[test]
+    fn set_cookie_is_parsed_ignoring_invalid_attributes() {
+        let cookie = SetCookie::from_bytes(
+            b"id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=-1; Path=docs; \
+            Domain=.example.com; secure; HttpOnly; SameSite=Strict; Unknown",
+        )
+        .unwrap();
+        assert_eq!(("id", "a3fWa"), (cookie.name(), cookie.value()));
+        assert_eq!(
+            HttpDate::from_bytes(b"Wed, 21 Oct 2015 07:28:00 GMT").ok(),
+            cookie.expires()
+        );
+        assert_eq!(Some(Duration::ZERO), cookie.max_age());
+        assert_eq!(None, cookie.path());
+        assert_eq!(Some("example.com"), cookie.domain());
+        assert!(cookie.is_secure() && cookie.is_http_only());
+        assert_eq!(Some(SameSite::Strict), cookie.same_site());
+
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            SetCookie::from_bytes(b"no-pair")
+        );
+        assert_eq!(Err(StatusCode::BAD_REQUEST), SetCookie::from_bytes(b"=x"));
+    }
+
+    #[test]
+    fn domain_and_path_matching() {
+        let host = |src: &str| Host::from_bytes(src.as_bytes()).unwrap();
+        assert!(domain_matches(&host("Example.com"), "example.com"));
+        assert!(domain_matches(&host("www.example.com"), ".example.com"));
+        assert!(!domain_matches(&host("badexample.com"), "example.com"));
+        assert!(!domain_matches(&host("example.com"), "www.example.com"));
+        assert!(domain_matches(&host("192.0.2.1"), "192.0.2.1"));
+        assert!(!domain_matches(&host("192.0.2.1"), "2.1"));
+
+        let path = |src: &str| Path::from_bytes(src.as_bytes()).unwrap();
+        assert!(path_matches(&path("/docs"), "/docs"));
+        assert!(path_matches(&path("/docs/web"), "/docs"));
+        assert!(path_matches(&path("/docs/web"), "/docs/"));
+        assert!(path_matches(&path(""), "/"));
+        assert!(!path_matches(&path("/documents"), "/docs"));
+        assert!(!path_matches(&path("/"), "/docs"));
+    }
+
+    #[test]
+    fn secure_cookie_only_matches_https() {
+        let cookie = SetCookie::new("id", "1").with_secure(true);
+        let uri = |src: &str| crate::http::Uri::from_bytes(src.as_bytes()).unwrap();
+        assert!(cookie.matches(&uri("https://example.com/")));
+        assert!(!cookie.matches(&uri("http://example.com/")));
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    fmt,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::StatusCode;
+
+const DAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+const LONG_DAY_NAMES: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// HTTP-date as defined in [RFC7231 Section
+/// 7.1.1.1](https://datatracker.ietf.org/doc/html/rfc7231#section-7.1.1.1), which is used by
+/// header fields such as `Date`, `Last-Modified`, `Expires` and `Retry-After`.
+///
+/// ```text
+/// HTTP-date = IMF-fixdate / obs-date
+///
+/// IMF-fixdate = day-name "," SP date1 SP time-of-day SP GMT
+/// ; fixed length/zone/capitalization subset of the format
+/// ; see Section 3.3 of [RFC5322]
+/// date1 = day SP month SP year
+/// ; e.g., 02 Jun 1982
+///
+/// obs-date = rfc850-date / asctime-date
+/// rfc850-date = day-name-l "," SP date2 SP time-of-day SP GMT
+/// date2 = day "-" month "-" 2DIGIT
+/// ; e.g., 02-Jun-82
+/// asctime-date = day-name SP date3 SP time-of-day SP year
+/// date3 = month SP ( 2DIGIT / ( SP 1DIGIT ))
+/// ; e.g., Jun  2
+///
+/// time-of-day = hour ":" minute ":" second
+/// ```
+///
+/// All three formats are parsed, but a [`HttpDate`] is always formatted as an `IMF-fixdate`.
+/// A HTTP-date has a resolution of one second and cannot be before the Unix epoch.
+///
+/// ```
+/// use ramus::http::HttpDate;
+///
+/// let imf = HttpDate::from_bytes(b"Sun, 06 Nov 1994 08:49:37 GMT").expect("valid date");
+/// let rfc850 = HttpDate::from_bytes(b"Sunday, 06-Nov-94 08:49:37 GMT").expect("valid date");
+/// let asctime = HttpDate::from_bytes(b"Sun Nov  6 08:49:37 1994").expect("valid date");
+/// assert_eq!(imf, rfc850);
+/// assert_eq!(imf, asctime);
+/// assert_eq!("Sun, 06 Nov 1994 08:49:37 GMT", asctime.to_string());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HttpDate(u64);
+
+impl HttpDate {
+    /// The current time as a [`HttpDate`].
+    pub fn now() -> Self {
+        Self::from(SystemTime::now())
+    }
+
+    /// Derive a [`HttpDate`] from a slice of bytes in any of the three formats.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the slice of bytes does not match the ABNF
+    /// syntax, is not a valid date, or is before the Unix epoch. The day name is not checked
+    /// against the date, as it is redundant.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        parse_imf_fixdate(src)
+            .or_else(|| parse_rfc850_date(src))
+            .or_else(|| parse_asctime_date(src))
+            .map(Self)
+            .ok_or(StatusCode::BAD_REQUEST)
+    }
+
+    /// Returns the number of seconds since the Unix epoch.
+    pub fn as_secs(&self) -> u64 {
+        self.0
+    }
+}
+
+impl From<SystemTime> for HttpDate {
+    /// Truncates the time to the second, and any time before the Unix epoch is the epoch.
+    fn from(time: SystemTime) -> Self {
+        Self(
+            time.duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+        )
+    }
+}
+
+impl From<HttpDate> for SystemTime {
+    fn from(date: HttpDate) -> Self {
+        UNIX_EPOCH + Duration::from_secs(date.0)
+    }
+}
+
+impl fmt::Display for HttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = self.0 / 86400;
+        let secs_of_day = self.0 % 86400;
+        let (year, month, day) = civil_from_days(days);
+        write!(
+            f,
+            "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+            // 1970-01-01 was a Thursday
+            DAY_NAMES[((days + 4) % 7) as usize],
+            day,
+            MONTH_NAMES[month as usize - 1],
+            year,
+            secs_of_day / 3600,
+            secs_of_day % 3600 / 60,
+            secs_of_day % 60
+        )
+    }
+}
+
+/// Returns the current time formatted as an `IMF-fixdate` for the `Date` header field.
+///
+/// The formatted date is cached and only formatted again once the second has changed, as a
+/// busy server would otherwise format the same date for many responses.
+pub(crate) fn cached_now() -> String {
+    static CACHE: Mutex<Option<(HttpDate, String)>> = Mutex::new(None);
+
+    let now = HttpDate::now();
+    let mut cache = CACHE.lock().unwrap_or_else(|error| error.into_inner());
+    match &*cache {
+        Some((date, formatted)) if *date == now => formatted.clone(),
+        _ => {
+            let formatted = now.to_string();
+            *cache = Some((now, formatted.clone()));
+            formatted
+        }
+    }
+}
+
+/// Parse an `IMF-fixdate`, such as "Sun, 06 Nov 1994 08:49:37 GMT".
+fn parse_imf_fixdate(src: &[u8]) -> Option<u64> {
+    let [d0, d1, d2, b',', b' ', day0, day1, b' ', m0, m1, m2, b' ', y0, y1, y2, y3, b' ', rest @ ..] =
+        src
+    else {
+        return None;
+    };
+    DAY_NAMES
+        .iter()
+        .position(|name| name.as_bytes() == [*d0, *d1, *d2])?;
+    let time = rest.strip_suffix(b" GMT")?;
+    let year = parse_digits(&[*y0, *y1, *y2, *y3])?;
+    to_secs(year, &[*m0, *m1, *m2], &[*day0, *day1], time)
+}
+
+/// Parse a `rfc850-date`, such as "Sunday, 06-Nov-94 08:49:37 GMT".
+///
+/// The two digit year is interpreted as the most recent year with those digits that is not
+/// more than 50 years in the future, as required by [RFC7231 Section
+/// 7.1.1.1](https://datatracker.ietf.org/doc/html/rfc7231#section-7.1.1.1).
+fn parse_rfc850_date(src: &[u8]) -> Option<u64> {
+    let (day_name, rest) = src.split_at(src.iter().position(|b| *b == b',')?);
+    LONG_DAY_NAMES
+        .iter()
+        .position(|name| name.as_bytes() == day_name)?;
+    let [b',', b' ', day0, day1, b'-', m0, m1, m2, b'-', y0, y1, b' ', rest @ ..] = rest else {
+        return None;
+    };
+    let time = rest.strip_suffix(b" GMT")?;
+
+    let (current_year, _, _) = civil_from_days(HttpDate::now().0 / 86400);
+    let mut year = current_year / 100 * 100 + parse_digits(&[*y0, *y1])?;
+    if year > current_year + 50 {
+        year -= 100;
+    }
+    to_secs(year, &[*m0, *m1, *m2], &[*day0, *day1], time)
+}
+
+/// Parse an `asctime-date`, such as "Sun Nov  6 08:49:37 1994".
+fn parse_asctime_date(src: &[u8]) -> Option<u64> {
+    let [d0, d1, d2, b' ', m0, m1, m2, b' ', day0, day1, b' ', rest @ ..] = src else {
+        return None;
+    };
+    DAY_NAMES
+        .iter()
+        .position(|name| name.as_bytes() == [*d0, *d1, *d2])?;
+    let [time @ .., b' ', y0, y1, y2, y3] = rest else {
+        return None;
+    };
+    let day = match (day0, day1) {
+        (b' ', day) => [b'0', *day],
+        (day0, day1) => [*day0, *day1],
+    };
+    let year = parse_digits(&[*y0, *y1, *y2, *y3])?;
+    to_secs(year, &[*m0, *m1, *m2], &day, time)
+}
+
+/// Returns the seconds since the Unix epoch of the date and the `time-of-day`.
+fn to_secs(year: u64, month: &[u8], day: &[u8], time: &[u8]) -> Option<u64> {
+    let month = MONTH_NAMES
+        .iter()
+        .position(|name| name.as_bytes() == month)? as u64
+        + 1;
+    let day = parse_digits(day)?;
+    let [h0, h1, b':', min0, min1, b':', s0, s1] = time else {
+        return None;
+    };
+    let (hour, minute, second) = (
+        parse_digits(&[*h0, *h1])?,
+        parse_digits(&[*min0, *min1])?,
+        parse_digits(&[*s0, *s1])?,
+    );
+    // RFC7231 Section 7.1.1.1: a second of 60 is allowed for a leap second
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day)?;
+    Some(days * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+fn parse_digits(src: &[u8]) -> Option<u64> {
+    src.iter().try_fold(0, |acc, b| {
+        b.is_ascii_digit().then(|| acc * 10 + u64::from(b - b'0'))
+    })
+}
+
+/// Returns the number of days since the Unix epoch of the date in the proleptic Gregorian
+/// calendar, or [`None`] if the date is not valid or is before the epoch.
+fn days_from_civil(year: u64, month: u64, day: u64) -> Option<u64> {
+    if !(1..=12).contains(&month) || day == 0 {
+        return None;
+    }
+    // the inverse of civil_from_days, with the year starting on 03-01
+    let year = if month <= 2 {
+        year.checked_sub(1)?
+    } else {
+        year
+    };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+    // a day past the end of the month rolls over into the next month
+    (civil_from_days(days).2 == day).then_some(days)
+}
+
+/// Returns the (year, month, day) of the number of days since the Unix epoch in the proleptic
+/// Gregorian calendar.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // shift the epoch to 0000-03-01 so that the leap day is at the end of each year
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as u64;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::http::StatusCode;
+
+    use super::{cached_now, civil_from_days, HttpDate};
+
+    fn date(secs: u64) -> HttpDate {
+        HttpDate::from(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn rfc_example() {
+        // Example from RFC7231 Section 7.1.1.1
+        assert_eq!(
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            date(784_111_777).to_string()
+        );
+    }
+
+    #[test]
+    fn leap_days_and_epoch() {
+        assert_eq!("Thu, 01 Jan 1970 00:00:00 GMT", date(0).to_string());
+        assert_eq!(
+            "Tue, 29 Feb 2000 23:59:59 GMT",
+            date(951_868_799).to_string()
+        );
+    }
+
+    #[test]
+    fn formatted_dates_are_parsed() {
+        for secs in [0, 784_111_777, 951_868_799, 951_868_800, 4_102_444_800] {
+            let date = date(secs);
+            assert_eq!(Ok(date), HttpDate::from_bytes(date.to_string().as_bytes()));
+        }
+    }
+
+    #[test]
+    fn system_time_is_truncated_to_the_second() {
+        let time = UNIX_EPOCH + Duration::from_millis(784_111_777_999);
+        let date = HttpDate::from(time);
+        assert_eq!(784_111_777, date.as_secs());
+        assert_eq!(
+            UNIX_EPOCH + Duration::from_secs(784_111_777),
+            std::time::SystemTime::from(date)
+        );
+        assert_eq!(
+            0,
+            HttpDate::from(UNIX_EPOCH - Duration::from_secs(1)).as_secs()
+        );
+    }
+
+    #[test]
+    fn obsolete_formats_are_parsed() {
+        assert_eq!(
+            Ok(date(784_111_777)),
+            HttpDate::from_bytes(b"Sunday, 06-Nov-94 08:49:37 GMT")
+        );
+        assert_eq!(
+            Ok(date(784_111_777)),
+            HttpDate::from_bytes(b"Sun Nov  6 08:49:37 1994")
+        );
+        assert_eq!(
+            Ok(date(1_133_913_600)),
+            HttpDate::from_bytes(b"Wed Dec 07 00:00:00 2005")
+        );
+    }
+
+    #[test]
+    fn rfc850_year_is_at_most_50_years_in_the_future() {
+        let (current_year, _, _) = civil_from_days(HttpDate::now().as_secs() / 86400);
+        let year_of = |src: &str| {
+            let date = HttpDate::from_bytes(src.as_bytes()).unwrap();
+            civil_from_days(date.as_secs() / 86400).0
+        };
+        let two_digits = |year: u64| format!("{:02}", year % 100);
+        assert_eq!(
+            current_year + 50,
+            year_of(&format!(
+                "Monday, 01-Jan-{} 00:00:00 GMT",
+                two_digits(current_year + 50)
+            ))
+        );
+        assert_eq!(
+            current_year - 49,
+            year_of(&format!(
+                "Monday, 01-Jan-{} 00:00:00 GMT",
+                two_digits(current_year + 51)
+            ))
+        );
+    }
+
+    #[test]
+    fn invalid_dates_are_a_bad_request() {
+        for src in [
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 6 Nov 1994 08:49:37 GMT",
+            "Sun, 06 nov 1994 08:49:37 GMT",
+            "sun, 06 Nov 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 24:00:00 GMT",
+            "Tue, 29 Feb 2001 00:00:00 GMT",
+            "Thu, 31 Dec 1969 23:59:59 GMT",
+            "Xyz, 06 Nov 1994 08:49:37 GMT",
+            "Sun, 06-Nov-94 08:49:37 GMT",
+            "Sunday, 06-Nov-1994 08:49:37 GMT",
+            "Sun Nov 6 08:49:37 1994",
+            "Sun Nov  6 08:49:37 94",
+        ] {
+            assert_eq!(
+                Err(StatusCode::BAD_REQUEST),
+                HttpDate::from_bytes(src.as_bytes()),
+                "{src}"
+            );
+        }
+    }
+
+    #[test]
+    fn cached_date_is_current() {
+        let before = HttpDate::now();
+        let cached = HttpDate::from_bytes(cached_now().as_bytes()).unwrap();
+        assert!(before <= cached && cached <= HttpDate::now());
+    }
+}
@@ -0,0 +1,322 @@
+use std::{
+    fmt,
+    io::{self, Read},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    time::Duration,
+};
+
+use super::{Request, Response, StatusCode};
+
+/// An event sent in an [`EventStream`], as defined in the [HTML Living Standard Section
+/// 9.2](https://html.spec.whatwg.org/multipage/server-sent-events.html#parsing-an-event-stream).
+///
+/// ```text
+/// event         = *( comment / field ) end-of-line
+/// comment       = colon *any-char end-of-line
+/// field         = 1*name-char [ colon [ space ] *any-char ] end-of-line
+/// ```
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use ramus::http::Event;
+///
+/// let event = Event::new("first\nsecond")
+///     .id("7")
+///     .event("update")
+///     .retry(Duration::from_secs(5));
+/// assert_eq!(
+///     "id: 7\nevent: update\nretry: 5000\ndata: first\ndata: second\n\n",
+///     event.to_string()
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    /// Create an [`Event`] with the data, which is sent as a `data` field for each line.
+    pub fn new(data: impl Into<String>) -> Self {
+        Self {
+            data: Some(data.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Set the `id` field, which the client sends back in the `Last-Event-ID` header field when
+    /// it reconnects, see [`EventStream::last_event_id`].
+    ///
+    /// Any line breaks or NULL characters are removed, as the client would ignore the field.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into().replace(['\r', '\n', '\0'], ""));
+        self
+    }
+
+    /// Set the `event` field, which is the type of the event on the client.
+    ///
+    /// Any line breaks are removed so that the value cannot start another field.
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into().replace(['\r', '\n'], ""));
+        self
+    }
+
+    /// Set the `retry` field, which is the time the client waits before reconnecting.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {id}")?;
+        }
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {event}")?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {}", retry.as_millis())?;
+        }
+        if let Some(data) = &self.data {
+            // a line may end with CRLF, LF or CR
+            for line in data.split("\r\n").flat_map(|line| line.split(['\r', '\n'])) {
+                writeln!(f, "data: {line}")?;
+            }
+        }
+        writeln!(f)
+    }
+}
+
+/// Sends [`Event`]s to an [`EventStream`], which can be moved to another thread.
+#[derive(Clone, Debug)]
+pub struct EventSender(Sender<Event>);
+
+impl EventSender {
+    /// Send the event to the client.
+    ///
+    /// # Errors
+    /// Returns an [`io::ErrorKind::BrokenPipe`] error once the [`EventStream`] has been dropped,
+    /// which happens when the client has gone away.
+    pub fn send(&self, event: Event) -> io::Result<()> {
+        self.0
+            .send(event)
+            .map_err(|_| io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+/// A `text/event-stream` response body for Server-Sent Events, as defined in the [HTML Living
+/// Standard Section 9.2](https://html.spec.whatwg.org/multipage/server-sent-events.html).
+///
+/// The events are sent as soon as they are given to the [`EventSender`], and a comment is sent
+/// when there has not been an event for the [`EventStream::keep_alive`] interval so that the
+/// connection is not closed as idle. The stream ends once every [`EventSender`] has been
+/// dropped.
+///
+/// ```no_run
+/// use std::thread;
+///
+/// use ramus::{
+///     http::{Event, EventStream, Request, Response},
+///     server::Server,
+/// };
+///
+/// Server::bind("127.0.0.1:8080")
+///     .expect("failed to bind")
+///     .serve(|request: &Request| {
+///         let next: u64 = EventStream::last_event_id(request)
+///             .and_then(|id| id.parse().ok())
+///             .map_or(0, |id: u64| id + 1);
+///         let (sender, stream) = EventStream::channel();
+///         thread::spawn(move || {
+///             for id in next.. {
+///                 let event = Event::new(format!("tick {id}")).id(id.to_string());
+///                 if sender.send(event).is_err() {
+///                     break;
+///                 }
+///                 thread::sleep(std::time::Duration::from_secs(1));
+///             }
+///         });
+///         Response::from(stream)
+///     })
+///     .expect("failed to accept connections");
+/// ```
+#[derive(Debug)]
+pub struct EventStream {
+    receiver: Receiver<Event>,
+    keep_alive: Duration,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl EventStream {
+    /// The default interval between keep-alive comments.
+    pub const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+    /// Create an [`EventStream`] and the [`EventSender`] for its events.
+    pub fn channel() -> (EventSender, Self) {
+        let (sender, receiver) = mpsc::channel();
+        let stream = Self {
+            receiver,
+            keep_alive: Self::KEEP_ALIVE,
+            buf: Vec::new(),
+            pos: 0,
+        };
+        (EventSender(sender), stream)
+    }
+
+    /// Set the interval after the last event before a keep-alive comment is sent.
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = interval;
+        self
+    }
+
+    /// Returns the `Last-Event-ID` header field of the request, which is the `id` of the last
+    /// [`Event`] that the client received before it reconnected.
+    pub fn last_event_id(request: &Request) -> Option<&str> {
+        request.headers().get("last-event-id")
+    }
+}
+
+impl Read for EventStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+            match self.receiver.recv_timeout(self.keep_alive) {
+                Ok(event) => {
+                    self.buf.extend_from_slice(event.to_string().as_bytes());
+                    // any events that are already waiting are sent together
+                    for event in self.receiver.try_iter() {
+                        self.buf.extend_from_slice(event.to_string().as_bytes());
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => self.buf.extend_from_slice(b":\n\n"),
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+            }
+        }
+
+        let read = (&self.buf[self.pos..]).read(buf)?;
+        self.pos += read;
+        Ok(read)
+    }
+}
+
+impl From<EventStream> for Response {
+    fn from(stream: EventStream) -> Self {
+        Response::new(StatusCode::OK)
+            .with_header("Content-Type", "text/event-stream")
+            .with_header("Cache-Control", "no-cache")
+            .with_stream(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpStream,
+        sync::{mpsc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        http::{test_request, Method, Request},
+        server::Server,
+    };
+
+    use super::{Event, EventStream};
+
+    #[test]
+    fn data_is_split_into_lines() {
+        assert_eq!(
+            "data: a\ndata: b\ndata: c\ndata: \n\n",
+            Event::new("a\r\nb\rc\n").to_string()
+        );
+        assert_eq!(
+            "retry: 10\n\n",
+            Event::default()
+                .retry(Duration::from_millis(10))
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn line_breaks_cannot_start_fields() {
+        let event = Event::default().id("1\n\0data: x").event("a\r\nretry: 0");
+        assert_eq!("id: 1data: x\nevent: aretry: 0\n\n", event.to_string());
+    }
+
+    #[test]
+    fn stream_ends_when_senders_are_dropped() {
+        let (sender, mut stream) = EventStream::channel();
+        sender.send(Event::new("1")).unwrap();
+        sender.send(Event::new("2")).unwrap();
+        drop(sender);
+        let mut body = String::new();
+        stream.read_to_string(&mut body).unwrap();
+        assert_eq!("data: 1\n\ndata: 2\n\n", body);
+    }
+
+    #[test]
+    fn sender_fails_once_stream_is_dropped() {
+        let (sender, stream) = EventStream::channel();
+        drop(stream);
+        assert!(sender.send(Event::new("gone")).is_err());
+    }
+
+    #[test]
+    fn comment_is_sent_when_idle() {
+        let (sender, stream) = EventStream::channel();
+        let mut stream = stream.keep_alive(Duration::from_millis(10));
+        let mut buf = [0; 16];
+        let read = stream.read(&mut buf).unwrap();
+        assert_eq!(b":\n\n", &buf[..read]);
+        drop(sender);
+    }
+
+    #[test]
+    fn last_event_id_is_read_from_request() {
+        let request = test_request(Method::Get, "/", &[("Last-Event-ID", "41")], "");
+        assert_eq!(Some("41"), EventStream::last_event_id(&request));
+    }
+
+    #[test]
+    fn events_are_flushed_through_chunked_encoding() {
+        let (done, wait) = mpsc::channel::<()>();
+        let wait = Mutex::new(Some(wait));
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || {
+            server.serve(move |_: &Request| {
+                let (sender, stream) = EventStream::channel();
+                let wait = wait.lock().unwrap().take().unwrap();
+                thread::spawn(move || {
+                    sender.send(Event::new("hello").id("1")).unwrap();
+                    // the stream stays open until the client has read the event
+                    let _ = wait.recv();
+                });
+                stream
+            })
+        });
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"GET /events HTTP/1.1\r\nHost: a\r\n\r\n")
+            .unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut received = String::new();
+        while !received.ends_with("data: hello\n\n\r\n") {
+            reader.read_line(&mut received).unwrap();
+        }
+        assert!(received.contains("content-type: text/event-stream\r\n"));
+        assert!(received.contains("transfer-encoding: chunked\r\n"));
+        assert!(received.contains("\r\n\r\n13\r\nid: 1\ndata: hello\n\n\r\n"));
+        done.send(()).unwrap();
+    }
+}
@@ -0,0 +1,52 @@
+use super::{Request, Response, StatusCode};
+
+/// A type that can be derived from a [`Request`], such as a parsed message body, which lets a
+/// handler take it as an argument, see [`Extract`](crate::server::Extract).
+///
+/// The [`FromRequest::Rejection`] returned when the request does not have a valid value is
+/// sent as the response, without calling the handler. This is usually a [`StatusCode`].
+///
+/// ```
+/// use ramus::http::{FromRequest, Request, StatusCode};
+///
+/// struct UserAgent(String);
+///
+/// impl FromRequest for UserAgent {
+///     type Rejection = StatusCode;
+///
+///     fn from_request(request: &Request) -> Result<Self, StatusCode> {
+///         request
+///             .headers()
+///             .get("user-agent")
+///             .map(|value| UserAgent(value.to_string()))
+///             .ok_or(StatusCode::BAD_REQUEST)
+///     }
+/// }
+/// ```
+pub trait FromRequest: Sized {
+    /// The error that is sent as the response when the value cannot be derived.
+    type Rejection: Into<Response>;
+
+    /// Derive the value from the request.
+    fn from_request(request: &Request) -> Result<Self, Self::Rejection>;
+}
+
+impl<T: FromRequest> FromRequest for Option<T> {
+    type Rejection = StatusCode;
+
+    /// Never fails, the value is [`None`] when it cannot be derived from the request.
+    fn from_request(request: &Request) -> Result<Self, StatusCode> {
+        Ok(T::from_request(request).ok())
+    }
+}
+
+impl<A: FromRequest, B: FromRequest> FromRequest for (A, B) {
+    type Rejection = Response;
+
+    /// Derive both values, failing with the first that fails.
+    fn from_request(request: &Request) -> Result<Self, Response> {
+        let a = A::from_request(request).map_err(Into::into)?;
+        let b = B::from_request(request).map_err(Into::into)?;
+        Ok((a, b))
+    }
+}
@@ -0,0 +1,294 @@
+use super::{utils, FromRequest, Request, StatusCode};
+
+/// The name and value pairs of an `application/x-www-form-urlencoded` body, as sent by a HTML
+/// form with the "POST" method, see the [URL
+/// Standard](https://url.spec.whatwg.org/#application/x-www-form-urlencoded).
+///
+/// The pairs are decoded with the same rules as a query, see [`Query::pairs`]. A [`Form`] can
+/// be taken as the argument of a handler, see [`Extract`](crate::server::Extract), and with
+/// the `serde` feature it can be deserialized into a struct with `Form::deserialize`.
+///
+/// ```
+/// use ramus::http::Form;
+///
+/// let form = Form::from_bytes(b"tag=a&tag=b&note=hello+world").expect("valid form");
+/// assert_eq!(Some("a"), form.get("tag"));
+/// assert_eq!(vec!["a", "b"], form.get_all("tag").collect::<Vec<_>>());
+/// assert_eq!(Some("hello world"), form.get("note"));
+/// ```
+///
+/// [`Query::pairs`]: super::Query::pairs
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Form(Vec<(String, String)>);
+
+impl Form {
+    /// The maximum number of octets in a body that is derived with [`FromRequest`], see
+    /// [`Form::from_request_with_limit`] for another limit.
+    pub const MAX_LENGTH: usize = 64 * 1024;
+
+    /// Derive a [`Form`] from a slice of bytes.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if a name or value is not validly encoded.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        utils::decode_pairs(src).map(Self)
+    }
+
+    /// Derive a [`Form`] from the body of a request, which must not be longer than the limit.
+    ///
+    /// Returns a [`StatusCode::UNSUPPORTED_MEDIA_TYPE`] unless the `Content-Type` is
+    /// `application/x-www-form-urlencoded`, a [`StatusCode::PAYLOAD_TOO_LARGE`] when the body
+    /// is longer than the limit and a [`StatusCode::BAD_REQUEST`] if it is not validly encoded.
+    pub fn from_request_with_limit(request: &Request, limit: usize) -> Result<Self, StatusCode> {
+        if request
+            .content_type()
+            .is_none_or(|media_type| media_type.essence() != "application/x-www-form-urlencoded")
+        {
+            return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+        if request.body().len() > limit {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        Self::from_bytes(request.body())
+    }
+
+    /// Returns the value of the first pair with the name, which is case-sensitive.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the values of all of the pairs with the name, in the order they were sent.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.0
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns all of the pairs in the order they were sent.
+    pub fn pairs(&self) -> &[(String, String)] {
+        &self.0
+    }
+
+    /// Deserialize the pairs into a type, such as a struct with a field for each name.
+    ///
+    /// Each value is parsed into the type of its field, so a field can be a string, a number,
+    /// a bool, a unit enum or an [`Option`] of them for a name that may not be sent.
+    ///
+    /// Returns a [`StatusCode::UNPROCESSABLE_ENTITY`] if the pairs do not match the type.
+    ///
+    /// ```
+    /// use ramus::http::{Form, StatusCode};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Login {
+    ///     user: String,
+    ///     remember: Option<bool>,
+    /// }
+    ///
+    /// let login: Login = Form::from_bytes(b"user=ferris&remember=true")
+    ///     .and_then(|form| form.deserialize())
+    ///     .expect("valid login");
+    /// assert_eq!("ferris", login.user);
+    /// assert_eq!(Some(true), login.remember);
+    ///
+    /// let form = Form::from_bytes(b"remember=true").expect("valid form");
+    /// assert_eq!(
+    ///     Err(StatusCode::UNPROCESSABLE_ENTITY),
+    ///     form.deserialize::<Login>().map(|_| ())
+    /// );
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn deserialize<T: serde::de::DeserializeOwned>(&self) -> Result<T, StatusCode> {
+        de::from_pairs(&self.0).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)
+    }
+}
+
+impl FromRequest for Form {
+    type Rejection = StatusCode;
+
+    /// Derive a [`Form`] from a body of at most [`Form::MAX_LENGTH`] octets, see
+    /// [`Form::from_request_with_limit`].
+    fn from_request(request: &Request) -> Result<Self, StatusCode> {
+        Self::from_request_with_limit(request, Self::MAX_LENGTH)
+    }
+}
+
+/// A deserializer of name and value pairs, where each value is parsed as the type that is
+/// asked for as a form has no other types.
+#[cfg(feature = "serde")]
+mod de {
+    use serde::de::{
+        self,
+        value::{Error, MapDeserializer},
+        DeserializeOwned, IntoDeserializer, Visitor,
+    };
+
+    pub(super) fn from_pairs<T: DeserializeOwned>(pairs: &[(String, String)]) -> Result<T, Error> {
+        T::deserialize(MapDeserializer::new(
+            pairs
+                .iter()
+                .map(|(name, value)| (name.as_str(), Value(value))),
+        ))
+    }
+
+    struct Value<'a>(&'a str);
+
+    impl<'de> IntoDeserializer<'de, Error> for Value<'_> {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    macro_rules! parse {
+        ($($deserialize:ident => $visit:ident,)*) => {
+            $(
+                fn $deserialize<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                    visitor.$visit(self.0.parse().map_err(de::Error::custom)?)
+                }
+            )*
+        };
+    }
+
+    impl<'de> de::Deserializer<'de> for Value<'_> {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_str(self.0)
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_some(self)
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            _variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_enum(self.0.into_deserializer())
+        }
+
+        parse! {
+            deserialize_bool => visit_bool,
+            deserialize_i8 => visit_i8,
+            deserialize_i16 => visit_i16,
+            deserialize_i32 => visit_i32,
+            deserialize_i64 => visit_i64,
+            deserialize_u8 => visit_u8,
+            deserialize_u16 => visit_u16,
+            deserialize_u32 => visit_u32,
+            deserialize_u64 => visit_u64,
+            deserialize_f32 => visit_f32,
+            deserialize_f64 => visit_f64,
+        }
+
+        serde::forward_to_deserialize_any! {
+            char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+            identifier ignored_any
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::{test_request, FromRequest, Method, StatusCode};
+
+    use super::Form;
+
+    #[test]
+    fn body_is_decoded_like_a_query() {
+        let request = test_request(
+            Method::Post,
+            "/",
+            &[(
+                "Content-Type",
+                "application/x-www-form-urlencoded; charset=utf-8",
+            )],
+            "q=a+b%26c&empty=",
+        );
+        let form = Form::from_request(&request).unwrap();
+        assert_eq!(Some("a b&c"), form.get("q"));
+        assert_eq!(Some(""), form.get("empty"));
+    }
+
+    #[test]
+    fn other_content_type_is_unsupported_media_type() {
+        for content_type in ["text/plain", "multipart/form-data; boundary=x"] {
+            assert_eq!(
+                Err(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+                Form::from_request(&test_request(
+                    Method::Post,
+                    "/",
+                    &[("Content-Type", content_type)],
+                    "a=1"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn body_above_limit_is_payload_too_large() {
+        let request = test_request(
+            Method::Post,
+            "/",
+            &[("Content-Type", "application/x-www-form-urlencoded")],
+            "a=12345",
+        );
+        assert_eq!(
+            Err(StatusCode::PAYLOAD_TOO_LARGE),
+            Form::from_request_with_limit(&request, 6)
+        );
+        assert!(Form::from_request_with_limit(&request, 7).is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn values_are_parsed_as_field_types() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        #[serde(rename_all = "lowercase")]
+        enum Size {
+            Small,
+            Large,
+        }
+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Order {
+            count: u32,
+            size: Size,
+            gift: bool,
+            note: Option<String>,
+        }
+
+        let form = Form::from_bytes(b"count=3&size=large&gift=false").unwrap();
+        assert_eq!(
+            Ok(Order {
+                count: 3,
+                size: Size::Large,
+                gift: false,
+                note: None,
+            }),
+            form.deserialize()
+        );
+
+        let form = Form::from_bytes(b"count=three&size=small&gift=true").unwrap();
+        assert_eq!(
+            Err(StatusCode::UNPROCESSABLE_ENTITY),
+            form.deserialize::<Order>()
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use super::{
+    utils::{self, split_at_next},
+    StatusCode,
+};
+
+/// Header field as defined in [RFC7230 Section
+/// 3.2](https://datatracker.ietf.org/doc/html/rfc7230#section-3.2).
+///
+/// ```text
+/// header-field = field-name ":" OWS field-value OWS
+///
+/// field-name = token
+/// field-value = *( field-content / obs-fold )
+/// field-content = field-vchar [ 1*( SP / HTAB ) field-vchar ]
+/// field-vchar = VCHAR / obs-text
+/// ```
+///
+/// The `field-name` is case-insensitive and so is normalized to lowercase.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeaderField {
+    name: String,
+    value: String,
+}
+
+impl HeaderField {
+    /// Create a [`HeaderField`] from a name and value.
+    ///
+    /// The name will be normalized to lowercase, no other validation is done so the caller must
+    /// make sure that the name is a valid `token` and that the value does not contain a CR or LF.
+    pub fn new(name: &str, value: impl Into<String>) -> Self {
+        Self {
+            name: name.to_ascii_lowercase(),
+            value: value.into(),
+        }
+    }
+
+    /// Derive a [`HeaderField`] from a slice of bytes, the slice should not include the
+    /// trailing CRLF.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the slice of bytes does not match the ABNF
+    /// syntax, this includes any whitespace between the `field-name` and colon as required by
+    /// [RFC7230 Section 3.2.4](https://datatracker.ietf.org/doc/html/rfc7230#section-3.2.4).
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        let (name_bytes, rest) = split_at_next(src, b':').ok_or(StatusCode::BAD_REQUEST)?;
+        let name = utils::abnf::parse_token(name_bytes)
+            .filter(|name| name.len() == name_bytes.len())
+            .ok_or(StatusCode::BAD_REQUEST)?;
+
+        let value_bytes = utils::abnf::trim_ows(rest);
+        if !value_bytes
+            .iter()
+            .all(|b| utils::abnf::is_field_vchar(*b) || utils::abnf::is_ows(*b))
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        Ok(Self {
+            name: name.to_ascii_lowercase(),
+            value: String::from_utf8_lossy(value_bytes).into_owned(),
+        })
+    }
+
+    /// Returns the lowercase `field-name`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the `field-value` without any leading or trailing whitespace.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// An ordered collection of [`HeaderField`]s that make up the header section of a message.
+///
+/// Lookups by name are case-insensitive and multiple fields with the same name are kept in the
+/// order they were added.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Headers(Vec<HeaderField>);
+
+impl Headers {
+    /// Create an empty [`Headers`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of the first field with the given name.
+    ///
+    /// ```
+    /// use ramus::http::{HeaderField, Headers};
+    ///
+    /// let mut headers = Headers::new();
+    /// headers.append(HeaderField::from_bytes(b"Host: example.com").unwrap());
+    /// assert_eq!(Some("example.com"), headers.get("host"));
+    /// ```
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|field| field.name.eq_ignore_ascii_case(name))
+            .map(HeaderField::value)
+    }
+
+    /// Returns an iterator over the values of all the fields with the given name.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.0
+            .iter()
+            .filter(move |field| field.name.eq_ignore_ascii_case(name))
+            .map(HeaderField::value)
+    }
+
+    /// Returns true if there is at least one field with the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Add a field to the end of the collection, keeping any existing fields with the same name.
+    pub fn append(&mut self, field: HeaderField) {
+        self.0.push(field);
+    }
+
+    /// Add a field to the collection, removing any existing fields with the same name.
+    pub fn insert(&mut self, name: &str, value: impl Into<String>) {
+        self.remove(name);
+        self.append(HeaderField::new(name, value));
+    }
+
+    /// Remove all the fields with the given name.
+    pub fn remove(&mut self, name: &str) {
+        self.0
+            .retain(|field| !field.name.eq_ignore_ascii_case(name));
+    }
+
+    /// Returns an iterator over all of the fields in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &HeaderField> {
+        self.0.iter()
+    }
+
+    /// Returns the number of fields.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if there are no fields.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod header_field_tests {
+    use super::{HeaderField, StatusCode};
+
+    fn assert_is_bad_request(bytes: &[u8]) {
+        assert_eq!(Err(StatusCode::BAD_REQUEST), HeaderField::from_bytes(bytes));
+    }
+
+    #[test]
+    fn empty_is_a_bad_request() {
+        assert_is_bad_request(&[]);
+    }
+
+    #[test]
+    fn missing_colon_is_a_bad_request() {
+        assert_is_bad_request(b"Host example.com");
+    }
+
+    #[test]
+    fn whitespace_before_colon_is_a_bad_request() {
+        assert_is_bad_request(b"Host : example.com");
+    }
+
+    #[test]
+    fn control_chars_in_value_is_a_bad_request() {
+        assert_is_bad_request(b"Host: exa\x00mple.com");
+    }
+
+    #[test]
+    fn name_is_lowercase_and_value_is_trimmed() {
+        assert_eq!(
+            Ok(HeaderField {
+                name: "content-type".to_owned(),
+                value: "text/html; charset=utf-8".to_owned(),
+            }),
+            HeaderField::from_bytes(b"Content-Type: \t text/html; charset=utf-8 \t")
+        );
+    }
+
+    #[test]
+    fn empty_value_is_valid() {
+        assert_eq!(
+            Ok(HeaderField {
+                name: "accept".to_owned(),
+                value: String::new(),
+            }),
+            HeaderField::from_bytes(b"Accept:")
+        );
+    }
+}
+
+#[cfg(test)]
+mod headers_tests {
+    use super::{HeaderField, Headers};
+
+    #[test]
+    fn lookups_are_case_insensitive() {
+        let mut headers = Headers::new();
+        headers.append(HeaderField::new("Content-Length", "10"));
+        assert_eq!(Some("10"), headers.get("CONTENT-LENGTH"));
+        assert!(headers.contains("content-length"));
+    }
+
+    #[test]
+    fn insert_replaces_all_fields_with_the_same_name() {
+        let mut headers = Headers::new();
+        headers.append(HeaderField::new("Vary", "Accept"));
+        headers.append(HeaderField::new("Vary", "Origin"));
+        assert_eq!(2, headers.get_all("vary").count());
+
+        headers.insert("vary", "*");
+        assert_eq!(vec!["*"], headers.get_all("vary").collect::<Vec<_>>());
+    }
+}
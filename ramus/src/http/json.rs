@@ -0,0 +1,268 @@
+use std::fmt;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{FromRequest, MediaType, Request, Response, StatusCode};
+
+/// A JSON body as defined in [RFC8259](https://datatracker.ietf.org/doc/html/rfc8259), which
+/// is deserialized from a request and serialized into a response with `serde`.
+///
+/// As a [`FromRequest`] type the `Content-Type` must be `application/json`, or any
+/// `application` subtype with the "+json" suffix, and the body must not be longer than
+/// [`Json::MAX_LENGTH`]. The [`JsonError`] of a failure is sent as a JSON object with an
+/// "error" message.
+///
+/// ```
+/// use ramus::http::{Json, Response};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Pet {
+///     name: String,
+/// }
+///
+/// let response = Response::from(Json(Pet { name: "ferris".to_string() }));
+/// assert_eq!(Some("application/json"), response.headers().get("content-type"));
+///
+/// let Json(pet) = Json::<Pet>::from_slice(br#"{"name":"ferris"}"#).expect("valid pet");
+/// assert_eq!("ferris", pet.name);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    /// The maximum number of octets in a body that is derived with [`FromRequest`], see
+    /// [`Json::from_request_with_limit`] for another limit.
+    pub const MAX_LENGTH: usize = 1024 * 1024;
+
+    /// Consumes the [`Json`] and returns the value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DeserializeOwned> Json<T> {
+    /// Deserialize a value from a slice of bytes.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the bytes are not valid JSON and a
+    /// [`StatusCode::UNPROCESSABLE_ENTITY`] if the JSON does not match the type, such as a
+    /// missing field.
+    pub fn from_slice(src: &[u8]) -> Result<Self, JsonError> {
+        serde_json::from_slice(src).map(Self).map_err(|error| {
+            let status = match error.classify() {
+                serde_json::error::Category::Data => StatusCode::UNPROCESSABLE_ENTITY,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            JsonError::new(status, error.to_string())
+        })
+    }
+
+    /// Deserialize a value from the body of a request, which must not be longer than the
+    /// limit.
+    ///
+    /// Returns a [`StatusCode::UNSUPPORTED_MEDIA_TYPE`] for any other `Content-Type`, a
+    /// [`StatusCode::PAYLOAD_TOO_LARGE`] when the body is longer than the limit, and the
+    /// errors of [`Json::from_slice`].
+    pub fn from_request_with_limit(request: &Request, limit: usize) -> Result<Self, JsonError> {
+        if !request.content_type().as_ref().is_some_and(is_json) {
+            return Err(JsonError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "expected a Content-Type of application/json",
+            ));
+        }
+        if request.body().len() > limit {
+            return Err(JsonError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("the body is longer than {limit} octets"),
+            ));
+        }
+        Self::from_slice(request.body())
+    }
+}
+
+fn is_json(media_type: &MediaType) -> bool {
+    media_type.type_() == "application"
+        && (media_type.subtype() == "json" || media_type.subtype().ends_with("+json"))
+}
+
+impl<T: DeserializeOwned> FromRequest for Json<T> {
+    type Rejection = JsonError;
+
+    /// Deserialize a value from a body of at most [`Json::MAX_LENGTH`] octets, see
+    /// [`Json::from_request_with_limit`].
+    fn from_request(request: &Request) -> Result<Self, JsonError> {
+        Self::from_request_with_limit(request, Self::MAX_LENGTH)
+    }
+}
+
+impl<T: Serialize> From<Json<T>> for Response {
+    /// A [`StatusCode::OK`] response with the serialized value, see [`Response::with_json`].
+    fn from(json: Json<T>) -> Self {
+        Response::new(StatusCode::OK).with_json(&json.0)
+    }
+}
+
+impl Response {
+    /// Set the message body of the response to the value serialized as JSON, with a
+    /// `Content-Type` of `application/json`.
+    ///
+    /// The response is replaced by a [`StatusCode::INTERNAL_SERVER_ERROR`] if the value cannot
+    /// be serialized, such as a map with keys that are not strings.
+    ///
+    /// ```
+    /// use ramus::http::{Body, Response, StatusCode};
+    ///
+    /// let response = Response::new(StatusCode::CREATED).with_json(&[1, 2, 3]);
+    /// assert!(matches!(response.body(), Body::Bytes(body) if body == b"[1,2,3]"));
+    /// ```
+    pub fn with_json<T: Serialize + ?Sized>(self, value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => self
+                .with_header("Content-Type", "application/json")
+                .with_body(body),
+            Err(_) => Response::new(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+}
+
+/// The error of a [`Json`] body that could not be deserialized, with a message that says why.
+///
+/// It is sent as a response with the [`StatusCode`] and a JSON object with the message as the
+/// "error", such as `{"error":"expected value at line 1 column 1"}`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonError {
+    status: StatusCode,
+    message: String,
+}
+
+impl JsonError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the [`StatusCode`] of the error.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the message that says why the body could not be deserialized.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl From<JsonError> for Response {
+    fn from(error: JsonError) -> Self {
+        Response::new(error.status).with_json(&serde_json::json!({ "error": error.message }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::http::{test_request, Body, FromRequest, Method, Response, StatusCode};
+
+    use super::Json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Pet {
+        name: String,
+        age: u8,
+    }
+
+    #[test]
+    fn json_content_types_are_deserialized() {
+        for content_type in [
+            "application/json",
+            "application/json; charset=utf-8",
+            "application/problem+json",
+        ] {
+            let request = test_request(
+                Method::Post,
+                "/",
+                &[("Content-Type", content_type)],
+                r#"{"name":"ferris","age":7}"#,
+            );
+            assert_eq!(
+                Ok(Json(Pet {
+                    name: "ferris".to_string(),
+                    age: 7
+                })),
+                Json::from_request(&request)
+            );
+        }
+    }
+
+    #[test]
+    fn errors_have_a_status_and_message() {
+        let status = |content_type, body| {
+            Json::<Pet>::from_request(&test_request(
+                Method::Post,
+                "/",
+                &[("Content-Type", content_type)],
+                body,
+            ))
+            .unwrap_err()
+            .status()
+        };
+        assert_eq!(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            status("text/plain", r#"{"name":"ferris","age":7}"#)
+        );
+        assert_eq!(StatusCode::BAD_REQUEST, status("application/json", "{"));
+        assert_eq!(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            status("application/json", r#"{"name":"ferris","age":-1}"#)
+        );
+
+        let error = Json::<Pet>::from_request_with_limit(
+            &test_request(
+                Method::Post,
+                "/",
+                &[("Content-Type", "application/json")],
+                r#"{"name":"ferris"}"#,
+            ),
+            64,
+        )
+        .unwrap_err();
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, error.status());
+        assert!(error.message().contains("missing field `age`"));
+
+        let response = Response::from(error);
+        assert_eq!(
+            Some("application/json"),
+            response.headers().get("content-type")
+        );
+        assert!(
+            matches!(response.body(), Body::Bytes(body) if body.starts_with(br#"{"error":"missing field"#))
+        );
+    }
+
+    #[test]
+    fn body_above_limit_is_payload_too_large() {
+        let request = test_request(
+            Method::Post,
+            "/",
+            &[("Content-Type", "application/json")],
+            r#"{"name":"ferris","age":7}"#,
+        );
+        assert_eq!(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json::<Pet>::from_request_with_limit(&request, 8)
+                .unwrap_err()
+                .status()
+        );
+    }
+}
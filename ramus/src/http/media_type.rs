@@ -0,0 +1,263 @@
+use std::fmt;
+
+use super::{utils::abnf, StatusCode};
+
+/// Media type as defined in [RFC7231 Section
+/// 3.1.1.1](https://datatracker.ietf.org/doc/html/rfc7231#section-3.1.1.1), which is used by
+/// `Content-Type` and `Accept`.
+///
+/// ```text
+/// media-type = type "/" subtype *( OWS ";" OWS parameter )
+/// type = token
+/// subtype = token
+///
+/// parameter = token "=" ( token / quoted-string )
+/// ```
+///
+/// The type, subtype and parameter names are case-insensitive and so are normalized to
+/// lowercase, as is the value of the `charset` parameter. The values of other parameters may
+/// be case-sensitive and are kept as they are, without the quotes of a `quoted-string`.
+///
+/// ```
+/// use ramus::http::MediaType;
+///
+/// let media_type =
+///     MediaType::from_bytes(b"Text/HTML; Charset=\"UTF-8\"").expect("valid media type");
+/// assert_eq!("text", media_type.type_());
+/// assert_eq!("html", media_type.subtype());
+/// assert_eq!(Some("utf-8"), media_type.charset());
+/// assert_eq!("text/html; charset=utf-8", media_type.to_string());
+/// assert_eq!(MediaType::from_bytes(b"text/html;charset=utf-8"), Ok(media_type));
+/// ```
+#[derive(Clone, Debug)]
+pub struct MediaType {
+    type_: String,
+    subtype: String,
+    params: Vec<(String, String)>,
+}
+
+impl MediaType {
+    /// Derive a [`MediaType`] from a slice of bytes.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the slice of bytes does not match the ABNF
+    /// syntax.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        let mut parts = abnf::split_unquoted(abnf::trim_ows(src), b';').into_iter();
+        let essence = parts.next().unwrap_or_default();
+        let (type_, subtype) = essence
+            .iter()
+            .position(|b| *b == b'/')
+            .map(|i| (&essence[..i], &essence[i + 1..]))
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        let mut media_type = Self {
+            type_: parse_whole_token(type_)?.to_ascii_lowercase(),
+            subtype: parse_whole_token(subtype)?.to_ascii_lowercase(),
+            params: Vec::new(),
+        };
+        for param in parts {
+            let (name, value) = parse_param(param)?;
+            media_type = media_type.with_param(&name, value);
+        }
+        Ok(media_type)
+    }
+
+    /// Returns the top-level type, such as "text".
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    /// Returns the subtype, such as "html".
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// Returns the type and subtype without any parameters, such as "text/html".
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_, self.subtype)
+    }
+
+    /// Returns the parameters in the order they were given.
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    /// Returns the value of the parameter, the name is case-insensitive.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the `charset` parameter, as defined in [RFC7231 Section
+    /// 3.1.1.2](https://datatracker.ietf.org/doc/html/rfc7231#section-3.1.1.2).
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// Returns the `boundary` parameter of a multipart media type, as defined in [RFC2046
+    /// Section 5.1.1](https://datatracker.ietf.org/doc/html/rfc2046#section-5.1.1).
+    pub fn boundary(&self) -> Option<&str> {
+        self.param("boundary")
+    }
+
+    /// Set a parameter, replacing any existing parameter with the same name.
+    ///
+    /// The caller must make sure that the name is a valid `token`.
+    pub fn with_param(mut self, name: &str, value: impl Into<String>) -> Self {
+        let name = name.to_ascii_lowercase();
+        let mut value = value.into();
+        if name == "charset" {
+            value.make_ascii_lowercase();
+        }
+        match self.params.iter_mut().find(|(n, _)| *n == name) {
+            Some(param) => param.1 = value,
+            None => self.params.push((name, value)),
+        }
+        self
+    }
+
+    /// Returns true if the type or subtype is a "*" wildcard, as used by a `media-range` of
+    /// `Accept`.
+    pub fn is_wildcard(&self) -> bool {
+        self.type_ == "*" || self.subtype == "*"
+    }
+
+    /// Returns true if this media type, as a `media-range`, matches the other media type, as
+    /// defined in [RFC7231 Section
+    /// 5.3.2](https://datatracker.ietf.org/doc/html/rfc7231#section-5.3.2).
+    ///
+    /// "*/*" matches any media type and "type/*" matches any subtype of the type. Any
+    /// parameters of this media type must also be parameters of the other media type.
+    ///
+    /// ```
+    /// use ramus::http::MediaType;
+    ///
+    /// let media_type = |src: &str| MediaType::from_bytes(src.as_bytes()).expect("valid");
+    /// let json = media_type("application/json; charset=utf-8");
+    /// assert!(media_type("*/*").matches(&json));
+    /// assert!(media_type("application/*").matches(&json));
+    /// assert!(media_type("application/json").matches(&json));
+    /// assert!(!media_type("application/json; charset=utf-16").matches(&json));
+    /// assert!(!media_type("text/*").matches(&json));
+    /// ```
+    pub fn matches(&self, other: &MediaType) -> bool {
+        let essence_matches = match (self.type_.as_str(), self.subtype.as_str()) {
+            ("*", "*") => true,
+            (type_, "*") => type_ == other.type_,
+            (type_, subtype) => type_ == other.type_ && subtype == other.subtype,
+        };
+        essence_matches
+            && self
+                .params
+                .iter()
+                .all(|(name, value)| other.param(name) == Some(value))
+    }
+}
+
+impl PartialEq for MediaType {
+    /// Media types are equal when they have the same type, subtype and parameters, in any order.
+    fn eq(&self, other: &Self) -> bool {
+        self.type_ == other.type_
+            && self.subtype == other.subtype
+            && self.params.len() == other.params.len()
+            && self.matches(other)
+    }
+}
+
+impl Eq for MediaType {}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+        for (name, value) in &self.params {
+            if !value.is_empty() && value.bytes().all(abnf::is_tchar) {
+                write!(f, "; {name}={value}")?;
+            } else {
+                // RFC7230 Section 3.2.6: a quoted-pair for DQUOTE and backslash
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "; {name}=\"{escaped}\"")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_whole_token(src: &[u8]) -> Result<String, StatusCode> {
+    abnf::parse_token(src)
+        .filter(|token| token.len() == src.len())
+        .ok_or(StatusCode::BAD_REQUEST)
+}
+
+/// Parse a `parameter`, returning the name and the value without the quotes of a
+/// `quoted-string`.
+pub(crate) fn parse_param(src: &[u8]) -> Result<(String, String), StatusCode> {
+    let (name, value) = src
+        .iter()
+        .position(|b| *b == b'=')
+        .map(|i| (&src[..i], &src[i + 1..]))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let name = parse_whole_token(name)?;
+    let value = match abnf::parse_quoted_string(value) {
+        Some((value, [])) => value,
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+        None => parse_whole_token(value)?,
+    };
+    Ok((name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::StatusCode;
+
+    use super::MediaType;
+
+    fn media_type(src: &str) -> MediaType {
+        MediaType::from_bytes(src.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn invalid_media_type_is_a_bad_request() {
+        for src in [
+            &b"text"[..],
+            b"text/",
+            b"/html",
+            b"text/html/x",
+            b"text /html",
+            b"text/html;",
+            b"text/html; charset",
+            b"text/html; charset=\"utf-8",
+            b"text/html; charset=\"utf-8\"x",
+            b"text/html; charset=a b",
+        ] {
+            assert_eq!(Err(StatusCode::BAD_REQUEST), MediaType::from_bytes(src));
+        }
+    }
+
+    #[test]
+    fn quoted_values_are_unquoted_and_requoted() {
+        let media_type = media_type(r#"multipart/form-data; boundary="a b\"c""#);
+        assert_eq!(Some("a b\"c"), media_type.boundary());
+        assert_eq!(
+            r#"multipart/form-data; boundary="a b\"c""#,
+            media_type.to_string()
+        );
+    }
+
+    #[test]
+    fn parameters_are_compared_in_any_order() {
+        assert_eq!(
+            media_type("text/plain; a=1; charset=UTF-8"),
+            media_type("TEXT/plain;charset=utf-8;A=1")
+        );
+        assert_ne!(media_type("text/plain; a=1"), media_type("text/plain; a=2"));
+        assert_ne!(media_type("text/plain; a=1"), media_type("text/plain"));
+    }
+
+    #[test]
+    fn repeated_parameter_replaces_the_first() {
+        let media_type = media_type("text/plain; charset=ascii; charset=utf-8");
+        assert_eq!(1, media_type.params().len());
+        assert_eq!(Some("utf-8"), media_type.charset());
+    }
+}
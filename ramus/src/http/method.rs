@@ -43,4 +43,31 @@ impl Method {
         };
         Ok(method)
     }
+
+    /// Returns the method token as it is sent in a `request-line`.
+    ///
+    /// ```
+    /// use ramus::http::Method;
+    ///
+    /// assert_eq!("OPTIONS", Method::Options.as_str());
+    /// ```
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Connect => "CONNECT",
+            Method::Options => "OPTIONS",
+            Method::Trace => "TRACE",
+        }
+    }
+
+    /// Returns true if the method is idempotent as defined in [RFC7231 Section
+    /// 4.2.2](https://datatracker.ietf.org/doc/html/rfc7231#section-4.2.2), so a request that
+    /// failed can be retried.
+    pub fn is_idempotent(&self) -> bool {
+        !matches!(self, Method::Post | Method::Connect)
+    }
 }
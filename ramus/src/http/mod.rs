@@ -1,15 +1,52 @@
 //! A general purpose module of common HTTP types
+mod auth;
+pub(crate) mod chunked;
+mod conditional;
+mod content_coding;
+mod cookie;
+mod date;
+mod event_stream;
+mod extract;
+mod form;
+mod header;
+#[cfg(feature = "json")]
+mod json;
+mod media_type;
 mod method;
+mod multipart;
+mod negotiation;
+mod origin;
+mod range;
 mod request;
+mod response;
 mod status_code;
 mod uri;
 pub(crate) mod utils;
 
+pub use auth::*;
+pub use conditional::*;
+pub use content_coding::ContentCoding;
+pub use cookie::*;
+pub use date::HttpDate;
+pub use event_stream::*;
+pub use extract::FromRequest;
+pub use form::Form;
+pub use header::*;
+#[cfg(feature = "json")]
+pub use json::*;
+pub use media_type::MediaType;
 pub use method::*;
+pub use multipart::*;
+pub use negotiation::*;
+pub use origin::Origin;
+pub use range::*;
 pub use request::*;
+pub use response::*;
 pub use status_code::*;
 pub use uri::*;
 
+use std::fmt;
+
 /// HTTP protocol version as defined in
 /// [RFC7230 Section 2.6](https://datatracker.ietf.org/doc/html/rfc7230#section-2.6).
 ///
@@ -19,10 +56,33 @@ pub use uri::*;
 /// HTTP-name = %x48.54.54.50 ; "HTTP", case-sensitive
 /// DIGIT = 0-9
 /// ```
-#[derive(Copy, Clone, Debug, PartialEq)]
+///
+/// Only a major version of 1 is supported, the minor version is used to determine the defaults
+/// for the semantics that differ between HTTP/1.0 and HTTP/1.1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Version((u8, u8));
 
 impl Version {
+    /// HTTP/0.9 as defined in [RFC1945 Section
+    /// 3.1](https://datatracker.ietf.org/doc/html/rfc1945#section-3.1).
+    ///
+    /// This version is never parsed from a `HTTP-version` and is only used for a
+    /// `Simple-Request`, which has no `HTTP-version`.
+    pub const HTTP_0_9: Version = Version((b'0', b'9'));
+
+    /// HTTP/1.0 as defined in [RFC1945](https://datatracker.ietf.org/doc/html/rfc1945).
+    pub const HTTP_1_0: Version = Version((b'1', b'0'));
+
+    /// HTTP/1.1 as defined in [RFC7230](https://datatracker.ietf.org/doc/html/rfc7230).
+    pub const HTTP_1_1: Version = Version((b'1', b'1'));
+
+    /// HTTP/2 as defined in [RFC9113](https://datatracker.ietf.org/doc/html/rfc9113).
+    ///
+    /// This version is never parsed from a `request-line`, as a HTTP/2 connection is started
+    /// using the connection preface or by upgrading a HTTP/1.1 connection, see
+    /// [`h2`](crate::h2).
+    pub const HTTP_2_0: Version = Version((b'2', b'0'));
+
     /// Derive [`Version`] from a slice of bytes.
     ///
     /// Any major and minor version will be accepted, so long the syntax of `HTTP-version` is
@@ -63,6 +123,82 @@ impl Version {
     pub fn minor(&self) -> u8 {
         self.0 .1 - b'0'
     }
+
+    /// Returns true if the major version is supported, which is only HTTP/1.x.
+    ///
+    /// A request with an unsupported major version should be sent a
+    /// [`StatusCode::HTTP_VERSION_NOT_SUPPORTED`].
+    ///
+    /// # Example
+    /// ```
+    /// # use ramus::http::Version;
+    /// assert!(Version::HTTP_1_0.is_supported());
+    /// assert!(!Version::from_bytes(b"HTTP/9.9").unwrap().is_supported());
+    /// ```
+    pub fn is_supported(&self) -> bool {
+        self.major() == 1
+    }
+
+    /// Returns true if connections are persistent unless the "close" connection option is
+    /// sent, as defined in [RFC7230 Section
+    /// 6.3](https://datatracker.ietf.org/doc/html/rfc7230#section-6.3).
+    ///
+    /// HTTP/1.0 connections are only persistent when the "keep-alive" connection option is
+    /// sent.
+    pub fn is_persistent_by_default(&self) -> bool {
+        *self >= Self::HTTP_1_1
+    }
+
+    /// Returns true if a request must contain a `Host` header field, as defined in [RFC7230
+    /// Section 5.4](https://datatracker.ietf.org/doc/html/rfc7230#section-5.4).
+    pub fn requires_host(&self) -> bool {
+        *self >= Self::HTTP_1_1
+    }
+
+    /// Returns true if messages have a `status-line` and header section.
+    ///
+    /// A HTTP/0.9 `Simple-Response` is only the message body, as defined in [RFC1945 Section
+    /// 6](https://datatracker.ietf.org/doc/html/rfc1945#section-6).
+    pub fn has_header_section(&self) -> bool {
+        *self >= Self::HTTP_1_0
+    }
+
+    /// Returns true if the chunked transfer coding can be used, as defined in [RFC7230 Section
+    /// 3.3.1](https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.1).
+    ///
+    /// A HTTP/1.0 client must never be sent a chunked response.
+    pub fn supports_chunked(&self) -> bool {
+        *self >= Self::HTTP_1_1
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HTTP/{}.{}", self.major(), self.minor())
+    }
+}
+
+/// Create a HTTP/1.1 [`Request`] for a test, which has a `Host` before the header fields and a
+/// `Content-Length` when the body is not empty.
+#[cfg(test)]
+pub(crate) fn test_request(
+    method: Method,
+    target: &str,
+    fields: &[(&str, &str)],
+    body: &str,
+) -> Request {
+    let mut bytes = format!("{} {target} HTTP/1.1\r\nHost: a\r\n", method.as_str());
+    for (name, value) in fields {
+        bytes.push_str(&format!("{name}: {value}\r\n"));
+    }
+    if !body.is_empty() {
+        bytes.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    bytes.push_str("\r\n");
+    bytes.push_str(body);
+    Request::from_reader(&mut bytes.as_bytes(), &Limits::default())
+        .expect("valid request")
+        .expect("a request")
 }
 
 #[cfg(test)]
@@ -98,4 +234,28 @@ mod tests {
     fn valid_http_version() {
         assert_eq!(Ok(Version((b'1', b'1'))), Version::from_bytes(b"HTTP/1.1"));
     }
+
+    #[test]
+    fn only_major_version_one_is_supported() {
+        assert!(Version::HTTP_1_0.is_supported());
+        assert!(Version::HTTP_1_1.is_supported());
+        assert!(Version::from_bytes(b"HTTP/1.2").unwrap().is_supported());
+        assert!(!Version::from_bytes(b"HTTP/0.9").unwrap().is_supported());
+        assert!(!Version::from_bytes(b"HTTP/2.0").unwrap().is_supported());
+    }
+
+    #[test]
+    fn http_1_0_defaults_differ_from_http_1_1() {
+        assert!(!Version::HTTP_1_0.is_persistent_by_default());
+        assert!(!Version::HTTP_1_0.requires_host());
+        assert!(!Version::HTTP_1_0.supports_chunked());
+        assert!(Version::HTTP_1_1.is_persistent_by_default());
+        assert!(Version::HTTP_1_1.requires_host());
+        assert!(Version::HTTP_1_1.supports_chunked());
+    }
+
+    #[test]
+    fn version_is_displayed_as_http_version() {
+        assert_eq!("HTTP/1.0", Version::HTTP_1_0.to_string());
+    }
 }
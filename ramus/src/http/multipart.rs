@@ -0,0 +1,823 @@
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use super::{
+    media_type,
+    utils::{self, abnf},
+    FromRequest, HeaderField, Headers, Limits, MediaType, Request, StatusCode,
+};
+
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// The limits applied when parsing a [`Multipart`] body.
+///
+/// A body that exceeds any of the limits is a [`StatusCode::PAYLOAD_TOO_LARGE`]. The part and
+/// total limits default to [`Limits::body`], as the server reads no longer body than that.
+///
+/// ```
+/// use ramus::http::MultipartLimits;
+///
+/// let limits = MultipartLimits {
+///     part: 1024 * 1024,
+///     ..MultipartLimits::default()
+/// };
+/// assert_eq!(1024 * 1024, limits.part);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MultipartLimits {
+    /// The maximum number of parts.
+    pub parts: usize,
+    /// The maximum number of octets in the body of a single part.
+    pub part: u64,
+    /// The maximum number of octets in the whole multipart body.
+    pub total: u64,
+    /// The maximum number of header fields of a single part.
+    pub header_count: usize,
+    /// The maximum number of octets in a single header field of a part, excluding the CRLF.
+    pub header_size: usize,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        let body = Limits::default().body as u64;
+        Self {
+            parts: 100,
+            part: body,
+            total: body,
+            header_count: 16,
+            header_size: 8 * 1024,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum State {
+    /// Reading the body of a part, or the preamble before the first part.
+    Body,
+    /// The delimiter of a part has been read but not what follows it.
+    Delimiter,
+    /// The close delimiter has been read.
+    Done,
+}
+
+/// A streaming parser of a `multipart/form-data` body as defined in
+/// [RFC7578](https://datatracker.ietf.org/doc/html/rfc7578), which uses the multipart syntax
+/// of [RFC2046 Section 5.1.1](https://datatracker.ietf.org/doc/html/rfc2046#section-5.1.1).
+///
+/// ```text
+/// multipart-body = [preamble CRLF]
+///                  dash-boundary transport-padding CRLF
+///                  body-part *encapsulation
+///                  close-delimiter transport-padding
+///                  [CRLF epilogue]
+///
+/// encapsulation = delimiter transport-padding
+///                 CRLF body-part
+/// delimiter = CRLF dash-boundary
+/// dash-boundary = "--" boundary
+/// close-delimiter = delimiter "--"
+/// ```
+///
+/// Each [`Part`] is read in turn with [`Multipart::next_part`], only as much of the body is
+/// held in memory as is needed to find the next delimiter. A [`Multipart`] can be taken as
+/// the argument of a handler, see [`Extract`](crate::server::Extract), which parses the body
+/// of the request without copying it.
+///
+/// The extractor does not stream the upload from the connection: the server reads the whole
+/// body of a request before the handler is called, so an upload to a handler is held in memory
+/// and limited to [`Limits::body`], which must be raised to accept larger files. Streaming the
+/// parts only keeps memory low for a body read from another reader, such as a file, though
+/// [`Multipart::spill_to_disk`] still saves large parts without another copy in memory.
+///
+/// ```
+/// use std::io::Read;
+///
+/// use ramus::http::Multipart;
+///
+/// let body = "--abc\r\n\
+///     Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+///     Hello\r\n\
+///     --abc\r\n\
+///     Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+///     Content-Type: text/plain\r\n\r\n\
+///     World\r\n\
+///     --abc--\r\n";
+/// let mut multipart = Multipart::new(body.as_bytes(), "abc").expect("valid boundary");
+///
+/// let part = multipart.next_part().expect("valid part").expect("a part");
+/// assert_eq!("title", part.name());
+/// assert_eq!(Ok("Hello".to_string()), part.text());
+///
+/// let mut part = multipart.next_part().expect("valid part").expect("a part");
+/// assert_eq!(Some("a.txt"), part.filename());
+/// let mut body = String::new();
+/// part.read_to_string(&mut body).expect("valid body");
+/// assert_eq!("World", body);
+///
+/// assert!(multipart.next_part().expect("valid end").is_none());
+/// ```
+pub struct Multipart<R = Cursor<Arc<[u8]>>> {
+    reader: R,
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    eof: bool,
+    state: State,
+    limits: MultipartLimits,
+    total: u64,
+    parts: usize,
+    spill: Option<(PathBuf, u64)>,
+}
+
+impl<R: Read> Multipart<R> {
+    /// Create a [`Multipart`] that reads the body from the reader, with parts delimited by the
+    /// boundary.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the boundary is not valid.
+    ///
+    /// ```text
+    /// boundary = 0*69<bchars> bcharsnospace
+    /// bchars = bcharsnospace / " "
+    /// bcharsnospace = DIGIT / ALPHA / "'" / "(" / ")" / "+" / "_" / "," / "-" / "." / "/" /
+    ///                 ":" / "=" / "?"
+    /// ```
+    pub fn new(reader: R, boundary: &str) -> Result<Self, StatusCode> {
+        let is_bchar = |b: u8| b.is_ascii_alphanumeric() || b"'()+_,-./:=? ".contains(&b);
+        if boundary.is_empty()
+            || boundary.len() > 70
+            || boundary.ends_with(' ')
+            || !boundary.bytes().all(is_bchar)
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Ok(Self {
+            reader,
+            delimiter: format!("\r\n--{boundary}").into_bytes(),
+            // the first dash-boundary is not preceded by a CRLF when there is no preamble, so
+            // one is added to find it like any other delimiter
+            buffer: b"\r\n".to_vec(),
+            eof: false,
+            state: State::Body,
+            limits: MultipartLimits::default(),
+            total: 0,
+            parts: 0,
+            spill: None,
+        })
+    }
+
+    /// Set the [`MultipartLimits`] used when parsing the body.
+    pub fn limits(mut self, limits: MultipartLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Write the body of a part saved with [`Part::save`] to a temporary file in the directory
+    /// once it is longer than the threshold, instead of holding all of it in memory.
+    pub fn spill_to_disk(mut self, dir: impl Into<PathBuf>, threshold: u64) -> Self {
+        self.spill = Some((dir.into(), threshold));
+        self
+    }
+
+    /// Returns the next [`Part`], or [`None`] once the close delimiter has been read. Any of
+    /// the body of the previous part that has not been read is skipped.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the body does not match the multipart syntax, a
+    /// part has no `Content-Disposition` of "form-data" with a "name" parameter, or it has an
+    /// invalid `Content-Type`. Returns a [`StatusCode::PAYLOAD_TOO_LARGE`] if any of the
+    /// [`MultipartLimits`] are exceeded.
+    pub fn next_part(&mut self) -> Result<Option<Part<'_, R>>, StatusCode> {
+        let mut skipped = vec![0; BUFFER_SIZE];
+        while self.state == State::Body {
+            self.read_body(&mut skipped)?;
+        }
+        if self.state == State::Done || !self.read_after_delimiter()? {
+            self.state = State::Done;
+            return Ok(None);
+        }
+        self.parts += 1;
+        if self.parts > self.limits.parts {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+
+        let headers = self.read_headers()?;
+        let (name, filename) = disposition(&headers)?;
+        // RFC7578 Section 4.4: the default content type of a part is text/plain
+        let content_type = match headers.get("content-type") {
+            Some(value) => MediaType::from_bytes(value.as_bytes())?,
+            None => MediaType::from_bytes(b"text/plain").expect("valid media type"),
+        };
+        self.state = State::Body;
+        Ok(Some(Part {
+            multipart: self,
+            headers,
+            name,
+            filename,
+            content_type,
+            length: 0,
+        }))
+    }
+
+    /// Save every remaining part with [`Part::save`].
+    pub fn fields(mut self) -> Result<Vec<Field>, StatusCode> {
+        let mut fields = Vec::new();
+        while let Some(part) = self.next_part()? {
+            fields.push(part.save()?);
+        }
+        Ok(fields)
+    }
+
+    /// Read more of the body into the buffer, returns false at the end of the body.
+    fn fill(&mut self) -> Result<bool, StatusCode> {
+        if self.eof {
+            return Ok(false);
+        }
+        let start = self.buffer.len();
+        self.buffer.resize(start + BUFFER_SIZE, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buffer[start..]) {
+                Ok(read) => break read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.buffer.truncate(start);
+                    return Err(utils::io_error_status(error));
+                }
+            }
+        };
+        self.buffer.truncate(start + read);
+        self.total += read as u64;
+        if self.total > self.limits.total {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        self.eof = read == 0;
+        Ok(!self.eof)
+    }
+
+    /// Read the body of the current part up to the next delimiter, returns 0 once the
+    /// delimiter has been read.
+    fn read_body(&mut self, buf: &mut [u8]) -> Result<usize, StatusCode> {
+        if self.state != State::Body || buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let (end, found) = match find(&self.buffer, &self.delimiter) {
+                Some(i) => (i, true),
+                // the end of the buffer may be the start of the delimiter
+                None => (
+                    self.buffer.len().saturating_sub(self.delimiter.len() - 1),
+                    false,
+                ),
+            };
+            if end > 0 {
+                let read = end.min(buf.len());
+                buf[..read].copy_from_slice(&self.buffer[..read]);
+                self.buffer.drain(..read);
+                return Ok(read);
+            }
+            if found {
+                self.buffer.drain(..self.delimiter.len());
+                self.state = State::Delimiter;
+                return Ok(0);
+            }
+            if !self.fill()? {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+    }
+
+    /// Read the rest of the line after a delimiter, returns false for a close delimiter.
+    fn read_after_delimiter(&mut self) -> Result<bool, StatusCode> {
+        while self.buffer.len() < 2 {
+            if !self.fill()? {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+        if self.buffer.starts_with(b"--") {
+            // the epilogue is ignored
+            return Ok(false);
+        }
+        let line = self.read_line()?;
+        if line.iter().all(|b| matches!(b, b' ' | b'\t')) {
+            Ok(true)
+        } else {
+            Err(StatusCode::BAD_REQUEST)
+        }
+    }
+
+    fn read_headers(&mut self) -> Result<Headers, StatusCode> {
+        let mut headers = Headers::new();
+        loop {
+            let field = self.read_line()?;
+            match field.as_slice() {
+                [] => return Ok(headers),
+                [b' ' | b'\t', ..] => return Err(StatusCode::BAD_REQUEST),
+                _ if headers.len() == self.limits.header_count => {
+                    return Err(StatusCode::PAYLOAD_TOO_LARGE)
+                }
+                _ => headers.append(HeaderField::from_bytes(&field)?),
+            }
+        }
+    }
+
+    /// Read a line that ends with a CRLF, which is not included.
+    fn read_line(&mut self) -> Result<Vec<u8>, StatusCode> {
+        loop {
+            if let Some(i) = find(&self.buffer, b"\r\n") {
+                if i > self.limits.header_size {
+                    return Err(StatusCode::PAYLOAD_TOO_LARGE);
+                }
+                let line = self.buffer[..i].to_vec();
+                self.buffer.drain(..i + 2);
+                return Ok(line);
+            }
+            if self.buffer.len() > self.limits.header_size + 1 {
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+            if !self.fill()? {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+    }
+}
+
+impl FromRequest for Multipart {
+    type Rejection = StatusCode;
+
+    /// Parse the body of a request with a `multipart/form-data` `Content-Type`, which has
+    /// already been read into memory by the server.
+    ///
+    /// Returns a [`StatusCode::UNSUPPORTED_MEDIA_TYPE`] for any other `Content-Type`, and a
+    /// [`StatusCode::BAD_REQUEST`] if the `boundary` parameter is missing or invalid.
+    fn from_request(request: &Request) -> Result<Self, StatusCode> {
+        let content_type = request
+            .content_type()
+            .filter(|media_type| media_type.essence() == "multipart/form-data")
+            .ok_or(StatusCode::UNSUPPORTED_MEDIA_TYPE)?;
+        let boundary = content_type.boundary().ok_or(StatusCode::BAD_REQUEST)?;
+        Multipart::new(Cursor::new(request.shared_body()), boundary)
+    }
+}
+
+impl<R> fmt::Debug for Multipart<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart")
+            .field("limits", &self.limits)
+            .field("parts", &self.parts)
+            .field("spill", &self.spill)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the name and filename from the `Content-Disposition` of a part, as defined in
+/// [RFC7578 Section 4.2](https://datatracker.ietf.org/doc/html/rfc7578#section-4.2).
+///
+/// ```text
+/// Content-Disposition = "form-data" *( OWS ";" OWS parameter )
+/// ```
+fn disposition(headers: &Headers) -> Result<(String, Option<String>), StatusCode> {
+    let value = headers
+        .get("content-disposition")
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let mut params = abnf::split_unquoted(value.as_bytes(), b';').into_iter();
+    if !params
+        .next()
+        .is_some_and(|type_| type_.eq_ignore_ascii_case(b"form-data"))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let (mut name, mut filename) = (None, None);
+    for param in params {
+        let (param, value) = media_type::parse_param(param)?;
+        match param.to_ascii_lowercase().as_str() {
+            "name" => name = Some(value),
+            "filename" => filename = Some(value),
+            _ => {}
+        }
+    }
+    Ok((name.ok_or(StatusCode::BAD_REQUEST)?, filename))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// A part of a [`Multipart`] body, the body of the part is read from it as a stream.
+///
+/// The body is limited to [`MultipartLimits::part`], reading past it fails with an
+/// [`io::ErrorKind::InvalidData`] error, as does a body that does not end with a delimiter.
+pub struct Part<'m, R> {
+    multipart: &'m mut Multipart<R>,
+    headers: Headers,
+    name: String,
+    filename: Option<String>,
+    content_type: MediaType,
+    length: u64,
+}
+
+impl<R: Read> Part<'_, R> {
+    /// Returns the header section of the part.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the "name" of the form field from the `Content-Disposition`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the "filename" from the `Content-Disposition` of a file, this is sent by the
+    /// client and so should not be used as a path without checking it.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// Returns the `Content-Type` of the part, which is "text/plain" when there is none.
+    pub fn content_type(&self) -> &MediaType {
+        &self.content_type
+    }
+
+    /// Read the whole body of the part into memory.
+    ///
+    /// Returns a [`StatusCode::PAYLOAD_TOO_LARGE`] when it is longer than
+    /// [`MultipartLimits::part`], or a [`StatusCode::BAD_REQUEST`] if it does not end with a
+    /// delimiter.
+    pub fn bytes(mut self) -> Result<Vec<u8>, StatusCode> {
+        let mut body = Vec::new();
+        let mut buf = vec![0; BUFFER_SIZE];
+        loop {
+            match self.read_body(&mut buf)? {
+                0 => return Ok(body),
+                read => body.extend_from_slice(&buf[..read]),
+            }
+        }
+    }
+
+    /// Read the whole body of the part into memory as UTF-8 text, see [`Part::bytes`].
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the body is not valid UTF-8.
+    pub fn text(self) -> Result<String, StatusCode> {
+        String::from_utf8(self.bytes()?).map_err(|_| StatusCode::BAD_REQUEST)
+    }
+
+    /// Read the whole body of the part into a [`Field`], which is held in memory unless it is
+    /// longer than the threshold of [`Multipart::spill_to_disk`], then it is written to a
+    /// [`TempFile`].
+    ///
+    /// Returns the same errors as [`Part::bytes`], and a
+    /// [`StatusCode::INTERNAL_SERVER_ERROR`] if the temporary file cannot be written.
+    pub fn save(mut self) -> Result<Field, StatusCode> {
+        let spill = self.multipart.spill.clone();
+        let mut memory = Vec::new();
+        let mut file: Option<(TempFile, File)> = None;
+        let mut buf = vec![0; BUFFER_SIZE];
+        loop {
+            let read = self.read_body(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+            match (&mut file, &spill) {
+                (Some((_, file)), _) => file
+                    .write_all(&buf[..read])
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+                (None, Some((dir, threshold))) if (memory.len() + read) as u64 > *threshold => {
+                    let (temp, mut handle) =
+                        TempFile::create(dir).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                    handle
+                        .write_all(&memory)
+                        .and_then(|_| handle.write_all(&buf[..read]))
+                        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                    memory = Vec::new();
+                    file = Some((temp, handle));
+                }
+                (None, _) => memory.extend_from_slice(&buf[..read]),
+            }
+        }
+
+        let body = match file {
+            Some((temp, mut handle)) => {
+                handle
+                    .flush()
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                FieldBody::File(temp)
+            }
+            None => FieldBody::Memory(memory),
+        };
+        Ok(Field {
+            headers: std::mem::take(&mut self.headers),
+            name: std::mem::take(&mut self.name),
+            filename: self.filename.take(),
+            content_type: self.content_type.clone(),
+            length: self.length,
+            body,
+        })
+    }
+
+    fn read_body(&mut self, buf: &mut [u8]) -> Result<usize, StatusCode> {
+        let read = self.multipart.read_body(buf)?;
+        self.length += read as u64;
+        if self.length > self.multipart.limits.part {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        Ok(read)
+    }
+}
+
+impl<R: Read> Read for Part<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_body(buf).map_err(|status| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} {}", status.as_u16(), status.reason()),
+            )
+        })
+    }
+}
+
+impl<R> fmt::Debug for Part<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Part")
+            .field("headers", &self.headers)
+            .field("name", &self.name)
+            .field("filename", &self.filename)
+            .field("content_type", &self.content_type)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Part`] whose whole body has been read, see [`Part::save`].
+#[derive(Debug)]
+pub struct Field {
+    headers: Headers,
+    name: String,
+    filename: Option<String>,
+    content_type: MediaType,
+    length: u64,
+    body: FieldBody,
+}
+
+impl Field {
+    /// Returns the header section of the part.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the "name" of the form field, see [`Part::name`].
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the "filename" of a file, see [`Part::filename`].
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// Returns the `Content-Type` of the part, see [`Part::content_type`].
+    pub fn content_type(&self) -> &MediaType {
+        &self.content_type
+    }
+
+    /// Returns the number of octets in the body.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// Returns true if the body is empty.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Returns the body of the part.
+    pub fn body(&self) -> &FieldBody {
+        &self.body
+    }
+
+    /// Consumes the field and returns the body.
+    pub fn into_body(self) -> FieldBody {
+        self.body
+    }
+}
+
+/// The body of a [`Field`].
+#[derive(Debug)]
+pub enum FieldBody {
+    /// A body that is held in memory.
+    Memory(Vec<u8>),
+    /// A body that has been written to a temporary file.
+    File(TempFile),
+}
+
+/// A file that is removed when it is dropped, unless it is kept with [`TempFile::persist`].
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    fn create(dir: &Path) -> io::Result<(Self, File)> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        loop {
+            let path = dir.join(format!(
+                "ramus-multipart-{}-{}",
+                std::process::id(),
+                COUNT.fetch_add(1, Ordering::Relaxed)
+            ));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((Self { path }, file)),
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Open the file for reading.
+    pub fn open(&self) -> io::Result<File> {
+        File::open(&self.path)
+    }
+
+    /// Move the file to the path so that it is no longer removed, copying it when it cannot be
+    /// renamed, such as to another file system.
+    pub fn persist(self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if fs::rename(&self.path, path).is_err() {
+            fs::copy(&self.path, path)?;
+            // the temporary file is removed when self is dropped
+            return Ok(());
+        }
+        std::mem::forget(self);
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Read},
+        path::PathBuf,
+        sync::Arc,
+    };
+
+    use crate::http::{test_request, FromRequest, Limits, Method, StatusCode};
+
+    use super::{FieldBody, Multipart, MultipartLimits};
+
+    /// A reader that returns a single octet at a time, so that delimiters are split between
+    /// reads.
+    struct OneByOne<'a>(&'a [u8]);
+
+    impl Read for OneByOne<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((b, rest)), Some(first)) => {
+                    *first = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    const BODY: &[u8] = b"preamble\r\n--xyz  \r\n\
+        Content-Disposition: form-data; name=\"a\"\r\n\r\n\
+        one\r\n--x\r\n\
+        --xyz\r\n\
+        Content-Disposition: form-data; name=\"b\"; filename=\"b.bin\"\r\n\
+        Content-Type: application/octet-stream\r\n\r\n\
+        \x00\x01\r\n\
+        --xyz--\r\nepilogue";
+
+    #[test]
+    fn parts_are_read_across_reads() {
+        let mut multipart = Multipart::new(OneByOne(BODY), "xyz").unwrap();
+        let part = multipart.next_part().unwrap().unwrap();
+        assert_eq!("a", part.name());
+        assert_eq!("text/plain", part.content_type().essence());
+        assert_eq!(Ok(b"one\r\n--x".to_vec()), part.bytes());
+
+        let part = multipart.next_part().unwrap().unwrap();
+        assert_eq!(Some("b.bin"), part.filename());
+        assert_eq!("application/octet-stream", part.content_type().essence());
+        assert_eq!(Ok(vec![0, 1]), part.bytes());
+        assert!(multipart.next_part().unwrap().is_none());
+        assert!(multipart.next_part().unwrap().is_none());
+    }
+
+    #[test]
+    fn unread_part_is_skipped() {
+        let mut multipart = Multipart::new(BODY, "xyz").unwrap();
+        multipart.next_part().unwrap().unwrap();
+        assert_eq!("b", multipart.next_part().unwrap().unwrap().name());
+    }
+
+    #[test]
+    fn invalid_bodies_are_a_bad_request() {
+        for body in [
+            &b"--xyz\r\n\r\nno disposition\r\n--xyz--"[..],
+            b"--xyz\r\nContent-Disposition: form-data\r\n\r\nno name\r\n--xyz--",
+            b"--xyz\r\nContent-Disposition: form-data; name=a\r\n\r\nno end",
+            b"no boundary",
+        ] {
+            let result = Multipart::new(body, "xyz")
+                .unwrap()
+                .fields()
+                .map(|fields| fields.len());
+            assert_eq!(Err(StatusCode::BAD_REQUEST), result);
+        }
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            Multipart::new(&b""[..], "ends with space ").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn limits_are_payload_too_large() {
+        let limits = |limits| Multipart::new(BODY, "xyz").unwrap().limits(limits).fields();
+        for exceeded in [
+            MultipartLimits {
+                part: 2,
+                ..MultipartLimits::default()
+            },
+            MultipartLimits {
+                parts: 1,
+                ..MultipartLimits::default()
+            },
+            MultipartLimits {
+                total: 64,
+                ..MultipartLimits::default()
+            },
+        ] {
+            assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, limits(exceeded).unwrap_err());
+        }
+    }
+
+    #[test]
+    fn large_part_spills_to_disk() {
+        let dir: PathBuf = std::env::temp_dir();
+        let fields = Multipart::new(BODY, "xyz")
+            .unwrap()
+            .spill_to_disk(&dir, 2)
+            .fields()
+            .unwrap();
+        assert!(matches!(fields[0].body(), FieldBody::File(_)));
+        assert!(matches!(fields[1].body(), FieldBody::Memory(body) if body == &[0, 1]));
+
+        let FieldBody::File(file) = &fields[0].body() else {
+            unreachable!();
+        };
+        let path = file.path().to_path_buf();
+        assert_eq!("one\r\n--x", std::fs::read_to_string(&path).unwrap());
+        drop(fields);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn request_must_be_multipart_form_data() {
+        let parse = |content_type: &str| {
+            let request = test_request(Method::Post, "/", &[("Content-Type", content_type)], "");
+            Multipart::from_request(&request).map(|_| ())
+        };
+        assert_eq!(Ok(()), parse("multipart/form-data; boundary=xyz"));
+        assert_eq!(Err(StatusCode::BAD_REQUEST), parse("multipart/form-data"));
+        assert_eq!(
+            Err(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            parse("application/json")
+        );
+    }
+
+    #[test]
+    fn request_body_is_shared_not_copied() {
+        let body = "--x\r\nContent-Disposition: form-data; name=a\r\n\r\none\r\n--x--";
+        let fields = [("Content-Type", "multipart/form-data; boundary=x")];
+        let request = test_request(Method::Post, "/", &fields, body);
+        let mut multipart = Multipart::from_request(&request).unwrap();
+        assert!(Arc::ptr_eq(
+            &request.shared_body(),
+            multipart.reader.get_ref()
+        ));
+        let part = multipart.next_part().unwrap().unwrap();
+        assert_eq!(Ok("one".to_string()), part.text());
+        assert_eq!(
+            Limits::default().body as u64,
+            MultipartLimits::default().total
+        );
+    }
+}
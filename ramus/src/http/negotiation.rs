@@ -0,0 +1,543 @@
+use std::fmt;
+
+use super::{media_type, utils::abnf, MediaType, Request, Response, StatusCode};
+
+/// Quality value as defined in [RFC7231 Section
+/// 5.3.1](https://datatracker.ietf.org/doc/html/rfc7231#section-5.3.1), which is the relative
+/// weight of a preference from 0 to 1 with up to three decimal places.
+///
+/// ```text
+/// weight = OWS ";" OWS "q=" qvalue
+/// qvalue = ( "0" [ "." 0*3DIGIT ] )
+///        / ( "1" [ "." 0*3("0") ] )
+/// ```
+///
+/// A quality of 0 means that the value is not acceptable.
+///
+/// ```
+/// use ramus::http::Quality;
+///
+/// let quality = Quality::from_bytes(b"0.5").expect("valid qvalue");
+/// assert_eq!(500, quality.as_thousandths());
+/// assert!(Quality::ZERO < quality && quality < Quality::ONE);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Quality(u16);
+
+impl Quality {
+    /// The quality of a value that is not acceptable.
+    pub const ZERO: Quality = Quality(0);
+
+    /// The highest quality, which is the default when no weight is given.
+    pub const ONE: Quality = Quality(1000);
+
+    /// Derive a [`Quality`] from a slice of bytes.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the slice of bytes does not match the ABNF
+    /// syntax.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        let (int, fraction) = match src {
+            [int, b'.', fraction @ ..] => (*int, fraction),
+            [int] => (*int, &[][..]),
+            _ => return Err(StatusCode::BAD_REQUEST),
+        };
+        if fraction.len() > 3 || !fraction.iter().all(u8::is_ascii_digit) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let thousandths = fraction
+            .iter()
+            .chain(std::iter::repeat(&b'0'))
+            .take(3)
+            .fold(0, |acc, b| acc * 10 + u16::from(b - b'0'));
+        match int {
+            b'0' => Ok(Self(thousandths)),
+            b'1' if thousandths == 0 => Ok(Self::ONE),
+            _ => Err(StatusCode::BAD_REQUEST),
+        }
+    }
+
+    /// Returns the quality in thousandths, from 0 to 1000.
+    pub fn as_thousandths(&self) -> u16 {
+        self.0
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0 => f.write_str("0"),
+            1000 => f.write_str("1"),
+            quality => write!(f, "0.{}", format!("{quality:03}").trim_end_matches('0')),
+        }
+    }
+}
+
+/// An element of an [`AcceptList`], which is a value with any parameters and its quality.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AcceptItem {
+    value: String,
+    params: Vec<(String, String)>,
+    quality: Quality,
+}
+
+impl AcceptItem {
+    /// Returns the value, normalized to lowercase as all of the values are case-insensitive.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns the parameters before the weight, with the names normalized to lowercase, which
+    /// are only used by the `media-range` of `Accept`.
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    /// Returns the [`Quality`], which is [`Quality::ONE`] when no weight was given.
+    pub fn quality(&self) -> Quality {
+        self.quality
+    }
+
+    /// Parse an element with any parameters, the element must not be empty.
+    ///
+    /// ```text
+    /// element = value *( OWS ";" OWS parameter ) [ weight ]
+    /// value = token [ "/" token ]
+    /// parameter = token "=" ( token / quoted-string )
+    /// ```
+    fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        let mut parts = abnf::split_unquoted(src, b';').into_iter();
+        let value = parts.next().unwrap_or_default();
+        let is_token = |src: &[u8]| abnf::parse_token(src).is_some_and(|t| t.len() == src.len());
+        let is_valid = match value.iter().position(|b| *b == b'/') {
+            Some(i) => is_token(&value[..i]) && is_token(&value[i + 1..]),
+            None => is_token(value),
+        };
+        if !is_valid {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let mut params = Vec::new();
+        let mut quality = Quality::ONE;
+        for param in parts {
+            let (name, value) = media_type::parse_param(param)?;
+            if name.eq_ignore_ascii_case("q") {
+                quality = Quality::from_bytes(value.as_bytes())?;
+                // RFC7231 Section 5.3.2: any parameters after the weight are accept-ext
+                break;
+            }
+            params.push((name.to_ascii_lowercase(), value));
+        }
+
+        Ok(Self {
+            value: String::from_utf8_lossy(value).to_ascii_lowercase(),
+            params,
+            quality,
+        })
+    }
+}
+
+/// The value of any of the `Accept`, `Accept-Charset`, `Accept-Encoding` and `Accept-Language`
+/// header fields, which are defined in [RFC7231 Section
+/// 5.3](https://datatracker.ietf.org/doc/html/rfc7231#section-5.3) as a list of values with
+/// optional weights.
+///
+/// ```text
+/// Accept = #( media-range [ accept-params ] )
+/// media-range = ( "*/*" / ( type "/" "*" ) / ( type "/" subtype ) )
+///               *( OWS ";" OWS parameter )
+/// accept-params = weight *( accept-ext )
+///
+/// Accept-Charset = 1#( ( charset / "*" ) [ weight ] )
+/// Accept-Encoding = #( codings [ weight ] )
+/// codings = content-coding / "identity" / "*"
+/// Accept-Language = 1#( language-range [ weight ] )
+/// language-range = <language-range, see [RFC4647], Section 2.1>
+/// ```
+///
+/// Each header field has its own rules for which element applies to a value, so there is a
+/// method to find the quality of a value for each.
+///
+/// ```
+/// use ramus::http::AcceptList;
+///
+/// let accept = AcceptList::from_bytes(b"text/*;q=0.3, text/html;q=0.7, */*;q=0.5")
+///     .expect("valid accept");
+/// assert_eq!("0.7", accept.media_type_quality("text/html").to_string());
+/// assert_eq!("0.3", accept.media_type_quality("text/plain").to_string());
+/// assert_eq!("0.5", accept.media_type_quality("image/png").to_string());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AcceptList(Vec<AcceptItem>);
+
+impl AcceptList {
+    /// Derive an [`AcceptList`] from a slice of bytes, empty list elements are ignored.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the slice of bytes does not match the ABNF
+    /// syntax.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        abnf::split_unquoted(src, b',')
+            .into_iter()
+            .filter(|element| !element.is_empty())
+            .map(AcceptItem::from_bytes)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Returns the elements of the list in the order they were received.
+    pub fn items(&self) -> &[AcceptItem] {
+        &self.0
+    }
+
+    /// Returns the [`Quality`] of the media type, such as "text/html; level=1", from the
+    /// most specific media range that matches it, as defined in [RFC7231 Section
+    /// 5.3.2](https://datatracker.ietf.org/doc/html/rfc7231#section-5.3.2).
+    ///
+    /// A media range with parameters only matches a media type with the same parameters, see
+    /// [`MediaType::matches`].
+    pub fn media_type_quality(&self, media_type: &str) -> Quality {
+        let Ok(offer) = MediaType::from_bytes(media_type.as_bytes()) else {
+            return Quality::ZERO;
+        };
+        self.best(|item| {
+            let range = item.params.iter().fold(
+                MediaType::from_bytes(item.value.as_bytes()).ok()?,
+                |range, (name, value)| range.with_param(name, value),
+            );
+            if !range.matches(&offer) {
+                return None;
+            }
+            Some(match (range.type_(), range.subtype()) {
+                ("*", "*") => 0,
+                (_, "*") => 1,
+                _ => 2 + range.params().len(),
+            })
+        })
+    }
+
+    /// Returns the [`Quality`] of the charset, as defined in [RFC7231 Section
+    /// 5.3.3](https://datatracker.ietf.org/doc/html/rfc7231#section-5.3.3), where a charset
+    /// that is not listed is not acceptable unless there is a "*".
+    pub fn charset_quality(&self, charset: &str) -> Quality {
+        self.exact_or_wildcard(charset)
+    }
+
+    /// Returns the [`Quality`] of the content-coding, as defined in [RFC7231 Section
+    /// 5.3.4](https://datatracker.ietf.org/doc/html/rfc7231#section-5.3.4), where a coding
+    /// that is not listed is not acceptable unless there is a "*".
+    ///
+    /// The "identity" coding, which is no encoding, is always acceptable unless it is excluded
+    /// with "identity;q=0", or with "*;q=0" and "identity" is not listed.
+    pub fn encoding_quality(&self, coding: &str) -> Quality {
+        match self.exact_or_wildcard(coding) {
+            Quality::ZERO
+                if coding.eq_ignore_ascii_case("identity")
+                    && !self
+                        .0
+                        .iter()
+                        .any(|item| matches!(&*item.value, "identity" | "*")) =>
+            {
+                Quality::ONE
+            }
+            quality => quality,
+        }
+    }
+
+    /// Returns the [`Quality`] of the language tag, such as "en-GB", from the longest language
+    /// range that matches it using the basic filtering of [RFC4647 Section
+    /// 3.3.1](https://datatracker.ietf.org/doc/html/rfc4647#section-3.3.1), as used by
+    /// [RFC7231 Section 5.3.5](https://datatracker.ietf.org/doc/html/rfc7231#section-5.3.5).
+    ///
+    /// A range matches a tag that is equal to it or starts with it followed by a "-", so "en"
+    /// matches "en-GB" but "en-GB" does not match "en".
+    pub fn language_quality(&self, tag: &str) -> Quality {
+        let tag = tag.to_ascii_lowercase();
+        self.best(|item| match &*item.value {
+            "*" => Some(0),
+            range
+                if tag == range
+                    || tag.starts_with(range) && tag.as_bytes().get(range.len()) == Some(&b'-') =>
+            {
+                Some(range.len())
+            }
+            _ => None,
+        })
+    }
+
+    fn exact_or_wildcard(&self, value: &str) -> Quality {
+        let value = value.to_ascii_lowercase();
+        self.best(|item| match &*item.value {
+            "*" => Some(0),
+            _ if item.value == value => Some(1),
+            _ => None,
+        })
+    }
+
+    /// Returns the quality of the first element with the highest specificity, or
+    /// [`Quality::ZERO`] if there is no element that matches.
+    fn best<F: Fn(&AcceptItem) -> Option<usize>>(&self, specificity: F) -> Quality {
+        let mut best: Option<(usize, Quality)> = None;
+        for item in &self.0 {
+            if let Some(specificity) = specificity(item) {
+                if best.is_none_or(|(best, _)| specificity > best) {
+                    best = Some((specificity, item.quality));
+                }
+            }
+        }
+        best.map_or(Quality::ZERO, |(_, quality)| quality)
+    }
+}
+
+/// Proactive negotiation as defined in [RFC7231 Section
+/// 3.4.1](https://datatracker.ietf.org/doc/html/rfc7231#section-3.4.1), which selects the
+/// best of the representations that a handler offers using the `Accept` header fields of the
+/// request.
+///
+/// The offers are given in order of preference, so the first offer with the highest quality
+/// is selected, and without the header field in the request the first offer is selected. A
+/// header field that is not valid is ignored.
+///
+/// Each header field that is used is added to `Vary` by [`Negotiator::apply`], so that a cache
+/// knows that the response depends on it.
+///
+/// ```
+/// use ramus::http::{Negotiator, Request, Response, StatusCode};
+///
+/// fn handle(request: &Request) -> Response {
+///     let mut negotiator = Negotiator::new(request);
+///     let response = match negotiator.media_type(&["application/json", "text/html"]) {
+///         Ok("application/json") => Response::new(StatusCode::OK).with_body("{}"),
+///         Ok(_) => Response::new(StatusCode::OK).with_body("<p></p>"),
+///         Err(status) => Response::new(status),
+///     };
+///     negotiator.apply(response)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Negotiator<'r> {
+    request: &'r Request,
+    vary: Vec<&'static str>,
+}
+
+impl<'r> Negotiator<'r> {
+    /// Create a [`Negotiator`] for the request.
+    pub fn new(request: &'r Request) -> Self {
+        Self {
+            request,
+            vary: Vec::new(),
+        }
+    }
+
+    /// Select the best media type using `Accept`, see [`AcceptList::media_type_quality`].
+    ///
+    /// Returns a [`StatusCode::NOT_ACCEPTABLE`] if none of the offers are acceptable.
+    pub fn media_type<'o>(&mut self, offers: &[&'o str]) -> Result<&'o str, StatusCode> {
+        self.select("Accept", offers, AcceptList::media_type_quality)
+    }
+
+    /// Select the best charset using `Accept-Charset`, see [`AcceptList::charset_quality`].
+    ///
+    /// Returns a [`StatusCode::NOT_ACCEPTABLE`] if none of the offers are acceptable.
+    pub fn charset<'o>(&mut self, offers: &[&'o str]) -> Result<&'o str, StatusCode> {
+        self.select("Accept-Charset", offers, AcceptList::charset_quality)
+    }
+
+    /// Select the best content-coding using `Accept-Encoding`, see
+    /// [`AcceptList::encoding_quality`].
+    ///
+    /// Returns a [`StatusCode::NOT_ACCEPTABLE`] if none of the offers are acceptable, which
+    /// cannot happen when "identity" is offered unless the client has excluded it.
+    pub fn encoding<'o>(&mut self, offers: &[&'o str]) -> Result<&'o str, StatusCode> {
+        self.select("Accept-Encoding", offers, AcceptList::encoding_quality)
+    }
+
+    /// Select the best language tag using `Accept-Language`, see
+    /// [`AcceptList::language_quality`].
+    ///
+    /// Returns a [`StatusCode::NOT_ACCEPTABLE`] if none of the offers are acceptable.
+    pub fn language<'o>(&mut self, offers: &[&'o str]) -> Result<&'o str, StatusCode> {
+        self.select("Accept-Language", offers, AcceptList::language_quality)
+    }
+
+    /// Returns the names of the header fields that have been used to select a representation.
+    pub fn vary(&self) -> &[&'static str] {
+        &self.vary
+    }
+
+    /// Add the header fields that have been used to select a representation to the `Vary` of
+    /// the response, see [`Response::with_vary`].
+    pub fn apply(&self, response: Response) -> Response {
+        self.vary
+            .iter()
+            .fold(response, |response, name| response.with_vary(name))
+    }
+
+    fn select<'o>(
+        &mut self,
+        name: &'static str,
+        offers: &[&'o str],
+        quality: fn(&AcceptList, &str) -> Quality,
+    ) -> Result<&'o str, StatusCode> {
+        if !self.vary.contains(&name) {
+            self.vary.push(name);
+        }
+        let values: Vec<_> = self.request.headers().get_all(name).collect();
+        let list = (!values.is_empty())
+            .then(|| AcceptList::from_bytes(values.join(",").as_bytes()).ok())
+            .flatten();
+        let Some(list) = list else {
+            return offers.first().copied().ok_or(StatusCode::NOT_ACCEPTABLE);
+        };
+
+        let mut best: Option<(&'o str, Quality)> = None;
+        for offer in offers {
+            let quality = quality(&list, offer);
+            if quality > best.map_or(Quality::ZERO, |(_, best)| best) {
+                best = Some((offer, quality));
+            }
+        }
+        best.map(|(offer, _)| offer)
+            .ok_or(StatusCode::NOT_ACCEPTABLE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::{test_request, Method, Response, StatusCode};
+
+    use super::{AcceptList, Negotiator, Quality};
+
+    fn list(src: &str) -> AcceptList {
+        AcceptList::from_bytes(src.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn invalid_quality_is_a_bad_request() {
+        for src in [&b""[..], b"2", b"1.5", b"0.1234", b"0.a", b".5", b"1.001"] {
+            assert_eq!(Err(StatusCode::BAD_REQUEST), Quality::from_bytes(src));
+        }
+        assert_eq!(Ok(Quality::ONE), Quality::from_bytes(b"1.000"));
+        assert_eq!("0.05", Quality::from_bytes(b"0.050").unwrap().to_string());
+        assert_eq!("0", Quality::ZERO.to_string());
+    }
+
+    #[test]
+    fn invalid_list_is_a_bad_request() {
+        for src in [
+            &b"text/"[..],
+            b"text/html;",
+            b"text/html;q",
+            b"a b",
+            b"text/html;q=2",
+        ] {
+            assert_eq!(Err(StatusCode::BAD_REQUEST), AcceptList::from_bytes(src));
+        }
+        assert!(AcceptList::from_bytes(b" , ").unwrap().items().is_empty());
+    }
+
+    #[test]
+    fn rfc_media_range_example() {
+        // Example from RFC7231 Section 5.3.2
+        let accept = list(
+            "text/*;q=0.3, text/html;q=0.7, text/html;level=1, \
+             text/html;level=2;q=0.4, */*;q=0.5",
+        );
+        for (media_type, quality) in [
+            ("text/html;level=1", 1000),
+            ("text/html", 700),
+            ("text/plain", 300),
+            ("image/jpeg", 500),
+            ("text/html;level=2", 400),
+            ("text/html;level=3", 700),
+        ] {
+            assert_eq!(
+                quality,
+                accept.media_type_quality(media_type).as_thousandths(),
+                "{media_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn media_range_parameters_may_be_quoted() {
+        let accept = list("text/plain; charset=\"utf-8\"; q=0.5; ext=\"a,b\", text/plain;q=0");
+        assert_eq!(1, accept.items()[0].params().len());
+        assert_eq!(
+            500,
+            accept
+                .media_type_quality("text/plain; charset=UTF-8")
+                .as_thousandths()
+        );
+        assert_eq!(Quality::ZERO, accept.media_type_quality("text/plain"));
+    }
+
+    #[test]
+    fn encoding_identity_is_acceptable_unless_excluded() {
+        assert_eq!(Quality::ONE, list("gzip").encoding_quality("identity"));
+        assert_eq!(Quality::ONE, list("").encoding_quality("identity"));
+        assert_eq!(Quality::ZERO, list("").encoding_quality("gzip"));
+        assert_eq!(Quality::ZERO, list("*;q=0").encoding_quality("identity"));
+        assert_eq!(
+            Quality::ONE,
+            list("*;q=0, identity").encoding_quality("identity")
+        );
+        assert_eq!(
+            Quality::ZERO,
+            list("identity;q=0").encoding_quality("IDENTITY")
+        );
+    }
+
+    #[test]
+    fn language_range_matches_prefix_of_subtags() {
+        let accept = list("da, en-gb;q=0.8, en;q=0.7, *;q=0.1");
+        assert_eq!(800, accept.language_quality("en-GB").as_thousandths());
+        assert_eq!(700, accept.language_quality("en-US").as_thousandths());
+        assert_eq!(700, accept.language_quality("en").as_thousandths());
+        assert_eq!(100, accept.language_quality("eng").as_thousandths());
+        assert_eq!(Quality::ZERO, list("en-gb").language_quality("en"));
+    }
+
+    #[test]
+    fn charset_without_wildcard_must_be_listed() {
+        let accept = list("iso-8859-5, unicode-1-1;q=0.8");
+        assert_eq!(Quality::ONE, accept.charset_quality("ISO-8859-5"));
+        assert_eq!(Quality::ZERO, accept.charset_quality("utf-8"));
+    }
+
+    #[test]
+    fn first_offer_with_highest_quality_is_selected() {
+        let fields = [
+            ("Accept", "text/html, application/json"),
+            ("Accept-Language", "fr;q=0.5, de;q=0.5"),
+        ];
+        let request = test_request(Method::Get, "/", &fields, "");
+        let mut negotiator = Negotiator::new(&request);
+        assert_eq!(
+            Ok("application/json"),
+            negotiator.media_type(&["application/json", "text/html"])
+        );
+        assert_eq!(Ok("de"), negotiator.language(&["en", "de", "fr"]));
+        assert_eq!(
+            Err(StatusCode::NOT_ACCEPTABLE),
+            negotiator.media_type(&["image/png"])
+        );
+        assert_eq!(&["Accept", "Accept-Language"], negotiator.vary());
+    }
+
+    #[test]
+    fn missing_or_invalid_header_selects_first_offer() {
+        let request = test_request(Method::Get, "/", &[("Accept", "text/html;q=2")], "");
+        let mut negotiator = Negotiator::new(&request);
+        assert_eq!(
+            Ok("image/png"),
+            negotiator.media_type(&["image/png", "text/html"])
+        );
+        assert_eq!(Ok("utf-8"), negotiator.charset(&["utf-8"]));
+        assert_eq!(Err(StatusCode::NOT_ACCEPTABLE), negotiator.encoding(&[]));
+
+        let response = negotiator.apply(Response::new(StatusCode::OK).with_vary("Origin"));
+        assert_eq!(
+            Some("Origin, Accept, Accept-Charset, Accept-Encoding"),
+            response.headers().get("vary")
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+
+use super::{Authority, Host, Scheme, StatusCode};
+
+/// The origin of a request, as sent in the `Origin` header field and defined in [RFC6454
+/// Section 6.2](https://datatracker.ietf.org/doc/html/rfc6454#section-6.2).
+///
+/// ```text
+/// serialized-origin = scheme "://" host [ ":" port ]
+/// ```
+///
+/// Two origins are the same when their scheme, host and port are the same, so the scheme and
+/// a domain name are compared without case and a default port, such as 443 for "https", is
+/// the same as no port.
+///
+/// ```
+/// use ramus::http::Origin;
+///
+/// let origin = Origin::from_bytes(b"HTTPS://Example.com:443").expect("valid origin");
+/// assert_eq!("https://example.com", origin.to_string());
+/// assert_eq!(Origin::from_bytes(b"https://example.com"), Ok(origin));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Origin {
+    scheme: Scheme,
+    host: Host,
+    port: Option<u16>,
+}
+
+impl Origin {
+    /// Derive an [`Origin`] from a slice of bytes.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] when the slice of bytes does not match the ABNF
+    /// syntax of `serialized-origin`, which includes the "null" of a privacy-sensitive origin.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        let i = src
+            .windows(3)
+            .position(|window| window == b"://")
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        let scheme = Scheme::from_bytes(&src[..i])?;
+        let authority = Authority::from_bytes(&src[i + 3..])?;
+        if scheme.is_empty() || authority.user_info().is_some() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let host = match authority.host() {
+            Host::Domain(name) => Host::Domain(name.to_ascii_lowercase()),
+            host => host.clone(),
+        };
+        let default_port = match scheme.as_str() {
+            "http" | "ws" => Some(80),
+            "https" | "wss" => Some(443),
+            _ => None,
+        };
+        // an empty port is parsed as 0 by the authority
+        let port = authority
+            .port()
+            .filter(|port| *port != 0 && Some(*port) != default_port);
+        Ok(Self { scheme, host, port })
+    }
+
+    /// Returns the scheme of the origin.
+    pub fn scheme(&self) -> &Scheme {
+        &self.scheme
+    }
+
+    /// Returns the host of the origin.
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    /// Returns the port of the origin, which is [`None`] for the default port of the scheme.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}", self.scheme.as_str(), self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::StatusCode;
+
+    use super::Origin;
+
+    #[test]
+    fn invalid_origin_is_a_bad_request() {
+        for src in [
+            &b"null"[..],
+            b"example.com",
+            b"://example.com",
+            b"https://user@example.com",
+            b"https://example.com/path",
+            b"https://example.com:99999",
+        ] {
+            assert_eq!(Err(StatusCode::BAD_REQUEST), Origin::from_bytes(src));
+        }
+    }
+
+    #[test]
+    fn ports_other_than_the_default_are_kept() {
+        let origin = |src: &str| Origin::from_bytes(src.as_bytes()).unwrap();
+        assert_eq!(Some(8080), origin("http://localhost:8080").port());
+        assert_eq!(None, origin("http://localhost:80").port());
+        assert_eq!(Some(80), origin("https://localhost:80").port());
+        assert_eq!("http://[::1]:3000", origin("http://[::1]:3000").to_string());
+    }
+}
@@ -0,0 +1,570 @@
+use std::{
+    cmp,
+    collections::{hash_map::RandomState, VecDeque},
+    fmt,
+    hash::{BuildHasher, Hasher},
+    io::{self, Read, SeekFrom},
+};
+
+use super::{utils, Headers, Request, Seekable, StatusCode};
+
+/// The most ranges that are sent in a `multipart/byteranges` response, a request for more ranges
+/// than this, after any overlapping ranges have been coalesced, is served as a whole.
+const MAX_RANGES: usize = 32;
+
+/// Byte range as defined in [RFC7233 Section
+/// 2.1](https://datatracker.ietf.org/doc/html/rfc7233#section-2.1).
+///
+/// ```text
+/// byte-range-spec = first-byte-pos "-" [ last-byte-pos ]
+/// suffix-byte-range-spec = "-" suffix-length
+///
+/// first-byte-pos = 1*DIGIT
+/// last-byte-pos = 1*DIGIT
+/// suffix-length = 1*DIGIT
+/// ```
+///
+/// The positions are inclusive and start at zero, so "0-499" is the first 500 bytes and "-500"
+/// is the last 500 bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ByteRangeSpec {
+    /// The bytes from the first position to the last position, or the end of the
+    /// representation when it is shorter.
+    FromTo(u64, u64),
+    /// The bytes from the first position to the end of the representation.
+    From(u64),
+    /// The last number of bytes of the representation.
+    Suffix(u64),
+}
+
+impl ByteRangeSpec {
+    /// Returns the first and last position of this range in a representation of the length, or
+    /// [`None`] when the range is not satisfiable as it starts after the end of the
+    /// representation or is an empty suffix.
+    ///
+    /// ```
+    /// use ramus::http::ByteRangeSpec;
+    ///
+    /// assert_eq!(Some((0, 9)), ByteRangeSpec::FromTo(0, 499).resolve(10));
+    /// assert_eq!(Some((7, 9)), ByteRangeSpec::Suffix(3).resolve(10));
+    /// assert_eq!(None, ByteRangeSpec::From(10).resolve(10));
+    /// ```
+    pub fn resolve(&self, length: u64) -> Option<(u64, u64)> {
+        match *self {
+            Self::FromTo(first, last) if first < length => {
+                Some((first, cmp::min(last, length - 1)))
+            }
+            Self::From(first) if first < length => Some((first, length - 1)),
+            Self::Suffix(suffix) if suffix > 0 && length > 0 => {
+                Some((length - cmp::min(suffix, length), length - 1))
+            }
+            _ => None,
+        }
+    }
+
+    fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        let (first, last) = utils::split_at_next(src, b'-').ok_or(StatusCode::BAD_REQUEST)?;
+        match (first.is_empty(), last.is_empty()) {
+            (true, true) => Err(StatusCode::BAD_REQUEST),
+            (true, false) => Ok(Self::Suffix(parse_digits(last)?)),
+            (false, true) => Ok(Self::From(parse_digits(first)?)),
+            (false, false) => {
+                let (first, last) = (parse_digits(first)?, parse_digits(last)?);
+                // a last position before the first is syntactically invalid
+                if last < first {
+                    return Err(StatusCode::BAD_REQUEST);
+                }
+                Ok(Self::FromTo(first, last))
+            }
+        }
+    }
+}
+
+/// Range header field as defined in [RFC7233 Section
+/// 3.1](https://datatracker.ietf.org/doc/html/rfc7233#section-3.1), for the only range unit
+/// that is defined, which is "bytes".
+///
+/// ```text
+/// Range = byte-ranges-specifier / other-ranges-specifier
+///
+/// byte-ranges-specifier = bytes-unit "=" byte-range-set
+/// byte-range-set = 1#( byte-range-spec / suffix-byte-range-spec )
+/// bytes-unit = "bytes"
+/// ```
+///
+/// ```
+/// use ramus::http::{ByteRangeSet, ByteRangeSpec};
+///
+/// let set = ByteRangeSet::from_bytes(b"bytes=0-99, -50").expect("valid range");
+/// assert_eq!(
+///     &[ByteRangeSpec::FromTo(0, 99), ByteRangeSpec::Suffix(50)],
+///     set.specs()
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ByteRangeSet(Vec<ByteRangeSpec>);
+
+impl ByteRangeSet {
+    /// Derive a [`ByteRangeSet`] from the value of a `Range` header field.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the value does not match the ABNF syntax or
+    /// uses a range unit other than "bytes", either of which a server ignores and so sends the
+    /// whole representation.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        let (unit, set) = utils::split_at_next(src, b'=').ok_or(StatusCode::BAD_REQUEST)?;
+        // RFC7233 Section 2: range units are case-insensitive
+        if !unit.eq_ignore_ascii_case(b"bytes") {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let specs = set
+            .split(|b| *b == b',')
+            .map(utils::abnf::trim_ows)
+            // RFC7230 Section 7: empty list elements are allowed and ignored
+            .filter(|spec| !spec.is_empty())
+            .map(ByteRangeSpec::from_bytes)
+            .collect::<Result<Vec<_>, _>>()?;
+        if specs.is_empty() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Ok(Self(specs))
+    }
+
+    /// Returns the ranges in the order they were requested.
+    pub fn specs(&self) -> &[ByteRangeSpec] {
+        &self.0
+    }
+
+    /// Returns the first and last positions of the satisfiable ranges in a representation of
+    /// the length, which is empty when none of the ranges are satisfiable.
+    ///
+    /// The ranges are in the order they were requested, unless any of them overlap, in which
+    /// case they are sorted and the overlapping ranges are coalesced as allowed by [RFC7233
+    /// Section 4.1](https://datatracker.ietf.org/doc/html/rfc7233#section-4.1).
+    ///
+    /// ```
+    /// use ramus::http::ByteRangeSet;
+    ///
+    /// let set = ByteRangeSet::from_bytes(b"bytes=50-59,0-9,100-").expect("valid range");
+    /// assert_eq!(vec![(50, 59), (0, 9)], set.satisfiable(100));
+    ///
+    /// let set = ByteRangeSet::from_bytes(b"bytes=50-59,0-9,5-55").expect("valid range");
+    /// assert_eq!(vec![(0, 59)], set.satisfiable(100));
+    /// ```
+    pub fn satisfiable(&self, length: u64) -> Vec<(u64, u64)> {
+        let mut ranges: Vec<_> = self
+            .0
+            .iter()
+            .filter_map(|spec| spec.resolve(length))
+            .collect();
+        let overlaps = ranges
+            .iter()
+            .enumerate()
+            .any(|(i, a)| ranges[i + 1..].iter().any(|b| a.0 <= b.1 && b.0 <= a.1));
+        if overlaps {
+            ranges.sort_unstable();
+            let mut coalesced: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+            for (first, last) in ranges {
+                match coalesced.last_mut() {
+                    Some(previous) if first <= previous.1 => {
+                        previous.1 = cmp::max(previous.1, last)
+                    }
+                    _ => coalesced.push((first, last)),
+                }
+            }
+            ranges = coalesced;
+        }
+        ranges
+    }
+}
+
+/// Content-Range header field as defined in [RFC7233 Section
+/// 4.2](https://datatracker.ietf.org/doc/html/rfc7233#section-4.2), for the "bytes" range
+/// unit.
+///
+/// ```text
+/// Content-Range = byte-content-range / other-content-range
+///
+/// byte-content-range = bytes-unit SP ( byte-range-resp / unsatisfied-range )
+/// byte-range-resp = byte-range "/" ( complete-length / "*" )
+/// byte-range = first-byte-pos "-" last-byte-pos
+/// unsatisfied-range = "*/" complete-length
+/// complete-length = 1*DIGIT
+/// ```
+///
+/// ```
+/// use ramus::http::ContentRange;
+///
+/// let range = ContentRange::Bytes { first: 0, last: 499, complete_length: Some(1234) };
+/// assert_eq!("bytes 0-499/1234", range.to_string());
+/// assert_eq!(Ok(range), ContentRange::from_bytes(b"bytes 0-499/1234"));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ContentRange {
+    /// The range of bytes that is enclosed in the response, with the length of the whole
+    /// representation when it is known.
+    Bytes {
+        /// The position of the first byte in the range.
+        first: u64,
+        /// The position of the last byte in the range, which is inclusive.
+        last: u64,
+        /// The length of the whole representation, if known.
+        complete_length: Option<u64>,
+    },
+    /// The length of the whole representation, sent with a
+    /// [`StatusCode::RANGE_NOT_SATISFIABLE`].
+    Unsatisfied(u64),
+}
+
+impl ContentRange {
+    /// Derive a [`ContentRange`] from the value of a `Content-Range` header field.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the value does not match the ABNF syntax, uses a
+    /// range unit other than "bytes", or the last position is before the first position or not
+    /// within the complete length.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        let (unit, rest) = utils::split_at_next(src, b' ').ok_or(StatusCode::BAD_REQUEST)?;
+        if !unit.eq_ignore_ascii_case(b"bytes") {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let (range, complete) = utils::split_at_next(rest, b'/').ok_or(StatusCode::BAD_REQUEST)?;
+        if range == b"*" {
+            return Ok(Self::Unsatisfied(parse_digits(complete)?));
+        }
+        let complete_length = match complete {
+            b"*" => None,
+            complete => Some(parse_digits(complete)?),
+        };
+        let (first, last) = utils::split_at_next(range, b'-').ok_or(StatusCode::BAD_REQUEST)?;
+        let (first, last) = (parse_digits(first)?, parse_digits(last)?);
+        if last < first || complete_length.is_some_and(|length| last >= length) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Ok(Self::Bytes {
+            first,
+            last,
+            complete_length,
+        })
+    }
+}
+
+impl fmt::Display for ContentRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes {
+                first,
+                last,
+                complete_length: Some(length),
+            } => write!(f, "bytes {first}-{last}/{length}"),
+            Self::Bytes { first, last, .. } => write!(f, "bytes {first}-{last}/*"),
+            Self::Unsatisfied(length) => write!(f, "bytes */{length}"),
+        }
+    }
+}
+
+/// Parse `1*DIGIT` as a u64, a number too large for a u64 is not expected from a valid message.
+fn parse_digits(src: &[u8]) -> Result<u64, StatusCode> {
+    if src.is_empty() || !src.iter().all(u8::is_ascii_digit) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    src.iter()
+        .try_fold(0u64, |acc, b| {
+            acc.checked_mul(10)?.checked_add(u64::from(b - b'0'))
+        })
+        .ok_or(StatusCode::BAD_REQUEST)
+}
+
+/// The ranges of a representation to send in response to a request.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Selected {
+    /// The whole representation, as there is no `Range`, it is ignored, or the `If-Range`
+    /// validator does not match.
+    Full,
+    /// The satisfiable ranges, of which there is at least one.
+    Partial(Vec<(u64, u64)>),
+    /// None of the ranges are satisfiable.
+    NotSatisfiable,
+}
+
+/// Select the ranges of a representation of the length to send in response to the request, as
+/// defined in [RFC7233 Section 3](https://datatracker.ietf.org/doc/html/rfc7233#section-3).
+///
+/// The headers are those of the response with the whole representation, which are used to
+/// evaluate any `If-Range` of the request.
+pub(crate) fn select(request: &Request, headers: &Headers, length: u64) -> Selected {
+    let Some(range) = request.headers().get("range") else {
+        return Selected::Full;
+    };
+    if let Some(validator) = request.headers().get("if-range") {
+        if !if_range_matches(validator, headers) {
+            return Selected::Full;
+        }
+    }
+    let Ok(set) = ByteRangeSet::from_bytes(range.as_bytes()) else {
+        return Selected::Full;
+    };
+    match set.satisfiable(length) {
+        ranges if ranges.is_empty() => Selected::NotSatisfiable,
+        ranges if ranges.len() > MAX_RANGES => Selected::Full,
+        ranges => Selected::Partial(ranges),
+    }
+}
+
+/// Returns true if the `If-Range` validator matches the representation, as defined in [RFC7233
+/// Section 3.2](https://datatracker.ietf.org/doc/html/rfc7233#section-3.2).
+///
+/// ```text
+/// If-Range = entity-tag / HTTP-date
+/// ```
+///
+/// An entity-tag uses the strong comparison and so a weak entity-tag never matches, and a
+/// HTTP-date must be an exact match of the `Last-Modified`.
+fn if_range_matches(validator: &str, headers: &Headers) -> bool {
+    if validator.starts_with("W/") {
+        false
+    } else if validator.starts_with('"') {
+        headers
+            .get("etag")
+            .is_some_and(|etag| !etag.starts_with("W/") && etag == validator)
+    } else {
+        headers.get("last-modified") == Some(validator)
+    }
+}
+
+/// A body that reads parts from a seekable stream, with the octets in between each part, such
+/// as the boundary and header fields of a part in a `multipart/byteranges` body.
+pub(crate) struct Parts {
+    stream: Box<dyn Seekable>,
+    parts: VecDeque<Part>,
+}
+
+enum Part {
+    Bytes(io::Cursor<Vec<u8>>),
+    /// The position to seek to before reading, which is taken once the seek is done, and the
+    /// number of octets left to read.
+    Range(Option<u64>, u64),
+}
+
+impl Parts {
+    /// A body of the single range of the stream.
+    pub(crate) fn single(stream: Box<dyn Seekable>, (first, last): (u64, u64)) -> Self {
+        Self {
+            stream,
+            parts: VecDeque::from([Part::Range(Some(first), last - first + 1)]),
+        }
+    }
+
+    /// A `multipart/byteranges` body of the ranges of the stream, as defined in [RFC7233
+    /// Appendix A](https://datatracker.ietf.org/doc/html/rfc7233#appendix-A), with the content
+    /// type of the whole representation in each part.
+    ///
+    /// Returns the body, its length and the boundary that separates the parts.
+    pub(crate) fn multipart(
+        stream: Box<dyn Seekable>,
+        ranges: &[(u64, u64)],
+        content_type: Option<&str>,
+        complete_length: u64,
+    ) -> (Self, u64, String) {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(complete_length);
+        let boundary = format!("ramus-{:016x}", hasher.finish());
+
+        let mut parts = VecDeque::with_capacity(ranges.len() * 2 + 1);
+        let mut length = 0;
+        let mut delimiter = String::new();
+        for &(first, last) in ranges {
+            delimiter.push_str(&format!("--{boundary}\r\n"));
+            if let Some(content_type) = content_type {
+                delimiter.push_str(&format!("Content-Type: {content_type}\r\n"));
+            }
+            let range = ContentRange::Bytes {
+                first,
+                last,
+                complete_length: Some(complete_length),
+            };
+            delimiter.push_str(&format!("Content-Range: {range}\r\n\r\n"));
+            length += delimiter.len() as u64 + last - first + 1;
+            parts.push_back(Part::Bytes(io::Cursor::new(delimiter.into_bytes())));
+            parts.push_back(Part::Range(Some(first), last - first + 1));
+            delimiter = "\r\n".to_string();
+        }
+        delimiter.push_str(&format!("--{boundary}--\r\n"));
+        length += delimiter.len() as u64;
+        parts.push_back(Part::Bytes(io::Cursor::new(delimiter.into_bytes())));
+
+        (Self { stream, parts }, length, boundary)
+    }
+}
+
+impl Read for Parts {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while let Some(part) = self.parts.front_mut() {
+            match part {
+                Part::Bytes(bytes) => {
+                    let read = bytes.read(buf)?;
+                    if read > 0 {
+                        return Ok(read);
+                    }
+                }
+                Part::Range(seek, remaining) if *remaining > 0 => {
+                    if let Some(position) = seek.take() {
+                        self.stream.seek(SeekFrom::Start(position))?;
+                    }
+                    let max = cmp::min(buf.len() as u64, *remaining) as usize;
+                    let read = self.stream.read(&mut buf[..max])?;
+                    if read == 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "stream ended before the range",
+                        ));
+                    }
+                    *remaining -= read as u64;
+                    return Ok(read);
+                }
+                Part::Range(..) => {}
+            }
+            self.parts.pop_front();
+        }
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use crate::http::{test_request, Headers, Method, StatusCode};
+
+    use super::{select, ByteRangeSet, ByteRangeSpec, ContentRange, Parts, Selected};
+
+    #[test]
+    fn invalid_range_is_a_bad_request() {
+        for src in [
+            &b"bytes="[..],
+            b"bytes=-",
+            b"bytes=5-1",
+            b"bytes=a-",
+            b"bytes=1-2;",
+            b"items=0-1",
+            b"0-1",
+            b"bytes=99999999999999999999-",
+        ] {
+            assert_eq!(Err(StatusCode::BAD_REQUEST), ByteRangeSet::from_bytes(src));
+        }
+    }
+
+    #[test]
+    fn range_unit_is_case_insensitive_and_empty_elements_are_ignored() {
+        let set = ByteRangeSet::from_bytes(b"Bytes= ,500-, 0-0 ,").unwrap();
+        assert_eq!(
+            &[ByteRangeSpec::From(500), ByteRangeSpec::FromTo(0, 0)],
+            set.specs()
+        );
+    }
+
+    #[test]
+    fn unsatisfiable_ranges_are_removed() {
+        let set = ByteRangeSet::from_bytes(b"bytes=10-20,-0,5-").unwrap();
+        assert_eq!(vec![(5, 9)], set.satisfiable(10));
+        assert!(set.satisfiable(0).is_empty());
+    }
+
+    #[test]
+    fn content_range_is_parsed() {
+        assert_eq!(
+            Ok(ContentRange::Unsatisfied(10)),
+            ContentRange::from_bytes(b"bytes */10")
+        );
+        assert_eq!(
+            Ok(ContentRange::Bytes {
+                first: 1,
+                last: 2,
+                complete_length: None
+            }),
+            ContentRange::from_bytes(b"bytes 1-2/*")
+        );
+        for src in [
+            &b"bytes 2-1/10"[..],
+            b"bytes 0-10/10",
+            b"items 0-1/2",
+            b"bytes */*",
+        ] {
+            assert_eq!(Err(StatusCode::BAD_REQUEST), ContentRange::from_bytes(src));
+        }
+    }
+
+    #[test]
+    fn if_range_must_match_validator() {
+        let mut headers = Headers::new();
+        headers.insert("ETag", "\"v1\"");
+        headers.insert("Last-Modified", "Sun, 06 Nov 1994 08:49:37 GMT");
+        let partial = Selected::Partial(vec![(0, 0)]);
+
+        assert_eq!(
+            partial,
+            select(
+                &test_request(Method::Get, "/", &[("Range", "bytes=0-0")], ""),
+                &headers,
+                5
+            )
+        );
+        for (validator, selected) in [
+            ("\"v1\"", &partial),
+            ("\"v2\"", &Selected::Full),
+            ("W/\"v1\"", &Selected::Full),
+            ("Sun, 06 Nov 1994 08:49:37 GMT", &partial),
+            ("Sun, 06 Nov 1994 08:49:38 GMT", &Selected::Full),
+        ] {
+            let fields = [("Range", "bytes=0-0"), ("If-Range", validator)];
+            let request = test_request(Method::Get, "/", &fields, "");
+            assert_eq!(selected, &select(&request, &headers, 5));
+        }
+    }
+
+    #[test]
+    fn invalid_or_too_many_ranges_are_ignored() {
+        let headers = Headers::new();
+        assert_eq!(
+            Selected::Full,
+            select(
+                &test_request(Method::Get, "/", &[("Range", "items=0-1")], ""),
+                &headers,
+                5
+            )
+        );
+        assert_eq!(
+            Selected::NotSatisfiable,
+            select(
+                &test_request(Method::Get, "/", &[("Range", "bytes=5-")], ""),
+                &headers,
+                5
+            )
+        );
+        let many = (0..40)
+            .map(|i| format!("{}-{}", i * 2, i * 2))
+            .collect::<Vec<_>>();
+        let range = format!("bytes={}", many.join(","));
+        let request = test_request(Method::Get, "/", &[("Range", &range)], "");
+        assert_eq!(Selected::Full, select(&request, &headers, 100));
+    }
+
+    #[test]
+    fn multipart_body_has_a_part_for_each_range() {
+        let stream = Box::new(Cursor::new(b"0123456789".to_vec()));
+        let (mut parts, length, boundary) =
+            Parts::multipart(stream, &[(7, 9), (0, 1)], Some("text/plain"), 10);
+        let mut body = String::new();
+        parts.read_to_string(&mut body).unwrap();
+        assert_eq!(
+            format!(
+                "--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 7-9/10\r\n\r\n789\r\n\
+                 --{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/10\r\n\r\n01\r\n\
+                 --{boundary}--\r\n"
+            ),
+            body
+        );
+        assert_eq!(length, body.len() as u64);
+    }
+}
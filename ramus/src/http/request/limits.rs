@@ -0,0 +1,56 @@
+use super::RequestLine;
+
+/// The limits applied when parsing a [`Request`](super::Request).
+///
+/// Each limit maps to the status code that is returned when it is exceeded:
+///
+/// | Limit | Status Code |
+/// |-------|-------------|
+/// | [`request_line`](Limits::request_line) | [`StatusCode::URI_TOO_LONG`] |
+/// | [`uri`](Limits::uri) | [`StatusCode::URI_TOO_LONG`] |
+/// | [`header_count`](Limits::header_count) | [`StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE`] |
+/// | [`header_size`](Limits::header_size) | [`StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE`] |
+/// | [`header_block`](Limits::header_block) | [`StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE`] |
+/// | [`body`](Limits::body) | [`StatusCode::PAYLOAD_TOO_LARGE`] |
+///
+/// ```
+/// use ramus::http::Limits;
+///
+/// let limits = Limits {
+///     body: 1024,
+///     ..Limits::default()
+/// };
+/// assert_eq!(1024, limits.body);
+/// ```
+///
+/// [`StatusCode::URI_TOO_LONG`]: crate::http::StatusCode::URI_TOO_LONG
+/// [`StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE`]: crate::http::StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+/// [`StatusCode::PAYLOAD_TOO_LARGE`]: crate::http::StatusCode::PAYLOAD_TOO_LARGE
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Limits {
+    /// The maximum number of octets in the `request-line`, excluding the CRLF.
+    pub request_line: usize,
+    /// The maximum number of octets in the `request-target`.
+    pub uri: usize,
+    /// The maximum number of header fields.
+    pub header_count: usize,
+    /// The maximum number of octets in a single header field, excluding the CRLF.
+    pub header_size: usize,
+    /// The maximum number of octets in the whole header section, including each CRLF.
+    pub header_block: usize,
+    /// The maximum number of octets in the message body.
+    pub body: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            request_line: 8 * 1024,
+            uri: RequestLine::URI_MAX_LENGTH,
+            header_count: 100,
+            header_size: 8 * 1024,
+            header_block: 64 * 1024,
+            body: 2 * 1024 * 1024,
+        }
+    }
+}
@@ -1,4 +1,395 @@
-use super::{method::Method, utils::split_at_next_space, StatusCode, Uri, Version};
+mod limits;
+
+pub use limits::*;
+
+use std::{io::BufRead, sync::Arc};
+
+use super::{
+    chunked,
+    method::Method,
+    utils::{self, split_at_next_space},
+    Authority, ContentCoding, CookieJar, HeaderField, Headers, MediaType, Scheme, StatusCode, Uri,
+    Version,
+};
+
+/// HTTP request message as defined in [RFC7230 Section
+/// 3](https://datatracker.ietf.org/doc/html/rfc7230#section-3).
+///
+/// ```text
+/// HTTP-message = start-line
+///                *( header-field CRLF )
+///                CRLF
+///                [ message-body ]
+/// ```
+/// For `start-line` see [`RequestLine`] and for `header-field` see [`HeaderField`].
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    line: RequestLine,
+    headers: Headers,
+    // shared so that an extractor, such as a multipart body, can read it without a copy
+    body: Arc<[u8]>,
+    scheme: Scheme,
+}
+
+impl Request {
+    /// Create a [`Request`] from parts that have already been parsed and validated, such as a
+    /// HTTP/2 request.
+    pub(crate) fn from_parts(line: RequestLine, headers: Headers, body: Vec<u8>) -> Self {
+        Self {
+            line,
+            headers,
+            body: body.into(),
+            scheme: Scheme::http(),
+        }
+    }
+
+    /// Read a whole [`Request`], both the head and the body, from the reader.
+    ///
+    /// See [`Request::head_from_reader`] and [`Request::read_body`] for the errors returned.
+    pub fn from_reader<R: BufRead>(
+        reader: &mut R,
+        limits: &Limits,
+    ) -> Result<Option<Self>, StatusCode> {
+        match Self::head_from_reader(reader, limits)? {
+            Some(mut request) => {
+                request.read_body(reader, limits)?;
+                Ok(Some(request))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Read the `request-line` and header section of a [`Request`] from the reader, the body of
+    /// the returned [`Request`] will be empty.
+    ///
+    /// Returns `None` if the reader has no more bytes before the `request-line`.
+    /// Returns a [`StatusCode::URI_TOO_LONG`] when the `request-line` is longer than
+    /// [`Limits::request_line`], and a [`StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE`] when any
+    /// of the header limits are exceeded.
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the `Host` header field is missing from a
+    /// request that requires one, see [`Version::requires_host`], or if there is more than one
+    /// `Host` header field or its value is not valid.
+    /// Returns a [`StatusCode::REQUEST_TIMEOUT`] if the reader times out.
+    pub fn head_from_reader<R: BufRead>(
+        reader: &mut R,
+        limits: &Limits,
+    ) -> Result<Option<Self>, StatusCode> {
+        Self::read_head(reader, limits, false)
+    }
+
+    /// Read the head of a [`Request`] as [`Request::head_from_reader`] does, but also accept a
+    /// HTTP/0.9 `Simple-Request` when `http_0_9` is true, see [`RequestLine::parse`].
+    pub(crate) fn read_head<R: BufRead>(
+        reader: &mut R,
+        limits: &Limits,
+        http_0_9: bool,
+    ) -> Result<Option<Self>, StatusCode> {
+        let mut line =
+            match utils::read_line(reader, limits.request_line, StatusCode::URI_TOO_LONG)? {
+                Some(line) => line,
+                None => return Ok(None),
+            };
+        // RFC7230 Section 3.5: a server SHOULD ignore at least one empty line received prior
+        // to the request-line
+        if line.is_empty() {
+            line = utils::read_line(reader, limits.request_line, StatusCode::URI_TOO_LONG)?
+                .ok_or(StatusCode::BAD_REQUEST)?;
+        }
+        let line = RequestLine::parse(&line, limits, http_0_9)?;
+
+        // a Simple-Request has no header section
+        if line.version == Version::HTTP_0_9 {
+            return Ok(Some(Self {
+                line,
+                headers: Headers::new(),
+                body: Arc::default(),
+                scheme: Scheme::http(),
+            }));
+        }
+
+        let headers = read_headers(reader, limits)?;
+        validate_host(&headers, line.version)?;
+
+        Ok(Some(Self {
+            line,
+            headers,
+            body: Arc::default(),
+            scheme: Scheme::http(),
+        }))
+    }
+
+    /// Read the message body of the [`Request`] from the reader, this should be called after
+    /// [`Request::head_from_reader`] with the same reader.
+    ///
+    /// The length of the body is determined as defined in [RFC7230 Section
+    /// 3.3.3](https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.3), by the chunked
+    /// `Transfer-Encoding` or the `Content-Length` header field, when neither is present the
+    /// body is empty.
+    ///
+    /// A body with a `Content-Encoding` is decoded, removing the `Content-Encoding`, when each
+    /// [`ContentCoding`] is [enabled](ContentCoding::enabled), otherwise a
+    /// [`StatusCode::UNSUPPORTED_MEDIA_TYPE`] is returned. The decoded body is also limited to
+    /// [`Limits::body`], so that a small body cannot decompress into an unbounded one.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the `Content-Length` is not valid, if both the
+    /// `Content-Length` and `Transfer-Encoding` are present, if a HTTP/1.0 request uses a
+    /// `Transfer-Encoding` or if the reader ends before the whole body is read.
+    /// Returns a [`StatusCode::PAYLOAD_TOO_LARGE`] when the body is longer than [`Limits::body`].
+    /// Returns a [`StatusCode::NOT_IMPLEMENTED`] when a `Transfer-Encoding` other than chunked
+    /// is used.
+    pub fn read_body<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        limits: &Limits,
+    ) -> Result<(), StatusCode> {
+        if let Some(coding) = self.headers.get("transfer-encoding") {
+            if self.headers.contains("content-length") || !self.version().supports_chunked() {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            if self.headers.get_all("transfer-encoding").count() > 1
+                || !coding.eq_ignore_ascii_case("chunked")
+            {
+                return Err(StatusCode::NOT_IMPLEMENTED);
+            }
+            self.body = chunked::read_chunked(reader, limits)?.into();
+            return self.decode_content(limits);
+        }
+
+        let length = match content_length(&self.headers)? {
+            Some(length) if length > limits.body as u64 => {
+                return Err(StatusCode::PAYLOAD_TOO_LARGE)
+            }
+            Some(length) => length as usize,
+            None => return Ok(()),
+        };
+
+        let mut body = vec![0; length];
+        reader
+            .read_exact(&mut body)
+            .map_err(utils::io_error_status)?;
+        self.body = body.into();
+        self.decode_content(limits)
+    }
+
+    /// Decode the body when it has a `Content-Encoding`, as defined in [RFC7231 Section
+    /// 3.1.2.2](https://datatracker.ietf.org/doc/html/rfc7231#section-3.1.2.2), undoing each
+    /// [`ContentCoding`] in the reverse order that they were applied.
+    ///
+    /// The `Content-Encoding` is removed and any `Content-Length` is set to the length of the
+    /// decoded body, so that the request describes the body that is passed on.
+    ///
+    /// Returns a [`StatusCode::UNSUPPORTED_MEDIA_TYPE`] for a coding that is not
+    /// [enabled](ContentCoding::enabled), a [`StatusCode::BAD_REQUEST`] if the body is not valid
+    /// for the coding and a [`StatusCode::PAYLOAD_TOO_LARGE`] when the decoded body is longer
+    /// than [`Limits::body`].
+    pub(crate) fn decode_content(&mut self, limits: &Limits) -> Result<(), StatusCode> {
+        let codings = self
+            .headers
+            .get_all("content-encoding")
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|coding| !coding.is_empty() && !coding.eq_ignore_ascii_case("identity"))
+            .map(|coding| ContentCoding::from_bytes(coding.as_bytes()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if codings.is_empty() {
+            return Ok(());
+        }
+        for coding in codings.into_iter().rev() {
+            self.body = coding.decode(&self.body, limits.body)?.into();
+        }
+        self.headers.remove("content-encoding");
+        if self.headers.contains("content-length") {
+            self.headers
+                .insert("Content-Length", self.body.len().to_string());
+        }
+        Ok(())
+    }
+
+    /// Returns the [`RequestLine`] of the request.
+    pub fn request_line(&self) -> &RequestLine {
+        &self.line
+    }
+
+    /// Returns the [`Method`] of the request.
+    pub fn method(&self) -> Method {
+        self.line.method
+    }
+
+    /// Returns the `request-target` of the request.
+    pub fn uri(&self) -> &Uri {
+        &self.line.uri
+    }
+
+    /// Returns the [`Scheme`] of the connection that the request was received on, which is
+    /// "https" for a TLS connection and otherwise "http".
+    pub fn scheme(&self) -> &Scheme {
+        &self.scheme
+    }
+
+    pub(crate) fn set_scheme(&mut self, scheme: Scheme) {
+        self.scheme = scheme;
+    }
+
+    /// Returns the effective request URI as defined in [RFC7230 Section
+    /// 5.5](https://datatracker.ietf.org/doc/html/rfc7230#section-5.5).
+    ///
+    /// The `request-target` is used when it is in absolute-form, otherwise the URI is
+    /// reconstructed from the [`Request::scheme`], the `Host` header field and the
+    /// `request-target`.
+    pub fn effective_uri(&self) -> Uri {
+        let host = self
+            .headers
+            .get("host")
+            .and_then(|host| Authority::from_bytes(host.as_bytes()).ok());
+        self.line.uri.effective(&self.scheme, host)
+    }
+
+    /// Returns the HTTP [`Version`] of the request.
+    pub fn version(&self) -> Version {
+        self.line.version
+    }
+
+    /// Returns the header section of the request.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the [`MediaType`] of the body from the `Content-Type` header field, or [`None`]
+    /// if there is no `Content-Type` or it is not a valid media type.
+    pub fn content_type(&self) -> Option<MediaType> {
+        self.headers
+            .get("content-type")
+            .and_then(|value| MediaType::from_bytes(value.as_bytes()).ok())
+    }
+
+    /// Returns the cookies from all of the `Cookie` header fields, see [`CookieJar`].
+    pub fn cookies(&self) -> CookieJar {
+        CookieJar::from_headers(&self.headers)
+    }
+
+    /// Returns the message body of the request.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns the message body of the request, shared rather than copied.
+    pub(crate) fn shared_body(&self) -> Arc<[u8]> {
+        Arc::clone(&self.body)
+    }
+
+    /// Returns true if the client wants the connection to persist after the response to this
+    /// request, as defined in [RFC7230 Section
+    /// 6.3](https://datatracker.ietf.org/doc/html/rfc7230#section-6.3).
+    ///
+    /// The "close" connection option always closes the connection, otherwise the connection
+    /// persists by default for HTTP/1.1 and only with the "keep-alive" connection option for
+    /// HTTP/1.0.
+    pub fn keep_alive(&self) -> bool {
+        let has_option = |option: &str| {
+            self.headers
+                .get_all("connection")
+                .flat_map(|value| value.split(','))
+                .any(|value| value.trim().eq_ignore_ascii_case(option))
+        };
+
+        if has_option("close") {
+            false
+        } else {
+            self.version().is_persistent_by_default() || has_option("keep-alive")
+        }
+    }
+}
+
+/// Read the header section of a message, up to and including the empty line that ends it, as
+/// defined in [RFC7230 Section 3](https://datatracker.ietf.org/doc/html/rfc7230#section-3).
+///
+/// ```text
+/// *( header-field CRLF )
+/// CRLF
+/// ```
+///
+/// Returns a [`StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE`] when any of the header limits are
+/// exceeded, and a [`StatusCode::BAD_REQUEST`] if a field is not valid, uses `obs-fold` or the
+/// reader ends before the empty line.
+pub(crate) fn read_headers<R: BufRead>(
+    reader: &mut R,
+    limits: &Limits,
+) -> Result<Headers, StatusCode> {
+    let mut headers = Headers::new();
+    let mut header_block = 0;
+    loop {
+        let field = utils::read_line(
+            reader,
+            limits.header_size,
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+        )?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+        // + 2 for the CRLF
+        header_block += field.len() + 2;
+        if header_block > limits.header_block {
+            return Err(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+        }
+
+        match field.as_slice() {
+            [] => return Ok(headers),
+            // obs-fold is only valid within the message/http media type
+            [b' ' | b'\t', ..] => return Err(StatusCode::BAD_REQUEST),
+            _ if headers.len() == limits.header_count => {
+                return Err(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE)
+            }
+            _ => headers.append(HeaderField::from_bytes(&field)?),
+        }
+    }
+}
+
+/// Validate the `Host` header field as defined in [RFC7230 Section
+/// 5.4](https://datatracker.ietf.org/doc/html/rfc7230#section-5.4).
+///
+/// ```text
+/// Host = uri-host [ ":" port ]
+/// ```
+/// An empty value is valid for when the target URI does not have an authority component.
+pub(crate) fn validate_host(headers: &Headers, version: Version) -> Result<(), StatusCode> {
+    let mut hosts = headers.get_all("host");
+    match (hosts.next(), hosts.next()) {
+        (Some(_), Some(_)) => Err(StatusCode::BAD_REQUEST),
+        (Some(host), None) if !host.is_empty() => {
+            if host.contains('@') {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            Authority::from_bytes(host.as_bytes()).map(|_| ())
+        }
+        (None, _) if version.requires_host() => Err(StatusCode::BAD_REQUEST),
+        _ => Ok(()),
+    }
+}
+
+/// Parse the `Content-Length` header field as defined in [RFC7230 Section
+/// 3.3.2](https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.2).
+///
+/// ```text
+/// Content-Length = 1*DIGIT
+/// ```
+///
+/// Multiple fields, or a list of values, are only accepted when each value is the same as
+/// required by [RFC7230 Section 3.3.3](https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.3).
+/// A value too large to be represented is returned as [`u64::MAX`].
+pub(crate) fn content_length(headers: &Headers) -> Result<Option<u64>, StatusCode> {
+    let mut length = None;
+    for value in headers.get_all("content-length").flat_map(|v| v.split(',')) {
+        let value = value.trim_matches(|c| c == ' ' || c == '\t');
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let value = value.parse().unwrap_or(u64::MAX);
+        match length {
+            Some(length) if length != value => return Err(StatusCode::BAD_REQUEST),
+            _ => length = Some(value),
+        }
+    }
+    Ok(length)
+}
 
 /// Request Line as defined in [RFC7230 Section
 /// 3.1.1](https://datatracker.ietf.org/doc/html/rfc7230#section-3.1.1).
@@ -20,36 +411,101 @@ impl RequestLine {
     /// returned.
     pub const URI_MAX_LENGTH: usize = 8000;
 
+    pub(crate) fn new(method: Method, uri: Uri, version: Version) -> Self {
+        Self {
+            method,
+            uri,
+            version,
+        }
+    }
+
     /// Derive [`RequestLine`] from a slice of bytes.
     ///
     /// Returns a [`StatusCode::BAD_REQUEST`] if the slice of bytes does not match the ABNF syntax.
     /// Returns a [`StatusCode::URI_TOO_LONG`] when the `request-target` is greater than the
     /// [`RequestLine::URI_MAX_LENGTH`].
+    /// Returns a [`StatusCode::HTTP_VERSION_NOT_SUPPORTED`] when the major version is not
+    /// supported, see [`Version::is_supported`].
     pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        Self::from_bytes_with_limits(src, &Limits::default())
+    }
+
+    /// Derive [`RequestLine`] from a slice of bytes using the given [`Limits`].
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the slice of bytes does not match the ABNF syntax.
+    /// Returns a [`StatusCode::URI_TOO_LONG`] when the slice of bytes is longer than
+    /// [`Limits::request_line`] or the `request-target` is longer than [`Limits::uri`].
+    /// Returns a [`StatusCode::HTTP_VERSION_NOT_SUPPORTED`] when the major version is not
+    /// supported, see [`Version::is_supported`].
+    pub fn from_bytes_with_limits(src: &[u8], limits: &Limits) -> Result<Self, StatusCode> {
+        Self::parse(src, limits, false)
+    }
+
+    /// Derive [`RequestLine`] as [`RequestLine::from_bytes_with_limits`] does, but when
+    /// `http_0_9` is true a HTTP/0.9 `Simple-Request`, as defined in [RFC1945 Section
+    /// 4.1](https://datatracker.ietf.org/doc/html/rfc1945#section-4.1), is also accepted and
+    /// uses the [`Version::HTTP_0_9`]:
+    ///
+    /// ```text
+    /// Simple-Request = "GET" SP Request-URI CRLF
+    /// ```
+    pub(crate) fn parse(src: &[u8], limits: &Limits, http_0_9: bool) -> Result<Self, StatusCode> {
+        if src.len() > limits.request_line {
+            return Err(StatusCode::URI_TOO_LONG);
+        }
+
         let (method_bytes, rest) = split_at_next_space(src).ok_or(StatusCode::BAD_REQUEST)?;
         let method = Method::from_bytes(method_bytes)?;
 
-        let (uri_bytes, rest) = split_at_next_space(rest).ok_or(StatusCode::BAD_REQUEST)?;
-        if uri_bytes.len() > Self::URI_MAX_LENGTH {
+        let (uri_bytes, version_bytes) = match split_at_next_space(rest) {
+            Some((uri_bytes, version_bytes)) => (uri_bytes, Some(version_bytes)),
+            // RFC1945 Section 4.1: Simple-Request = "GET" SP Request-URI CRLF
+            None if http_0_9 && method == Method::Get => (rest, None),
+            None => return Err(StatusCode::BAD_REQUEST),
+        };
+        if uri_bytes.len() > limits.uri {
             return Err(StatusCode::URI_TOO_LONG);
         }
         let uri = Uri::from_bytes(uri_bytes)?;
 
         // pattern match to assert that version bytes is the end of the array
         // otherwise the request line is not valid
+        let version = match version_bytes {
+            Some(version_bytes) => Version::from_bytes(version_bytes)?,
+            None => Version::HTTP_0_9,
+        };
+        if !version.is_supported() && version_bytes.is_some() {
+            return Err(StatusCode::HTTP_VERSION_NOT_SUPPORTED);
+        }
+
         Ok(Self {
             method,
             uri,
-            version: Version::from_bytes(rest)?,
+            version,
         })
     }
+
+    /// Returns the [`Method`] of the request line.
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Returns the `request-target` of the request line.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Returns the HTTP [`Version`] of the request line.
+    pub fn version(&self) -> Version {
+        self.version
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::http::{method::Method, StatusCode, Uri, Version};
 
-    use super::RequestLine;
+    use super::{Limits, RequestLine};
 
     fn assert_is_bad_request(right: Result<RequestLine, StatusCode>) {
         assert_eq!(Err(StatusCode::BAD_REQUEST), right);
@@ -101,4 +557,362 @@ mod tests {
             RequestLine::from_bytes(b"GET / HTTP/1.1")
         );
     }
+
+    #[test]
+    fn uri_octets_above_limit_is_a_uri_too_long() {
+        let limits = Limits {
+            uri: 4,
+            ..Limits::default()
+        };
+        assert_eq!(
+            Err(StatusCode::URI_TOO_LONG),
+            RequestLine::from_bytes_with_limits(b"GET /hello HTTP/1.1", &limits)
+        );
+        assert!(RequestLine::from_bytes_with_limits(b"GET /hey HTTP/1.1", &limits).is_ok());
+    }
+
+    #[test]
+    fn unsupported_major_version_is_http_version_not_supported() {
+        assert_eq!(
+            Err(StatusCode::HTTP_VERSION_NOT_SUPPORTED),
+            RequestLine::from_bytes(b"GET / HTTP/9.9")
+        );
+        assert_eq!(
+            Err(StatusCode::HTTP_VERSION_NOT_SUPPORTED),
+            RequestLine::from_bytes(b"GET / HTTP/0.9")
+        );
+    }
+
+    #[test]
+    fn simple_request_is_only_valid_when_enabled() {
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            RequestLine::from_bytes(b"GET /")
+        );
+        assert_eq!(
+            Ok(RequestLine {
+                method: Method::Get,
+                uri: Uri::from_bytes(b"/").expect("failed to parse uri"),
+                version: Version::HTTP_0_9,
+            }),
+            RequestLine::parse(b"GET /", &Limits::default(), true)
+        );
+    }
+
+    #[test]
+    fn simple_request_must_use_get() {
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            RequestLine::parse(b"POST /", &Limits::default(), true)
+        );
+    }
+
+    #[test]
+    fn explicit_http_0_9_version_is_http_version_not_supported() {
+        assert_eq!(
+            Err(StatusCode::HTTP_VERSION_NOT_SUPPORTED),
+            RequestLine::parse(b"GET / HTTP/0.9", &Limits::default(), true)
+        );
+    }
+
+    #[test]
+    fn request_line_above_limit_is_a_uri_too_long() {
+        let limits = Limits {
+            request_line: 10,
+            ..Limits::default()
+        };
+        assert_eq!(
+            Err(StatusCode::URI_TOO_LONG),
+            RequestLine::from_bytes_with_limits(b"GET / HTTP/1.1", &limits)
+        );
+    }
+}
+
+#[cfg(test)]
+mod request_tests {
+    use crate::http::{Authority, Limits, Method, StatusCode, Uri, Version};
+
+    use super::Request;
+
+    fn parse(bytes: &[u8], limits: &Limits) -> Result<Option<Request>, StatusCode> {
+        Request::from_reader(&mut &bytes[..], limits)
+    }
+
+    #[test]
+    fn empty_reader_is_no_request() {
+        assert_eq!(Ok(None), parse(&[], &Limits::default()));
+    }
+
+    #[test]
+    fn missing_end_of_header_section_is_a_bad_request() {
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            parse(
+                b"GET / HTTP/1.1\r\nHost: example.com\r\n",
+                &Limits::default()
+            )
+        );
+    }
+
+    #[test]
+    fn obs_fold_is_a_bad_request() {
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            parse(
+                b"GET / HTTP/1.1\r\nHost: a\r\nX-Long: a\r\n b\r\n\r\n",
+                &Limits::default()
+            )
+        );
+    }
+
+    #[test]
+    fn simple_request_without_body() {
+        let request = parse(
+            b"\r\nGET / HTTP/1.1\r\nHost: example.com\r\n\r\n",
+            &Limits::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(Method::Get, request.method());
+        assert_eq!(Some("example.com"), request.headers().get("host"));
+        assert!(request.body().is_empty());
+    }
+
+    #[test]
+    fn body_is_read_using_content_length() {
+        let request = parse(
+            b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nhello world",
+            &Limits::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(b"hello", request.body());
+    }
+
+    #[test]
+    fn conflicting_content_lengths_is_a_bad_request() {
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            parse(
+                b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nhello",
+                &Limits::default()
+            )
+        );
+    }
+
+    #[test]
+    fn too_many_header_fields_is_request_header_fields_too_large() {
+        let limits = Limits {
+            header_count: 1,
+            ..Limits::default()
+        };
+        assert_eq!(
+            Err(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE),
+            parse(b"GET / HTTP/1.0\r\nA: 1\r\nB: 2\r\n\r\n", &limits)
+        );
+        assert!(parse(b"GET / HTTP/1.0\r\nA: 1\r\n\r\n", &limits).is_ok());
+    }
+
+    #[test]
+    fn header_field_above_size_is_request_header_fields_too_large() {
+        let limits = Limits {
+            header_size: 8,
+            ..Limits::default()
+        };
+        assert_eq!(
+            Err(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE),
+            parse(b"GET / HTTP/1.1\r\nLonger: field\r\n\r\n", &limits)
+        );
+    }
+
+    #[test]
+    fn header_section_above_size_is_request_header_fields_too_large() {
+        let limits = Limits {
+            header_block: 16,
+            ..Limits::default()
+        };
+        assert_eq!(
+            Err(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE),
+            parse(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n", &limits)
+        );
+    }
+
+    #[test]
+    fn body_above_limit_is_payload_too_large() {
+        let limits = Limits {
+            body: 4,
+            ..Limits::default()
+        };
+        assert_eq!(
+            Err(StatusCode::PAYLOAD_TOO_LARGE),
+            parse(
+                b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nhello",
+                &limits
+            )
+        );
+    }
+
+    #[test]
+    fn http_1_1_request_without_host_is_a_bad_request() {
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            parse(b"GET / HTTP/1.1\r\n\r\n", &Limits::default())
+        );
+        assert!(parse(b"GET / HTTP/1.0\r\n\r\n", &Limits::default()).is_ok());
+    }
+
+    #[test]
+    fn multiple_or_invalid_hosts_is_a_bad_request() {
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            parse(
+                b"GET / HTTP/1.1\r\nHost: a\r\nHost: b\r\n\r\n",
+                &Limits::default()
+            )
+        );
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            parse(
+                b"GET / HTTP/1.1\r\nHost: user@example.com\r\n\r\n",
+                &Limits::default()
+            )
+        );
+        assert!(parse(b"GET / HTTP/1.1\r\nHost:\r\n\r\n", &Limits::default()).is_ok());
+    }
+
+    #[test]
+    fn chunked_body_is_decoded() {
+        let request = parse(
+            b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nping\r\n0\r\n\r\n",
+            &Limits::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(b"ping", request.body());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_content_encoding_is_decoded_within_the_body_limit() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&[b'a'; 64]).unwrap();
+        let body = encoder.finish().unwrap();
+        let mut bytes = format!(
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        bytes.extend_from_slice(&body);
+
+        let request = parse(&bytes, &Limits::default()).unwrap().unwrap();
+        assert_eq!(&[b'a'; 64][..], request.body());
+        assert_eq!(None, request.headers().get("content-encoding"));
+        assert_eq!(Some("64"), request.headers().get("content-length"));
+
+        let limits = Limits {
+            body: 63,
+            ..Limits::default()
+        };
+        assert_eq!(Err(StatusCode::PAYLOAD_TOO_LARGE), parse(&bytes, &limits));
+    }
+
+    #[test]
+    fn unknown_content_encoding_is_unsupported_media_type() {
+        assert_eq!(
+            Err(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            parse(
+                b"POST / HTTP/1.1\r\nHost: a\r\nContent-Encoding: compress\r\nContent-Length: 1\r\n\r\na",
+                &Limits::default()
+            )
+        );
+    }
+
+    #[test]
+    fn transfer_encoding_in_http_1_0_is_a_bad_request() {
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            parse(
+                b"POST / HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+                &Limits::default()
+            )
+        );
+    }
+
+    #[test]
+    fn transfer_encoding_with_content_length_is_a_bad_request() {
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            parse(
+                b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+                &Limits::default()
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_transfer_encoding_is_not_implemented() {
+        assert_eq!(
+            Err(StatusCode::NOT_IMPLEMENTED),
+            parse(
+                b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: gzip, chunked\r\n\r\n",
+                &Limits::default()
+            )
+        );
+    }
+
+    #[test]
+    fn keep_alive_defaults_depend_on_version() {
+        let keep_alive = |bytes: &[u8]| {
+            parse(bytes, &Limits::default())
+                .unwrap()
+                .unwrap()
+                .keep_alive()
+        };
+        assert!(keep_alive(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n"));
+        assert!(!keep_alive(
+            b"GET / HTTP/1.1\r\nHost: a\r\nConnection: Close\r\n\r\n"
+        ));
+        assert!(!keep_alive(b"GET / HTTP/1.0\r\n\r\n"));
+        assert!(keep_alive(
+            b"GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n"
+        ));
+    }
+
+    #[test]
+    fn simple_request_has_no_header_section() {
+        let mut bytes = &b"GET /status\r\nHost: ignored\r\n"[..];
+        let request = Request::read_head(&mut bytes, &Limits::default(), true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(Version::HTTP_0_9, request.version());
+        assert!(request.headers().is_empty());
+        assert!(!request.keep_alive());
+    }
+
+    #[test]
+    fn effective_uri_is_reconstructed_from_host() {
+        let effective_uri = |bytes: &[u8]| {
+            parse(bytes, &Limits::default())
+                .unwrap()
+                .unwrap()
+                .effective_uri()
+        };
+        assert_eq!(
+            Uri::from_bytes(b"http://example.com:8080/a?b").unwrap(),
+            effective_uri(b"GET /a?b HTTP/1.1\r\nHost: example.com:8080\r\n\r\n")
+        );
+        // the asterisk-form has an empty path, which cannot be parsed after an authority
+        let uri = effective_uri(b"OPTIONS * HTTP/1.1\r\nHost: example.com\r\n\r\n");
+        assert_eq!("http", uri.scheme().as_str());
+        assert_eq!(
+            Authority::from_bytes(b"example.com").ok().as_ref(),
+            uri.authority()
+        );
+        assert_eq!(
+            Uri::from_bytes(b"https://other.com/a").unwrap(),
+            effective_uri(b"GET https://other.com/a HTTP/1.1\r\nHost: example.com\r\n\r\n")
+        );
+    }
 }
@@ -0,0 +1,1011 @@
+use std::{
+    fmt,
+    io::{self, BufRead, Read, Seek, Write},
+};
+
+use super::{
+    chunked::ChunkedWriter,
+    content_coding::{self, ContentCoding},
+    date,
+    range::{self, Parts, Selected},
+    utils::{self, split_at_next_space},
+    ContentRange, EntityTag, HeaderField, Headers, HttpDate, MediaType, Method, Negotiator,
+    Request, SetCookie, StatusCode, Validators, Version,
+};
+
+/// A stream that can be read from any position, such as a [`File`](std::fs::File) or a
+/// [`Cursor`](std::io::Cursor), which is implemented for any such type.
+pub trait Seekable: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> Seekable for T {}
+
+/// The message body of a [`Response`].
+pub enum Body {
+    /// No message body.
+    Empty,
+    /// A message body that is already in memory and so has a known length.
+    Bytes(Vec<u8>),
+    /// A message body of an unknown length that is read as it is sent.
+    ///
+    /// This is sent using the chunked transfer coding when the client supports it, otherwise
+    /// the end of the body is signalled by closing the connection.
+    Stream(Box<dyn Read + Send>),
+    /// A message body that is read as it is sent and has a known length, such as a file.
+    ///
+    /// This is sent with a `Content-Length` and only that many octets are read from the stream,
+    /// which must not end before the length.
+    Sized(Box<dyn Read + Send>, u64),
+    /// A message body that is read as it is sent, has a known length and can be read from any
+    /// position, such as a file.
+    ///
+    /// This is sent like a [`Body::Sized`], except that a `GET` request for a range of the body
+    /// is answered with only that range, as defined in
+    /// [RFC7233](https://datatracker.ietf.org/doc/html/rfc7233).
+    Seekable(Box<dyn Seekable>, u64),
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("Empty"),
+            Self::Bytes(bytes) => f.debug_tuple("Bytes").field(bytes).finish(),
+            Self::Stream(_) => f.debug_tuple("Stream").finish_non_exhaustive(),
+            Self::Sized(_, length) => f.debug_tuple("Sized").field(length).finish_non_exhaustive(),
+            Self::Seekable(_, length) => f
+                .debug_tuple("Seekable")
+                .field(length)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// The function that takes over the connection once a `101 Switching Protocols` response has
+/// been sent, with the reader and writer of the connection.
+pub(crate) struct OnUpgrade(Box<UpgradeFn>);
+
+type UpgradeFn = dyn FnOnce(&mut dyn BufRead, &mut dyn Write) + Send;
+
+impl OnUpgrade {
+    pub(crate) fn call(self, reader: &mut dyn BufRead, writer: &mut dyn Write) {
+        (self.0)(reader, writer)
+    }
+}
+
+impl fmt::Debug for OnUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OnUpgrade").finish_non_exhaustive()
+    }
+}
+
+/// HTTP response message as defined in [RFC7230 Section
+/// 3](https://datatracker.ietf.org/doc/html/rfc7230#section-3).
+///
+/// ```text
+/// HTTP-message = start-line
+///                *( header-field CRLF )
+///                CRLF
+///                [ message-body ]
+///
+/// status-line = HTTP-version SP status-code SP reason-phrase CRLF
+/// ```
+#[derive(Debug)]
+pub struct Response {
+    status: StatusCode,
+    reason: Option<String>,
+    headers: Headers,
+    body: Body,
+    upgrade: Option<OnUpgrade>,
+}
+
+impl Response {
+    /// Create a [`Response`] with the given [`StatusCode`], no header fields and an empty body.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            reason: None,
+            headers: Headers::new(),
+            body: Body::Empty,
+            upgrade: None,
+        }
+    }
+
+    /// Set a header field on the response, replacing any existing fields with the same name.
+    ///
+    /// ```
+    /// use ramus::http::{Response, StatusCode};
+    ///
+    /// let response = Response::new(StatusCode::OK).with_header("Content-Type", "text/plain");
+    /// assert_eq!(Some("text/plain"), response.headers().get("content-type"));
+    /// ```
+    pub fn with_header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Add a header field name to `Vary`, as defined in [RFC7231 Section
+    /// 7.1.4](https://datatracker.ietf.org/doc/html/rfc7231#section-7.1.4), which lists the
+    /// header fields of the request that were used to select the representation.
+    ///
+    /// A name that is already listed is not added again, and "*" replaces all of the names as
+    /// it means that the representation depends on more than the request header fields.
+    ///
+    /// ```
+    /// use ramus::http::{Response, StatusCode};
+    ///
+    /// let response = Response::new(StatusCode::OK)
+    ///     .with_vary("Accept")
+    ///     .with_vary("accept")
+    ///     .with_vary("Accept-Language");
+    /// assert_eq!(Some("Accept, Accept-Language"), response.headers().get("vary"));
+    /// ```
+    pub fn with_vary(mut self, name: &str) -> Self {
+        let names: Vec<_> = self
+            .headers
+            .get_all("vary")
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.contains(&"*") || names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            return self;
+        }
+        let value = if name == "*" || names.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}, {name}", names.join(", "))
+        };
+        self.headers.insert("Vary", value);
+        self
+    }
+
+    /// Add a `Set-Cookie` header field for the cookie, as defined in [RFC6265 Section
+    /// 4.1](https://datatracker.ietf.org/doc/html/rfc6265#section-4.1).
+    ///
+    /// Unlike [`Response::with_header`] any existing `Set-Cookie` fields are kept, as each
+    /// cookie is sent in a field of its own.
+    pub fn with_cookie(mut self, cookie: &SetCookie) -> Self {
+        self.headers
+            .append(HeaderField::new("Set-Cookie", cookie.to_string()));
+        self
+    }
+
+    /// Set a custom `reason-phrase`, instead of the [`StatusCode::reason`], which is useful for
+    /// a status code that is not registered.
+    ///
+    /// Any characters that are not valid in a `reason-phrase` are removed. There is no
+    /// `reason-phrase` in HTTP/2 and so it is not sent on a HTTP/2 connection.
+    ///
+    /// ```text
+    /// reason-phrase = *( HTAB / SP / VCHAR / obs-text )
+    /// ```
+    ///
+    /// ```
+    /// use ramus::http::{Response, StatusCode};
+    ///
+    /// let status = StatusCode::from_u16(299).expect("valid status code");
+    /// let response = Response::new(status).with_reason("Mostly\r\nOK");
+    /// assert_eq!("MostlyOK", response.reason());
+    /// ```
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        let mut reason = reason.into();
+        reason.retain(|c| c == '\t' || !c.is_ascii_control());
+        self.reason = Some(reason);
+        self
+    }
+
+    /// Set the message body of the response.
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Body::Bytes(body.into());
+        self
+    }
+
+    /// Set the message body of the response to a stream of an unknown length.
+    pub fn with_stream(mut self, stream: impl Read + Send + 'static) -> Self {
+        self.body = Body::Stream(Box::new(stream));
+        self
+    }
+
+    /// Set the message body of the response to a stream of a known length, see
+    /// [`Body::Sized`].
+    pub fn with_sized_stream(mut self, stream: impl Read + Send + 'static, length: u64) -> Self {
+        self.body = Body::Sized(Box::new(stream), length);
+        self
+    }
+
+    /// Set the message body of the response to a seekable stream of a known length, see
+    /// [`Body::Seekable`].
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use ramus::http::{Body, Response, StatusCode};
+    ///
+    /// let response = Response::new(StatusCode::OK)
+    ///     .with_seekable_stream(Cursor::new(b"0123456789".to_vec()), 10);
+    /// assert!(matches!(response.body(), Body::Seekable(_, 10)));
+    /// ```
+    pub fn with_seekable_stream(mut self, stream: impl Seekable + 'static, length: u64) -> Self {
+        self.body = Body::Seekable(Box::new(stream), length);
+        self
+    }
+
+    /// Set the `ETag` and `Last-Modified` header fields from the [`Validators`] of the
+    /// representation in the response.
+    ///
+    /// ```
+    /// use ramus::http::{EntityTag, Response, StatusCode, Validators};
+    ///
+    /// let validators = Validators::new().with_etag(EntityTag::weak("v1"));
+    /// let response = Response::new(StatusCode::OK).with_validators(&validators);
+    /// assert_eq!(Some("W/\"v1\""), response.headers().get("etag"));
+    /// ```
+    pub fn with_validators(mut self, validators: &Validators) -> Self {
+        if let Some(etag) = validators.etag() {
+            self.headers.insert("ETag", etag.to_string());
+        }
+        if let Some(modified) = validators.last_modified() {
+            self.headers
+                .insert("Last-Modified", HttpDate::from(modified).to_string());
+        }
+        self
+    }
+
+    /// Set the `Date` header field to the current time, unless it has already been set, as
+    /// required of an origin server by [RFC7231 Section
+    /// 7.1.1.2](https://datatracker.ietf.org/doc/html/rfc7231#section-7.1.1.2).
+    pub(crate) fn with_date(mut self) -> Self {
+        if !self.headers.contains("date") {
+            self.headers.insert("Date", date::cached_now());
+        }
+        self
+    }
+
+    /// Compress the body with the best of the [`ContentCoding::enabled`] codings that is
+    /// acceptable to the client, using the `Accept-Encoding` of the request as defined in
+    /// [RFC7231 Section 5.3.4](https://datatracker.ietf.org/doc/html/rfc7231#section-5.3.4).
+    ///
+    /// The body is compressed while it is sent, so it becomes a [`Body::Stream`] with a
+    /// `Content-Encoding`, and the tag of any `ETag` has the coding appended as the compressed
+    /// body is a different representation. `Accept-Encoding` is added to the `Vary` of any
+    /// response that could have been compressed.
+    ///
+    /// A response is left as it is when it is not a 2xx response with a body, it already has a
+    /// `Content-Encoding` or `Content-Range`, it has `Cache-Control: no-transform`, the body
+    /// is shorter than [`MIN_COMPRESS_LENGTH`](content_coding::MIN_COMPRESS_LENGTH) or its
+    /// `Content-Type` is not worth compressing, such as an image that is already compressed. A
+    /// [`Body::Seekable`] is also left as it is, so that it keeps its `Accept-Ranges` and a
+    /// client can resume the download with a `Range`, see [`Response::with_range`].
+    pub(crate) fn with_compression(mut self, request: &Request) -> Self {
+        if ContentCoding::enabled().is_empty()
+            || !self.status.is_success()
+            || matches!(
+                self.status,
+                StatusCode::NO_CONTENT | StatusCode::PARTIAL_CONTENT
+            )
+            || self.headers.contains("content-encoding")
+            || self.headers.contains("content-range")
+            || self
+                .headers
+                .get_all("cache-control")
+                .flat_map(|value| value.split(','))
+                .any(|value| value.trim().eq_ignore_ascii_case("no-transform"))
+        {
+            return self;
+        }
+        let compressible = self
+            .headers
+            .get("content-type")
+            .and_then(|value| MediaType::from_bytes(value.as_bytes()).ok())
+            .is_some_and(|media_type| content_coding::is_compressible(&media_type));
+        let length = match &self.body {
+            Body::Empty | Body::Seekable(..) => return self,
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            Body::Stream(_) => None,
+            Body::Sized(_, length) => Some(*length),
+        };
+        if !compressible {
+            return self;
+        }
+        self = self.with_vary("Accept-Encoding");
+        if length.is_some_and(|length| length < content_coding::MIN_COMPRESS_LENGTH)
+            || !request.headers().contains("accept-encoding")
+        {
+            return self;
+        }
+
+        let offers: Vec<_> = ContentCoding::enabled()
+            .iter()
+            .map(|coding| coding.as_str())
+            .chain(["identity"])
+            .collect();
+        let coding = match Negotiator::new(request).encoding(&offers) {
+            Ok(offer) => match ContentCoding::from_bytes(offer.as_bytes()) {
+                Ok(coding) => coding,
+                Err(_) => return self,
+            },
+            Err(_) => return self,
+        };
+
+        let reader: Box<dyn Read + Send> = match std::mem::replace(&mut self.body, Body::Empty) {
+            Body::Empty | Body::Seekable(..) => {
+                unreachable!("an empty or seekable body is not compressed")
+            }
+            Body::Bytes(bytes) => Box::new(io::Cursor::new(bytes)),
+            Body::Stream(stream) => stream,
+            Body::Sized(stream, length) => Box::new(stream.take(length)),
+        };
+        self.body = Body::Stream(coding.encoder(reader));
+        self.headers.insert("Content-Encoding", coding.as_str());
+        self.headers.remove("content-length");
+        if let Some(etag) = self
+            .headers
+            .get("etag")
+            .and_then(|value| EntityTag::from_bytes(value.as_bytes()).ok())
+        {
+            let tag = format!("{}-{coding}", etag.tag());
+            let etag = if etag.is_weak() {
+                EntityTag::weak(tag)
+            } else {
+                EntityTag::strong(tag)
+            };
+            self.headers.insert("ETag", etag.to_string());
+        }
+        self
+    }
+
+    /// Evaluate the preconditions of a `GET` or `HEAD` request against the `ETag` and
+    /// `Last-Modified` of this response, when it is a 2xx response, see
+    /// [`Validators::evaluate`].
+    ///
+    /// A [`StatusCode::NOT_MODIFIED`] response only keeps the header fields that a cache uses to
+    /// update its stored response, as defined in [RFC7232 Section
+    /// 4.1](https://datatracker.ietf.org/doc/html/rfc7232#section-4.1).
+    pub(crate) fn with_conditions(self, request: &Request) -> Self {
+        if !matches!(request.method(), Method::Get | Method::Head) || !self.status.is_success() {
+            return self;
+        }
+        match Validators::from_headers(&self.headers).evaluate(request) {
+            Ok(()) => self,
+            Err(StatusCode::NOT_MODIFIED) => {
+                let mut response = Response::new(StatusCode::NOT_MODIFIED);
+                for name in [
+                    "cache-control",
+                    "content-location",
+                    "date",
+                    "etag",
+                    "expires",
+                    "last-modified",
+                    "vary",
+                ] {
+                    for value in self.headers.get_all(name) {
+                        response.headers.append(HeaderField::new(name, value));
+                    }
+                }
+                response
+            }
+            Err(status) => Response::new(status),
+        }
+    }
+
+    /// Answer the `Range` of the request, as defined in [RFC7233 Section
+    /// 3](https://datatracker.ietf.org/doc/html/rfc7233#section-3), when this is a
+    /// [`StatusCode::OK`] response with a [`Body::Seekable`].
+    ///
+    /// `Accept-Ranges: bytes` is set on such a response. For a `GET` request with a `Range`,
+    /// and an `If-Range` that matches the `ETag` or `Last-Modified` when there is one, this
+    /// becomes either:
+    /// - A [`StatusCode::PARTIAL_CONTENT`] with the `Content-Range` of a single range, or a
+    ///   `multipart/byteranges` body with a part for each range.
+    /// - A [`StatusCode::RANGE_NOT_SATISFIABLE`] when none of the ranges are within the body.
+    pub(crate) fn with_range(mut self, request: &Request) -> Self {
+        let Body::Seekable(_, length) = self.body else {
+            return self;
+        };
+        if self.status != StatusCode::OK {
+            return self;
+        }
+        self.headers.insert("Accept-Ranges", "bytes");
+        if request.method() != Method::Get {
+            return self;
+        }
+
+        match range::select(request, &self.headers, length) {
+            Selected::Full => self,
+            Selected::NotSatisfiable => {
+                let content_range = ContentRange::Unsatisfied(length);
+                Response::new(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .with_header("Accept-Ranges", "bytes")
+                    .with_header("Content-Range", content_range.to_string())
+            }
+            Selected::Partial(ranges) => {
+                let Body::Seekable(stream, _) = std::mem::replace(&mut self.body, Body::Empty)
+                else {
+                    unreachable!("the body is seekable");
+                };
+                self.status = StatusCode::PARTIAL_CONTENT;
+                self.reason = None;
+                if let [(first, last)] = ranges[..] {
+                    let content_range = ContentRange::Bytes {
+                        first,
+                        last,
+                        complete_length: Some(length),
+                    };
+                    self.headers
+                        .insert("Content-Range", content_range.to_string());
+                    self.body = Body::Sized(
+                        Box::new(Parts::single(stream, (first, last))),
+                        last - first + 1,
+                    );
+                } else {
+                    let content_type = self.headers.get("content-type");
+                    let (parts, parts_length, boundary) =
+                        Parts::multipart(stream, &ranges, content_type, length);
+                    self.headers.insert(
+                        "Content-Type",
+                        format!("multipart/byteranges; boundary={boundary}"),
+                    );
+                    self.body = Body::Sized(Box::new(parts), parts_length);
+                }
+                self
+            }
+        }
+    }
+
+    /// Set the function that takes over the connection after this response, which is only
+    /// called by the [`Server`](crate::server::Server) for a
+    /// [`StatusCode::SWITCHING_PROTOCOLS`] response to a HTTP/1.1 request.
+    pub(crate) fn with_upgrade<F>(mut self, on_upgrade: F) -> Self
+    where
+        F: FnOnce(&mut dyn BufRead, &mut dyn Write) + Send + 'static,
+    {
+        self.upgrade = Some(OnUpgrade(Box::new(on_upgrade)));
+        self
+    }
+
+    pub(crate) fn take_upgrade(&mut self) -> Option<OnUpgrade> {
+        self.upgrade.take()
+    }
+
+    /// Returns the [`StatusCode`] of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the `reason-phrase` of the response, which is the [`StatusCode::reason`] unless a
+    /// custom one has been set with [`Response::with_reason`].
+    pub fn reason(&self) -> &str {
+        self.reason.as_deref().unwrap_or(self.status.reason())
+    }
+
+    /// Returns the header section of the response.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns a mutable reference to the header section of the response.
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+
+    /// Returns the message body of the response.
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    /// Consumes the response and returns the message body.
+    pub fn into_body(self) -> Body {
+        self.body
+    }
+
+    /// Returns true if the end of the body can only be signalled by closing the connection when
+    /// sent to a client using the given [`Version`].
+    ///
+    /// This is the case for a [`Body::Stream`] when the client does not support the chunked
+    /// transfer coding, and for any HTTP/0.9 `Simple-Response`.
+    pub fn is_close_delimited(&self, version: Version) -> bool {
+        !version.has_header_section()
+            || (matches!(self.body, Body::Stream(_)) && !version.supports_chunked())
+    }
+
+    /// Returns true if the "close" connection option has been set on the response.
+    pub fn has_close_option(&self) -> bool {
+        self.headers
+            .get_all("connection")
+            .flat_map(|value| value.split(','))
+            .any(|value| value.trim().eq_ignore_ascii_case("close"))
+    }
+
+    /// Write the response as a HTTP/1.1 message for a request with the given [`Method`] and
+    /// [`Version`].
+    ///
+    /// The framing header fields are always set by this function as defined in [RFC7230 Section
+    /// 3.3](https://datatracker.ietf.org/doc/html/rfc7230#section-3.3), replacing any set on
+    /// the response:
+    /// - A [`Body::Bytes`] is sent with a `Content-Length`.
+    /// - A [`Body::Stream`] is sent using the chunked `Transfer-Encoding` when the [`Version`]
+    ///   supports it, otherwise the body is close-delimited and the caller must close the
+    ///   connection after this returns, see [`Response::is_close_delimited`].
+    /// - A response to a [`Method::Head`] request, or with a 1xx, 204 or 304 [`StatusCode`], is
+    ///   sent without a body.
+    ///
+    /// A response to a HTTP/0.9 `Simple-Request` is downgraded to a `Simple-Response` as defined
+    /// in [RFC1945 Section 6](https://datatracker.ietf.org/doc/html/rfc1945#section-6), which is
+    /// only the body and so the caller must close the connection after this returns.
+    ///
+    /// ```text
+    /// Simple-Response = [ Entity-Body ]
+    /// ```
+    pub fn write_to<W: Write>(
+        mut self,
+        writer: &mut W,
+        method: Method,
+        version: Version,
+    ) -> io::Result<()> {
+        if !version.has_header_section() {
+            match self.body {
+                Body::Bytes(bytes) => writer.write_all(&bytes)?,
+                Body::Stream(mut stream) => copy_flushed(&mut stream, writer)?,
+                Body::Sized(stream, length) => copy_sized(stream, length, writer)?,
+                Body::Seekable(stream, length) => copy_sized(stream, length, writer)?,
+                Body::Empty => {}
+            }
+            return writer.flush();
+        }
+
+        self.headers.remove("content-length");
+        self.headers.remove("transfer-encoding");
+
+        let code = self.status.as_u16();
+        let has_body = !matches!(code, 100..=199 | 204 | 304);
+        match &self.body {
+            Body::Bytes(bytes) if has_body => {
+                self.headers
+                    .insert("Content-Length", bytes.len().to_string());
+            }
+            Body::Sized(_, length) | Body::Seekable(_, length) if has_body => {
+                self.headers.insert("Content-Length", length.to_string());
+            }
+            Body::Empty if has_body => self.headers.insert("Content-Length", "0"),
+            Body::Stream(_) if has_body && version.supports_chunked() => {
+                self.headers.insert("Transfer-Encoding", "chunked");
+            }
+            _ => {}
+        }
+
+        write!(writer, "HTTP/1.1 {} {}\r\n", code, self.reason())?;
+        for field in self.headers.iter() {
+            write!(writer, "{}: {}\r\n", field.name(), field.value())?;
+        }
+        writer.write_all(b"\r\n")?;
+
+        if has_body && method != Method::Head {
+            match self.body {
+                Body::Bytes(bytes) => writer.write_all(&bytes)?,
+                Body::Stream(mut stream) if version.supports_chunked() => {
+                    copy_flushed(&mut stream, &mut ChunkedWriter::new(&mut *writer))?;
+                    ChunkedWriter::new(&mut *writer).finish()?;
+                }
+                Body::Stream(mut stream) => copy_flushed(&mut stream, writer)?,
+                Body::Sized(stream, length) => copy_sized(stream, length, writer)?,
+                Body::Seekable(stream, length) => copy_sized(stream, length, writer)?,
+                Body::Empty => {}
+            }
+        }
+        writer.flush()
+    }
+}
+
+/// Copy the stream to the writer, flushing after each read so that the client receives each
+/// part of the body as soon as it has been read, such as the events of an [`EventStream`].
+///
+/// [`EventStream`]: super::EventStream
+fn copy_flushed<W: Write>(stream: &mut dyn Read, writer: &mut W) -> io::Result<()> {
+    let mut buf = [0; 8 * 1024];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(read) => {
+                writer.write_all(&buf[..read])?;
+                writer.flush()?;
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Copy exactly `length` octets of the stream to the writer.
+///
+/// Returns an [`io::ErrorKind::UnexpectedEof`] error if the stream ends before the length, as
+/// the client can no longer tell where the message ends and so the connection must be closed.
+fn copy_sized<R: Read, W: Write>(stream: R, length: u64, writer: &mut W) -> io::Result<()> {
+    if io::copy(&mut stream.take(length), writer)? != length {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "body ended before its length",
+        ));
+    }
+    Ok(())
+}
+
+impl From<StatusCode> for Response {
+    fn from(status: StatusCode) -> Self {
+        Self::new(status)
+    }
+}
+
+/// Either the response or the error response, so that a handler can use `?` with a
+/// [`StatusCode`] error.
+impl<T: Into<Response>, E: Into<Response>> From<Result<T, E>> for Response {
+    fn from(result: Result<T, E>) -> Self {
+        match result {
+            Ok(response) => response.into(),
+            Err(error) => error.into(),
+        }
+    }
+}
+
+/// Status Line as defined in [RFC7230 Section
+/// 3.1.2](https://datatracker.ietf.org/doc/html/rfc7230#section-3.1.2).
+///
+/// ```text
+/// status-line = HTTP-version SP status-code SP reason-phrase CRLF
+///
+/// status-code = 3DIGIT
+/// reason-phrase = *( HTAB / SP / VCHAR / obs-text )
+/// ```
+/// For `HTTP-version` see [`Version`], and for `status-code` see [`StatusCode`].
+///
+/// A `status-code` that is not registered is understood as the x00 [`StatusCode`] of its class,
+/// as required by [RFC7231 Section 6](https://datatracker.ietf.org/doc/html/rfc7231#section-6),
+/// and the code that was sent is kept in [`StatusLine::code`].
+///
+/// ```
+/// use ramus::http::{StatusCode, StatusLine, Version};
+///
+/// let line = StatusLine::from_bytes(b"HTTP/1.1 299 Mostly OK").expect("valid status line");
+/// assert_eq!(Version::HTTP_1_1, line.version());
+/// assert_eq!(299, line.code());
+/// assert_eq!(StatusCode::OK, line.status());
+/// assert_eq!("Mostly OK", line.reason());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatusLine {
+    version: Version,
+    code: u16,
+    status: StatusCode,
+    reason: String,
+}
+
+impl StatusLine {
+    /// Derive [`StatusLine`] from a slice of bytes, the slice should not include the trailing
+    /// CRLF.
+    ///
+    /// The SP after the `status-code` is optional as some servers do not send it when the
+    /// `reason-phrase` is empty.
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if the slice of bytes does not match the ABNF
+    /// syntax, or the `status-code` is not in one of the classes 1xx to 5xx.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, StatusCode> {
+        let (version, rest) = split_at_next_space(src).ok_or(StatusCode::BAD_REQUEST)?;
+        let version = Version::from_bytes(version)?;
+
+        let (code, reason) = split_at_next_space(rest).unwrap_or((rest, &[]));
+        let code = match code {
+            [a @ b'1'..=b'5', b @ b'0'..=b'9', c @ b'0'..=b'9'] => {
+                (a - b'0') as u16 * 100 + (b - b'0') as u16 * 10 + (c - b'0') as u16
+            }
+            _ => return Err(StatusCode::BAD_REQUEST),
+        };
+        // RFC7231 Section 6: an unrecognized status code is equivalent to the x00 status code
+        // of its class, which are all registered
+        let status = StatusCode::registered(code)
+            .or(StatusCode::registered(code / 100 * 100))
+            .ok_or(StatusCode::BAD_REQUEST)?;
+
+        if !reason
+            .iter()
+            .all(|b| utils::abnf::is_field_vchar(*b) || utils::abnf::is_ows(*b))
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        Ok(Self {
+            version,
+            code,
+            status,
+            reason: String::from_utf8_lossy(reason).into_owned(),
+        })
+    }
+
+    /// Returns the HTTP [`Version`] of the status line.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the `status-code` that was sent, which may not be registered.
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// Returns the [`StatusCode`] of the status line, which is the x00 code of the class when
+    /// the [`StatusLine::code`] is not registered.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the `reason-phrase` of the status line, which may differ from
+    /// [`StatusCode::reason`] or be empty.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::http::{test_request, EntityTag, Method, Validators, Version};
+
+    use super::{Response, StatusCode, StatusLine};
+
+    fn write(response: Response, method: Method, version: Version) -> String {
+        let mut bytes = Vec::new();
+        response.write_to(&mut bytes, method, version).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn response_is_written_with_status_line_headers_and_body() {
+        let response = Response::new(StatusCode::NOT_FOUND)
+            .with_header("Content-Type", "text/plain")
+            .with_body("missing");
+        assert_eq!(
+            "HTTP/1.1 404 Not Found\r\ncontent-type: text/plain\r\ncontent-length: 7\r\n\r\nmissing",
+            write(response, Method::Get, Version::HTTP_1_1)
+        );
+    }
+
+    #[test]
+    fn stream_is_chunked_for_http_1_1() {
+        let response = Response::new(StatusCode::OK).with_stream(&b"Hello"[..]);
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n5\r\nHello\r\n0\r\n\r\n",
+            write(response, Method::Get, Version::HTTP_1_1)
+        );
+    }
+
+    #[test]
+    fn stream_is_never_chunked_for_http_1_0() {
+        let response = Response::new(StatusCode::OK).with_stream(&b"Hello"[..]);
+        assert!(response.is_close_delimited(Version::HTTP_1_0));
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\n\r\nHello",
+            write(response, Method::Get, Version::HTTP_1_0)
+        );
+    }
+
+    #[test]
+    fn head_response_has_content_length_without_body() {
+        let response = Response::new(StatusCode::OK).with_body("Hello");
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\n",
+            write(response, Method::Head, Version::HTTP_1_1)
+        );
+    }
+
+    #[test]
+    fn no_content_response_has_no_body_or_framing() {
+        let response = Response::new(StatusCode::NO_CONTENT).with_body("ignored");
+        assert_eq!(
+            "HTTP/1.1 204 No Content\r\n\r\n",
+            write(response, Method::Get, Version::HTTP_1_1)
+        );
+    }
+
+    #[test]
+    fn simple_response_is_only_the_body() {
+        let response = Response::new(StatusCode::NOT_FOUND)
+            .with_header("Content-Type", "text/plain")
+            .with_body("missing");
+        assert!(response.is_close_delimited(Version::HTTP_0_9));
+        assert_eq!("missing", write(response, Method::Get, Version::HTTP_0_9));
+    }
+
+    #[test]
+    fn bytes_are_not_close_delimited_for_http_1_0() {
+        let response = Response::new(StatusCode::OK).with_body("Hello");
+        assert!(!response.is_close_delimited(Version::HTTP_1_0));
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nHello",
+            write(response, Method::Get, Version::HTTP_1_0)
+        );
+    }
+
+    #[test]
+    fn sized_stream_is_sent_with_content_length() {
+        let response = Response::new(StatusCode::OK).with_sized_stream(&b"Hello, World"[..], 5);
+        assert!(!response.is_close_delimited(Version::HTTP_1_0));
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nHello",
+            write(response, Method::Get, Version::HTTP_1_1)
+        );
+
+        let response = Response::new(StatusCode::OK).with_sized_stream(&b"Hi"[..], 5);
+        let mut bytes = Vec::new();
+        assert!(response
+            .write_to(&mut bytes, Method::Get, Version::HTTP_1_1)
+            .is_err());
+    }
+
+    fn ranged(method: Method, fields: &[(&str, &str)]) -> String {
+        let request = test_request(method, "/", fields, "");
+        let response = Response::new(StatusCode::OK)
+            .with_header("Content-Type", "text/plain")
+            .with_seekable_stream(Cursor::new(b"0123456789".to_vec()), 10)
+            .with_range(&request);
+        write(response, request.method(), Version::HTTP_1_1)
+    }
+
+    #[test]
+    fn range_of_seekable_stream_is_partial_content() {
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\naccept-ranges: bytes\r\n\
+             content-length: 10\r\n\r\n0123456789",
+            ranged(Method::Get, &[])
+        );
+        assert_eq!(
+            "HTTP/1.1 206 Partial Content\r\ncontent-type: text/plain\r\naccept-ranges: bytes\r\n\
+             content-range: bytes 7-9/10\r\ncontent-length: 3\r\n\r\n789",
+            ranged(Method::Get, &[("Range", "bytes=-3")])
+        );
+        assert_eq!(
+            "HTTP/1.1 416 Range Not Satisfiable\r\naccept-ranges: bytes\r\n\
+             content-range: bytes */10\r\ncontent-length: 0\r\n\r\n",
+            ranged(Method::Get, &[("Range", "bytes=10-")])
+        );
+        // a Range is only defined for GET
+        assert!(ranged(Method::Head, &[("Range", "bytes=0-0")]).starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn multiple_ranges_are_sent_as_multipart_byteranges() {
+        let response = ranged(Method::Get, &[("Range", "bytes=0-1,5-6")]);
+        let boundary = response
+            .split("boundary=")
+            .nth(1)
+            .and_then(|rest| rest.split("\r\n").next())
+            .unwrap();
+        assert!(response.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+        assert!(response.ends_with(&format!(
+            "\r\n\r\n--{boundary}\r\nContent-Type: text/plain\r\n\
+             Content-Range: bytes 0-1/10\r\n\r\n01\r\n--{boundary}\r\n\
+             Content-Type: text/plain\r\nContent-Range: bytes 5-6/10\r\n\r\n56\r\n\
+             --{boundary}--\r\n"
+        )));
+    }
+
+    #[test]
+    fn not_modified_keeps_cache_header_fields() {
+        let request = test_request(Method::Get, "/", &[("If-None-Match", "\"v1\"")], "");
+        let response = Response::new(StatusCode::OK)
+            .with_header("Content-Type", "text/plain")
+            .with_header("Cache-Control", "max-age=60")
+            .with_validators(&Validators::new().with_etag(EntityTag::strong("v1")))
+            .with_body("hello")
+            .with_conditions(&request);
+        assert_eq!(
+            "HTTP/1.1 304 Not Modified\r\ncache-control: max-age=60\r\netag: \"v1\"\r\n\r\n",
+            write(response, Method::Get, Version::HTTP_1_1)
+        );
+
+        let response = Response::new(StatusCode::NOT_FOUND)
+            .with_validators(&Validators::new().with_etag(EntityTag::strong("v1")))
+            .with_conditions(&request);
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn body_is_compressed_for_accepted_encoding() {
+        use std::io::Read;
+
+        use crate::http::{Body, ContentCoding};
+
+        let fields = [("Accept-Encoding", "deflate;q=0.5, gzip, br;q=0")];
+        let request = test_request(Method::Get, "/", &fields, "");
+        let body = "compress me ".repeat(256);
+        let response = Response::new(StatusCode::OK)
+            .with_header("Content-Type", "text/plain")
+            .with_validators(&Validators::new().with_etag(EntityTag::strong("v1")))
+            .with_body(body.clone())
+            .with_compression(&request);
+        assert_eq!(Some("gzip"), response.headers().get("content-encoding"));
+        assert_eq!(Some("Accept-Encoding"), response.headers().get("vary"));
+        assert_eq!(Some("\"v1-gzip\""), response.headers().get("etag"));
+
+        let Body::Stream(mut stream) = response.into_body() else {
+            panic!("compressed body is a stream");
+        };
+        let mut compressed = Vec::new();
+        stream.read_to_end(&mut compressed).unwrap();
+        assert_eq!(
+            Ok(body.into_bytes()),
+            ContentCoding::Gzip.decode(&compressed, usize::MAX - 1)
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn small_or_compressed_bodies_are_not_compressed() {
+        let request = test_request(Method::Get, "/", &[("Accept-Encoding", "gzip")], "");
+        let response = Response::new(StatusCode::OK)
+            .with_header("Content-Type", "text/plain")
+            .with_body("small")
+            .with_compression(&request);
+        assert_eq!(None, response.headers().get("content-encoding"));
+        assert_eq!(Some("Accept-Encoding"), response.headers().get("vary"));
+
+        let response = Response::new(StatusCode::OK)
+            .with_header("Content-Type", "image/png")
+            .with_body(vec![0; 4096])
+            .with_compression(&request);
+        assert_eq!(None, response.headers().get("content-encoding"));
+        assert_eq!(None, response.headers().get("vary"));
+    }
+
+    #[test]
+    fn custom_reason_is_written_in_status_line() {
+        let status = StatusCode::from_u16(599).unwrap();
+        let response = Response::new(status);
+        assert_eq!(
+            "HTTP/1.1 599 \r\ncontent-length: 0\r\n\r\n",
+            write(response, Method::Get, Version::HTTP_1_1)
+        );
+        let response = Response::new(status).with_reason("Network Timeout");
+        assert_eq!(
+            "HTTP/1.1 599 Network Timeout\r\ncontent-length: 0\r\n\r\n",
+            write(response, Method::Get, Version::HTTP_1_1)
+        );
+    }
+
+    #[test]
+    fn status_line_is_parsed() {
+        let line = StatusLine::from_bytes(b"HTTP/1.1 404 Not Found").unwrap();
+        assert_eq!(Version::HTTP_1_1, line.version());
+        assert_eq!(404, line.code());
+        assert_eq!(StatusCode::NOT_FOUND, line.status());
+        assert_eq!("Not Found", line.reason());
+
+        let line = StatusLine::from_bytes(b"HTTP/1.0 200").unwrap();
+        assert_eq!(StatusCode::OK, line.status());
+        assert_eq!("", line.reason());
+    }
+
+    #[test]
+    fn unregistered_code_is_understood_by_class() {
+        let line = StatusLine::from_bytes(b"HTTP/1.1 599 Network Timeout").unwrap();
+        assert_eq!(599, line.code());
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, line.status());
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            StatusLine::from_bytes(b"HTTP/1.1 418 ").unwrap().status()
+        );
+    }
+
+    #[test]
+    fn invalid_status_lines_are_a_bad_request() {
+        for line in [
+            &b""[..],
+            b"HTTP/1.1",
+            b"HTTP/1.1 20 OK",
+            b"HTTP/1.1 2000 OK",
+            b"HTTP/1.1 600 Unknown",
+            b"HTTP/1.1 099 Unknown",
+            b"HTTX/1.1 200 OK",
+            b"HTTP/1.1 200 O\0K",
+        ] {
+            assert_eq!(Err(StatusCode::BAD_REQUEST), StatusLine::from_bytes(line));
+        }
+    }
+}
@@ -9,6 +9,79 @@ use std::num::NonZeroU16;
 #[derive(Copy, Clone, Debug, Hash, PartialEq)]
 pub struct StatusCode(NonZeroU16);
 
+impl StatusCode {
+    /// Returns the status code as a number.
+    ///
+    /// ```
+    /// use ramus::http::StatusCode;
+    ///
+    /// assert_eq!(404, StatusCode::NOT_FOUND.as_u16());
+    /// ```
+    pub const fn as_u16(&self) -> u16 {
+        self.0.get()
+    }
+
+    /// Create a [`StatusCode`] from any code from 100 to 999, which includes the codes that are
+    /// not registered, such as an extension code used by an application.
+    ///
+    /// Returns an [`InvalidStatusCode`] if the code is not in the range.
+    ///
+    /// ```
+    /// use ramus::http::StatusCode;
+    ///
+    /// let status = StatusCode::from_u16(299).expect("valid status code");
+    /// assert!(status.is_success());
+    /// assert!(!status.is_registered());
+    /// assert_eq!(StatusCode::OK, StatusCode::from_u16(200).unwrap());
+    /// assert!(StatusCode::from_u16(1000).is_err());
+    /// ```
+    pub const fn from_u16(code: u16) -> Result<Self, InvalidStatusCode> {
+        match NonZeroU16::new(code) {
+            Some(code) if code.get() >= 100 && code.get() <= 999 => Ok(Self(code)),
+            _ => Err(InvalidStatusCode),
+        }
+    }
+
+    /// Returns true for a 1xx status code, which is an interim response as defined in [RFC 7231
+    /// Section 6.2](https://datatracker.ietf.org/doc/html/rfc7231#section-6.2).
+    pub const fn is_informational(&self) -> bool {
+        self.as_u16() / 100 == 1
+    }
+
+    /// Returns true for a 2xx status code, which means the request was successfully received,
+    /// understood, and accepted, as defined in [RFC 7231 Section
+    /// 6.3](https://datatracker.ietf.org/doc/html/rfc7231#section-6.3).
+    pub const fn is_success(&self) -> bool {
+        self.as_u16() / 100 == 2
+    }
+
+    /// Returns true for a 3xx status code, which means further action needs to be taken by the
+    /// user agent, as defined in [RFC 7231 Section
+    /// 6.4](https://datatracker.ietf.org/doc/html/rfc7231#section-6.4).
+    pub const fn is_redirection(&self) -> bool {
+        self.as_u16() / 100 == 3
+    }
+
+    /// Returns true for a 4xx status code, which means the client seems to have erred, as
+    /// defined in [RFC 7231 Section 6.5](https://datatracker.ietf.org/doc/html/rfc7231#section-6.5).
+    pub const fn is_client_error(&self) -> bool {
+        self.as_u16() / 100 == 4
+    }
+
+    /// Returns true for a 5xx status code, which means the server is aware that it has erred or
+    /// is incapable of performing the request, as defined in [RFC 7231 Section
+    /// 6.6](https://datatracker.ietf.org/doc/html/rfc7231#section-6.6).
+    pub const fn is_server_error(&self) -> bool {
+        self.as_u16() / 100 == 5
+    }
+
+    /// Returns true if the status code is one of the registered constants, such as
+    /// [`StatusCode::OK`].
+    pub const fn is_registered(&self) -> bool {
+        Self::registered(self.as_u16()).is_some()
+    }
+}
+
 /// An Error type to signal that a conversion failed
 #[derive(Debug)]
 pub struct InvalidStatusCode;
@@ -26,23 +99,23 @@ macro_rules! const_status_codes {
                 pub const $name: StatusCode = StatusCode(unsafe { NonZeroU16::new_unchecked($code) });
             )*
 
-            /// Returns the human readable reason phrase.
+            /// Returns the human readable reason phrase, which is empty for a status code that is
+            /// not registered.
             pub const fn reason(&self) -> &'static str {
                 match self.0.get() {
                     $(
                         $code => $reason,
                     )*
-                    // StatusCode valid instances are defined at compile time and so
-                    // the u16 must match on one of the codes used to define a valid instance
-                    // Note: unreachable & panic are not stable
-                    _ => "Unreachable"
+                    // RFC7230 Section 3.1.2: the reason-phrase can be empty
+                    _ => ""
                 }
             }
 
-            /// Derives a [`StatusCode`] from a slice of bytes.
+            /// Derives a registered [`StatusCode`] from a slice of bytes.
             ///
             /// Returns a `InvalidStatusCode` if the slice of bytes does not match
-            /// the ABNF syntax of [`StatusCode`].
+            /// the ABNF syntax of [`StatusCode`] or is not a registered code, see
+            /// [`StatusCode::from_u16`] for any code.
             pub fn from_bytes(src: &[u8]) -> Result<Self, InvalidStatusCode> {
                 if let [a @ b'1'..=b'9', b @ b'0'..=b'9', c @ b'0'..=b'9'] = src {
                     let a = a.wrapping_sub(b'0') as u16;
@@ -50,16 +123,23 @@ macro_rules! const_status_codes {
                     let c = c.wrapping_sub(b'0') as u16;
 
                     let code = (a * 100) + (b * 10) + c;
-                    match code {
-                        $(
-                            $code => return Ok(Self::$name),
-                        )*
-                        _ => {},
+                    if let Some(status) = Self::registered(code) {
+                        return Ok(status);
                     }
                 }
 
                 Err(InvalidStatusCode)
             }
+
+            /// Returns the [`StatusCode`] of a registered code.
+            pub(crate) const fn registered(code: u16) -> Option<Self> {
+                match code {
+                    $(
+                        $code => Some(Self::$name),
+                    )*
+                    _ => None,
+                }
+            }
         }
 
     }
@@ -130,6 +210,10 @@ const_status_codes! {
     /// Redirection code as defined in [RFC 7231 Section
     /// 6.4.7](https://datatracker.ietf.org/doc/html/rfc7231#section-6.4.7)
     TEMPORARY_REDIRECT => 307, "Temporary Redirect",
+    /// 308 Permanent Redirect
+    /// Redirection code as defined in [RFC 7538 Section
+    /// 3](https://datatracker.ietf.org/doc/html/rfc7538#section-3)
+    PERMANENT_REDIRECT => 308, "Permanent Redirect",
     /// 400 Bad Request
     /// Client Error code as defined in [RFC 7231 Section
     /// 6.5.1](https://datatracker.ietf.org/doc/html/rfc7231#section-6.5.1)
@@ -202,10 +286,34 @@ const_status_codes! {
     /// Client Error code as defined in [RFC 7231 Section
     /// 6.5.14](https://datatracker.ietf.org/doc/html/rfc7231#section-6.5.14)
     EXPECTATION_FAILED => 417, "Expectation Failed",
+    /// 421 Misdirected Request
+    /// Client Error code as defined in [RFC 7540 Section
+    /// 9.1.2](https://datatracker.ietf.org/doc/html/rfc7540#section-9.1.2)
+    MISDIRECTED_REQUEST => 421, "Misdirected Request",
+    /// 422 Unprocessable Entity
+    /// Client Error code as defined in [RFC 4918 Section
+    /// 11.2](https://datatracker.ietf.org/doc/html/rfc4918#section-11.2)
+    UNPROCESSABLE_ENTITY => 422, "Unprocessable Entity",
     /// 426 Upgrade Required
     /// Client Error code as defined in [RFC 7231 Section
     /// 6.5.15](https://datatracker.ietf.org/doc/html/rfc7231#section-6.5.15)
     UPGRADE_REQUIRED => 426, "Upgrade Required",
+    /// 428 Precondition Required
+    /// Client Error code as defined in [RFC 6585 Section
+    /// 3](https://datatracker.ietf.org/doc/html/rfc6585#section-3)
+    PRECONDITION_REQUIRED => 428, "Precondition Required",
+    /// 429 Too Many Requests
+    /// Client Error code as defined in [RFC 6585 Section
+    /// 4](https://datatracker.ietf.org/doc/html/rfc6585#section-4)
+    TOO_MANY_REQUESTS => 429, "Too Many Requests",
+    /// 431 Request Header Fields Too Large
+    /// Client Error code as defined in [RFC 6585 Section
+    /// 5](https://datatracker.ietf.org/doc/html/rfc6585#section-5)
+    REQUEST_HEADER_FIELDS_TOO_LARGE => 431, "Request Header Fields Too Large",
+    /// 451 Unavailable For Legal Reasons
+    /// Client Error code as defined in [RFC 7725 Section
+    /// 3](https://datatracker.ietf.org/doc/html/rfc7725#section-3)
+    UNAVAILABLE_FOR_LEGAL_REASONS => 451, "Unavailable For Legal Reasons",
     /// 500 Internal Server Error
     /// Server Error code as defined in [RFC 7231 Section
     /// 6.6.1](https://datatracker.ietf.org/doc/html/rfc7231#section-6.6.1)
@@ -230,6 +338,10 @@ const_status_codes! {
     /// Server Error code as defined in [RFC 7231 Section
     /// 6.6.6](https://datatracker.ietf.org/doc/html/rfc7231#section-6.6.6)
     HTTP_VERSION_NOT_SUPPORTED => 505, "HTTP Version Not Supported",
+    /// 511 Network Authentication Required
+    /// Server Error code as defined in [RFC 6585 Section
+    /// 6](https://datatracker.ietf.org/doc/html/rfc6585#section-6)
+    NETWORK_AUTHENTICATION_REQUIRED => 511, "Network Authentication Required",
 }
 
 #[cfg(test)]
@@ -267,4 +379,28 @@ mod tests {
         assert!(StatusCode::from_bytes(b"190").is_err());
         assert!(StatusCode::from_bytes(b"999").is_err());
     }
+
+    #[test]
+    fn any_code_from_100_to_999_can_be_created() {
+        let status = StatusCode::from_u16(999).unwrap();
+        assert_eq!(999, status.as_u16());
+        assert_eq!("", status.reason());
+        assert!(StatusCode::from_u16(0).is_err());
+        assert!(StatusCode::from_u16(99).is_err());
+        assert_eq!(
+            "Too Many Requests",
+            StatusCode::from_u16(429).unwrap().reason()
+        );
+    }
+
+    #[test]
+    fn status_codes_have_a_class() {
+        assert!(StatusCode::CONTINUE.is_informational());
+        assert!(StatusCode::NO_CONTENT.is_success());
+        assert!(StatusCode::PERMANENT_REDIRECT.is_redirection());
+        assert!(StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS.is_client_error());
+        assert!(StatusCode::NETWORK_AUTHENTICATION_REQUIRED.is_server_error());
+        let status = StatusCode::from_u16(650).unwrap();
+        assert!(!status.is_server_error() && !status.is_client_error());
+    }
 }
@@ -1,4 +1,5 @@
 use std::{
+    fmt,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     str::FromStr,
 };
@@ -17,7 +18,7 @@ use crate::http::{
 /// ```
 /// For information on `userinfo` or `host`, see [`UserInfo`]
 /// or [`Host`] respectively.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Authority {
     user_info: Option<UserInfo>,
     host: Host,
@@ -38,10 +39,10 @@ impl Authority {
 
         if let Some(last_colon) = rest.iter().rposition(|b| *b == b':') {
             // might be a port
-            // rest len - 5 as maximum 4 octets for the port and
-            // 1 octet for the last_colon position
-            if last_colon >= rest.len() - 5 {
-                // last colon position is within the last 4 digits
+            // a maximum of 5 octets for the port after
+            // the last_colon position
+            if rest.len() - last_colon <= 6 {
+                // last colon position is within the last 5 digits
                 // which could be a valid port so try to parse Host
                 // from slice before last colon then if successful
                 // parse last octets as digits for port
@@ -59,11 +60,11 @@ impl Authority {
 
                     let mut port = 0u16;
                     for digit in &rest[last_colon + 1..] {
-                        if digit.is_ascii_digit() {
-                            port = (port * 10) + (digit - b'0') as u16;
-                        } else {
-                            return Err(StatusCode::BAD_REQUEST);
-                        }
+                        port = port
+                            .checked_mul(10)
+                            .zip(digit.is_ascii_digit().then(|| (digit - b'0') as u16))
+                            .and_then(|(port, digit)| port.checked_add(digit))
+                            .ok_or(StatusCode::BAD_REQUEST)?;
                     }
                     return Ok(Authority {
                         user_info,
@@ -82,6 +83,32 @@ impl Authority {
             port: None,
         })
     }
+    /// Returns the [`UserInfo`] of the authority, if it has one.
+    pub fn user_info(&self) -> Option<&UserInfo> {
+        self.user_info.as_ref()
+    }
+
+    /// Returns the [`Host`] of the authority.
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    /// Returns the port of the authority, if it has one.
+    ///
+    /// An empty port is returned as 0.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Returns the authority without the [`UserInfo`] and with the default port of the scheme
+    /// when the port is missing or empty, which identifies the origin server of a connection.
+    pub(crate) fn origin(&self, default_port: u16) -> Self {
+        Self {
+            user_info: None,
+            host: self.host.clone(),
+            port: Some(self.port.filter(|port| *port != 0).unwrap_or(default_port)),
+        }
+    }
 }
 
 /// A subcompont of [`Authority`]
@@ -93,7 +120,7 @@ impl Authority {
 /// pct-encoded = "%" HEXDIG HEXDIG
 /// sub-delims = "!" / "$" / "&" / "'" / "(" / ")" / "*" / "+" / "," / ";" / "="
 /// ```
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct UserInfo(String);
 
 impl UserInfo {
@@ -129,7 +156,7 @@ impl UserInfo {
 ///
 /// reg-name = *( unreserved / pct-encoded / sub-delims )
 /// ```
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Host {
     /// Contains a [`IpAddr`] abstraction over either a IPv4address or a
     /// IPv6address.
@@ -181,6 +208,19 @@ impl Host {
     }
 }
 
+impl fmt::Display for Host {
+    /// Formats the host as it appears in an [`Authority`], so an IPv6 address or IPvFuture is
+    /// enclosed in square brackets.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Host::IpvN(IpAddr::V4(addr)) => write!(f, "{addr}"),
+            Host::IpvN(IpAddr::V6(addr)) => write!(f, "[{addr}]"),
+            Host::IpvFuture((version, name)) => write!(f, "[v{version:X}.{name}]"),
+            Host::Domain(name) => f.write_str(name),
+        }
+    }
+}
+
 /// Parse sequence of octets to the components of IpvFuture
 ///
 /// ```text
@@ -222,7 +262,19 @@ mod authority_tests {
 
     #[test]
     fn domain_name_with_too_large_port_is_a_bad_request() {
-        assert_is_bad_request(b"example.com:50000");
+        assert_is_bad_request(b"example.com:70000");
+    }
+
+    #[test]
+    fn five_digit_port_is_valid() {
+        assert_eq!(
+            Ok(Some(50000)),
+            Authority::from_bytes(b"example.com:50000").map(|authority| authority.port)
+        );
+        assert_eq!(
+            Ok(Some(1)),
+            Authority::from_bytes(b"a:1").map(|authority| authority.port)
+        );
     }
 
     #[test]
@@ -35,7 +35,7 @@ use super::{
 /// / \ /                        \
 /// urn:example:animal:ferret:nose
 /// ```
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Uri {
     scheme: Scheme,
     authority: Option<Authority>,
@@ -54,7 +54,11 @@ impl Uri {
             return Err(StatusCode::BAD_REQUEST);
         }
 
-        let (scheme, rest) = if let Some((bytes, rest)) = split_at_next(src, b':') {
+        // a colon after the first "/", "?" or "#" is not the end of a scheme, such as in the
+        // relative reference "/path?time=12:00"
+        let scheme_end =
+            split_at_next(src, b':').filter(|(bytes, _)| !bytes.iter().any(|b| b"/?#".contains(b)));
+        let (scheme, rest) = if let Some((bytes, rest)) = scheme_end {
             (Scheme::from_bytes(bytes)?, rest)
         } else {
             (Scheme::default(), src)
@@ -119,6 +123,145 @@ impl Uri {
             _ => Err(StatusCode::BAD_REQUEST),
         }
     }
+
+    /// Returns the [`Scheme`] of the URI, which is empty when the URI does not have one.
+    pub fn scheme(&self) -> &Scheme {
+        &self.scheme
+    }
+
+    /// Returns the [`Authority`] of the URI, if it has one.
+    pub fn authority(&self) -> Option<&Authority> {
+        self.authority.as_ref()
+    }
+
+    /// Returns the [`Path`] of the URI.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the [`Query`] of the URI, which is empty when the URI does not have one.
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    /// Resolve a URI reference against this base URI, as defined in [RFC3986 Section
+    /// 5.2](https://datatracker.ietf.org/doc/html/rfc3986#section-5.2).
+    ///
+    /// An empty [`Query`] is treated as undefined, so a reference with only a fragment keeps
+    /// the query of the base URI.
+    ///
+    /// ```
+    /// use ramus::http::Uri;
+    ///
+    /// let base = Uri::from_bytes(b"http://a/b/c/d;p?q").expect("valid base uri");
+    /// let reference = Uri::from_bytes(b"../g?y").expect("valid reference");
+    /// let target = base.resolve(&reference);
+    /// assert_eq!("/b/g", target.path().as_str());
+    /// assert_eq!("y", target.query().as_str());
+    /// ```
+    pub fn resolve(&self, reference: &Uri) -> Uri {
+        if !reference.scheme.is_empty() {
+            return Uri {
+                path: Path(remove_dot_segments(&reference.path.0)),
+                ..reference.clone()
+            };
+        }
+
+        let (authority, path, query) = if reference.authority.is_some() {
+            (
+                reference.authority.clone(),
+                remove_dot_segments(&reference.path.0),
+                reference.query.clone(),
+            )
+        } else if reference.path.0.is_empty() {
+            let query = if reference.query.0.is_empty() {
+                self.query.clone()
+            } else {
+                reference.query.clone()
+            };
+            (self.authority.clone(), self.path.0.clone(), query)
+        } else if reference.path.0.starts_with('/') {
+            (
+                self.authority.clone(),
+                remove_dot_segments(&reference.path.0),
+                reference.query.clone(),
+            )
+        } else {
+            // RFC3986 Section 5.2.3: merge the reference path with the base path
+            let merged = if self.authority.is_some() && self.path.0.is_empty() {
+                format!("/{}", reference.path.0)
+            } else {
+                let base = self.path.0.rfind('/').map_or("", |i| &self.path.0[..=i]);
+                format!("{base}{}", reference.path.0)
+            };
+            (
+                self.authority.clone(),
+                remove_dot_segments(&merged),
+                reference.query.clone(),
+            )
+        };
+
+        Uri {
+            scheme: self.scheme.clone(),
+            authority,
+            path: Path(path),
+            query,
+            fragment: reference.fragment.clone(),
+        }
+    }
+
+    /// Reconstruct the effective request URI from this `request-target`, as defined in
+    /// [RFC7230 Section 5.5](https://datatracker.ietf.org/doc/html/rfc7230#section-5.5).
+    ///
+    /// A target in absolute-form is already the effective request URI, otherwise the scheme of
+    /// the connection and the authority from the `Host` header field are used. The
+    /// asterisk-form has an empty path.
+    pub(crate) fn effective(&self, scheme: &Scheme, host: Option<Authority>) -> Self {
+        if !self.scheme.is_empty() {
+            return self.clone();
+        }
+        let path = if self.path.0 == "*" {
+            Path::default()
+        } else {
+            self.path.clone()
+        };
+        Self {
+            scheme: scheme.clone(),
+            authority: self.authority.clone().or(host),
+            path,
+            query: self.query.clone(),
+            fragment: Fragment::default(),
+        }
+    }
+}
+
+/// Remove the "." and ".." segments from a path as defined in [RFC3986 Section
+/// 5.2.4](https://datatracker.ietf.org/doc/html/rfc3986#section-5.2.4).
+fn remove_dot_segments(mut input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    while !input.is_empty() {
+        if let Some(rest) = input
+            .strip_prefix("../")
+            .or_else(|| input.strip_prefix("./"))
+        {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            output.truncate(output.rfind('/').unwrap_or(0));
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            // move the first segment, with its leading "/", to the output
+            let end = input[1..].find('/').map_or(input.len(), |i| i + 1);
+            output.push_str(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output
 }
 
 /// Fragment as defined in [RFC3986 Section
@@ -129,7 +272,7 @@ impl Uri {
 ///
 /// pchar = unreserved / pct-encoded / sub-delims / ":" / "@"
 /// ```
-#[derive(Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Fragment(String);
 
 impl Fragment {
@@ -178,7 +321,7 @@ impl Fragment {
 ///
 /// pchar = unreserved / pct-encoded / sub-delims / ":" / "@"
 /// ```
-#[derive(Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Query(String);
 
 impl Query {
@@ -192,11 +335,44 @@ impl Query {
             .map(Self)
             .ok_or(StatusCode::BAD_REQUEST)
     }
+
+    /// Returns the Query as a string, without the "?" prefix.
+    ///
+    /// ```
+    /// use ramus::http::Query;
+    ///
+    /// let query = Query::from_bytes(b"name=ferret").expect("valid query bytes");
+    /// assert_eq!("name=ferret", query.as_str());
+    /// ```
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the decoded name and value pairs of the query, using the same rules as an
+    /// `application/x-www-form-urlencoded` body, see [`Form`](crate::http::Form).
+    ///
+    /// Returns a [`StatusCode::BAD_REQUEST`] if a name or value is not validly encoded.
+    ///
+    /// ```
+    /// use ramus::http::Query;
+    ///
+    /// let query = Query::from_bytes(b"name=ferret+jr&colour=%23fff").expect("valid query bytes");
+    /// assert_eq!(
+    ///     Ok(vec![
+    ///         ("name".to_string(), "ferret jr".to_string()),
+    ///         ("colour".to_string(), "#fff".to_string()),
+    ///     ]),
+    ///     query.pairs()
+    /// );
+    /// ```
+    pub fn pairs(&self) -> Result<Vec<(String, String)>, StatusCode> {
+        utils::decode_pairs(self.0.as_bytes())
+    }
 }
 
 #[cfg(test)]
 mod uri_tests {
-    use super::{Authority, Fragment, Path, Query, Scheme, StatusCode, Uri};
+    use super::{remove_dot_segments, Authority, Fragment, Path, Query, Scheme, StatusCode, Uri};
 
     fn assert_is_bad_request(bytes: &[u8]) {
         assert_eq!(Err(StatusCode::BAD_REQUEST), Uri::from_bytes(bytes));
@@ -212,6 +388,53 @@ mod uri_tests {
         assert_is_bad_request(b"http://example.com")
     }
 
+    #[test]
+    fn colon_in_query_of_relative_reference_is_valid() {
+        let uri = Uri::from_bytes(b"/path?time=12:00").expect("failed to parse uri");
+        assert!(uri.scheme().is_empty());
+        assert_eq!("/path", uri.path().as_str());
+        assert_eq!("time=12:00", uri.query().as_str());
+    }
+
+    #[test]
+    fn dot_segments_are_removed() {
+        // Examples from RFC3986 Section 5.2.4
+        assert_eq!("/a/g", remove_dot_segments("/a/b/c/./../../g"));
+        assert_eq!("mid/6", remove_dot_segments("mid/content=5/../6"));
+    }
+
+    #[test]
+    fn references_are_resolved_against_base() {
+        // Examples from RFC3986 Section 5.4
+        let base = Uri::from_bytes(b"http://a/b/c/d;p?q").expect("failed to parse base");
+        for (reference, path, query) in [
+            ("g", "/b/c/g", ""),
+            ("./g/", "/b/c/g/", ""),
+            ("/g", "/g", ""),
+            ("?y", "/b/c/d;p", "y"),
+            ("g?y", "/b/c/g", "y"),
+            ("#s", "/b/c/d;p", "q"),
+            ("..", "/b/", ""),
+            ("../../../g", "/g", ""),
+            ("g;x=1/../y", "/b/c/y", ""),
+        ] {
+            let reference = Uri::from_bytes(reference.as_bytes()).expect("failed to parse");
+            let target = base.resolve(&reference);
+            assert_eq!("http", target.scheme().as_str());
+            assert_eq!(base.authority(), target.authority());
+            assert_eq!(path, target.path().as_str());
+            assert_eq!(query, target.query().as_str());
+        }
+
+        let reference = Uri::from_bytes(b"//g/x").expect("failed to parse");
+        let target = base.resolve(&reference);
+        assert_eq!(
+            Authority::from_bytes(b"g").ok().as_ref(),
+            target.authority()
+        );
+        assert_eq!("/x", target.path().as_str());
+    }
+
     #[test]
     fn double_hash_is_a_bad_request() {
         assert_is_bad_request(b"http://example.com/#sss#sh");
@@ -22,8 +22,8 @@ use crate::http::{utils, StatusCode};
 ///
 /// pchar = unreserved / pct-encoded / sub-delims / ":" / "@"
 /// ```
-#[derive(Debug, Default, PartialEq)]
-pub struct Path(String);
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path(pub(super) String);
 
 impl Path {
     /// Derive a [`Path`] from a slice of bytes.
@@ -92,6 +92,18 @@ impl Path {
             }
         }
     }
+
+    /// Returns the Path as a string.
+    ///
+    /// ```
+    /// use ramus::http::Path;
+    ///
+    /// let path = Path::from_bytes(b"/over/there").expect("valid path bytes");
+    /// assert_eq!("/over/there", path.as_str());
+    /// ```
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 #[cfg(test)]
@@ -10,6 +10,18 @@ use crate::http::StatusCode;
 pub struct Scheme(String);
 
 impl Scheme {
+    /// The "http" Scheme as defined in [RFC7230 Section
+    /// 2.7.1](https://datatracker.ietf.org/doc/html/rfc7230#section-2.7.1).
+    pub fn http() -> Self {
+        Self("http".to_string())
+    }
+
+    /// The "https" Scheme as defined in [RFC7230 Section
+    /// 2.7.2](https://datatracker.ietf.org/doc/html/rfc7230#section-2.7.2).
+    pub fn https() -> Self {
+        Self("https".to_string())
+    }
+
     /// Create a Scheme from a sequence of bytes.
     ///
     /// An empty slice will return a valid Scheme which is empty.
@@ -55,6 +67,18 @@ impl Scheme {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Returns the Scheme as a lowercase string.
+    ///
+    /// ```
+    /// use ramus::http::Scheme;
+    ///
+    /// let scheme = Scheme::from_bytes(b"HTTPS").expect("valid scheme bytes");
+    /// assert_eq!("https", scheme.as_str());
+    /// ```
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 #[cfg(test)]
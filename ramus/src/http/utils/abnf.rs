@@ -1,5 +1,5 @@
-/// Crate Module:
-/// Module that contains functions relating to parsing or validating of ABNF syntax types.
+//! Crate Module:
+//! Module that contains functions relating to parsing or validating of ABNF syntax types.
 
 /// Checks if the value is a `unreserved` ABNF as defined in
 /// [RFC3986](https://datatracker.ietf.org/doc/html/rfc3986)
@@ -47,6 +47,140 @@ pub(crate) const fn is_sub_delims(byte: u8) -> bool {
     )
 }
 
+/// Checks if the value is a `tchar` ABNF as defined in
+/// [RFC7230 Section 3.2.6](https://datatracker.ietf.org/doc/html/rfc7230#section-3.2.6)
+///
+/// ```text
+/// tchar = "!" / "#" / "$" / "%" / "&" / "'" / "*"
+///       / "+" / "-" / "." / "^" / "_" / "`" / "|" / "~"
+///       / DIGIT / ALPHA
+/// ```
+#[inline]
+pub(crate) const fn is_tchar(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric()
+        || matches!(
+            byte,
+            b'!' | b'#'
+                | b'$'
+                | b'%'
+                | b'&'
+                | b'\''
+                | b'*'
+                | b'+'
+                | b'-'
+                | b'.'
+                | b'^'
+                | b'_'
+                | b'`'
+                | b'|'
+                | b'~'
+        )
+}
+
+/// Checks if the value is a `field-vchar` ABNF as defined in
+/// [RFC7230 Section 3.2](https://datatracker.ietf.org/doc/html/rfc7230#section-3.2)
+///
+/// ```text
+/// field-vchar = VCHAR / obs-text
+///
+/// VCHAR = %x21-7E
+/// obs-text = %x80-FF
+/// ```
+#[inline]
+pub(crate) const fn is_field_vchar(byte: u8) -> bool {
+    byte.is_ascii_graphic() || byte >= 0x80
+}
+
+/// Checks if the value is a whitespace octet that can make up the `OWS` ABNF as defined in
+/// [RFC7230 Section 3.2.3](https://datatracker.ietf.org/doc/html/rfc7230#section-3.2.3)
+///
+/// ```text
+/// OWS = *( SP / HTAB )
+/// ```
+#[inline]
+pub(crate) const fn is_ows(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t')
+}
+
+/// Removes any leading and trailing `OWS` from the sequence of octets.
+pub(crate) fn trim_ows(src: &[u8]) -> &[u8] {
+    let start = src.iter().position(|b| !is_ows(*b)).unwrap_or(src.len());
+    let end = src
+        .iter()
+        .rposition(|b| !is_ows(*b))
+        .map_or(start, |i| i + 1);
+    &src[start..end]
+}
+
+/// Parse a `token` from a sequence of bytes as a String.
+///
+/// Returns None if the sequence of bytes does not start with at least one `tchar`.
+///
+/// ```text
+/// token = 1*tchar
+/// ```
+pub(crate) fn parse_token(src: &[u8]) -> Option<String> {
+    // SAFETY:
+    // tchar are valid ascii characters so this satisfies the safety requirements
+    // of parse_seq
+    unsafe { parse_seq(src, is_tchar) }.filter(|s| !s.is_empty())
+}
+
+/// Parse a `quoted-string` from the start of a sequence of octets as defined in [RFC7230 Section
+/// 3.2.6](https://datatracker.ietf.org/doc/html/rfc7230#section-3.2.6), returning the unquoted
+/// value and the rest of the sequence.
+///
+/// Returns None if the sequence of octets does not start with a valid `quoted-string`.
+///
+/// ```text
+/// quoted-string = DQUOTE *( qdtext / quoted-pair ) DQUOTE
+/// qdtext = HTAB / SP /%x21 / %x23-5B / %x5D-7E / obs-text
+/// quoted-pair = "\" ( HTAB / SP / VCHAR / obs-text )
+/// ```
+pub(crate) fn parse_quoted_string(src: &[u8]) -> Option<(String, &[u8])> {
+    let mut bytes = src.strip_prefix(b"\"")?.iter().enumerate();
+    let mut value = Vec::new();
+    while let Some((i, b)) = bytes.next() {
+        match *b {
+            // the index is of the slice after the opening DQUOTE
+            b'"' => return Some((String::from_utf8_lossy(&value).into_owned(), &src[i + 2..])),
+            b'\\' => match bytes.next() {
+                Some((_, b)) if is_field_vchar(*b) || is_ows(*b) => value.push(*b),
+                _ => return None,
+            },
+            b if is_field_vchar(b) || is_ows(b) => value.push(b),
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Split a list of elements on the delimiter, ignoring any delimiter that is within a
+/// `quoted-string`, and removes the `OWS` around each element.
+///
+/// This is used for the `#rule` lists of [RFC7230 Section
+/// 7](https://datatracker.ietf.org/doc/html/rfc7230#section-7), where the delimiter is a ",",
+/// and for the ";" delimited parameters of an element. Empty elements are kept so that the
+/// caller can decide whether they are allowed.
+pub(crate) fn split_unquoted(src: &[u8], delimiter: u8) -> Vec<&[u8]> {
+    let mut elements = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, b) in src.iter().enumerate() {
+        match *b {
+            _ if escaped => escaped = false,
+            b'\\' if quoted => escaped = true,
+            b'"' => quoted = !quoted,
+            b if b == delimiter && !quoted => {
+                elements.push(trim_ows(&src[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    elements.push(trim_ows(&src[start..]));
+    elements
+}
+
 /// Checks that the sequence of octets is a valid reg-name as defined in
 /// [RFC3986 Section 3.2.2](https://datatracker.ietf.org/doc/html/rfc3986#section-3.2.2)
 ///
@@ -206,8 +340,8 @@ pub(crate) fn parse_frag_or_query(src: &[u8]) -> Option<String> {
 pub(crate) const fn parse_hex_dig(byte: u8) -> Option<u8> {
     let digit = match byte {
         // b'A' - 10 so that A == 10
-        b'A'..=b'F' => (byte - (b'A' - 10)),
-        b'0'..=b'9' => (byte - b'0'),
+        b'A'..=b'F' => byte - (b'A' - 10),
+        b'0'..=b'9' => byte - b'0',
         _ => return None,
     };
     Some(digit)
@@ -263,7 +397,8 @@ parse_hex_uint_impl! {
 #[cfg(test)]
 mod tests {
     use super::{
-        parse_hex_dig, parse_hex_u16, parse_hex_u8, parse_pct_encoded_ext, parse_reg_name,
+        parse_hex_dig, parse_hex_u16, parse_hex_u8, parse_pct_encoded_ext, parse_quoted_string,
+        parse_reg_name, parse_token, split_unquoted, trim_ows,
     };
 
     #[test]
@@ -360,16 +495,52 @@ mod tests {
 
     #[test]
     fn all_valid_hex_digs() {
-        for (i, digit) in (b'0'..=b'9').into_iter().enumerate() {
+        for (i, digit) in (b'0'..=b'9').enumerate() {
             assert_eq!(Some(i as u8), parse_hex_dig(digit));
         }
 
-        for (i, letter) in (b'A'..=b'F')
-            .into_iter()
-            .enumerate()
-            .map(|(i, l)| (i + 10, l))
-        {
+        for (i, letter) in (b'A'..=b'F').enumerate().map(|(i, l)| (i + 10, l)) {
             assert_eq!(Some(i as u8), parse_hex_dig(letter));
         }
     }
+
+    #[test]
+    fn token_must_have_at_least_one_tchar() {
+        assert!(parse_token(&[]).is_none());
+        assert!(parse_token(b":value").is_none());
+    }
+
+    #[test]
+    fn token_stops_at_first_non_tchar() {
+        assert_eq!(
+            Some("Content-Length".to_owned()),
+            parse_token(b"Content-Length: 20")
+        );
+    }
+
+    #[test]
+    fn trim_ows_removes_leading_and_trailing_whitespace() {
+        assert_eq!(b"value".as_ref(), trim_ows(b" \t value\t "));
+        assert_eq!(b"a b".as_ref(), trim_ows(b"a b"));
+        assert!(trim_ows(b" \t ").is_empty());
+    }
+
+    #[test]
+    fn quoted_string_is_unquoted_with_rest() {
+        assert_eq!(
+            Some(("a \"b\", c".to_owned(), b"; d".as_ref())),
+            parse_quoted_string(br#""a \"b\", c"; d"#)
+        );
+        assert_eq!(None, parse_quoted_string(b"\"unterminated"));
+        assert_eq!(None, parse_quoted_string(b"\"a\x01\""));
+        assert_eq!(None, parse_quoted_string(b"token"));
+    }
+
+    #[test]
+    fn split_unquoted_ignores_delimiters_in_quoted_strings() {
+        assert_eq!(
+            vec![b"a".as_ref(), b"b=\"1,\\\"2\"", b"", b"c"],
+            split_unquoted(b" a , b=\"1,\\\"2\",, c", b',')
+        );
+    }
 }
@@ -0,0 +1,125 @@
+//! Crate Module:
+//! Base 64 encoding and decoding as defined in [RFC4648](https://datatracker.ietf.org/doc/html/rfc4648).
+
+/// The alphabet used to encode each 6-bit group.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Alphabet {
+    /// The base 64 alphabet as defined in [RFC4648 Section
+    /// 4](https://datatracker.ietf.org/doc/html/rfc4648#section-4).
+    Standard,
+    /// The URL and filename safe alphabet as defined in [RFC4648 Section
+    /// 5](https://datatracker.ietf.org/doc/html/rfc4648#section-5).
+    UrlSafe,
+}
+
+impl Alphabet {
+    fn symbols(self) -> &'static [u8; 64] {
+        match self {
+            Self::Standard => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+            Self::UrlSafe => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+        }
+    }
+}
+
+/// Encode the bytes using the alphabet, the encoded string is always padded.
+pub(crate) fn encode(src: &[u8], alphabet: Alphabet) -> String {
+    let symbols = alphabet.symbols();
+    let mut encoded = String::with_capacity(src.len().div_ceil(3) * 4);
+    for group in src.chunks(3) {
+        let bits = group
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, b)| bits | (*b as u32) << (16 - i * 8));
+        for i in 0..4 {
+            if i <= group.len() {
+                encoded.push(symbols[(bits >> (18 - i * 6)) as usize & 0x3f] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decode the bytes using the alphabet.
+///
+/// The padding is optional but when present it must be correct. Returns None if any of the
+/// bytes are not in the alphabet or the length is not valid.
+pub(crate) fn decode(src: &[u8], alphabet: Alphabet) -> Option<Vec<u8>> {
+    let src = match src {
+        [rest @ .., b'=', b'='] | [rest @ .., b'='] if src.len().is_multiple_of(4) => rest,
+        _ => src,
+    };
+    if src.len() % 4 == 1 {
+        return None;
+    }
+
+    let symbols = alphabet.symbols();
+    let mut decoded = Vec::with_capacity(src.len() * 3 / 4);
+    for group in src.chunks(4) {
+        let bits = group.iter().enumerate().try_fold(0u32, |bits, (i, b)| {
+            symbols
+                .iter()
+                .position(|symbol| symbol == b)
+                .map(|value| bits | (value as u32) << (18 - i * 6))
+        })?;
+        for i in 0..group.len() - 1 {
+            decoded.push((bits >> (16 - i * 8)) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, Alphabet};
+
+    // Test vectors from RFC4648 Section 10
+    const VECTORS: [(&str, &str); 7] = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn rfc_test_vectors_decode() {
+        for (decoded, encoded) in VECTORS {
+            assert_eq!(
+                Some(decoded.as_bytes().to_vec()),
+                decode(encoded.as_bytes(), Alphabet::UrlSafe)
+            );
+        }
+    }
+
+    #[test]
+    fn rfc_test_vectors_encode() {
+        for (decoded, encoded) in VECTORS {
+            assert_eq!(encoded, encode(decoded.as_bytes(), Alphabet::Standard));
+        }
+    }
+
+    #[test]
+    fn padding_is_optional() {
+        assert_eq!(Some(b"fo".to_vec()), decode(b"Zm8", Alphabet::UrlSafe));
+    }
+
+    #[test]
+    fn invalid_symbols_or_length_cannot_be_decoded() {
+        assert!(decode(b"Zm9v!", Alphabet::UrlSafe).is_none());
+        assert!(decode(b"Zm9vY", Alphabet::UrlSafe).is_none());
+        assert!(decode(b"+/", Alphabet::UrlSafe).is_none());
+        assert!(decode(b"-_", Alphabet::Standard).is_none());
+    }
+
+    #[test]
+    fn alphabets_differ_in_last_two_symbols() {
+        assert_eq!(Some(vec![0xfb, 0xff]), decode(b"-_8", Alphabet::UrlSafe));
+        assert_eq!(Some(vec![0xfb, 0xff]), decode(b"+/8", Alphabet::Standard));
+        assert_eq!("+/8=", encode(&[0xfb, 0xff], Alphabet::Standard));
+        assert_eq!("-_8=", encode(&[0xfb, 0xff], Alphabet::UrlSafe));
+    }
+}
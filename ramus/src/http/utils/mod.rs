@@ -1,4 +1,11 @@
 pub(crate) mod abnf;
+pub(crate) mod base64;
+pub(crate) mod md5;
+pub(crate) mod sha1;
+
+use std::io::{self, BufRead, Read};
+
+use super::StatusCode;
 
 /// Divides one slice into two at the first occurrence of the given element.
 ///
@@ -22,9 +29,166 @@ pub(crate) fn split_at_next_space(src: &[u8]) -> Option<(&[u8], &[u8])> {
     split_at_next(src, b' ')
 }
 
+/// Read a single line, terminated by a CRLF, from the reader and return the line without the
+/// CRLF.
+///
+/// A bare LF is also accepted as the line terminator as recommended by [RFC7230 Section
+/// 3.5](https://datatracker.ietf.org/doc/html/rfc7230#section-3.5).
+///
+/// Returns `None` if the reader has no more bytes before the line was started.
+/// Returns the `too_long` status code if the line (excluding the CRLF) is longer than `max`
+/// octets, or a [`StatusCode::BAD_REQUEST`] if the reader ends part way through the line.
+pub(crate) fn read_line<R: BufRead>(
+    reader: &mut R,
+    max: usize,
+    too_long: StatusCode,
+) -> Result<Option<Vec<u8>>, StatusCode> {
+    let mut line = Vec::new();
+    // + 2 to allow for the CRLF after the maximum number of octets
+    reader
+        .by_ref()
+        .take(max as u64 + 2)
+        .read_until(b'\n', &mut line)
+        .map_err(io_error_status)?;
+
+    match line.as_slice() {
+        [] => return Ok(None),
+        [.., b'\r', b'\n'] => line.truncate(line.len() - 2),
+        [.., b'\n'] => line.truncate(line.len() - 1),
+        _ if line.len() > max => return Err(too_long),
+        _ => return Err(StatusCode::BAD_REQUEST),
+    }
+    // a bare LF leaves room for one more octet than the maximum
+    if line.len() > max {
+        return Err(too_long);
+    }
+    Ok(Some(line))
+}
+
+/// Decode the `pct-encoded` octets of a sequence of bytes as defined in [RFC3986 Section
+/// 2.1](https://datatracker.ietf.org/doc/html/rfc3986#section-2.1).
+///
+/// ```text
+/// pct-encoded = "%" HEXDIG HEXDIG
+/// ```
+///
+/// Lowercase hex digits are also accepted as they are equivalent.
+/// Returns None if a "%" is not followed by two hex digits.
+pub(crate) fn percent_decode(src: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(src.len());
+    let mut bytes = src.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'%' {
+            let high = (*bytes.next()? as char).to_digit(16)?;
+            let low = (*bytes.next()? as char).to_digit(16)?;
+            decoded.push((high << 4 | low) as u8);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    Some(decoded)
+}
+
+/// Encode every octet that is not `unreserved` as `pct-encoded`, the reverse of
+/// [`percent_decode`].
+///
+/// ```text
+/// unreserved = ALPHA / DIGIT / "-" / "." / "_" / "~"
+/// ```
+pub(crate) fn percent_encode(src: &[u8]) -> String {
+    let mut encoded = String::with_capacity(src.len());
+    for &byte in src {
+        if abnf::is_unreserved(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Decode the name and value pairs of a query or an `application/x-www-form-urlencoded` body,
+/// as defined by the [URL Standard](https://url.spec.whatwg.org/#urlencoded-parsing).
+///
+/// The pairs are delimited by "&" and the name is separated from the value by the first "=",
+/// a pair without a "=" has an empty value. Each "+" is a space and then the `pct-encoded`
+/// octets are decoded, see [`percent_decode`]. Empty pairs are skipped.
+///
+/// Returns a [`StatusCode::BAD_REQUEST`] if a "%" is not followed by two hex digits or a
+/// decoded name or value is not valid UTF-8.
+pub(crate) fn decode_pairs(src: &[u8]) -> Result<Vec<(String, String)>, StatusCode> {
+    let decode = |src: &[u8]| {
+        let src: Vec<_> = src
+            .iter()
+            .map(|&b| if b == b'+' { b' ' } else { b })
+            .collect();
+        percent_decode(&src)
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .ok_or(StatusCode::BAD_REQUEST)
+    };
+    src.split(|&b| b == b'&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = split_at_next(pair, b'=').unwrap_or((pair, &[]));
+            Ok((decode(name)?, decode(value)?))
+        })
+        .collect()
+}
+
+/// Returns octets from the random number generator of the operating system, for values that
+/// must not be guessed such as a session id.
+///
+/// Panics if the operating system cannot provide random octets, as there is no safe fallback.
+pub(crate) fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    getrandom::fill(&mut bytes).expect("the operating system provides random octets");
+    bytes
+}
+
+/// Map an [`io::Error`] that happened while reading a request to the [`StatusCode`] that best
+/// describes it.
+///
+/// Timeouts are a [`StatusCode::REQUEST_TIMEOUT`] and everything else is treated as an
+/// incomplete message and so is a [`StatusCode::BAD_REQUEST`].
+pub(crate) fn io_error_status(error: io::Error) -> StatusCode {
+    match error.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => StatusCode::REQUEST_TIMEOUT,
+        _ => StatusCode::BAD_REQUEST,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{split_at_next, split_at_next_space};
+    use super::{
+        decode_pairs, percent_decode, percent_encode, read_line, split_at_next,
+        split_at_next_space, StatusCode,
+    };
+
+    #[test]
+    fn pct_encoded_octets_are_decoded() {
+        assert_eq!(Some(b"a b/\xff".to_vec()), percent_decode(b"a%20b%2F%ff"));
+        assert_eq!(None, percent_decode(b"%2"));
+        assert_eq!(None, percent_decode(b"%+1"));
+    }
+
+    #[test]
+    fn octets_other_than_unreserved_are_encoded() {
+        assert_eq!("a-b.c_~%20%2B%3D%FF", percent_encode(b"a-b.c_~ +=\xff"));
+    }
+
+    #[test]
+    fn pairs_are_split_and_decoded() {
+        assert_eq!(
+            Ok(vec![
+                ("a b".to_string(), "1+2=3".to_string()),
+                ("flag".to_string(), String::new()),
+                ("a b".to_string(), "\u{e9}".to_string()),
+            ]),
+            decode_pairs(b"a+b=1%2B2=3&&flag&a%20b=%C3%A9")
+        );
+        assert_eq!(Err(StatusCode::BAD_REQUEST), decode_pairs(b"a=%zz"));
+        assert_eq!(Err(StatusCode::BAD_REQUEST), decode_pairs(b"a=%ff"));
+    }
 
     #[test]
     fn split_at_first_element_empty_left_and_right_with_rest() {
@@ -54,4 +218,62 @@ mod tests {
     fn no_next_element_is_none() {
         assert!(split_at_next(b"baaaaaaaaaaaaaaaaa", b'$').is_none());
     }
+
+    #[test]
+    fn read_line_strips_crlf_or_bare_lf() {
+        let mut bytes = &b"first\r\nsecond\nthird"[..];
+        assert_eq!(
+            Ok(Some(b"first".to_vec())),
+            read_line(&mut bytes, 10, StatusCode::URI_TOO_LONG)
+        );
+        assert_eq!(
+            Ok(Some(b"second".to_vec())),
+            read_line(&mut bytes, 10, StatusCode::URI_TOO_LONG)
+        );
+    }
+
+    #[test]
+    fn read_line_without_terminator_is_a_bad_request() {
+        let mut bytes = &b"third"[..];
+        assert_eq!(
+            Err(StatusCode::BAD_REQUEST),
+            read_line(&mut bytes, 10, StatusCode::URI_TOO_LONG)
+        );
+    }
+
+    #[test]
+    fn read_line_at_end_of_reader_is_none() {
+        assert_eq!(
+            Ok(None),
+            read_line(&mut &[][..], 10, StatusCode::URI_TOO_LONG)
+        );
+    }
+
+    #[test]
+    fn read_line_above_max_is_the_too_long_status() {
+        let mut bytes = &b"0123456789A\r\n"[..];
+        assert_eq!(
+            Err(StatusCode::URI_TOO_LONG),
+            read_line(&mut bytes, 10, StatusCode::URI_TOO_LONG)
+        );
+        let mut bytes = &b"0123456789\r\n"[..];
+        assert_eq!(
+            Ok(Some(b"0123456789".to_vec())),
+            read_line(&mut bytes, 10, StatusCode::URI_TOO_LONG)
+        );
+    }
+
+    #[test]
+    fn read_line_above_max_with_a_bare_lf_is_the_too_long_status() {
+        let mut bytes = &b"0123456789A\n"[..];
+        assert_eq!(
+            Err(StatusCode::URI_TOO_LONG),
+            read_line(&mut bytes, 10, StatusCode::URI_TOO_LONG)
+        );
+        let mut bytes = &b"0123456789\n"[..];
+        assert_eq!(
+            Ok(Some(b"0123456789".to_vec())),
+            read_line(&mut bytes, 10, StatusCode::URI_TOO_LONG)
+        );
+    }
 }
@@ -6,4 +6,9 @@
     missing_debug_implementations
 )]
 
+pub mod client;
+pub mod h2;
+pub mod hpack;
 pub mod http;
+pub mod server;
+pub mod ws;
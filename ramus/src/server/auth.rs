@@ -0,0 +1,586 @@
+use std::{
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+
+use crate::http::{
+    utils, BasicCredentials, BearerToken, Challenge, Credentials, DigestAlgorithm,
+    DigestCredentials, HeaderField, Request, Response, StatusCode, Uri,
+};
+
+use super::Handler;
+
+/// A way to authenticate the user of a request from its [`Credentials`], such as
+/// [`BasicAuth`], [`BearerAuth`] or [`DigestAuth`].
+///
+/// A pair of authenticators with the same user accepts the credentials of either, and sends
+/// the challenges of both.
+pub trait Authenticator: Send + Sync + 'static {
+    /// The user that the credentials are for, which is passed to the handler.
+    type User;
+
+    /// Returns the user of the credentials, or the challenges to send when the credentials are
+    /// missing or not valid.
+    fn authenticate(
+        &self,
+        request: &Request,
+        credentials: Option<&Credentials>,
+    ) -> Result<Self::User, Vec<Challenge>>;
+}
+
+impl<A, B> Authenticator for (A, B)
+where
+    A: Authenticator,
+    B: Authenticator<User = A::User>,
+{
+    type User = A::User;
+
+    fn authenticate(
+        &self,
+        request: &Request,
+        credentials: Option<&Credentials>,
+    ) -> Result<A::User, Vec<Challenge>> {
+        match self.0.authenticate(request, credentials) {
+            Ok(user) => Ok(user),
+            Err(mut challenges) => match self.1.authenticate(request, credentials) {
+                Ok(user) => Ok(user),
+                Err(more) => {
+                    challenges.extend(more);
+                    Err(challenges)
+                }
+            },
+        }
+    }
+}
+
+/// A [`Handler`] that only calls the function for a request with valid credentials, as defined
+/// in [RFC7235](https://datatracker.ietf.org/doc/html/rfc7235), passing it the user as well as
+/// the request.
+///
+/// The credentials are read from the `Authorization` header field. Without valid credentials
+/// the response is a [`StatusCode::UNAUTHORIZED`] with a `WWW-Authenticate` field for each
+/// challenge of the [`Authenticator`], and credentials that are not valid syntax are a
+/// [`StatusCode::BAD_REQUEST`]. A proxy uses the `Proxy-` fields instead, see
+/// [`Authenticate::proxy`].
+///
+/// ```no_run
+/// use ramus::{
+///     http::{Request, Response, StatusCode},
+///     server::{Authenticate, BasicAuth, Server},
+/// };
+///
+/// let users = BasicAuth::new("admin", |user: &str, password: &str| {
+///     (user == "ferris" && password == "crab").then(|| user.to_string())
+/// });
+/// Server::bind("127.0.0.1:8080")
+///     .expect("failed to bind")
+///     .serve(Authenticate::new(users, |_: &Request, user: String| {
+///         Response::new(StatusCode::OK).with_body(format!("Hello, {user}!"))
+///     }))
+///     .expect("failed to accept connections");
+/// ```
+pub struct Authenticate<A, F> {
+    authenticator: A,
+    handler: F,
+    proxy: bool,
+}
+
+impl<A, F> Authenticate<A, F> {
+    /// Create an [`Authenticate`] handler that calls the function with the authenticated user.
+    pub fn new(authenticator: A, handler: F) -> Self {
+        Self {
+            authenticator,
+            handler,
+            proxy: false,
+        }
+    }
+
+    /// Set whether the credentials are for a proxy, which reads the `Proxy-Authorization`
+    /// header field and sends a [`StatusCode::PROXY_AUTHENTICATION_REQUIRED`] with
+    /// `Proxy-Authenticate` fields instead.
+    pub fn proxy(mut self, proxy: bool) -> Self {
+        self.proxy = proxy;
+        self
+    }
+}
+
+impl<A: fmt::Debug, F> fmt::Debug for Authenticate<A, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Authenticate")
+            .field("authenticator", &self.authenticator)
+            .field("proxy", &self.proxy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, F, R> Handler for Authenticate<A, F>
+where
+    A: Authenticator,
+    F: Fn(&Request, A::User) -> R + Send + Sync + 'static,
+    R: Into<Response>,
+{
+    fn handle(&self, request: &Request) -> Response {
+        let (field, status, challenge_field) = if self.proxy {
+            (
+                "proxy-authorization",
+                StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+                "Proxy-Authenticate",
+            )
+        } else {
+            (
+                "authorization",
+                StatusCode::UNAUTHORIZED,
+                "WWW-Authenticate",
+            )
+        };
+        let credentials = match request.headers().get(field).map(str::as_bytes) {
+            Some(value) => match Credentials::from_bytes(value) {
+                Ok(credentials) => Some(credentials),
+                Err(status) => return Response::new(status),
+            },
+            None => None,
+        };
+
+        match self
+            .authenticator
+            .authenticate(request, credentials.as_ref())
+        {
+            Ok(user) => (self.handler)(request, user).into(),
+            Err(challenges) => {
+                let mut response = Response::new(status);
+                for challenge in challenges {
+                    response
+                        .headers_mut()
+                        .append(HeaderField::new(challenge_field, challenge.to_string()));
+                }
+                response
+            }
+        }
+    }
+}
+
+/// An [`Authenticator`] for the `Basic` scheme, as defined in
+/// [RFC7617](https://datatracker.ietf.org/doc/html/rfc7617), which passes the user-id and
+/// password to the function to find the user.
+///
+/// The password is sent as plain text, so this should only be used over TLS.
+pub struct BasicAuth<F> {
+    realm: String,
+    verify: F,
+}
+
+impl<F> BasicAuth<F> {
+    /// Create a [`BasicAuth`] for the realm, which is shown to the user when they are asked for
+    /// a password.
+    pub fn new(realm: &str, verify: F) -> Self {
+        Self {
+            realm: realm.to_string(),
+            verify,
+        }
+    }
+}
+
+impl<F> fmt::Debug for BasicAuth<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("realm", &self.realm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, U> Authenticator for BasicAuth<F>
+where
+    F: Fn(&str, &str) -> Option<U> + Send + Sync + 'static,
+{
+    type User = U;
+
+    fn authenticate(
+        &self,
+        _: &Request,
+        credentials: Option<&Credentials>,
+    ) -> Result<U, Vec<Challenge>> {
+        credentials
+            .and_then(|credentials| BasicCredentials::from_credentials(credentials).ok())
+            .and_then(|basic| (self.verify)(basic.user_id(), basic.password()))
+            .ok_or_else(|| vec![Challenge::basic(&self.realm)])
+    }
+}
+
+/// An [`Authenticator`] for the `Bearer` scheme, as defined in
+/// [RFC6750](https://datatracker.ietf.org/doc/html/rfc6750), which passes the access token to
+/// the function to find the user.
+///
+/// A token that the function does not accept is sent an "invalid_token" error in the
+/// challenge.
+pub struct BearerAuth<F> {
+    realm: String,
+    verify: F,
+}
+
+impl<F> BearerAuth<F> {
+    /// Create a [`BearerAuth`] for the realm.
+    pub fn new(realm: &str, verify: F) -> Self {
+        Self {
+            realm: realm.to_string(),
+            verify,
+        }
+    }
+}
+
+impl<F> fmt::Debug for BearerAuth<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BearerAuth")
+            .field("realm", &self.realm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, U> Authenticator for BearerAuth<F>
+where
+    F: Fn(&str) -> Option<U> + Send + Sync + 'static,
+{
+    type User = U;
+
+    fn authenticate(
+        &self,
+        _: &Request,
+        credentials: Option<&Credentials>,
+    ) -> Result<U, Vec<Challenge>> {
+        let Some(token) =
+            credentials.and_then(|credentials| BearerToken::from_credentials(credentials).ok())
+        else {
+            return Err(vec![Challenge::bearer(&self.realm)]);
+        };
+        (self.verify)(token.as_str()).ok_or_else(|| {
+            // RFC6750 Section 3.1
+            vec![Challenge::bearer(&self.realm)
+                .with_param("error", "invalid_token")
+                .with_param("error_description", "The access token is not valid")]
+        })
+    }
+}
+
+/// An [`Authenticator`] for the `Digest` scheme, as defined in
+/// [RFC7616](https://datatracker.ietf.org/doc/html/rfc7616), which passes the username to the
+/// function to find the password and is authenticated with the username.
+///
+/// The password is never sent, instead the client sends a hash of it with a nonce from the
+/// challenge, see [`DigestCredentials::verify`]. A nonce is the time it was made and an
+/// HMAC-SHA256 of that time with a random secret, so any nonce older than the
+/// [`DigestAuth::nonce_lifetime`] is stale and the client is asked to try again with a new
+/// one. The nonce count is not tracked, so a response could be replayed within the lifetime.
+///
+/// A challenge is sent for each algorithm, by default SHA-256 and then MD5 for older clients.
+pub struct DigestAuth<F> {
+    realm: String,
+    password: F,
+    algorithms: Vec<DigestAlgorithm>,
+    nonce_lifetime: Duration,
+    secret: [u8; 32],
+}
+
+impl<F> DigestAuth<F> {
+    /// Create a [`DigestAuth`] for the realm with a nonce lifetime of 5 minutes.
+    pub fn new(realm: &str, password: F) -> Self {
+        Self {
+            realm: realm.to_string(),
+            password,
+            algorithms: vec![DigestAlgorithm::Sha256, DigestAlgorithm::Md5],
+            nonce_lifetime: Duration::from_secs(5 * 60),
+            secret: utils::random_bytes(),
+        }
+    }
+
+    /// Set the algorithms that are accepted, in order of preference.
+    pub fn algorithms<I>(mut self, algorithms: I) -> Self
+    where
+        I: IntoIterator<Item = DigestAlgorithm>,
+    {
+        self.algorithms = algorithms.into_iter().collect();
+        self
+    }
+
+    /// Set how long a nonce can be used before it is stale.
+    pub fn nonce_lifetime(mut self, lifetime: Duration) -> Self {
+        self.nonce_lifetime = lifetime;
+        self
+    }
+
+    /// Returns the MAC of the time of a nonce, in hex digits.
+    fn mac(&self, secs: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts a key of any length");
+        mac.update(secs.as_bytes());
+        mac
+    }
+
+    fn nonce(&self, secs: u64) -> String {
+        let secs = format!("{secs:x}");
+        let mac = self.mac(&secs).finalize().into_bytes();
+        format!(
+            "{secs}-{}",
+            utils::base64::encode(&mac, utils::base64::Alphabet::UrlSafe)
+        )
+    }
+
+    /// Returns whether the nonce is fresh, or [`None`] if it was not made by this
+    /// authenticator.
+    fn check_nonce(&self, nonce: &str, now: u64) -> Option<bool> {
+        let (hex_secs, mac) = nonce.split_once('-')?;
+        let secs = u64::from_str_radix(hex_secs, 16).ok()?;
+        let mac = utils::base64::decode(mac.as_bytes(), utils::base64::Alphabet::UrlSafe)?;
+        // the MAC is compared in constant time, so it cannot be guessed one octet at a time
+        self.mac(hex_secs).verify_slice(&mac).ok()?;
+        Some(now.saturating_sub(secs) <= self.nonce_lifetime.as_secs())
+    }
+
+    fn challenges(&self, now: u64, stale: bool) -> Vec<Challenge> {
+        let nonce = self.nonce(now);
+        self.algorithms
+            .iter()
+            .map(|algorithm| {
+                let challenge = Challenge::new("Digest")
+                    .with_param("realm", &self.realm)
+                    .with_param("qop", "auth, auth-int")
+                    .with_param("algorithm", algorithm.as_str())
+                    .with_param("nonce", &nonce);
+                if stale {
+                    challenge.with_param("stale", "true")
+                } else {
+                    challenge
+                }
+            })
+            .collect()
+    }
+}
+
+impl<F> fmt::Debug for DigestAuth<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestAuth")
+            .field("realm", &self.realm)
+            .field("algorithms", &self.algorithms)
+            .field("nonce_lifetime", &self.nonce_lifetime)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F> Authenticator for DigestAuth<F>
+where
+    F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+{
+    type User = String;
+
+    fn authenticate(
+        &self,
+        request: &Request,
+        credentials: Option<&Credentials>,
+    ) -> Result<String, Vec<Challenge>> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let Some(digest) = credentials
+            .and_then(|credentials| DigestCredentials::from_credentials(credentials).ok())
+        else {
+            return Err(self.challenges(now, false));
+        };
+        // the username is never hashed as the challenges do not offer userhash
+        let is_for_request = digest.realm() == self.realm
+            && self.algorithms.contains(&digest.algorithm())
+            && !digest.userhash()
+            && Uri::from_bytes(digest.uri().as_bytes()).as_ref() == Ok(request.uri());
+        let fresh = match self.check_nonce(digest.nonce(), now) {
+            Some(fresh) if is_for_request => fresh,
+            _ => return Err(self.challenges(now, false)),
+        };
+        let verified = (self.password)(digest.username())
+            .is_some_and(|password| digest.verify(request.method(), &password, request.body()));
+        match (verified, fresh) {
+            (true, true) => Ok(digest.username().to_string()),
+            // RFC7616 Section 3.3: the password was right so only the nonce needs to change
+            (true, false) => Err(self.challenges(now, true)),
+            (false, _) => Err(self.challenges(now, false)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        http::{
+            test_request, BasicCredentials, Challenge, Credentials, Method, Request, Response,
+            StatusCode,
+        },
+        server::Handler,
+    };
+
+    use super::{Authenticate, BasicAuth, BearerAuth, DigestAuth};
+
+    fn get(fields: &[(&str, &str)]) -> Request {
+        test_request(Method::Get, "/private", fields, "")
+    }
+
+    fn challenges(response: &Response, name: &str) -> Vec<Challenge> {
+        response
+            .headers()
+            .get_all(name)
+            .flat_map(|value| Challenge::list_from_bytes(value.as_bytes()).unwrap())
+            .collect()
+    }
+
+    fn hello(_: &Request, user: String) -> Response {
+        Response::new(StatusCode::OK).with_body(user)
+    }
+
+    fn basic() -> BasicAuth<impl Fn(&str, &str) -> Option<String> + Send + Sync + 'static> {
+        BasicAuth::new("test", |user: &str, password: &str| {
+            (user == "ferris" && password == "crab").then(|| user.to_string())
+        })
+    }
+
+    #[test]
+    fn basic_credentials_are_verified() {
+        let handler = Authenticate::new(basic(), hello);
+        let authorization = Credentials::from(BasicCredentials::new("ferris", "crab")).to_string();
+        let response = handler.handle(&get(&[("Authorization", &authorization)]));
+        assert_eq!(StatusCode::OK, response.status());
+
+        for field in [&[][..], &[("Authorization", "Basic ZmVycmlzOmNyYWI9")]] {
+            let response = handler.handle(&get(field));
+            assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+            assert_eq!(
+                vec![Challenge::basic("test")],
+                challenges(&response, "www-authenticate")
+            );
+        }
+
+        let response = handler.handle(&get(&[("Authorization", "Basic, a=b")]));
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[test]
+    fn proxy_uses_proxy_fields() {
+        let handler = Authenticate::new(basic(), hello).proxy(true);
+        let authorization = Credentials::from(BasicCredentials::new("ferris", "crab")).to_string();
+        let response = handler.handle(&get(&[("Authorization", &authorization)]));
+        assert_eq!(StatusCode::PROXY_AUTHENTICATION_REQUIRED, response.status());
+        assert_eq!(1, challenges(&response, "proxy-authenticate").len());
+
+        let response = handler.handle(&get(&[("Proxy-Authorization", &authorization)]));
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[test]
+    fn either_authenticator_of_a_pair() {
+        let bearer = BearerAuth::new("test", |token: &str| {
+            (token == "secret").then(|| "robot".to_string())
+        });
+        let handler = Authenticate::new((basic(), bearer), hello);
+
+        let response = handler.handle(&get(&[("Authorization", "Bearer secret")]));
+        assert_eq!(StatusCode::OK, response.status());
+
+        let response = handler.handle(&get(&[("Authorization", "Bearer guess")]));
+        let challenges = challenges(&response, "www-authenticate");
+        assert_eq!(
+            vec!["Basic", "Bearer"],
+            challenges.iter().map(Challenge::scheme).collect::<Vec<_>>()
+        );
+        assert_eq!(Some("invalid_token"), challenges[1].param("error"));
+    }
+
+    fn digest_response(challenge: &Challenge, uri: &str, password: &str) -> String {
+        let h = |data: String| {
+            <sha2::Sha256 as sha2::Digest>::digest(data.as_bytes())
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<String>()
+        };
+        let nonce = challenge.param("nonce").unwrap();
+        let a1 = h(format!("ferris:test:{password}"));
+        let a2 = h(format!("GET:{uri}"));
+        let response = h(format!("{a1}:{nonce}:00000001:abc:auth:{a2}"));
+        Credentials::with_params(
+            "Digest",
+            [
+                ("username", "ferris"),
+                ("realm", "test"),
+                ("uri", uri),
+                ("algorithm", "SHA-256"),
+                ("nonce", nonce),
+                ("nc", "00000001"),
+                ("cnonce", "abc"),
+                ("qop", "auth"),
+                ("response", &response),
+            ],
+        )
+        .to_string()
+    }
+
+    #[test]
+    fn digest_response_is_verified_with_the_challenge_nonce() {
+        let digest = DigestAuth::new("test", |user: &str| {
+            (user == "ferris").then(|| "crab".to_string())
+        });
+        let handler = Authenticate::new(digest, hello);
+
+        let response = handler.handle(&get(&[]));
+        let challenges = challenges(&response, "www-authenticate");
+        assert_eq!(
+            vec![Some("SHA-256"), Some("MD5")],
+            challenges
+                .iter()
+                .map(|challenge| challenge.param("algorithm"))
+                .collect::<Vec<_>>()
+        );
+
+        let authorization = digest_response(&challenges[0], "/private", "crab");
+        let response = handler.handle(&get(&[("Authorization", &authorization)]));
+        assert_eq!(StatusCode::OK, response.status());
+
+        for authorization in [
+            digest_response(&challenges[0], "/private", "guess"),
+            digest_response(&challenges[0], "/other", "crab"),
+        ] {
+            let response = handler.handle(&get(&[("Authorization", &authorization)]));
+            assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        }
+    }
+
+    #[test]
+    fn old_nonce_is_stale() {
+        let digest = DigestAuth::new("test", |_: &str| Some("crab".to_string()));
+        let nonce = digest.nonce(0);
+        let challenge = Challenge::new("Digest").with_param("nonce", &nonce);
+        let handler = Authenticate::new(digest, hello);
+
+        let authorization = digest_response(&challenge, "/private", "crab");
+        let response = handler.handle(&get(&[("Authorization", &authorization)]));
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert!(challenges(&response, "www-authenticate")
+            .iter()
+            .all(|challenge| challenge.param("stale") == Some("true")));
+    }
+
+    #[test]
+    fn forged_nonce_is_not_accepted() {
+        let digest = DigestAuth::new("test", |_: &str| Some("crab".to_string()));
+        let now = 600;
+        let nonce = digest.nonce(now);
+        assert_eq!(Some(true), digest.check_nonce(&nonce, now));
+
+        let (secs, mac) = nonce.split_once('-').unwrap();
+        let other = DigestAuth::new("test", |_: &str| None::<String>).nonce(now);
+        for forged in [
+            other.as_str(),
+            &format!("0{secs}-{mac}"),
+            &format!("{:x}-{mac}", now + 1),
+            secs,
+        ] {
+            assert_eq!(None, digest.check_nonce(forged, now));
+        }
+    }
+}
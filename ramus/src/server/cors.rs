@@ -0,0 +1,412 @@
+use std::{fmt, time::Duration};
+
+use crate::http::{utils::abnf, Method, Origin, Request, Response, StatusCode};
+
+use super::Handler;
+
+/// A [`Handler`] that lets pages from other origins call the handler, using Cross-Origin
+/// Resource Sharing as defined in the [Fetch
+/// Standard](https://fetch.spec.whatwg.org/#http-cors-protocol).
+///
+/// The `Origin` of a request must be one of the allowed origins, see [`Cors::allow_origins`],
+/// otherwise the response has no CORS header fields and the browser does not let the page read
+/// it. No origin is allowed by default.
+///
+/// A preflight request, an `OPTIONS` request with an `Access-Control-Request-Method`, is
+/// answered without calling the handler: with a [`StatusCode::NO_CONTENT`] when the origin,
+/// method and every header field name in `Access-Control-Request-Headers` are allowed, and a
+/// [`StatusCode::FORBIDDEN`] otherwise. The methods "GET", "HEAD" and "POST" are allowed by
+/// default.
+///
+/// Every response that depends on the `Origin` has it in its `Vary`.
+///
+/// ```no_run
+/// use ramus::{
+///     http::{Method, Origin, Request, Response, StatusCode},
+///     server::{Cors, Server},
+/// };
+///
+/// let origin = Origin::from_bytes(b"https://app.example.com").expect("valid origin");
+/// Server::bind("127.0.0.1:8080")
+///     .expect("failed to bind")
+///     .serve(
+///         Cors::new(|_: &Request| Response::new(StatusCode::OK).with_body("{}"))
+///             .allow_origins([origin])
+///             .allow_methods([Method::Get, Method::Put, Method::Delete])
+///             .allow_headers(["Content-Type", "Authorization"])
+///             .allow_credentials(true),
+///     )
+///     .expect("failed to accept connections");
+/// ```
+#[derive(Clone)]
+pub struct Cors<H> {
+    handler: H,
+    origins: Option<Vec<Origin>>,
+    methods: Vec<Method>,
+    headers: Vec<String>,
+    expose_headers: Vec<String>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+
+impl<H> Cors<H> {
+    /// Create a [`Cors`] handler that does not allow any origin yet.
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler,
+            origins: Some(Vec::new()),
+            methods: vec![Method::Get, Method::Head, Method::Post],
+            headers: Vec::new(),
+            expose_headers: Vec::new(),
+            credentials: false,
+            max_age: None,
+        }
+    }
+
+    /// Allow requests from the origins, in addition to any that are already allowed.
+    pub fn allow_origins<I>(mut self, origins: I) -> Self
+    where
+        I: IntoIterator<Item = Origin>,
+    {
+        if let Some(allowed) = &mut self.origins {
+            allowed.extend(origins);
+        }
+        self
+    }
+
+    /// Allow requests from any origin, which is sent as "*".
+    ///
+    /// Credentials are never allowed with any origin, see [`Cors::allow_credentials`], as any
+    /// page could then read the responses to requests made with the cookies of the user.
+    pub fn allow_any_origin(mut self) -> Self {
+        self.origins = None;
+        self
+    }
+
+    /// Set the methods that are allowed in a preflight request.
+    pub fn allow_methods<I>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = Method>,
+    {
+        self.methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Set the names of the request header fields that are allowed in a preflight request,
+    /// which are compared without case.
+    pub fn allow_headers<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.headers = field_names(names);
+        self
+    }
+
+    /// Set the names of the response header fields that the page can read, other than those
+    /// that it can always read such as `Content-Type`.
+    pub fn expose_headers<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.expose_headers = field_names(names);
+        self
+    }
+
+    /// Set whether a request can include credentials, such as cookies or an `Authorization`,
+    /// and the page can read the response to it.
+    ///
+    /// Credentials are only allowed for the origins of [`Cors::allow_origins`], and are
+    /// ignored after [`Cors::allow_any_origin`].
+    pub fn allow_credentials(mut self, credentials: bool) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Set how long a browser can cache the result of a preflight request, which is sent in
+    /// whole seconds.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Returns the value of `Access-Control-Allow-Origin` for the origin, or [`None`] if it is
+    /// not allowed.
+    fn allow_origin(&self, origin: &str) -> Option<String> {
+        let Some(allowed) = &self.origins else {
+            return Some("*".to_string());
+        };
+        // a "null" origin is never allowed as any sandboxed page can send it
+        let origin = Origin::from_bytes(origin.as_bytes()).ok()?;
+        allowed.contains(&origin).then(|| origin.to_string())
+    }
+
+    /// Returns true if the response depends on the `Origin`, so it must be in its `Vary`.
+    fn varies(&self) -> bool {
+        self.origins.is_some()
+    }
+
+    fn with_allow_origin(&self, response: Response, allow_origin: String) -> Response {
+        let response = response.with_header("Access-Control-Allow-Origin", allow_origin);
+        if self.credentials && self.origins.is_some() {
+            response.with_header("Access-Control-Allow-Credentials", "true")
+        } else {
+            response
+        }
+    }
+
+    fn preflight(&self, request: &Request, allow_origin: Option<String>) -> Response {
+        let forbidden = Response::new(StatusCode::FORBIDDEN);
+        let Some(allow_origin) = allow_origin else {
+            return forbidden;
+        };
+        let method = request
+            .headers()
+            .get("access-control-request-method")
+            .and_then(|method| Method::from_bytes(method.as_bytes()).ok());
+        if !method.is_some_and(|method| self.methods.contains(&method)) {
+            return forbidden;
+        }
+        let headers_allowed = request
+            .headers()
+            .get_all("access-control-request-headers")
+            .flat_map(|value| abnf::split_unquoted(value.as_bytes(), b','))
+            .filter(|name| !name.is_empty())
+            .all(|name| {
+                self.headers
+                    .iter()
+                    .any(|allowed| allowed.as_bytes().eq_ignore_ascii_case(name))
+            });
+        if !headers_allowed {
+            return forbidden;
+        }
+
+        let methods: Vec<_> = self.methods.iter().map(Method::as_str).collect();
+        let mut response = Response::new(StatusCode::NO_CONTENT)
+            .with_header("Access-Control-Allow-Methods", methods.join(", "));
+        if !self.headers.is_empty() {
+            response =
+                response.with_header("Access-Control-Allow-Headers", self.headers.join(", "));
+        }
+        if let Some(max_age) = self.max_age {
+            response =
+                response.with_header("Access-Control-Max-Age", max_age.as_secs().to_string());
+        }
+        self.with_allow_origin(response, allow_origin)
+    }
+}
+
+impl<H> fmt::Debug for Cors<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cors")
+            .field("origins", &self.origins)
+            .field("methods", &self.methods)
+            .field("headers", &self.headers)
+            .field("expose_headers", &self.expose_headers)
+            .field("credentials", &self.credentials)
+            .field("max_age", &self.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+fn field_names<I, S>(names: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    names
+        .into_iter()
+        .map(Into::into)
+        .filter(|name| !name.is_empty() && name.bytes().all(abnf::is_tchar))
+        .collect()
+}
+
+impl<H: Handler> Handler for Cors<H> {
+    fn handle(&self, request: &Request) -> Response {
+        let Some(origin) = request.headers().get("origin") else {
+            return self.handler.handle(request);
+        };
+        let allow_origin = self.allow_origin(origin);
+
+        let is_preflight = request.method() == Method::Options
+            && request.headers().contains("access-control-request-method");
+        let response = if is_preflight {
+            self.preflight(request, allow_origin)
+                .with_vary("Access-Control-Request-Method")
+                .with_vary("Access-Control-Request-Headers")
+        } else {
+            let response = self.handler.handle(request);
+            match allow_origin {
+                Some(allow_origin) if self.expose_headers.is_empty() => {
+                    self.with_allow_origin(response, allow_origin)
+                }
+                Some(allow_origin) => self.with_allow_origin(response, allow_origin).with_header(
+                    "Access-Control-Expose-Headers",
+                    self.expose_headers.join(", "),
+                ),
+                None => response,
+            }
+        };
+        if self.varies() {
+            response.with_vary("Origin")
+        } else {
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        http::{test_request, Method, Origin, Request, Response, StatusCode},
+        server::Handler,
+    };
+
+    use super::Cors;
+
+    fn ok(_: &Request) -> Response {
+        Response::new(StatusCode::OK).with_header("X-Total", "1")
+    }
+
+    fn cors() -> Cors<fn(&Request) -> Response> {
+        Cors::new(ok as fn(&Request) -> Response)
+            .allow_origins([Origin::from_bytes(b"https://app.example.com").unwrap()])
+            .allow_methods([Method::Get, Method::Put])
+            .allow_headers(["Content-Type", "X-Request-Id"])
+            .expose_headers(["X-Total"])
+            .allow_credentials(true)
+            .max_age(Duration::from_secs(600))
+    }
+
+    #[test]
+    fn allowed_preflight_is_answered() {
+        let response = cors().handle(&test_request(
+            Method::Options,
+            "/api",
+            &[
+                ("Origin", "https://APP.example.com:443"),
+                ("Access-Control-Request-Method", "PUT"),
+                (
+                    "Access-Control-Request-Headers",
+                    "content-type,x-request-id",
+                ),
+            ],
+            "",
+        ));
+        let headers = response.headers();
+        assert_eq!(StatusCode::NO_CONTENT, response.status());
+        assert_eq!(
+            Some("https://app.example.com"),
+            headers.get("access-control-allow-origin")
+        );
+        assert_eq!(
+            Some("GET, PUT"),
+            headers.get("access-control-allow-methods")
+        );
+        assert_eq!(
+            Some("Content-Type, X-Request-Id"),
+            headers.get("access-control-allow-headers")
+        );
+        assert_eq!(
+            Some("true"),
+            headers.get("access-control-allow-credentials")
+        );
+        assert_eq!(Some("600"), headers.get("access-control-max-age"));
+        assert_eq!(
+            Some("Access-Control-Request-Method, Access-Control-Request-Headers, Origin"),
+            headers.get("vary")
+        );
+    }
+
+    #[test]
+    fn preflight_is_forbidden_unless_all_allowed() {
+        for fields in [
+            [
+                ("Origin", "https://evil.example.com"),
+                ("Access-Control-Request-Method", "PUT"),
+            ],
+            [
+                ("Origin", "https://app.example.com"),
+                ("Access-Control-Request-Method", "DELETE"),
+            ],
+            [
+                ("Origin", "https://app.example.com"),
+                ("Access-Control-Request-Headers", "x-secret"),
+            ],
+        ] {
+            let mut fields = fields.to_vec();
+            if !fields.iter().any(|(name, _)| name.ends_with("Method")) {
+                fields.push(("Access-Control-Request-Method", "GET"));
+            }
+            let response = cors().handle(&test_request(Method::Options, "/api", &fields, ""));
+            assert_eq!(StatusCode::FORBIDDEN, response.status());
+            assert_eq!(None, response.headers().get("access-control-allow-origin"));
+        }
+    }
+
+    #[test]
+    fn actual_request_is_only_shared_with_allowed_origin() {
+        let response = cors().handle(&test_request(
+            Method::Get,
+            "/api",
+            &[("Origin", "https://app.example.com")],
+            "",
+        ));
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("https://app.example.com"),
+            response.headers().get("access-control-allow-origin")
+        );
+        assert_eq!(
+            Some("X-Total"),
+            response.headers().get("access-control-expose-headers")
+        );
+        assert_eq!(Some("Origin"), response.headers().get("vary"));
+
+        let response = cors().handle(&test_request(
+            Method::Get,
+            "/api",
+            &[("Origin", "null")],
+            "",
+        ));
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(None, response.headers().get("access-control-allow-origin"));
+        assert_eq!(Some("Origin"), response.headers().get("vary"));
+    }
+
+    #[test]
+    fn any_origin_is_a_wildcard_that_never_allows_credentials() {
+        let cors = Cors::new(ok).allow_any_origin();
+        let response = cors.handle(&test_request(
+            Method::Get,
+            "/api",
+            &[("Origin", "http://localhost:3000")],
+            "",
+        ));
+        assert_eq!(
+            Some("*"),
+            response.headers().get("access-control-allow-origin")
+        );
+        assert_eq!(None, response.headers().get("vary"));
+
+        let cors = cors.allow_credentials(true);
+        let response = cors.handle(&test_request(
+            Method::Get,
+            "/api",
+            &[("Origin", "http://localhost:3000")],
+            "",
+        ));
+        assert_eq!(
+            Some("*"),
+            response.headers().get("access-control-allow-origin")
+        );
+        assert_eq!(
+            None,
+            response.headers().get("access-control-allow-credentials")
+        );
+        assert_eq!(None, response.headers().get("vary"));
+    }
+}
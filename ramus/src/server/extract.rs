@@ -0,0 +1,96 @@
+use std::{fmt, marker::PhantomData};
+
+use crate::http::{FromRequest, Request, Response};
+
+use super::Handler;
+
+/// A [`Handler`] that derives a value from the request with [`FromRequest`] and passes it to
+/// the function as well as the request.
+///
+/// When the value cannot be derived the function is not called and the
+/// [`Rejection`](FromRequest::Rejection), such as a [`StatusCode`], is sent instead. Several
+/// values can be taken at once as a tuple.
+///
+/// ```no_run
+/// use ramus::{
+///     http::{Multipart, Request, Response, StatusCode},
+///     server::{Extract, Server},
+/// };
+///
+/// Server::bind("127.0.0.1:8080")
+///     .expect("failed to bind")
+///     .serve(Extract::new(|_: &Request, mut form: Multipart| {
+///         let mut names = Vec::new();
+///         while let Some(part) = form.next_part()? {
+///             names.push(part.name().to_string());
+///         }
+///         Ok::<_, StatusCode>(Response::new(StatusCode::OK).with_body(names.join(", ")))
+///     }))
+///     .expect("failed to accept connections");
+/// ```
+///
+/// [`StatusCode`]: crate::http::StatusCode
+pub struct Extract<T, F> {
+    handler: F,
+    extractor: PhantomData<fn() -> T>,
+}
+
+impl<T, F> Extract<T, F> {
+    /// Create an [`Extract`] handler that calls the function with the derived value.
+    pub fn new(handler: F) -> Self {
+        Self {
+            handler,
+            extractor: PhantomData,
+        }
+    }
+}
+
+impl<T, F> fmt::Debug for Extract<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extract")
+            .field("extractor", &std::any::type_name::<T>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, F, R> Handler for Extract<T, F>
+where
+    T: FromRequest + 'static,
+    F: Fn(&Request, T) -> R + Send + Sync + 'static,
+    R: Into<Response>,
+{
+    fn handle(&self, request: &Request) -> Response {
+        match T::from_request(request) {
+            Ok(value) => (self.handler)(request, value).into(),
+            Err(rejection) => rejection.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        http::{test_request, Body, Method, Multipart, Request, Response, StatusCode},
+        server::Handler,
+    };
+
+    use super::Extract;
+
+    fn request(content_type: &str) -> Request {
+        let body = "--x\r\nContent-Disposition: form-data; name=a\r\n\r\n\r\n--x--";
+        test_request(Method::Post, "/", &[("Content-Type", content_type)], body)
+    }
+
+    #[test]
+    fn handler_is_only_called_with_extracted_value() {
+        let handler = Extract::new(|_: &Request, mut multipart: Multipart| {
+            let part = multipart.next_part()?.ok_or(StatusCode::BAD_REQUEST)?;
+            Ok::<_, StatusCode>(Response::new(StatusCode::OK).with_body(part.name()))
+        });
+        let response = handler.handle(&request("multipart/form-data; boundary=x"));
+        assert!(matches!(response.body(), Body::Bytes(body) if body == b"a"));
+
+        let response = handler.handle(&request("text/plain"));
+        assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, response.status());
+    }
+}
@@ -0,0 +1,557 @@
+//! A blocking HTTP/1.1 server that handles each connection on its own thread.
+//!
+//! HTTP/2 connections are also accepted, either with prior knowledge or by upgrading a HTTP/1.1
+//! request, see [`h2`].
+//!
+//! With the `tls` feature the connections can use TLS instead, see `TlsConfig`.
+//!
+//! The files in a directory can be served with the [`StaticFiles`] handler, and a handler can
+//! take values derived from the request, such as a parsed body, with [`Extract`]. A handler can
+//! keep values for each client between requests with [`Sessions`]. Requests without valid
+//! credentials are sent a challenge with [`Authenticate`], and pages from other origins can
+//! call a handler with [`Cors`].
+mod auth;
+mod cors;
+mod extract;
+mod session;
+mod static_files;
+mod stream;
+#[cfg(feature = "tls")]
+mod tls;
+
+#[cfg(feature = "tls")]
+pub use tls::TlsConfig;
+
+pub use auth::{Authenticate, Authenticator, BasicAuth, BearerAuth, DigestAuth};
+pub use cors::Cors;
+pub use extract::Extract;
+pub use session::{FileStore, MemoryStore, Session, SessionRecord, SessionStore, Sessions};
+pub use static_files::StaticFiles;
+pub(crate) use stream::Stream;
+
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    h2,
+    http::{Limits, Method, Request, Response, StatusCode, Version},
+};
+
+/// A type that can produce a [`Response`] for a [`Request`].
+///
+/// This is implemented for any function or closure that takes a reference to a [`Request`] and
+/// returns any type that can be converted into a [`Response`], such as a [`StatusCode`] or an
+/// [`EventStream`](crate::http::EventStream).
+pub trait Handler: Send + Sync + 'static {
+    /// Handle the request and return the response to send back to the client.
+    fn handle(&self, request: &Request) -> Response;
+}
+
+impl<F, R> Handler for F
+where
+    F: Fn(&Request) -> R + Send + Sync + 'static,
+    R: Into<Response>,
+{
+    fn handle(&self, request: &Request) -> Response {
+        self(request).into()
+    }
+}
+
+/// A HTTP server bound to a TCP socket.
+///
+/// ```no_run
+/// use ramus::{
+///     http::{Request, Response, StatusCode},
+///     server::Server,
+/// };
+///
+/// Server::bind("127.0.0.1:8080")
+///     .expect("failed to bind")
+///     .serve(|_: &Request| Response::new(StatusCode::OK).with_body("Hello, World!"))
+///     .expect("failed to accept connections");
+/// ```
+pub struct Server {
+    listener: TcpListener,
+    limits: Limits,
+    http_0_9: bool,
+    header_read_timeout: Duration,
+    body_read_timeout: Duration,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<rustls::ServerConfig>>,
+}
+
+impl fmt::Debug for Server {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Server");
+        debug
+            .field("listener", &self.listener)
+            .field("limits", &self.limits)
+            .field("http_0_9", &self.http_0_9)
+            .field("header_read_timeout", &self.header_read_timeout)
+            .field("body_read_timeout", &self.body_read_timeout);
+        #[cfg(feature = "tls")]
+        debug.field("tls", &self.tls.is_some());
+        debug.finish()
+    }
+}
+
+impl Server {
+    /// The default time allowed to read the `request-line` and header section of a request.
+    pub const HEADER_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// The default time allowed between each read of the message body of a request.
+    pub const BODY_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Create a [`Server`] that is bound to the given address.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            limits: Limits::default(),
+            http_0_9: false,
+            header_read_timeout: Self::HEADER_READ_TIMEOUT,
+            body_read_timeout: Self::BODY_READ_TIMEOUT,
+            #[cfg(feature = "tls")]
+            tls: None,
+        })
+    }
+
+    /// Set the [`Limits`] used when parsing requests.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set whether a HTTP/0.9 `Simple-Request` is accepted, which is a `request-line` without a
+    /// `HTTP-version` or header section and is answered with a `Simple-Response`.
+    ///
+    /// This is disabled by default as a `Simple-Request` is only sent by very old clients and
+    /// simple probes.
+    pub fn http_0_9(mut self, enabled: bool) -> Self {
+        self.http_0_9 = enabled;
+        self
+    }
+
+    /// Set the total time allowed to read the `request-line` and header section of a request.
+    ///
+    /// The timeout is for the whole of the head and not for each read, so a client that sends
+    /// the head slowly, a byte at a time, will still be sent a
+    /// [`StatusCode::REQUEST_TIMEOUT`](crate::http::StatusCode::REQUEST_TIMEOUT) once the
+    /// timeout has passed.
+    pub fn header_read_timeout(mut self, timeout: Duration) -> Self {
+        self.header_read_timeout = timeout;
+        self
+    }
+
+    /// Set the time allowed between each read of the message body of a request.
+    ///
+    /// This is also the time allowed between each read of a connection that has been upgraded
+    /// to another protocol, such as a [`WebSocket`](crate::ws::WebSocket), so a client that
+    /// stays idle for longer is disconnected.
+    pub fn body_read_timeout(mut self, timeout: Duration) -> Self {
+        self.body_read_timeout = timeout;
+        self
+    }
+
+    /// Accept only TLS connections, using the certificates of the [`TlsConfig`].
+    ///
+    /// The handshake must be completed within the
+    /// [`header_read_timeout`](Server::header_read_timeout), and the [`Request::scheme`] of
+    /// every request is "https".
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, config: TlsConfig) -> Self {
+        self.tls = Some(config.into_server_config());
+        self
+    }
+
+    /// Returns the local address that the server is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept connections and handle the requests on each using the given [`Handler`].
+    ///
+    /// This blocks the current thread and only returns if accepting a connection fails.
+    pub fn serve<H: Handler>(self, handler: H) -> io::Result<()> {
+        let server = Arc::new(self);
+        let handler = Arc::new(handler);
+        for stream in server.listener.incoming() {
+            let stream = stream?;
+            let server = Arc::clone(&server);
+            let handler = Arc::clone(&handler);
+            thread::spawn(move || {
+                // the client may go away at any time so there is nothing to do on an error
+                let _ = server.handle_connection(stream, handler.as_ref());
+            });
+        }
+        Ok(())
+    }
+
+    fn handle_connection<H: Handler>(&self, stream: TcpStream, handler: &H) -> io::Result<()> {
+        #[cfg(feature = "tls")]
+        if let Some(config) = &self.tls {
+            let stream =
+                tls::TlsStream::accept(stream, Arc::clone(config), self.header_read_timeout)?;
+            return self.serve_connection(&Stream::Tls(Box::new(stream)), handler);
+        }
+        self.serve_connection(&Stream::Tcp(stream), handler)
+    }
+
+    fn serve_connection<H: Handler>(&self, stream: &Stream, handler: &H) -> io::Result<()> {
+        let mut reader = BufReader::new(DeadlineReader {
+            stream,
+            deadline: None,
+        });
+
+        reader.get_mut().deadline = Some(Instant::now() + self.header_read_timeout);
+        let is_h2 = match stream {
+            // RFC9113 Section 3.3: a client with prior knowledge starts with the connection preface
+            Stream::Tcp(_) => matches!(reader.fill_buf(), Ok(buf) if h2::is_preface(buf)),
+            // RFC9113 Section 3.2: a TLS connection uses HTTP/2 when "h2" is selected with ALPN
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => stream.alpn_protocol().as_deref() == Some(b"h2"),
+        };
+        if is_h2 {
+            reader.get_mut().deadline = None;
+            h2::serve(
+                &mut reader,
+                stream,
+                handler,
+                &self.limits,
+                self.header_read_timeout,
+                None,
+            )?;
+            return linger(stream);
+        }
+
+        loop {
+            reader.get_mut().deadline = Some(Instant::now() + self.header_read_timeout);
+            let (response, method, version, keep_alive) =
+                match Request::read_head(&mut reader, &self.limits, self.http_0_9) {
+                    Ok(Some(mut request)) => {
+                        reader.get_mut().deadline = None;
+                        request.set_scheme(stream.scheme());
+                        stream
+                            .tcp()
+                            .set_read_timeout(Some(self.body_read_timeout))?;
+                        match request.read_body(&mut reader, &self.limits) {
+                            // RFC7540 Section 3.2: "h2c" is only used for cleartext TCP
+                            Ok(())
+                                if let Some(settings) = matches!(stream, Stream::Tcp(_))
+                                    .then(|| h2::upgrade_settings(&request))
+                                    .flatten() =>
+                            {
+                                Response::new(StatusCode::SWITCHING_PROTOCOLS)
+                                    .with_header("Connection", "Upgrade")
+                                    .with_header("Upgrade", "h2c")
+                                    .write_to(
+                                        &mut BufWriter::new(stream),
+                                        request.method(),
+                                        request.version(),
+                                    )?;
+                                h2::serve(
+                                    &mut reader,
+                                    stream,
+                                    handler,
+                                    &self.limits,
+                                    self.header_read_timeout,
+                                    Some(h2::Upgrade { request, settings }),
+                                )?;
+                                break;
+                            }
+                            Ok(()) => (
+                                handler
+                                    .handle(&request)
+                                    .with_compression(&request)
+                                    .with_conditions(&request)
+                                    .with_range(&request),
+                                request.method(),
+                                request.version(),
+                                request.keep_alive(),
+                            ),
+                            Err(status) => (
+                                Response::new(status),
+                                request.method(),
+                                request.version(),
+                                false,
+                            ),
+                        }
+                    }
+                    Ok(None) => return Ok(()),
+                    Err(status) => (Response::new(status), Method::Get, Version::HTTP_1_1, false),
+                };
+
+            // RFC7230 Section 6.7: the connection is used by the new protocol after the 101
+            let mut response = response.with_date();
+            if response.status() == StatusCode::SWITCHING_PROTOCOLS && version == Version::HTTP_1_1
+            {
+                if let Some(on_upgrade) = response.take_upgrade() {
+                    let mut writer = BufWriter::new(stream);
+                    response.write_to(&mut writer, method, version)?;
+                    // an idle connection must not hold its thread forever
+                    stream
+                        .tcp()
+                        .set_read_timeout(Some(self.body_read_timeout))?;
+                    on_upgrade.call(&mut reader, &mut writer);
+                    writer.flush()?;
+                    break;
+                }
+            }
+
+            let keep_alive =
+                keep_alive && !response.has_close_option() && !response.is_close_delimited(version);
+            let response = if !keep_alive {
+                response.with_header("Connection", "close")
+            } else if !version.is_persistent_by_default() {
+                response.with_header("Connection", "keep-alive")
+            } else {
+                response
+            };
+            response.write_to(&mut BufWriter::new(stream), method, version)?;
+
+            if !keep_alive {
+                break;
+            }
+        }
+
+        linger(stream)
+    }
+}
+
+/// Close the connection as defined in [RFC7230 Section
+/// 6.6](https://datatracker.ietf.org/doc/html/rfc7230#section-6.6).
+///
+/// The write side is closed first and any remaining data is read so that the client has a
+/// chance to read the response before the connection is reset.
+fn linger(stream: &Stream) -> io::Result<()> {
+    stream.shutdown_write()?;
+    let tcp = stream.tcp();
+    tcp.set_read_timeout(Some(Duration::from_secs(1)))?;
+    io::copy(&mut tcp.take(64 * 1024), &mut io::sink()).map(|_| ())
+}
+
+/// A reader over a [`Stream`] that fails with a [`io::ErrorKind::TimedOut`] error once the
+/// deadline has passed, no matter how many reads have happened before it.
+struct DeadlineReader<'a> {
+    stream: &'a Stream,
+    deadline: Option<Instant>,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(deadline) = self.deadline {
+            let remaining = deadline
+                .checked_duration_since(Instant::now())
+                .filter(|remaining| !remaining.is_zero())
+                .ok_or_else(|| io::Error::from(io::ErrorKind::TimedOut))?;
+            self.stream.tcp().set_read_timeout(Some(remaining))?;
+        }
+        self.stream.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{SocketAddr, TcpStream},
+        thread,
+        time::Duration,
+    };
+
+    use crate::http::{Limits, Request, Response, StatusCode};
+
+    use super::Server;
+
+    fn spawn(server: Server) -> SocketAddr {
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || {
+            server.serve(|request: &Request| {
+                Response::new(StatusCode::OK).with_body(request.body().to_vec())
+            })
+        });
+        addr
+    }
+
+    fn send(addr: SocketAddr, bytes: &[u8]) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(bytes).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn request_is_passed_to_handler() {
+        let addr = spawn(Server::bind("127.0.0.1:0").unwrap());
+        let response = send(
+            addr,
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\nConnection: close\r\n\r\nping",
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\nping"));
+        assert!(response.contains("\r\ndate: ") && response.contains(" GMT\r\n"));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn seekable_body_keeps_its_ranges_instead_of_being_compressed() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || {
+            server.serve(|request: &Request| {
+                let body = "<p>compress me</p>".repeat(64);
+                let response =
+                    Response::new(StatusCode::OK).with_header("Content-Type", "text/html");
+                if request.uri().path().as_str() == "/file" {
+                    let length = body.len() as u64;
+                    response.with_seekable_stream(std::io::Cursor::new(body), length)
+                } else {
+                    response.with_body(body)
+                }
+            })
+        });
+        let send = |bytes: &[u8]| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(bytes).unwrap();
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).unwrap();
+            String::from_utf8_lossy(&response).into_owned()
+        };
+
+        let response = send(
+            b"GET /file HTTP/1.1\r\nHost: a\r\nAccept-Encoding: gzip\r\nConnection: close\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("accept-ranges: bytes\r\n"));
+        assert!(!response.contains("content-encoding"));
+
+        let response = send(
+            b"GET /file HTTP/1.1\r\nHost: a\r\nAccept-Encoding: gzip\r\nRange: bytes=0-2\r\n\
+              Connection: close\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+        assert!(response.ends_with("\r\n\r\n<p>"));
+
+        let response = send(
+            b"GET / HTTP/1.1\r\nHost: a\r\nAccept-Encoding: gzip\r\nConnection: close\r\n\r\n",
+        );
+        assert!(response.contains("content-encoding: gzip\r\n"));
+        assert!(!response.contains("accept-ranges"));
+    }
+
+    #[test]
+    fn http_1_1_connection_persists_until_close_option() {
+        let addr = spawn(Server::bind("127.0.0.1:0").unwrap());
+        let response = send(
+            addr,
+            b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 1\r\n\r\n1\
+              POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 1\r\nConnection: close\r\n\r\n2",
+        );
+        assert_eq!(2, response.matches("HTTP/1.1 200 OK").count());
+        assert!(response.contains("connection: close\r\n"));
+    }
+
+    #[test]
+    fn http_1_0_connection_closes_by_default() {
+        let addr = spawn(Server::bind("127.0.0.1:0").unwrap());
+        let response = send(
+            addr,
+            b"POST / HTTP/1.0\r\nContent-Length: 1\r\n\r\n1\
+              POST / HTTP/1.0\r\nContent-Length: 1\r\n\r\n2",
+        );
+        assert_eq!(1, response.matches("HTTP/1.1 200 OK").count());
+        assert!(response.contains("connection: close\r\n"));
+    }
+
+    #[test]
+    fn http_1_0_connection_persists_with_keep_alive_option() {
+        let addr = spawn(Server::bind("127.0.0.1:0").unwrap());
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n")
+            .unwrap();
+        let mut buf = [0; 1024];
+        let read = stream.read(&mut buf).unwrap();
+        let response = String::from_utf8_lossy(&buf[..read]);
+        assert!(response.contains("connection: keep-alive\r\n"));
+
+        stream.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn http_1_0_stream_is_close_delimited() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || {
+            server.serve(|_: &Request| Response::new(StatusCode::OK).with_stream(&b"Hello"[..]))
+        });
+        let response = send(addr, b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n");
+        assert!(!response.contains("transfer-encoding"));
+        assert!(response.contains("connection: close\r\n"));
+        assert!(response.ends_with("\r\n\r\nHello"));
+    }
+
+    #[test]
+    fn simple_request_is_only_accepted_when_enabled() {
+        let addr = spawn(Server::bind("127.0.0.1:0").unwrap());
+        let response = send(addr, b"GET /\r\n");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+        let server = Server::bind("127.0.0.1:0").unwrap().http_0_9(true);
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || {
+            server.serve(|_: &Request| Response::new(StatusCode::OK).with_body("up"))
+        });
+        assert_eq!("up", send(addr, b"GET /\r\n"));
+    }
+
+    #[test]
+    fn unsupported_version_is_http_version_not_supported() {
+        let addr = spawn(Server::bind("127.0.0.1:0").unwrap());
+        let response = send(addr, b"GET / HTTP/2.0\r\nHost: a\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 505 HTTP Version Not Supported\r\n"));
+    }
+
+    #[test]
+    fn exceeded_limit_is_sent_as_response() {
+        let server = Server::bind("127.0.0.1:0").unwrap().limits(Limits {
+            body: 2,
+            ..Limits::default()
+        });
+        let addr = spawn(server);
+        let response = send(
+            addr,
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\nping",
+        );
+        assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    }
+
+    #[test]
+    fn slow_header_section_is_a_request_timeout() {
+        let server = Server::bind("127.0.0.1:0")
+            .unwrap()
+            .header_read_timeout(Duration::from_millis(200));
+        let addr = spawn(server);
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        // keep sending bytes so that no single read times out
+        for byte in b"GET / HTTP/1.1\r\nX-Slow: " {
+            if stream.write_all(&[*byte]).is_err() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+    }
+}
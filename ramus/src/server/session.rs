@@ -0,0 +1,729 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs, io,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::http::{utils, Request, Response, SameSite, SetCookie, StatusCode};
+
+use super::Handler;
+
+/// The values of a session and when it was created and last used, as saved in a
+/// [`SessionStore`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionRecord {
+    /// The values of the session by name.
+    pub values: BTreeMap<String, String>,
+    /// When the session was created, which is used for the [`Sessions::max_age`].
+    pub created: SystemTime,
+    /// When the session was last used, which is used for the [`Sessions::idle_timeout`].
+    pub accessed: SystemTime,
+}
+
+impl SessionRecord {
+    fn new(now: SystemTime) -> Self {
+        Self {
+            values: BTreeMap::new(),
+            created: now,
+            accessed: now,
+        }
+    }
+}
+
+/// A place to keep the [`SessionRecord`] of each session by its id, such as a [`MemoryStore`]
+/// or [`FileStore`].
+///
+/// A store is shared by every connection, so it must do its own locking. An id is always 64
+/// lowercase hex digits, which is safe to use as a file name.
+pub trait SessionStore: Send + Sync + 'static {
+    /// Returns the record of the session, or [`None`] if there is no session with the id.
+    fn load(&self, id: &str) -> io::Result<Option<SessionRecord>>;
+
+    /// Save the record of the session, replacing any record with the same id.
+    fn save(&self, id: &str, record: &SessionRecord) -> io::Result<()>;
+
+    /// Remove the record of the session, if there is one.
+    fn remove(&self, id: &str) -> io::Result<()>;
+
+    /// Remove the record of every session that the predicate says has expired.
+    fn remove_expired(&self, expired: &dyn Fn(&SessionRecord) -> bool) -> io::Result<()>;
+}
+
+impl<S: SessionStore> SessionStore for Arc<S> {
+    fn load(&self, id: &str) -> io::Result<Option<SessionRecord>> {
+        (**self).load(id)
+    }
+
+    fn save(&self, id: &str, record: &SessionRecord) -> io::Result<()> {
+        (**self).save(id, record)
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        (**self).remove(id)
+    }
+
+    fn remove_expired(&self, expired: &dyn Fn(&SessionRecord) -> bool) -> io::Result<()> {
+        (**self).remove_expired(expired)
+    }
+}
+
+/// A [`SessionStore`] that keeps the sessions in memory, so they are lost when the server
+/// stops.
+#[derive(Debug, Default)]
+pub struct MemoryStore(Mutex<HashMap<String, SessionRecord>>);
+
+impl MemoryStore {
+    /// Create an empty [`MemoryStore`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of sessions in the store.
+    pub fn len(&self) -> usize {
+        self.records().len()
+    }
+
+    /// Returns true if there are no sessions in the store.
+    pub fn is_empty(&self) -> bool {
+        self.records().is_empty()
+    }
+
+    fn records(&self) -> std::sync::MutexGuard<'_, HashMap<String, SessionRecord>> {
+        // a panic while the lock is held cannot leave a record half written
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> io::Result<Option<SessionRecord>> {
+        Ok(self.records().get(id).cloned())
+    }
+
+    fn save(&self, id: &str, record: &SessionRecord) -> io::Result<()> {
+        self.records().insert(id.to_string(), record.clone());
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        self.records().remove(id);
+        Ok(())
+    }
+
+    fn remove_expired(&self, expired: &dyn Fn(&SessionRecord) -> bool) -> io::Result<()> {
+        self.records().retain(|_, record| !expired(record));
+        Ok(())
+    }
+}
+
+/// A [`SessionStore`] that keeps each session in a file of its own in a directory, so they
+/// are kept when the server restarts.
+///
+/// A file has the times the session was created and last used, in seconds since the Unix
+/// epoch, on the first two lines and then the values as `application/x-www-form-urlencoded`
+/// pairs. A file is written in full and then renamed, so a record is never read half written.
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    /// Create a [`FileStore`] in the directory, creating the directory if it does not exist.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, id: &str) -> io::Result<PathBuf> {
+        if is_valid_id(id) {
+            Ok(self.dir.join(id))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid session id",
+            ))
+        }
+    }
+
+    fn read(path: &std::path::Path) -> io::Result<SessionRecord> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid session file");
+        let src = fs::read_to_string(path)?;
+        let mut lines = src.splitn(3, '\n');
+        let mut time = || {
+            lines
+                .next()
+                .and_then(|line| line.parse().ok())
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+                .ok_or_else(invalid)
+        };
+        let (created, accessed) = (time()?, time()?);
+        let values = utils::decode_pairs(lines.next().unwrap_or_default().as_bytes())
+            .map_err(|_| invalid())?
+            .into_iter()
+            .collect();
+        Ok(SessionRecord {
+            values,
+            created,
+            accessed,
+        })
+    }
+}
+
+impl SessionStore for FileStore {
+    fn load(&self, id: &str) -> io::Result<Option<SessionRecord>> {
+        match Self::read(&self.path(id)?) {
+            Ok(record) => Ok(Some(record)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn save(&self, id: &str, record: &SessionRecord) -> io::Result<()> {
+        let secs = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        };
+        let mut src = format!("{}\n{}\n", secs(record.created), secs(record.accessed));
+        for (i, (name, value)) in record.values.iter().enumerate() {
+            if i > 0 {
+                src.push('&');
+            }
+            src.push_str(&utils::percent_encode(name.as_bytes()));
+            src.push('=');
+            src.push_str(&utils::percent_encode(value.as_bytes()));
+        }
+
+        // each save has its own temporary file, so that saves of the same session at the same
+        // time do not write to the same file before it is renamed
+        let path = self.path(id)?;
+        let temp = self
+            .dir
+            .join(format!("{id}.{}.tmp", hex(&utils::random_bytes::<8>())));
+        let saved = fs::write(&temp, src).and_then(|()| fs::rename(&temp, path));
+        if saved.is_err() {
+            let _ = fs::remove_file(temp);
+        }
+        saved
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(id)?) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    fn remove_expired(&self, expired: &dyn Fn(&SessionRecord) -> bool) -> io::Result<()> {
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let is_session = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(is_valid_id);
+            // a file that cannot be read is left alone, it may be written at the same time
+            if is_session && Self::read(&path).is_ok_and(|record| expired(&record)) {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The session of a request, which is passed to the handler of [`Sessions`] to read and
+/// change its values.
+///
+/// A value is stored as a string and is parsed back into any type that implements [`FromStr`]
+/// when it is read, so the handler can keep the types that it needs.
+#[derive(Clone, Debug)]
+pub struct Session {
+    id: Option<String>,
+    record: SessionRecord,
+    changed: bool,
+    rotate: bool,
+    destroy: bool,
+}
+
+impl Session {
+    fn new(id: Option<String>, record: SessionRecord) -> Self {
+        Self {
+            id,
+            record,
+            changed: false,
+            rotate: false,
+            destroy: false,
+        }
+    }
+
+    /// Returns the id of the session, or [`None`] if it is a new session that has not been
+    /// saved yet.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns true if the session was created by this request.
+    pub fn is_new(&self) -> bool {
+        self.id.is_none()
+    }
+
+    /// Returns when the session was created.
+    pub fn created(&self) -> SystemTime {
+        self.record.created
+    }
+
+    /// Returns the value with the name parsed as the type, or [`None`] if there is no value
+    /// with the name or it is not a valid value of the type.
+    pub fn get<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.record.values.get(name)?.parse().ok()
+    }
+
+    /// Returns the value with the name as it is stored.
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.record.values.get(name).map(String::as_str)
+    }
+
+    /// Set the value with the name, replacing any value with the same name.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl ToString) {
+        self.record.values.insert(name.into(), value.to_string());
+        self.changed = true;
+    }
+
+    /// Remove the value with the name and return it.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let value = self.record.values.remove(name);
+        self.changed |= value.is_some();
+        value
+    }
+
+    /// Remove all of the values, but keep the session.
+    pub fn clear(&mut self) {
+        self.changed |= !self.record.values.is_empty();
+        self.record.values.clear();
+    }
+
+    /// Give the session a new id and keep its values, which should be done when the privilege
+    /// of the session changes, such as after a log in, so that an id that was known before
+    /// cannot be used to take over the session.
+    pub fn rotate(&mut self) {
+        self.rotate = true;
+    }
+
+    /// Remove the session from the store and the client, such as after a log out.
+    pub fn destroy(&mut self) {
+        self.destroy = true;
+    }
+}
+
+/// A [`Handler`] that keeps a [`Session`] for each client in a [`SessionStore`], using a
+/// cookie with the id of the session, and passes it to the function as well as the request.
+///
+/// A new session is only saved, and its cookie only sent, once it has a value, so a client
+/// that never has a value does not fill the store. A session expires when it is older than the
+/// [`Sessions::max_age`] or has not been used for the [`Sessions::idle_timeout`], and is then
+/// replaced by a new session. Expired sessions that are never used again are removed from the
+/// store once every idle timeout.
+///
+/// The cookie is `HttpOnly` with `SameSite=Lax` and a `Path` of "/", and is `Secure` when the
+/// request is "https". Every response has `Cookie` in its `Vary`, as it depends on the
+/// session.
+///
+/// ```no_run
+/// use ramus::{
+///     http::{Request, Response, StatusCode},
+///     server::{MemoryStore, Server, Session, Sessions},
+/// };
+///
+/// Server::bind("127.0.0.1:8080")
+///     .expect("failed to bind")
+///     .serve(Sessions::new(
+///         MemoryStore::new(),
+///         |_: &Request, session: &mut Session| {
+///             let visits = session.get::<u32>("visits").unwrap_or(0) + 1;
+///             session.insert("visits", visits);
+///             Response::new(StatusCode::OK).with_body(format!("{visits} visits"))
+///         },
+///     ))
+///     .expect("failed to accept connections");
+/// ```
+pub struct Sessions<S, F> {
+    store: S,
+    handler: F,
+    cookie_name: String,
+    max_age: Duration,
+    idle_timeout: Duration,
+    purged: Mutex<SystemTime>,
+}
+
+impl<S, F> Sessions<S, F> {
+    /// Create a [`Sessions`] handler with the store, a cookie named "session", a max age of a
+    /// day and an idle timeout of 30 minutes.
+    pub fn new(store: S, handler: F) -> Self {
+        Self {
+            store,
+            handler,
+            cookie_name: "session".to_string(),
+            max_age: Duration::from_secs(24 * 60 * 60),
+            idle_timeout: Duration::from_secs(30 * 60),
+            purged: Mutex::new(SystemTime::now()),
+        }
+    }
+
+    /// Set the name of the cookie with the id of the session.
+    pub fn cookie_name(mut self, name: &str) -> Self {
+        self.cookie_name = name.to_string();
+        self
+    }
+
+    /// Set how long after it was created that a session expires, which is also the `Max-Age`
+    /// of its cookie.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Set how long after it was last used that a session expires.
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    fn is_expired(&self, record: &SessionRecord, now: SystemTime) -> bool {
+        let elapsed = |time: SystemTime| now.duration_since(time).unwrap_or_default();
+        elapsed(record.created) > self.max_age || elapsed(record.accessed) > self.idle_timeout
+    }
+
+    fn cookie(&self, request: &Request, id: &str) -> SetCookie {
+        SetCookie::new(&self.cookie_name, id)
+            .with_path("/")
+            .with_max_age(self.max_age)
+            .with_secure(request.scheme().as_str() == "https")
+            .with_http_only(true)
+            .with_same_site(SameSite::Lax)
+    }
+}
+
+impl<S: SessionStore, F> Sessions<S, F> {
+    fn load(&self, id: Option<&str>, now: SystemTime) -> io::Result<Option<SessionRecord>> {
+        let Some(id) = id.filter(|id| is_valid_id(id)) else {
+            return Ok(None);
+        };
+        match self.store.load(id)? {
+            Some(record) if self.is_expired(&record, now) => {
+                self.store.remove(id)?;
+                Ok(None)
+            }
+            record => Ok(record),
+        }
+    }
+
+    fn save(&self, request: &Request, session: Session, now: SystemTime) -> io::Result<Saved> {
+        let Session {
+            id,
+            mut record,
+            changed,
+            rotate,
+            destroy,
+        } = session;
+        if destroy {
+            if let Some(id) = &id {
+                self.store.remove(id)?;
+            }
+            return Ok(Saved::Removed);
+        }
+        if id.is_none() && !changed {
+            return Ok(Saved::None);
+        }
+
+        record.accessed = now;
+        let (id, new_id) = match id {
+            Some(old) if rotate => {
+                self.store.remove(&old)?;
+                (new_id(), true)
+            }
+            Some(id) => (id, false),
+            None => (new_id(), true),
+        };
+        self.store.save(&id, &record)?;
+        Ok(if new_id {
+            Saved::Cookie(self.cookie(request, &id))
+        } else {
+            Saved::None
+        })
+    }
+
+    fn purge(&self, now: SystemTime) -> io::Result<()> {
+        {
+            let mut purged = self.purged.lock().unwrap_or_else(|e| e.into_inner());
+            if now.duration_since(*purged).unwrap_or_default() < self.idle_timeout {
+                return Ok(());
+            }
+            *purged = now;
+        }
+        self.store
+            .remove_expired(&|record| self.is_expired(record, now))
+    }
+}
+
+enum Saved {
+    None,
+    Cookie(SetCookie),
+    Removed,
+}
+
+impl<S, F> fmt::Debug for Sessions<S, F>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sessions")
+            .field("store", &self.store)
+            .field("cookie_name", &self.cookie_name)
+            .field("max_age", &self.max_age)
+            .field("idle_timeout", &self.idle_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, F, R> Handler for Sessions<S, F>
+where
+    S: SessionStore,
+    F: Fn(&Request, &mut Session) -> R + Send + Sync + 'static,
+    R: Into<Response>,
+{
+    fn handle(&self, request: &Request) -> Response {
+        let now = SystemTime::now();
+        let cookies = request.cookies();
+        let sent = cookies.get(&self.cookie_name);
+        let mut session = match self.load(sent, now) {
+            Ok(Some(record)) => Session::new(sent.map(str::to_string), record),
+            Ok(None) => Session::new(None, SessionRecord::new(now)),
+            Err(_) => return Response::new(StatusCode::INTERNAL_SERVER_ERROR),
+        };
+        let had_session = !session.is_new();
+
+        let response = (self.handler)(request, &mut session).into();
+        let response = match self.save(request, session, now) {
+            Ok(Saved::Cookie(cookie)) => response.with_cookie(&cookie),
+            // the client has an id of a session that has expired or does not exist
+            Ok(Saved::None) if sent.is_some() && !had_session => {
+                response.with_cookie(&SetCookie::removal(&self.cookie_name).with_path("/"))
+            }
+            Ok(Saved::Removed) if sent.is_some() => {
+                response.with_cookie(&SetCookie::removal(&self.cookie_name).with_path("/"))
+            }
+            Ok(_) => response,
+            Err(_) => Response::new(StatusCode::INTERNAL_SERVER_ERROR),
+        };
+        // expired sessions are only removed when they are used again, so the rest are purged
+        // now and then instead of filling the store
+        let _ = self.purge(now);
+        response.with_vary("Cookie")
+    }
+}
+
+/// A new session id of 256 bits from the random number generator of the operating system, as
+/// 64 lowercase hex digits.
+fn new_id() -> String {
+    hex(&utils::random_bytes::<32>())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn is_valid_id(id: &str) -> bool {
+    id.len() == 64 && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::{Duration, SystemTime},
+    };
+
+    use crate::{
+        http::{test_request, Body, Method, Request, Response, SetCookie, StatusCode},
+        server::Handler,
+    };
+
+    use super::{
+        is_valid_id, new_id, FileStore, MemoryStore, Session, SessionRecord, SessionStore, Sessions,
+    };
+
+    fn set_cookie(response: &Response) -> SetCookie {
+        let value = response.headers().get("set-cookie").expect("a Set-Cookie");
+        SetCookie::from_bytes(value.as_bytes()).unwrap()
+    }
+
+    fn cookie(response: &Response) -> String {
+        let cookie = set_cookie(response);
+        format!("{}={}", cookie.name(), cookie.value())
+    }
+
+    fn counter(_: &Request, session: &mut Session) -> Response {
+        let visits = session.get::<u32>("visits").unwrap_or(0) + 1;
+        session.insert("visits", visits);
+        Response::new(StatusCode::OK).with_body(visits.to_string())
+    }
+
+    #[test]
+    fn session_is_kept_between_requests() {
+        let store = Arc::new(MemoryStore::new());
+        let sessions = Sessions::new(store.clone(), counter);
+
+        let response = sessions.handle(&test_request(Method::Get, "/", &[], ""));
+        let cookie = cookie(&response);
+        assert!(set_cookie(&response).is_http_only());
+        assert_eq!(Some("Cookie"), response.headers().get("vary"));
+
+        let response = sessions.handle(&test_request(Method::Get, "/", &[("Cookie", &cookie)], ""));
+        assert!(matches!(response.body(), Body::Bytes(body) if body == b"2"));
+        assert_eq!(None, response.headers().get("set-cookie"));
+        assert_eq!(1, store.len());
+    }
+
+    #[test]
+    fn session_without_values_is_not_saved() {
+        let store = Arc::new(MemoryStore::new());
+        let sessions = Sessions::new(store.clone(), |_: &Request, _: &mut Session| StatusCode::OK);
+        let response = sessions.handle(&test_request(Method::Get, "/", &[], ""));
+        assert_eq!(None, response.headers().get("set-cookie"));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn rotate_replaces_the_id_and_destroy_removes_the_session() {
+        let store = Arc::new(MemoryStore::new());
+        let sessions = Sessions::new(store.clone(), |request: &Request, session: &mut Session| {
+            match request.cookies().get("action") {
+                Some("rotate") => session.rotate(),
+                Some("destroy") => session.destroy(),
+                _ => session.insert("user", "ferris"),
+            }
+            StatusCode::OK
+        });
+
+        let first = cookie(&sessions.handle(&test_request(Method::Get, "/", &[], "")));
+        let response = sessions.handle(&test_request(
+            Method::Get,
+            "/",
+            &[("Cookie", &format!("{first}; action=rotate"))],
+            "",
+        ));
+        let second = cookie(&response);
+        assert_ne!(first, second);
+        assert_eq!(1, store.len());
+
+        let response = sessions.handle(&test_request(
+            Method::Get,
+            "/",
+            &[("Cookie", &format!("{second}; action=destroy"))],
+            "",
+        ));
+        assert_eq!(Some(Duration::ZERO), set_cookie(&response).max_age());
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn expired_session_is_replaced() {
+        let store = Arc::new(MemoryStore::new());
+        let sessions = Sessions::new(store.clone(), counter).idle_timeout(Duration::from_secs(60));
+        let id = new_id();
+        let now = SystemTime::now();
+        let mut record = SessionRecord::new(now - Duration::from_secs(120));
+        record.values.insert("visits".to_string(), "5".to_string());
+        store.save(&id, &record).unwrap();
+
+        let response = sessions.handle(&test_request(
+            Method::Get,
+            "/",
+            &[("Cookie", &format!("session={id}"))],
+            "",
+        ));
+        assert!(matches!(response.body(), Body::Bytes(body) if body == b"1"));
+        assert_ne!(id, set_cookie(&response).value());
+        assert_eq!(None, store.load(&id).unwrap());
+    }
+
+    #[test]
+    fn unknown_id_is_removed_from_the_client() {
+        let sessions = Sessions::new(MemoryStore::new(), |_: &Request, _: &mut Session| {
+            StatusCode::OK
+        });
+        for id in [new_id(), "../../etc/passwd".to_string()] {
+            let response = sessions.handle(&test_request(
+                Method::Get,
+                "/",
+                &[("Cookie", &format!("session={id}"))],
+                "",
+            ));
+            assert_eq!(Some(Duration::ZERO), set_cookie(&response).max_age());
+        }
+    }
+
+    /// A directory for a [`FileStore`], which is removed when it is dropped.
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("ramus-{name}-{}", std::process::id())))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn file_store_round_trips_records() {
+        let dir = TempDir::new("sessions");
+        let store = FileStore::new(&dir.0).unwrap();
+        let id = new_id();
+        let mut record = SessionRecord::new(SystemTime::UNIX_EPOCH + Duration::from_secs(60));
+        record
+            .values
+            .insert("name".to_string(), "a b&c=d\n".to_string());
+        record.values.insert("user".to_string(), "1".to_string());
+
+        store.save(&id, &record).unwrap();
+        assert_eq!(Some(record), store.load(&id).unwrap());
+        store.remove_expired(&|_| true).unwrap();
+        assert_eq!(None, store.load(&id).unwrap());
+        assert!(store.load("../secret").is_err());
+    }
+
+    #[test]
+    fn file_store_saves_of_one_session_at_the_same_time_succeed() {
+        let dir = TempDir::new("concurrent-sessions");
+        let store = FileStore::new(&dir.0).unwrap();
+        let id = new_id();
+        let record = SessionRecord::new(SystemTime::UNIX_EPOCH);
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..20 {
+                        store.save(&id, &record).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(Some(record), store.load(&id).unwrap());
+        assert_eq!(1, std::fs::read_dir(&dir.0).unwrap().count());
+    }
+
+    #[test]
+    fn ids_are_unique_hex() {
+        let (a, b) = (new_id(), new_id());
+        assert!(is_valid_id(&a) && is_valid_id(&b));
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,409 @@
+use std::{
+    fs::{self, File, Metadata},
+    io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::http::{utils, EntityTag, Method, Request, Response, StatusCode, Validators};
+
+use super::Handler;
+
+/// A [`Handler`] that serves the files in a directory.
+///
+/// The path of the request URI is mapped to a file under the root directory:
+/// - The `Content-Type` is guessed from the file extension.
+/// - The `Last-Modified` and `ETag` header fields are sent from the file metadata.
+/// - The file is streamed as it is sent and is never read into memory, and a range of it can
+///   be requested, see [`Body::Seekable`](crate::http::Body::Seekable).
+/// - A directory is served by its first index file that exists, or a listing of the directory
+///   when [`StaticFiles::list_directories`] is enabled. A request for a directory without a
+///   trailing "/" is redirected to the path with one so that relative links work.
+///
+/// A path that tries to leave the root directory, with a ".." segment or through a symbolic
+/// link, is a [`StatusCode::FORBIDDEN`].
+///
+/// ```no_run
+/// use ramus::server::{Server, StaticFiles};
+///
+/// Server::bind("127.0.0.1:8080")
+///     .expect("failed to bind")
+///     .serve(StaticFiles::new("public").list_directories(true))
+///     .expect("failed to accept connections");
+/// ```
+#[derive(Clone, Debug)]
+pub struct StaticFiles {
+    root: PathBuf,
+    index_files: Vec<String>,
+    list_directories: bool,
+}
+
+impl StaticFiles {
+    /// Create a [`StaticFiles`] handler for the root directory, with "index.html" as the index
+    /// file and directory listings disabled.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            index_files: vec!["index.html".to_string()],
+            list_directories: false,
+        }
+    }
+
+    /// Set the names of the files that are served for a directory, in order of preference.
+    pub fn index_files<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.index_files = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set whether a directory without an index file is served as a HTML listing of its
+    /// entries, otherwise it is a [`StatusCode::FORBIDDEN`].
+    pub fn list_directories(mut self, list: bool) -> Self {
+        self.list_directories = list;
+        self
+    }
+
+    /// Returns the response for the request, which is useful to serve files from another
+    /// [`Handler`].
+    ///
+    /// Returns a [`StatusCode::METHOD_NOT_ALLOWED`] for a method other than `GET` or `HEAD`, and
+    /// a [`StatusCode::NOT_FOUND`] when there is no file for the path.
+    pub fn serve(&self, request: &Request) -> Response {
+        if !matches!(request.method(), Method::Get | Method::Head) {
+            return Response::new(StatusCode::METHOD_NOT_ALLOWED).with_header("Allow", "GET, HEAD");
+        }
+        match self.try_serve(request) {
+            Ok(response) => response,
+            Err(status) => Response::new(status),
+        }
+    }
+
+    fn try_serve(&self, request: &Request) -> Result<Response, StatusCode> {
+        let path = request.uri().path().as_str();
+        let root = self.root.canonicalize().map_err(io_error_status)?;
+        let file = root
+            .join(relative_path(path)?)
+            .canonicalize()
+            .map_err(io_error_status)?;
+        // a symbolic link can point outside of the root
+        if !file.starts_with(&root) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        let metadata = fs::metadata(&file).map_err(io_error_status)?;
+        if !metadata.is_dir() {
+            return serve_file(&file, &metadata);
+        }
+
+        if !path.ends_with('/') {
+            let mut location = format!("{path}/");
+            if !request.uri().query().as_str().is_empty() {
+                location = format!("{location}?{}", request.uri().query().as_str());
+            }
+            return Ok(
+                Response::new(StatusCode::MOVED_PERMANENTLY).with_header("Location", location)
+            );
+        }
+        for name in &self.index_files {
+            let index = file.join(name);
+            if let Ok(metadata) = fs::metadata(&index) {
+                if metadata.is_file() {
+                    return serve_file(&index, &metadata);
+                }
+            }
+        }
+        if self.list_directories {
+            return list_directory(&file, path, file != root);
+        }
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+impl Handler for StaticFiles {
+    fn handle(&self, request: &Request) -> Response {
+        self.serve(request)
+    }
+}
+
+/// Map the path of a request URI to a relative file system path, decoding each segment.
+///
+/// Returns a [`StatusCode::FORBIDDEN`] for a "." or ".." segment, or a segment that decodes to
+/// a path separator, as these could leave the root directory.
+fn relative_path(path: &str) -> Result<PathBuf, StatusCode> {
+    let mut relative = PathBuf::new();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        let segment = utils::percent_decode(segment.as_bytes())
+            .and_then(|segment| String::from_utf8(segment).ok())
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        if segment == "."
+            || segment == ".."
+            || segment.contains(['/', '\\', '\0'])
+            // a segment such as "C:" is a prefix on Windows
+            || cfg!(windows) && segment.contains(':')
+        {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        relative.push(segment);
+    }
+    Ok(relative)
+}
+
+fn io_error_status(error: io::Error) -> StatusCode {
+    match error.kind() {
+        io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn serve_file(path: &Path, metadata: &Metadata) -> Result<Response, StatusCode> {
+    let file = File::open(path).map_err(io_error_status)?;
+    let mut validators = Validators::new();
+    if let Ok(modified) = metadata.modified() {
+        let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let tag = format!("{:x}-{:x}", since_epoch.as_nanos(), metadata.len());
+        validators = validators
+            .with_etag(EntityTag::strong(tag))
+            .with_last_modified(modified);
+    }
+    Ok(Response::new(StatusCode::OK)
+        .with_header("Content-Type", content_type(path))
+        .with_validators(&validators)
+        .with_seekable_stream(file, metadata.len()))
+}
+
+/// Guess the media type of a file from its extension, a file without a known extension is
+/// "application/octet-stream".
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("md") => "text/markdown; charset=utf-8",
+        Some("csv") => "text/csv; charset=utf-8",
+        Some("xml") => "application/xml",
+        Some("json") => "application/json",
+        Some("wasm") => "application/wasm",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("gz") => "application/gzip",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        Some("otf") => "font/otf",
+        Some("mp3") => "audio/mpeg",
+        Some("ogg") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A HTML page that links to each entry of the directory, with a trailing "/" for directories.
+fn list_directory(dir: &Path, path: &str, has_parent: bool) -> Result<Response, StatusCode> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir).map_err(io_error_status)? {
+        let entry = entry.map_err(io_error_status)?;
+        let mut name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            name.push('/');
+        }
+        entries.push(name);
+    }
+    entries.sort();
+
+    let title = html_escape(&String::from_utf8_lossy(
+        &utils::percent_decode(path.as_bytes()).unwrap_or_default(),
+    ));
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {title}</title></head>\n\
+         <body>\n<h1>Index of {title}</h1>\n<ul>\n"
+    );
+    if has_parent {
+        html.push_str("<li><a href=\"../\">../</a></li>\n");
+    }
+    for name in entries {
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            percent_encode(&name),
+            html_escape(&name)
+        ));
+    }
+    html.push_str("</ul>\n</body>\n</html>\n");
+
+    Ok(Response::new(StatusCode::OK)
+        .with_header("Content-Type", "text/html; charset=utf-8")
+        .with_body(html))
+}
+
+/// Encode a name as a relative path segment, keeping a trailing "/".
+fn percent_encode(name: &str) -> String {
+    match name.strip_suffix('/') {
+        Some(name) => utils::percent_encode(name.as_bytes()) + "/",
+        None => utils::percent_encode(name.as_bytes()),
+    }
+}
+
+fn html_escape(src: &str) -> String {
+    let mut escaped = String::with_capacity(src.len());
+    for c in src.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::Read,
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use crate::http::{test_request, Body, Method, Response, StatusCode};
+
+    use super::StaticFiles;
+
+    /// A directory in the system temp directory that is removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        /// Returns the directory that is served, which has a "secret.txt" file next to it.
+        fn root(&self) -> PathBuf {
+            self.0.join("root")
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Create a directory with the files, which are created with any parent directories.
+    fn root(files: &[(&str, &str)]) -> TempDir {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::SeqCst);
+        let dir = TempDir(
+            std::env::temp_dir().join(format!("ramus-static-{}-{count}", std::process::id())),
+        );
+        fs::create_dir_all(dir.root()).unwrap();
+        for (name, contents) in files {
+            let path = dir.root().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        fs::write(dir.0.join("secret.txt"), "secret").unwrap();
+        dir
+    }
+
+    fn get(files: &StaticFiles, target: &str) -> Response {
+        files.serve(&test_request(Method::Get, target, &[], ""))
+    }
+
+    fn body(response: Response) -> String {
+        let mut body = String::new();
+        match response.into_body() {
+            Body::Bytes(bytes) => body = String::from_utf8(bytes).unwrap(),
+            Body::Seekable(mut stream, _) => {
+                stream.read_to_string(&mut body).unwrap();
+            }
+            other => panic!("unexpected body {other:?}"),
+        }
+        body
+    }
+
+    #[test]
+    fn file_is_served_with_metadata() {
+        let dir = root(&[("css/site.css", "body {}")]);
+        let files = StaticFiles::new(dir.root());
+        let response = get(&files, "/css/site.css");
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            Some("text/css; charset=utf-8"),
+            response.headers().get("content-type")
+        );
+        assert!(response
+            .headers()
+            .get("last-modified")
+            .unwrap()
+            .ends_with(" GMT"));
+        assert!(response.headers().get("etag").unwrap().starts_with('"'));
+        assert!(matches!(response.body(), Body::Seekable(_, 7)));
+        assert_eq!("body {}", body(response));
+    }
+
+    #[test]
+    fn index_file_is_served_for_directory() {
+        let dir = root(&[("docs/index.html", "<p>docs</p>")]);
+        let files = StaticFiles::new(dir.root());
+        assert_eq!("<p>docs</p>", body(get(&files, "/docs/")));
+
+        let response = get(&files, "/docs?page=1");
+        assert_eq!(StatusCode::MOVED_PERMANENTLY, response.status());
+        assert_eq!(Some("/docs/?page=1"), response.headers().get("location"));
+    }
+
+    #[test]
+    fn directory_is_listed_when_enabled() {
+        let dir = root(&[("a b.txt", "a"), ("sub/c.txt", "c"), ("<x>.txt", "x")]);
+        assert_eq!(
+            StatusCode::FORBIDDEN,
+            get(&StaticFiles::new(dir.root()), "/").status()
+        );
+
+        let listing = body(get(
+            &StaticFiles::new(dir.root()).list_directories(true),
+            "/",
+        ));
+        assert!(listing.contains("<a href=\"a%20b.txt\">a b.txt</a>"));
+        assert!(listing.contains("<a href=\"sub/\">sub/</a>"));
+        assert!(listing.contains("<a href=\"%3Cx%3E.txt\">&lt;x&gt;.txt</a>"));
+        assert!(!listing.contains("../"));
+    }
+
+    #[test]
+    fn path_traversal_is_forbidden() {
+        let dir = root(&[("public.txt", "public")]);
+        let files = StaticFiles::new(dir.root());
+        assert_eq!("public", body(get(&files, "/public.txt")));
+        for target in ["/../secret.txt", "/%2E%2E/secret.txt", "/..%2Fsecret.txt"] {
+            assert_eq!(StatusCode::FORBIDDEN, get(&files, target).status());
+        }
+    }
+
+    #[test]
+    fn missing_file_and_other_methods_are_rejected() {
+        let dir = root(&[]);
+        let files = StaticFiles::new(dir.root());
+        assert_eq!(StatusCode::NOT_FOUND, get(&files, "/missing").status());
+
+        let response = files.serve(&test_request(Method::Post, "/", &[], ""));
+        assert_eq!(StatusCode::METHOD_NOT_ALLOWED, response.status());
+        assert_eq!(Some("GET, HEAD"), response.headers().get("allow"));
+    }
+}
@@ -0,0 +1,80 @@
+//! Crate Module:
+//! The transport of a connection, which is a plain [`TcpStream`] or a TLS session over one when
+//! the `tls` feature is enabled.
+//!
+//! Both [`Read`] and [`Write`] are implemented for a shared reference, so that a HTTP/2
+//! connection can read frames on one thread while the responses are written on others.
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, TcpStream},
+};
+
+use crate::http::Scheme;
+
+#[cfg(feature = "tls")]
+use super::tls::TlsStream;
+
+/// A connection accepted by the [`Server`](super::Server).
+pub(crate) enum Stream {
+    Tcp(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<TlsStream>),
+}
+
+impl Stream {
+    /// Returns the underlying [`TcpStream`], which is used to set the read and write timeouts.
+    pub(crate) fn tcp(&self) -> &TcpStream {
+        match self {
+            Self::Tcp(stream) => stream,
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => stream.tcp(),
+        }
+    }
+
+    /// Returns the [`Scheme`] of the requests received on this connection.
+    pub(crate) fn scheme(&self) -> Scheme {
+        match self {
+            Self::Tcp(_) => Scheme::http(),
+            #[cfg(feature = "tls")]
+            Self::Tls(_) => Scheme::https(),
+        }
+    }
+
+    /// Close the write side of the connection, a TLS session sends the close_notify alert
+    /// first.
+    pub(crate) fn shutdown_write(&self) -> io::Result<()> {
+        #[cfg(feature = "tls")]
+        if let Self::Tls(stream) = self {
+            stream.close_notify()?;
+        }
+        self.tcp().shutdown(Shutdown::Write)
+    }
+}
+
+impl Read for &Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => (&*stream).read(buf),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => (&**stream).read(buf),
+        }
+    }
+}
+
+impl Write for &Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => (&*stream).write(buf),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => (&**stream).write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => (&*stream).flush(),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => (&**stream).flush(),
+        }
+    }
+}
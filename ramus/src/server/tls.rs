@@ -0,0 +1,474 @@
+//! Crate Module:
+//! TLS for the [`Server`](super::Server) using [rustls](https://docs.rs/rustls), as required by
+//! the "https" URI scheme defined in [RFC7230 Section
+//! 2.7.2](https://datatracker.ietf.org/doc/html/rfc7230#section-2.7.2).
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, Read, Write},
+    net::TcpStream,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use rustls::{
+    crypto::ring::{default_provider, sign::any_supported_type},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig, ServerConnection,
+};
+
+/// The protocols offered with ALPN as defined in [RFC7301](https://datatracker.ietf.org/doc/html/rfc7301),
+/// in order of preference.
+const ALPN_PROTOCOLS: [&[u8]; 2] = [b"h2", b"http/1.1"];
+
+/// The largest TLS record that can be received, see [RFC8446 Section
+/// 5.2](https://datatracker.ietf.org/doc/html/rfc8446#section-5.2).
+const MAX_RECORD_SIZE: usize = 5 + (1 << 14) + 256;
+
+/// The certificates used for TLS connections to the [`Server`](super::Server).
+///
+/// The default certificate is used unless the client asks for a server name, with the Server
+/// Name Indication extension defined in [RFC6066 Section
+/// 3](https://datatracker.ietf.org/doc/html/rfc6066#section-3), that has its own certificate.
+///
+/// Both "h2" and "http/1.1" are offered with ALPN, so a client can use HTTP/2 without the
+/// connection preface being sent first in the clear.
+///
+/// ```no_run
+/// use ramus::server::{Server, TlsConfig};
+///
+/// let tls = TlsConfig::from_pem_files("cert.pem", "key.pem")
+///     .and_then(|tls| tls.with_server_name("example.com", "example.pem", "example.key"))
+///     .expect("failed to load the certificates");
+/// let server = Server::bind("127.0.0.1:8443")
+///     .expect("failed to bind")
+///     .tls(tls);
+/// ```
+#[derive(Debug)]
+pub struct TlsConfig {
+    resolver: CertResolver,
+}
+
+impl TlsConfig {
+    /// Create a [`TlsConfig`] with the default certificate chain and private key loaded from
+    /// PEM files.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if either file cannot be read, or an error of the kind
+    /// [`io::ErrorKind::InvalidData`] if there are no certificates, no private key, or the
+    /// private key does not match the first certificate.
+    pub fn from_pem_files<C, K>(cert: C, key: K) -> io::Result<Self>
+    where
+        C: AsRef<Path>,
+        K: AsRef<Path>,
+    {
+        Ok(Self {
+            resolver: CertResolver {
+                default: Some(load_certified_key(cert.as_ref(), key.as_ref())?),
+                names: HashMap::new(),
+            },
+        })
+    }
+
+    /// Create a [`TlsConfig`] without a default certificate, so a client must ask for a server
+    /// name that has been added with [`TlsConfig::with_server_name`].
+    pub fn new() -> Self {
+        Self {
+            resolver: CertResolver {
+                default: None,
+                names: HashMap::new(),
+            },
+        }
+    }
+
+    /// Use the certificate chain and private key loaded from PEM files for a client that asks
+    /// for the server name.
+    ///
+    /// Server names are compared ignoring case, as defined for DNS names in [RFC4343 Section
+    /// 3](https://datatracker.ietf.org/doc/html/rfc4343#section-3).
+    ///
+    /// # Errors
+    /// The same as [`TlsConfig::from_pem_files`].
+    pub fn with_server_name<C, K>(mut self, name: &str, cert: C, key: K) -> io::Result<Self>
+    where
+        C: AsRef<Path>,
+        K: AsRef<Path>,
+    {
+        let key = load_certified_key(cert.as_ref(), key.as_ref())?;
+        self.resolver.names.insert(name.to_ascii_lowercase(), key);
+        Ok(self)
+    }
+
+    pub(crate) fn into_server_config(self) -> Arc<ServerConfig> {
+        let mut config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .expect("the ring provider supports the default protocol versions")
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::new(self.resolver));
+        config.alpn_protocols = ALPN_PROTOCOLS.iter().map(|p| p.to_vec()).collect();
+        Arc::new(config)
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Selects the certificate by the server name that the client asks for.
+#[derive(Debug)]
+struct CertResolver {
+    default: Option<Arc<CertifiedKey>>,
+    names: HashMap<String, Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        client_hello
+            .server_name()
+            .and_then(|name| self.names.get(&name.to_ascii_lowercase()))
+            .or(self.default.as_ref())
+            .cloned()
+    }
+}
+
+fn load_certified_key(cert: &Path, key: &Path) -> io::Result<Arc<CertifiedKey>> {
+    let invalid = |error| io::Error::new(io::ErrorKind::InvalidData, error);
+
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert)?))
+        .collect::<io::Result<Vec<_>>>()?;
+    if certs.is_empty() {
+        return Err(invalid(format!("no certificates in {}", cert.display())));
+    }
+    let key = rustls_pemfile::private_key(&mut BufReader::new(File::open(key)?))?
+        .ok_or_else(|| invalid(format!("no private key in {}", key.display())))?;
+    let key = any_supported_type(&key).map_err(|error| invalid(error.to_string()))?;
+
+    let certified_key = CertifiedKey::new(certs, key);
+    certified_key
+        .keys_match()
+        .map_err(|error| invalid(error.to_string()))?;
+    Ok(Arc::new(certified_key))
+}
+
+/// A TLS session over a [`TcpStream`].
+///
+/// The session is behind a lock that is never held while waiting to read from the
+/// [`TcpStream`], so one thread can wait for the next record while others write.
+pub(crate) struct TlsStream {
+    tcp: TcpStream,
+    session: Mutex<Session>,
+}
+
+struct Session {
+    connection: ServerConnection,
+    /// The bytes read from the [`TcpStream`] that have not been given to the connection yet.
+    received: Vec<u8>,
+    /// True once the client has closed its side of the [`TcpStream`].
+    eof: bool,
+}
+
+impl TlsStream {
+    /// Complete the handshake with the client, which must happen before the timeout.
+    pub(crate) fn accept(
+        tcp: TcpStream,
+        config: Arc<ServerConfig>,
+        timeout: Duration,
+    ) -> io::Result<Self> {
+        let connection = ServerConnection::new(config).map_err(io::Error::other)?;
+        let stream = Self {
+            tcp,
+            session: Mutex::new(Session {
+                connection,
+                received: Vec::new(),
+                eof: false,
+            }),
+        };
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut session = stream.lock();
+            if !session.connection.is_handshaking() {
+                break;
+            }
+            if session.eof {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            if !session.received.is_empty() {
+                stream.process(&mut session)?;
+                continue;
+            }
+            drop(session);
+
+            let remaining = deadline
+                .checked_duration_since(Instant::now())
+                .filter(|remaining| !remaining.is_zero())
+                .ok_or_else(|| io::Error::from(io::ErrorKind::TimedOut))?;
+            stream.tcp.set_read_timeout(Some(remaining))?;
+            stream.receive()?;
+        }
+        Ok(stream)
+    }
+
+    pub(crate) fn tcp(&self) -> &TcpStream {
+        &self.tcp
+    }
+
+    /// Returns the protocol selected with ALPN, if any.
+    pub(crate) fn alpn_protocol(&self) -> Option<Vec<u8>> {
+        self.lock().connection.alpn_protocol().map(<[u8]>::to_vec)
+    }
+
+    /// Send the close_notify alert to the client.
+    pub(crate) fn close_notify(&self) -> io::Result<()> {
+        let mut session = self.lock();
+        session.connection.send_close_notify();
+        self.write_tls(&mut session.connection)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Session> {
+        // a thread that panics while holding the lock cannot leave the session invalid
+        self.session.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wait for the next bytes from the [`TcpStream`], without holding the lock.
+    fn receive(&self) -> io::Result<()> {
+        let mut buf = [0; MAX_RECORD_SIZE];
+        let read = (&self.tcp).read(&mut buf)?;
+        let mut session = self.lock();
+        if read == 0 {
+            session.eof = true;
+        } else {
+            session.received.extend_from_slice(&buf[..read]);
+        }
+        Ok(())
+    }
+
+    /// Process the received records until there is plaintext to read, any handshake messages
+    /// or alerts are sent straight away.
+    ///
+    /// Stopping once there is plaintext keeps the connection from buffering more plaintext
+    /// than it allows.
+    fn process(&self, session: &mut Session) -> io::Result<()> {
+        loop {
+            let used = session.connection.read_tls(&mut &session.received[..])?;
+            session.received.drain(..used);
+            let state = session.connection.process_new_packets();
+            self.write_tls(&mut session.connection)?;
+            let state = state.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            if state.plaintext_bytes_to_read() > 0 || session.received.is_empty() || used == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn write_tls(&self, connection: &mut ServerConnection) -> io::Result<()> {
+        while connection.wants_write() {
+            connection.write_tls(&mut &self.tcp)?;
+        }
+        Ok(())
+    }
+}
+
+impl Read for &TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            {
+                let mut session = self.lock();
+                match session.connection.reader().read(buf) {
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => {}
+                    result => return result,
+                }
+                // an empty slice tells the connection that the client has closed the stream
+                if !session.received.is_empty() || session.eof {
+                    self.process(&mut session)?;
+                    continue;
+                }
+            }
+            self.receive()?;
+        }
+    }
+}
+
+impl Write for &TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let connection = &mut self.lock().connection;
+        loop {
+            let written = connection.writer().write(buf)?;
+            self.write_tls(connection)?;
+            if written > 0 || buf.is_empty() {
+                return Ok(written);
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let connection = &mut self.lock().connection;
+        connection.writer().flush()?;
+        self.write_tls(connection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{self, Read, Write},
+        net::{SocketAddr, TcpStream},
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    use rustls::{
+        crypto::ring::default_provider,
+        pki_types::{CertificateDer, ServerName},
+        ClientConfig, ClientConnection, RootCertStore, StreamOwned,
+    };
+
+    use crate::{
+        h2::PREFACE,
+        http::{Request, Response, StatusCode},
+        server::Server,
+    };
+
+    use super::TlsConfig;
+
+    /// A self-signed certificate, whose files are removed when dropped.
+    struct Certificate {
+        dir: PathBuf,
+        cert: PathBuf,
+        key: PathBuf,
+        der: CertificateDer<'static>,
+    }
+
+    impl Drop for Certificate {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn certificate(name: &str) -> Certificate {
+        let generated = rcgen::generate_simple_self_signed(vec![name.to_string()]).unwrap();
+        // each test writes its own files as the tests run in parallel
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("ramus-tls-{}-{count}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (cert, key) = (dir.join("cert.pem"), dir.join("key.pem"));
+        fs::write(&cert, generated.cert.pem()).unwrap();
+        fs::write(&key, generated.key_pair.serialize_pem()).unwrap();
+        Certificate {
+            dir,
+            cert,
+            key,
+            der: generated.cert.der().clone(),
+        }
+    }
+
+    fn spawn(config: TlsConfig) -> SocketAddr {
+        let server = Server::bind("127.0.0.1:0").unwrap().tls(config);
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || {
+            server.serve(|request: &Request| {
+                let body = format!(
+                    "{} {}",
+                    request.scheme().as_str(),
+                    request.effective_uri().scheme().as_str()
+                );
+                Response::new(StatusCode::OK).with_body(body)
+            })
+        });
+        addr
+    }
+
+    fn connect(
+        addr: SocketAddr,
+        root: &CertificateDer<'static>,
+        name: &str,
+        alpn: &[&[u8]],
+    ) -> StreamOwned<ClientConnection, TcpStream> {
+        let mut roots = RootCertStore::empty();
+        roots.add(root.clone()).unwrap();
+        let mut config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+        let name = ServerName::try_from(name.to_string()).unwrap();
+        let connection = ClientConnection::new(Arc::new(config), name).unwrap();
+        StreamOwned::new(connection, TcpStream::connect(addr).unwrap())
+    }
+
+    fn get(stream: &mut StreamOwned<ClientConnection, TcpStream>) -> io::Result<String> {
+        stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    }
+
+    #[test]
+    fn https_scheme_is_used_for_effective_request_uri() {
+        let localhost = certificate("localhost");
+        let addr = spawn(TlsConfig::from_pem_files(&localhost.cert, &localhost.key).unwrap());
+
+        let mut stream = connect(addr, &localhost.der, "localhost", &[b"http/1.1"]);
+        let response = get(&mut stream).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\nhttps https"));
+        assert_eq!(Some(&b"http/1.1"[..]), stream.conn.alpn_protocol());
+    }
+
+    #[test]
+    fn server_name_selects_certificate() {
+        let localhost = certificate("localhost");
+        let example = certificate("example.test");
+        let config = TlsConfig::from_pem_files(&localhost.cert, &localhost.key)
+            .and_then(|config| config.with_server_name("Example.Test", &example.cert, &example.key))
+            .unwrap();
+        let addr = spawn(config);
+
+        let mut stream = connect(addr, &example.der, "example.test", &[]);
+        assert!(get(&mut stream).is_ok());
+        assert_eq!(
+            Some(&example.der),
+            stream
+                .conn
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+        );
+
+        // the default certificate is not trusted by this client
+        let mut stream = connect(addr, &example.der, "localhost", &[]);
+        assert!(get(&mut stream).is_err());
+    }
+
+    #[test]
+    fn h2_is_selected_with_alpn() {
+        let localhost = certificate("localhost");
+        let addr = spawn(TlsConfig::from_pem_files(&localhost.cert, &localhost.key).unwrap());
+
+        let mut stream = connect(addr, &localhost.der, "localhost", &[b"h2", b"http/1.1"]);
+        stream.write_all(PREFACE).unwrap();
+        stream.write_all(&[0, 0, 0, 0x4, 0, 0, 0, 0, 0]).unwrap();
+        let mut head = [0; 9];
+        stream.read_exact(&mut head).unwrap();
+        assert_eq!(Some(&b"h2"[..]), stream.conn.alpn_protocol());
+        // the server starts with its SETTINGS frame
+        assert_eq!(0x4, head[3]);
+    }
+
+    #[test]
+    fn missing_private_key_is_invalid_data() {
+        let localhost = certificate("localhost");
+        let error = TlsConfig::from_pem_files(&localhost.cert, &localhost.cert).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+}
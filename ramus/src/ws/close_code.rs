@@ -0,0 +1,79 @@
+/// A status code sent in a Close frame as listed in [RFC6455 Section
+/// 7.4.1](https://datatracker.ietf.org/doc/html/rfc6455#section-7.4.1).
+///
+/// The status code gives the reason that an endpoint is closing the connection.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct CloseCode(pub(crate) u16);
+
+impl CloseCode {
+    /// Create a [`CloseCode`] from a number.
+    ///
+    /// Returns `None` if the code cannot be sent in a Close frame, which is any code below 1000,
+    /// the codes 1004, 1005, 1006 and 1015 that are reserved, and any code that is not defined
+    /// by [RFC6455 Section 7.4](https://datatracker.ietf.org/doc/html/rfc6455#section-7.4) or
+    /// in the 3000-4999 range used by libraries and applications.
+    ///
+    /// ```
+    /// use ramus::ws::CloseCode;
+    ///
+    /// assert_eq!(Some(CloseCode::NORMAL), CloseCode::from_u16(1000));
+    /// assert!(CloseCode::from_u16(4000).is_some());
+    /// assert!(CloseCode::from_u16(1005).is_none());
+    /// ```
+    pub const fn from_u16(code: u16) -> Option<Self> {
+        match code {
+            1000..=1003 | 1007..=1014 | 3000..=4999 => Some(Self(code)),
+            _ => None,
+        }
+    }
+
+    /// Returns the status code as a number.
+    ///
+    /// ```
+    /// use ramus::ws::CloseCode;
+    ///
+    /// assert_eq!(1002, CloseCode::PROTOCOL_ERROR.as_u16());
+    /// ```
+    pub const fn as_u16(&self) -> u16 {
+        self.0
+    }
+}
+
+macro_rules! const_close_codes {
+    (
+        $(
+            $(#[$comment:meta])+
+            $name:ident => $code:literal,
+        )*
+    ) => {
+        impl CloseCode {
+            $(
+                $(#[$comment])+
+                pub const $name: CloseCode = CloseCode($code);
+            )*
+        }
+    }
+}
+
+const_close_codes! {
+    /// The purpose for which the connection was established has been fulfilled.
+    NORMAL => 1000,
+    /// The endpoint is going away, such as a server going down.
+    GOING_AWAY => 1001,
+    /// The endpoint is closing the connection due to a protocol error.
+    PROTOCOL_ERROR => 1002,
+    /// The endpoint received a type of data that it cannot accept.
+    UNSUPPORTED_DATA => 1003,
+    /// The endpoint received data within a message that was not consistent with the type of
+    /// the message, such as a text message that is not UTF-8.
+    INVALID_PAYLOAD_DATA => 1007,
+    /// The endpoint received a message that violates its policy.
+    POLICY_VIOLATION => 1008,
+    /// The endpoint received a message that is too big for it to process.
+    MESSAGE_TOO_BIG => 1009,
+    /// The client expected the server to negotiate one or more extensions.
+    MANDATORY_EXTENSION => 1010,
+    /// The server encountered an unexpected condition that prevented it from fulfilling the
+    /// request.
+    INTERNAL_ERROR => 1011,
+}
@@ -0,0 +1,280 @@
+//! Crate Module:
+//! The WebSocket base framing protocol as defined in [RFC6455 Section
+//! 5](https://datatracker.ietf.org/doc/html/rfc6455#section-5).
+//!
+//! ```text
+//!  0                   1                   2                   3
+//!  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+//! +-+-+-+-+-------+-+-------------+-------------------------------+
+//! |F|R|R|R| opcode|M| Payload len |    Extended payload length    |
+//! |I|S|S|S|  (4)  |A|     (7)     |             (16/64)           |
+//! |N|V|V|V|       |S|             |   (if payload len==126/127)   |
+//! | |1|2|3|       |K|             |                               |
+//! +-+-+-+-+-------+-+-------------+ - - - - - - - - - - - - - - - +
+//! |     Extended payload length continued, if payload len == 127  |
+//! + - - - - - - - - - - - - - - - +-------------------------------+
+//! |                               |Masking-key, if MASK set to 1  |
+//! +-------------------------------+-------------------------------+
+//! | Masking-key (continued)       |          Payload Data         |
+//! +-------------------------------- - - - - - - - - - - - - - - - +
+//! ```
+use std::io::{self, Read, Write};
+
+use super::CloseCode;
+
+/// The opcodes as defined in [RFC6455 Section
+/// 5.2](https://datatracker.ietf.org/doc/html/rfc6455#section-5.2).
+pub(crate) mod opcode {
+    pub(crate) const CONTINUATION: u8 = 0x0;
+    pub(crate) const TEXT: u8 = 0x1;
+    pub(crate) const BINARY: u8 = 0x2;
+    pub(crate) const CLOSE: u8 = 0x8;
+    pub(crate) const PING: u8 = 0x9;
+    pub(crate) const PONG: u8 = 0xa;
+}
+
+/// The largest payload of a control frame.
+pub(crate) const MAX_CONTROL_PAYLOAD: usize = 125;
+
+/// An error from reading a frame.
+#[derive(Debug)]
+pub(crate) enum Error {
+    Io(io::Error),
+    /// The frame is not valid and the connection must be closed with the status code.
+    Close(CloseCode),
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// A WebSocket frame, which is unmasked once read.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Frame {
+    pub(crate) fin: bool,
+    pub(crate) opcode: u8,
+    pub(crate) payload: Vec<u8>,
+}
+
+impl Frame {
+    pub(crate) fn new(fin: bool, opcode: u8, payload: Vec<u8>) -> Self {
+        Self {
+            fin,
+            opcode,
+            payload,
+        }
+    }
+
+    /// Returns true for the Close, Ping and Pong frames.
+    pub(crate) fn is_control(&self) -> bool {
+        self.opcode & 0x8 != 0
+    }
+
+    /// Read a frame, where `masked` is whether the peer must mask its frames, which is true for
+    /// frames sent by a client.
+    ///
+    /// Returns an [`Error::Close`] when the frame is not valid, or has a payload larger than
+    /// `max_payload`, before the payload is read.
+    pub(crate) fn read_from<R: Read + ?Sized>(
+        reader: &mut R,
+        max_payload: usize,
+        masked: bool,
+    ) -> Result<Self, Error> {
+        let mut head = [0; 2];
+        reader.read_exact(&mut head)?;
+        let fin = head[0] & 0x80 != 0;
+        let opcode = head[0] & 0x0f;
+
+        // no extensions are negotiated so the reserved bits must be 0
+        if head[0] & 0x70 != 0 {
+            return Err(Error::Close(CloseCode::PROTOCOL_ERROR));
+        }
+        // RFC6455 Section 5.1: a client must mask every frame and a server must not
+        if (head[1] & 0x80 != 0) != masked {
+            return Err(Error::Close(CloseCode::PROTOCOL_ERROR));
+        }
+
+        let len = match head[1] & 0x7f {
+            126 => {
+                let mut len = [0; 2];
+                reader.read_exact(&mut len)?;
+                u16::from_be_bytes(len) as u64
+            }
+            127 => {
+                let mut len = [0; 8];
+                reader.read_exact(&mut len)?;
+                let len = u64::from_be_bytes(len);
+                // the most significant bit must be 0
+                if len >> 63 != 0 {
+                    return Err(Error::Close(CloseCode::PROTOCOL_ERROR));
+                }
+                len
+            }
+            len => len as u64,
+        };
+
+        match opcode {
+            opcode::CONTINUATION | opcode::TEXT | opcode::BINARY => {}
+            // RFC6455 Section 5.5: control frames cannot be fragmented and have a small payload
+            opcode::CLOSE | opcode::PING | opcode::PONG => {
+                if !fin || len > MAX_CONTROL_PAYLOAD as u64 {
+                    return Err(Error::Close(CloseCode::PROTOCOL_ERROR));
+                }
+            }
+            _ => return Err(Error::Close(CloseCode::PROTOCOL_ERROR)),
+        }
+        if len > max_payload as u64 {
+            return Err(Error::Close(CloseCode::MESSAGE_TOO_BIG));
+        }
+
+        let mut key = [0; 4];
+        if masked {
+            reader.read_exact(&mut key)?;
+        }
+        let mut payload = vec![0; len as usize];
+        reader.read_exact(&mut payload)?;
+        apply_mask(&mut payload, key);
+
+        Ok(Self {
+            fin,
+            opcode,
+            payload,
+        })
+    }
+
+    /// Write the frame, masking the payload with the key when there is one.
+    pub(crate) fn write_to<W: Write + ?Sized>(
+        &self,
+        writer: &mut W,
+        mask: Option<[u8; 4]>,
+    ) -> io::Result<()> {
+        let mut head = Vec::with_capacity(14);
+        head.push((self.fin as u8) << 7 | self.opcode);
+        let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+        // RFC6455 Section 5.2: the minimal number of bytes must be used for the length
+        match self.payload.len() {
+            len @ 0..=125 => head.push(mask_bit | len as u8),
+            len @ 126..=0xffff => {
+                head.push(mask_bit | 126);
+                head.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                head.push(mask_bit | 127);
+                head.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+
+        match mask {
+            Some(key) => {
+                head.extend_from_slice(&key);
+                let mut payload = self.payload.clone();
+                apply_mask(&mut payload, key);
+                writer.write_all(&head)?;
+                writer.write_all(&payload)
+            }
+            None => {
+                writer.write_all(&head)?;
+                writer.write_all(&self.payload)
+            }
+        }
+    }
+}
+
+/// Mask or unmask the payload as defined in [RFC6455 Section
+/// 5.3](https://datatracker.ietf.org/doc/html/rfc6455#section-5.3).
+fn apply_mask(payload: &mut [u8], key: [u8; 4]) {
+    for (i, b) in payload.iter_mut().enumerate() {
+        *b ^= key[i % 4];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{opcode, Error, Frame};
+    use crate::ws::CloseCode;
+
+    fn read(mut bytes: &[u8], masked: bool) -> Result<Frame, Error> {
+        Frame::read_from(&mut bytes, 1 << 20, masked)
+    }
+
+    fn assert_close(code: CloseCode, result: Result<Frame, Error>) {
+        assert!(matches!(result, Err(Error::Close(c)) if c == code));
+    }
+
+    #[test]
+    fn rfc_examples() {
+        // Examples from RFC6455 Section 5.7
+        let hello = Frame::new(true, opcode::TEXT, b"Hello".to_vec());
+        let unmasked = [0x81, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f];
+        let masked = [
+            0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+        ];
+        assert_eq!(hello, read(&unmasked, false).unwrap());
+        assert_eq!(hello, read(&masked, true).unwrap());
+
+        let mut dst = Vec::new();
+        hello.write_to(&mut dst, None).unwrap();
+        assert_eq!(&unmasked[..], dst);
+        let mut dst = Vec::new();
+        hello
+            .write_to(&mut dst, Some([0x37, 0xfa, 0x21, 0x3d]))
+            .unwrap();
+        assert_eq!(&masked[..], dst);
+
+        let first = [0x01, 0x03, 0x48, 0x65, 0x6c];
+        let second = [0x80, 0x02, 0x6c, 0x6f];
+        assert_eq!(
+            Frame::new(false, opcode::TEXT, b"Hel".to_vec()),
+            read(&first, false).unwrap()
+        );
+        assert_eq!(
+            Frame::new(true, opcode::CONTINUATION, b"lo".to_vec()),
+            read(&second, false).unwrap()
+        );
+    }
+
+    #[test]
+    fn extended_payload_lengths_round_trip() {
+        for (len, head_len) in [(126, 4), (0x10000, 10)] {
+            let frame = Frame::new(true, opcode::BINARY, vec![7; len]);
+            let mut dst = Vec::new();
+            frame.write_to(&mut dst, Some([1, 2, 3, 4])).unwrap();
+            assert_eq!(head_len + 4 + len, dst.len());
+            assert_eq!(frame, read(&dst, true).unwrap());
+        }
+    }
+
+    #[test]
+    fn unmasked_frame_from_client_is_protocol_error() {
+        assert_close(
+            CloseCode::PROTOCOL_ERROR,
+            read(&[0x81, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f], true),
+        );
+    }
+
+    #[test]
+    fn reserved_bits_and_opcodes_are_protocol_errors() {
+        assert_close(CloseCode::PROTOCOL_ERROR, read(&[0xc1, 0x00], false));
+        assert_close(CloseCode::PROTOCOL_ERROR, read(&[0x83, 0x00], false));
+        assert_close(CloseCode::PROTOCOL_ERROR, read(&[0x8b, 0x00], false));
+    }
+
+    #[test]
+    fn control_frames_cannot_be_fragmented_or_large() {
+        assert_close(CloseCode::PROTOCOL_ERROR, read(&[0x09, 0x00], false));
+        let mut ping = vec![0x89, 126, 0, 126];
+        ping.extend_from_slice(&[0; 126]);
+        assert_close(CloseCode::PROTOCOL_ERROR, read(&ping, false));
+    }
+
+    #[test]
+    fn payload_larger_than_max_is_message_too_big() {
+        let mut bytes = &[0x82, 0x7f, 0, 0, 0, 1, 0, 0, 0, 0][..];
+        assert_close(
+            CloseCode::MESSAGE_TOO_BIG,
+            Frame::read_from(&mut bytes, 1 << 20, false),
+        );
+    }
+}
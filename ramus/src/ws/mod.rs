@@ -0,0 +1,345 @@
+//! The WebSocket protocol as defined in [RFC6455](https://datatracker.ietf.org/doc/html/rfc6455).
+//!
+//! A [`Handler`](crate::server::Handler) accepts a WebSocket by returning the response from
+//! [`upgrade`], or from [`Handshake::accept`] to select a subprotocol, for a `GET` request
+//! with the opening handshake. Once the `101 Switching Protocols` response has been sent the
+//! function is called with the [`WebSocket`] on the connection thread, and the connection is
+//! closed when it returns.
+//!
+//! ```no_run
+//! use ramus::{http::Request, server::Server, ws};
+//!
+//! Server::bind("127.0.0.1:8080")
+//!     .expect("failed to bind")
+//!     .serve(|request: &Request| {
+//!         ws::upgrade(request, |mut socket| {
+//!             while let Ok(Some(message)) = socket.recv() {
+//!                 if socket.send(message).is_err() {
+//!                     break;
+//!                 }
+//!             }
+//!         })
+//!     })
+//!     .expect("failed to accept connections");
+//! ```
+//!
+//! Only HTTP/1.1 connections can be upgraded, so a HTTP/2 request is answered with
+//! [`StatusCode::UPGRADE_REQUIRED`].
+mod close_code;
+mod frame;
+mod socket;
+
+pub use close_code::*;
+pub use socket::*;
+
+use crate::http::{
+    utils::{
+        base64::{self, Alphabet},
+        sha1::sha1,
+    },
+    Method, Request, Response, StatusCode, Version,
+};
+
+/// The GUID appended to the `Sec-WebSocket-Key` as defined in [RFC6455 Section
+/// 1.3](https://datatracker.ietf.org/doc/html/rfc6455#section-1.3).
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The only WebSocket version supported, as defined in [RFC6455 Section
+/// 4.1](https://datatracker.ietf.org/doc/html/rfc6455#section-4.1).
+pub const VERSION: &str = "13";
+
+/// Returns the `Sec-WebSocket-Accept` value for a `Sec-WebSocket-Key`, as defined in [RFC6455
+/// Section 4.2.2](https://datatracker.ietf.org/doc/html/rfc6455#section-4.2.2).
+///
+/// ```
+/// use ramus::ws;
+///
+/// assert_eq!(
+///     "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=",
+///     ws::accept_key("dGhlIHNhbXBsZSBub25jZQ==")
+/// );
+/// ```
+pub fn accept_key(key: &str) -> String {
+    let digest = sha1(format!("{key}{GUID}").as_bytes());
+    base64::encode(&digest, Alphabet::Standard)
+}
+
+/// Accept the opening handshake in the request and call the function with the [`WebSocket`]
+/// once the `101 Switching Protocols` response has been sent.
+///
+/// When the request is not a valid opening handshake the response is the [`StatusCode`] from
+/// [`Handshake::from_request`], with the header fields that tell the client how to upgrade.
+pub fn upgrade<F>(request: &Request, on_upgrade: F) -> Response
+where
+    F: FnOnce(WebSocket<'_>) + Send + 'static,
+{
+    match Handshake::from_request(request) {
+        Ok(handshake) => handshake.accept(on_upgrade),
+        Err(status) => reject(status),
+    }
+}
+
+/// The response for a request that is not a valid opening handshake.
+fn reject(status: StatusCode) -> Response {
+    let response = Response::new(status);
+    match status {
+        StatusCode::METHOD_NOT_ALLOWED => response.with_header("Allow", "GET"),
+        // RFC6455 Section 4.2.2: the versions that are supported are sent to the client
+        StatusCode::UPGRADE_REQUIRED => response
+            .with_header("Connection", "Upgrade")
+            .with_header("Upgrade", "websocket")
+            .with_header("Sec-WebSocket-Version", VERSION),
+        _ => response,
+    }
+}
+
+/// A valid opening handshake from a client as defined in [RFC6455 Section
+/// 4.2.1](https://datatracker.ietf.org/doc/html/rfc6455#section-4.2.1).
+#[derive(Debug)]
+pub struct Handshake {
+    accept: String,
+    protocols: Vec<String>,
+    protocol: Option<String>,
+}
+
+impl Handshake {
+    /// Read the opening handshake from the request.
+    ///
+    /// Returns a [`StatusCode::METHOD_NOT_ALLOWED`] if the request is not a `GET`.
+    /// Returns a [`StatusCode::UPGRADE_REQUIRED`] if the request is not a HTTP/1.1 request with
+    /// the "websocket" upgrade protocol and "upgrade" connection option, or has a
+    /// `Sec-WebSocket-Version` other than [`VERSION`].
+    /// Returns a [`StatusCode::BAD_REQUEST`] if there is not exactly one `Sec-WebSocket-Key`
+    /// that is 16 bytes encoded in base 64.
+    pub fn from_request(request: &Request) -> Result<Self, StatusCode> {
+        let headers = request.headers();
+        let has_token = |name, token: &str| {
+            headers
+                .get_all(name)
+                .flat_map(|value| value.split(','))
+                .any(|value| value.trim().eq_ignore_ascii_case(token))
+        };
+
+        if request.method() != Method::Get {
+            return Err(StatusCode::METHOD_NOT_ALLOWED);
+        }
+        if request.version() != Version::HTTP_1_1
+            || !has_token("upgrade", "websocket")
+            || !has_token("connection", "upgrade")
+        {
+            return Err(StatusCode::UPGRADE_REQUIRED);
+        }
+
+        let mut keys = headers.get_all("sec-websocket-key");
+        let key = match (keys.next(), keys.next()) {
+            (Some(key), None) => key.trim(),
+            _ => return Err(StatusCode::BAD_REQUEST),
+        };
+        match base64::decode(key.as_bytes(), Alphabet::Standard) {
+            Some(nonce) if nonce.len() == 16 => {}
+            _ => return Err(StatusCode::BAD_REQUEST),
+        }
+
+        let mut versions = headers.get_all("sec-websocket-version");
+        match (versions.next(), versions.next()) {
+            (Some(version), None) if version.trim() == VERSION => {}
+            (None, _) => return Err(StatusCode::BAD_REQUEST),
+            _ => return Err(StatusCode::UPGRADE_REQUIRED),
+        }
+
+        let protocols = headers
+            .get_all("sec-websocket-protocol")
+            .flat_map(|value| value.split(','))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect();
+
+        Ok(Self {
+            accept: accept_key(key),
+            protocols,
+            protocol: None,
+        })
+    }
+
+    /// Returns the subprotocols that the client asked for in the `Sec-WebSocket-Protocol`
+    /// header field, in order of the client's preference.
+    pub fn protocols(&self) -> &[String] {
+        &self.protocols
+    }
+
+    /// Select the subprotocol that the connection will use, which is ignored if the client did
+    /// not ask for it.
+    pub fn protocol(mut self, protocol: &str) -> Self {
+        self.protocol = self
+            .protocols
+            .iter()
+            .find(|p| p.as_str() == protocol)
+            .cloned();
+        self
+    }
+
+    /// Accept the handshake and call the function with the [`WebSocket`] once the `101
+    /// Switching Protocols` response has been sent.
+    pub fn accept<F>(self, on_upgrade: F) -> Response
+    where
+        F: FnOnce(WebSocket<'_>) + Send + 'static,
+    {
+        let response = Response::new(StatusCode::SWITCHING_PROTOCOLS)
+            .with_header("Connection", "Upgrade")
+            .with_header("Upgrade", "websocket")
+            .with_header("Sec-WebSocket-Accept", self.accept);
+        let response = match self.protocol {
+            Some(protocol) => response.with_header("Sec-WebSocket-Protocol", protocol),
+            None => response,
+        };
+        response.with_upgrade(move |reader, writer| on_upgrade(WebSocket::new(reader, writer)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, BufRead, BufReader, Read, Write},
+        net::TcpStream,
+        sync::mpsc,
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        http::{Limits, Request, StatusCode},
+        server::Server,
+    };
+
+    use super::{
+        frame::{opcode, Frame},
+        upgrade, CloseCode, Handshake,
+    };
+
+    const HANDSHAKE: &str = "GET /chat HTTP/1.1\r\nHost: server.example.com\r\n\
+                             Upgrade: websocket\r\nConnection: Upgrade\r\n\
+                             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                             Sec-WebSocket-Protocol: chat, superchat\r\n\
+                             Sec-WebSocket-Version: 13\r\n\r\n";
+
+    fn request(bytes: &str) -> Request {
+        Request::from_reader(&mut bytes.as_bytes(), &Limits::default())
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn handshake_is_accepted() {
+        let handshake = Handshake::from_request(&request(HANDSHAKE)).unwrap();
+        assert_eq!(&["chat", "superchat"], handshake.protocols());
+        let response = handshake.protocol("superchat").accept(|_| {});
+        assert_eq!(StatusCode::SWITCHING_PROTOCOLS, response.status());
+        assert_eq!(
+            Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="),
+            response.headers().get("sec-websocket-accept")
+        );
+        assert_eq!(
+            Some("superchat"),
+            response.headers().get("sec-websocket-protocol")
+        );
+    }
+
+    #[test]
+    fn protocol_not_offered_is_not_selected() {
+        let response = Handshake::from_request(&request(HANDSHAKE))
+            .unwrap()
+            .protocol("other")
+            .accept(|_| {});
+        assert!(!response.headers().contains("sec-websocket-protocol"));
+    }
+
+    #[test]
+    fn invalid_handshakes_are_rejected() {
+        let response = upgrade(&request(&HANDSHAKE.replacen("GET", "POST", 1)), |_| {});
+        assert_eq!(StatusCode::METHOD_NOT_ALLOWED, response.status());
+        assert_eq!(Some("GET"), response.headers().get("allow"));
+
+        let response = upgrade(&request("GET / HTTP/1.1\r\nHost: a\r\n\r\n"), |_| {});
+        assert_eq!(StatusCode::UPGRADE_REQUIRED, response.status());
+
+        let response = upgrade(&request(&HANDSHAKE.replace(": 13", ": 8")), |_| {});
+        assert_eq!(StatusCode::UPGRADE_REQUIRED, response.status());
+        assert_eq!(Some("13"), response.headers().get("sec-websocket-version"));
+
+        let response = upgrade(&request(&HANDSHAKE.replace("ZQ==", "")), |_| {});
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[test]
+    fn server_echoes_messages_until_closed() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || {
+            server.serve(|request: &Request| {
+                upgrade(request, |mut socket| {
+                    while let Ok(Some(message)) = socket.recv() {
+                        socket.send(message).unwrap();
+                    }
+                })
+            })
+        });
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        // the first frame is sent straight after the handshake
+        let mut bytes = HANDSHAKE.as_bytes().to_vec();
+        Frame::new(true, opcode::TEXT, b"Hello".to_vec())
+            .write_to(&mut bytes, Some([1, 2, 3, 4]))
+            .unwrap();
+        stream.write_all(&bytes).unwrap();
+
+        let mut head = String::new();
+        while !head.ends_with("\r\n\r\n") {
+            reader.read_line(&mut head).unwrap();
+        }
+        assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(head.contains("sec-websocket-accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+        assert_eq!(
+            Frame::new(true, opcode::TEXT, b"Hello".to_vec()),
+            Frame::read_from(&mut reader, 1024, false).unwrap()
+        );
+
+        let close = Frame::new(true, opcode::CLOSE, vec![0x03, 0xe8]);
+        close.write_to(&mut stream, Some([5, 6, 7, 8])).unwrap();
+        let echoed = Frame::read_from(&mut reader, 1024, false).unwrap();
+        assert_eq!(close, echoed);
+        assert_eq!(
+            CloseCode::NORMAL.as_u16().to_be_bytes().to_vec(),
+            echoed.payload
+        );
+        // the server closes the connection after the closing handshake
+        assert_eq!(0, reader.read(&mut [0; 1]).unwrap());
+    }
+
+    #[test]
+    fn idle_client_times_out() {
+        let server = Server::bind("127.0.0.1:0")
+            .unwrap()
+            .body_read_timeout(Duration::from_millis(100));
+        let addr = server.local_addr().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            server.serve(move |request: &Request| {
+                let sender = sender.clone();
+                upgrade(request, move |mut socket| {
+                    let kind = socket.recv().err().map(|error| error.kind());
+                    sender.send(kind).unwrap();
+                })
+            })
+        });
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(HANDSHAKE.as_bytes()).unwrap();
+        let kind = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(
+            kind,
+            Some(io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+        ));
+    }
+}
@@ -0,0 +1,441 @@
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+use super::{
+    frame::{opcode, Error, Frame, MAX_CONTROL_PAYLOAD},
+    CloseCode,
+};
+
+/// A message sent over a [`WebSocket`], which may have been sent as many fragments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// A message of UTF-8 text.
+    Text(String),
+    /// A message of binary data.
+    Binary(Vec<u8>),
+}
+
+impl From<String> for Message {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&str> for Message {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Message {
+    fn from(data: Vec<u8>) -> Self {
+        Self::Binary(data)
+    }
+}
+
+/// The server side of a WebSocket connection, as defined in [RFC6455 Section
+/// 6](https://datatracker.ietf.org/doc/html/rfc6455#section-6).
+///
+/// Messages are received with [`WebSocket::recv`], which reassembles fragmented messages and
+/// answers Ping frames with a Pong frame. When the client starts the closing handshake the
+/// Close frame is sent back and [`WebSocket::recv`] returns `None`, see
+/// [`WebSocket::close`] for the server starting it.
+///
+/// A protocol error from the client closes the connection with the matching [`CloseCode`],
+/// and a [`WebSocket`] that is dropped before the closing handshake sends a Close frame with
+/// [`CloseCode::NORMAL`].
+pub struct WebSocket<'a> {
+    reader: &'a mut dyn BufRead,
+    writer: &'a mut dyn Write,
+    max_message_size: usize,
+    frame_size: usize,
+    close_sent: bool,
+    close_received: Option<Option<(CloseCode, String)>>,
+}
+
+impl fmt::Debug for WebSocket<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocket")
+            .field("max_message_size", &self.max_message_size)
+            .field("frame_size", &self.frame_size)
+            .field("close_sent", &self.close_sent)
+            .field("close_received", &self.close_received)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> WebSocket<'a> {
+    /// The default largest message that can be received.
+    pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+    /// The default largest frame that is sent, larger messages are sent as many fragments.
+    pub const FRAME_SIZE: usize = 64 * 1024;
+
+    pub(crate) fn new(reader: &'a mut dyn BufRead, writer: &'a mut dyn Write) -> Self {
+        Self {
+            reader,
+            writer,
+            max_message_size: Self::MAX_MESSAGE_SIZE,
+            frame_size: Self::FRAME_SIZE,
+            close_sent: false,
+            close_received: None,
+        }
+    }
+
+    /// Set the largest message that can be received, a larger message closes the connection
+    /// with [`CloseCode::MESSAGE_TOO_BIG`].
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Set the largest payload of each frame sent, larger messages are fragmented as defined
+    /// in [RFC6455 Section 5.4](https://datatracker.ietf.org/doc/html/rfc6455#section-5.4).
+    pub fn frame_size(mut self, size: usize) -> Self {
+        self.frame_size = size.max(1);
+        self
+    }
+
+    /// Receive the next message from the client.
+    ///
+    /// Returns `None` once the client has sent a Close frame, which is answered with a Close
+    /// frame of the same status code.
+    ///
+    /// # Errors
+    /// Returns an [`io::ErrorKind::InvalidData`] error when the client violates the protocol,
+    /// after the connection has been closed with the matching [`CloseCode`], and any error from
+    /// reading the connection. This includes the error of a read that times out, when the client
+    /// sends nothing within the
+    /// [`body_read_timeout`](crate::server::Server::body_read_timeout) of the server.
+    pub fn recv(&mut self) -> io::Result<Option<Message>> {
+        let mut fragments: Option<(u8, Vec<u8>)> = None;
+        loop {
+            if self.close_received.is_some() {
+                return Ok(None);
+            }
+
+            let frame = match Frame::read_from(self.reader, self.max_message_size, true) {
+                Ok(frame) => frame,
+                Err(Error::Io(error)) => return Err(error),
+                Err(Error::Close(code)) => return Err(self.fail(code)),
+            };
+
+            if frame.is_control() {
+                self.receive_control(frame)?;
+                continue;
+            }
+
+            let (kind, mut data) = match (fragments.take(), frame.opcode) {
+                (None, opcode::CONTINUATION) | (Some(_), opcode::TEXT | opcode::BINARY) => {
+                    return Err(self.fail(CloseCode::PROTOCOL_ERROR))
+                }
+                (None, kind) => (kind, Vec::new()),
+                (Some(fragments), _) => fragments,
+            };
+            if data.len() + frame.payload.len() > self.max_message_size {
+                return Err(self.fail(CloseCode::MESSAGE_TOO_BIG));
+            }
+            data.extend_from_slice(&frame.payload);
+
+            if !frame.fin {
+                fragments = Some((kind, data));
+                continue;
+            }
+            return match kind {
+                opcode::TEXT => match String::from_utf8(data) {
+                    Ok(text) => Ok(Some(Message::Text(text))),
+                    Err(_) => Err(self.fail(CloseCode::INVALID_PAYLOAD_DATA)),
+                },
+                _ => Ok(Some(Message::Binary(data))),
+            };
+        }
+    }
+
+    /// Send a message to the client, which is fragmented when larger than the
+    /// [`WebSocket::frame_size`].
+    ///
+    /// # Errors
+    /// Returns an [`io::ErrorKind::NotConnected`] error if a Close frame has already been sent.
+    pub fn send(&mut self, message: impl Into<Message>) -> io::Result<()> {
+        let (kind, data) = match message.into() {
+            Message::Text(text) => (opcode::TEXT, text.into_bytes()),
+            Message::Binary(data) => (opcode::BINARY, data),
+        };
+        self.check_open()?;
+
+        let mut chunks = data.chunks(self.frame_size).peekable();
+        let mut kind = kind;
+        if chunks.peek().is_none() {
+            self.write(Frame::new(true, kind, Vec::new()))?;
+        }
+        while let Some(chunk) = chunks.next() {
+            self.write(Frame::new(chunks.peek().is_none(), kind, chunk.to_vec()))?;
+            kind = opcode::CONTINUATION;
+        }
+        self.writer.flush()
+    }
+
+    /// Send a Ping frame with the payload, the Pong frame sent back by the client is ignored.
+    ///
+    /// # Errors
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if the payload is longer than 125
+    /// bytes, and an [`io::ErrorKind::NotConnected`] error if a Close frame has already been
+    /// sent.
+    pub fn ping(&mut self, payload: &[u8]) -> io::Result<()> {
+        if payload.len() > MAX_CONTROL_PAYLOAD {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        self.check_open()?;
+        self.write(Frame::new(true, opcode::PING, payload.to_vec()))?;
+        self.writer.flush()
+    }
+
+    /// Start the closing handshake as defined in [RFC6455 Section
+    /// 7.1.2](https://datatracker.ietf.org/doc/html/rfc6455#section-7.1.2), by sending a
+    /// Close frame and then discarding any messages until the client sends its Close frame.
+    ///
+    /// Does nothing if the client has already closed the connection.
+    ///
+    /// # Errors
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if the reason is longer than 123 bytes,
+    /// and any error from reading or writing the connection.
+    pub fn close(&mut self, code: CloseCode, reason: &str) -> io::Result<()> {
+        if reason.len() > MAX_CONTROL_PAYLOAD - 2 {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        if !self.close_sent {
+            self.send_close(Some(code), reason)?;
+        }
+        while self.recv()?.is_some() {}
+        Ok(())
+    }
+
+    /// Returns the [`CloseCode`] and reason from the Close frame sent by the client.
+    ///
+    /// Returns `None` if the client has not sent a Close frame or the frame did not have a
+    /// status code.
+    pub fn close_status(&self) -> Option<(CloseCode, &str)> {
+        match &self.close_received {
+            Some(Some((code, reason))) => Some((*code, reason)),
+            _ => None,
+        }
+    }
+
+    /// Handle a control frame as defined in [RFC6455 Section
+    /// 5.5](https://datatracker.ietf.org/doc/html/rfc6455#section-5.5).
+    fn receive_control(&mut self, frame: Frame) -> io::Result<()> {
+        match frame.opcode {
+            opcode::PING if !self.close_sent => {
+                self.write(Frame::new(true, opcode::PONG, frame.payload))?;
+                self.writer.flush()
+            }
+            opcode::CLOSE => {
+                let status = match frame.payload.as_slice() {
+                    [] => None,
+                    [high, low, reason @ ..] => {
+                        let code = CloseCode::from_u16(u16::from_be_bytes([*high, *low]))
+                            .ok_or_else(|| self.fail(CloseCode::PROTOCOL_ERROR))?;
+                        let reason = String::from_utf8(reason.to_vec())
+                            .map_err(|_| self.fail(CloseCode::INVALID_PAYLOAD_DATA))?;
+                        Some((code, reason))
+                    }
+                    [_] => return Err(self.fail(CloseCode::PROTOCOL_ERROR)),
+                };
+                // RFC6455 Section 5.5.1: the Close frame is answered with the same status code
+                if !self.close_sent {
+                    self.send_close(status.as_ref().map(|(code, _)| *code), "")?;
+                }
+                self.close_received = Some(status);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Close the connection because of an error in what the client sent, returning the error
+    /// for the caller.
+    fn fail(&mut self, code: CloseCode) -> io::Error {
+        if !self.close_sent {
+            // the client may already be gone, the caller is told about the protocol error
+            let _ = self.send_close(Some(code), "");
+        }
+        self.close_received = Some(None);
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("websocket closed with status code {}", code.as_u16()),
+        )
+    }
+
+    fn send_close(&mut self, code: Option<CloseCode>, reason: &str) -> io::Result<()> {
+        self.close_sent = true;
+        let mut payload = Vec::new();
+        if let Some(code) = code {
+            payload.extend_from_slice(&code.as_u16().to_be_bytes());
+            payload.extend_from_slice(reason.as_bytes());
+        }
+        self.write(Frame::new(true, opcode::CLOSE, payload))?;
+        self.writer.flush()
+    }
+
+    fn check_open(&self) -> io::Result<()> {
+        if self.close_sent {
+            Err(io::ErrorKind::NotConnected.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn write(&mut self, frame: Frame) -> io::Result<()> {
+        // RFC6455 Section 5.1: a server must not mask any frames
+        frame.write_to(self.writer, None)
+    }
+}
+
+impl Drop for WebSocket<'_> {
+    fn drop(&mut self) {
+        if !self.close_sent {
+            let _ = self.send_close(Some(CloseCode::NORMAL), "");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufRead};
+
+    use super::{CloseCode, Message, WebSocket};
+    use crate::ws::frame::{opcode, Frame};
+
+    const KEY: [u8; 4] = [1, 2, 3, 4];
+
+    fn client(frames: &[Frame]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for frame in frames {
+            frame.write_to(&mut bytes, Some(KEY)).unwrap();
+        }
+        bytes
+    }
+
+    fn sent(mut bytes: &[u8]) -> Vec<Frame> {
+        let mut frames = Vec::new();
+        while !bytes.is_empty() {
+            frames.push(Frame::read_from(&mut bytes, usize::MAX, false).unwrap());
+        }
+        frames
+    }
+
+    fn close(code: CloseCode) -> Frame {
+        Frame::new(true, opcode::CLOSE, code.as_u16().to_be_bytes().to_vec())
+    }
+
+    #[test]
+    fn fragmented_message_is_reassembled_around_ping() {
+        let bytes = client(&[
+            Frame::new(false, opcode::TEXT, b"Hel".to_vec()),
+            Frame::new(true, opcode::PING, b"p".to_vec()),
+            Frame::new(true, opcode::CONTINUATION, b"lo".to_vec()),
+        ]);
+        let (mut reader, mut writer) = (&bytes[..], Vec::new());
+        let mut socket = WebSocket::new(&mut reader as &mut dyn BufRead, &mut writer);
+        assert_eq!(Some(Message::Text("Hello".into())), socket.recv().unwrap());
+        // the eof is not a clean close
+        assert!(socket.recv().is_err());
+        drop(socket);
+        assert_eq!(
+            vec![
+                Frame::new(true, opcode::PONG, b"p".to_vec()),
+                close(CloseCode::NORMAL)
+            ],
+            sent(&writer)
+        );
+    }
+
+    #[test]
+    fn close_frame_is_echoed() {
+        let mut frame = close(CloseCode::GOING_AWAY);
+        frame.payload.extend_from_slice(b"bye");
+        let bytes = client(&[frame]);
+        let (mut reader, mut writer) = (&bytes[..], Vec::new());
+        let mut socket = WebSocket::new(&mut reader, &mut writer);
+        assert_eq!(None, socket.recv().unwrap());
+        assert_eq!(Some((CloseCode::GOING_AWAY, "bye")), socket.close_status());
+        assert_eq!(
+            io::ErrorKind::NotConnected,
+            socket.send("late").unwrap_err().kind()
+        );
+        drop(socket);
+        assert_eq!(vec![close(CloseCode::GOING_AWAY)], sent(&writer));
+    }
+
+    #[test]
+    fn close_waits_for_client_close_frame() {
+        let bytes = client(&[
+            Frame::new(true, opcode::BINARY, vec![1]),
+            close(CloseCode::NORMAL),
+        ]);
+        let (mut reader, mut writer) = (&bytes[..], Vec::new());
+        let mut socket = WebSocket::new(&mut reader, &mut writer);
+        socket.close(CloseCode::POLICY_VIOLATION, "no").unwrap();
+        drop(socket);
+        let mut expected = close(CloseCode::POLICY_VIOLATION);
+        expected.payload.extend_from_slice(b"no");
+        assert_eq!(vec![expected], sent(&writer));
+    }
+
+    #[test]
+    fn invalid_utf8_closes_with_invalid_payload_data() {
+        let bytes = client(&[Frame::new(true, opcode::TEXT, vec![0xff])]);
+        let (mut reader, mut writer) = (&bytes[..], Vec::new());
+        let mut socket = WebSocket::new(&mut reader, &mut writer);
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            socket.recv().unwrap_err().kind()
+        );
+        drop(socket);
+        assert_eq!(vec![close(CloseCode::INVALID_PAYLOAD_DATA)], sent(&writer));
+    }
+
+    #[test]
+    fn unexpected_continuation_is_protocol_error() {
+        let bytes = client(&[Frame::new(true, opcode::CONTINUATION, vec![1])]);
+        let (mut reader, mut writer) = (&bytes[..], Vec::new());
+        let mut socket = WebSocket::new(&mut reader, &mut writer);
+        assert!(socket.recv().is_err());
+        drop(socket);
+        assert_eq!(vec![close(CloseCode::PROTOCOL_ERROR)], sent(&writer));
+    }
+
+    #[test]
+    fn message_larger_than_max_is_message_too_big() {
+        let bytes = client(&[
+            Frame::new(false, opcode::BINARY, vec![0; 3]),
+            Frame::new(true, opcode::CONTINUATION, vec![0; 3]),
+        ]);
+        let (mut reader, mut writer) = (&bytes[..], Vec::new());
+        let mut socket = WebSocket::new(&mut reader, &mut writer).max_message_size(4);
+        assert!(socket.recv().is_err());
+        drop(socket);
+        assert_eq!(vec![close(CloseCode::MESSAGE_TOO_BIG)], sent(&writer));
+    }
+
+    #[test]
+    fn large_message_is_sent_as_fragments() {
+        let (mut reader, mut writer) = (&[][..], Vec::new());
+        let mut socket = WebSocket::new(&mut reader, &mut writer).frame_size(2);
+        socket.send(vec![1, 2, 3, 4, 5]).unwrap();
+        socket.send("").unwrap();
+        drop(socket);
+        assert_eq!(
+            vec![
+                Frame::new(false, opcode::BINARY, vec![1, 2]),
+                Frame::new(false, opcode::CONTINUATION, vec![3, 4]),
+                Frame::new(true, opcode::CONTINUATION, vec![5]),
+                Frame::new(true, opcode::TEXT, vec![]),
+                close(CloseCode::NORMAL),
+            ],
+            sent(&writer)
+        );
+    }
+}